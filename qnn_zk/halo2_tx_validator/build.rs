@@ -0,0 +1,24 @@
+// build.rs
+// With --features ffi, regenerates the C header of src/ffi.rs. The header is committed, so C and
+// Go callers don't need cbindgen.
+fn main() {
+    #[cfg(feature = "ffi")]
+    {
+        println!("cargo:rerun-if-changed=src/ffi.rs");
+        let dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+        let config = cbindgen::Config {
+            language: cbindgen::Language::C,
+            include_guard: Some("QUANTUM_GUARD_H".into()),
+            cpp_compat: true,
+            // QgStatus_Ok, ...: C enumerators share one namespace
+            enumeration: cbindgen::EnumConfig { prefix_with_name: true, ..Default::default() },
+            ..Default::default()
+        };
+        cbindgen::Builder::new()
+            .with_config(config)
+            .with_src(format!("{dir}/src/ffi.rs"))
+            .generate()
+            .expect("no se pudo generar quantum_guard.h")
+            .write_to_file(format!("{dir}/include/quantum_guard.h"));
+    }
+}
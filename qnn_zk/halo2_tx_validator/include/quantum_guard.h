@@ -0,0 +1,109 @@
+#ifndef QUANTUM_GUARD_H
+#define QUANTUM_GUARD_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef enum QgStatus {
+  QgStatus_Ok = 0,
+  QgStatus_InvalidArgument = 1,
+  QgStatus_Witness = 2,
+  QgStatus_Params = 3,
+  QgStatus_Key = 4,
+  QgStatus_Shape = 5,
+  QgStatus_Proving = 6,
+  QgStatus_Rejected = 7,
+  QgStatus_Unavailable = 8,
+  QgStatus_Panic = 9,
+} QgStatus;
+
+/**
+ * Opaque to C.
+ */
+typedef struct QgContext QgContext;
+
+typedef struct QgBuffer {
+  uint8_t *data;
+  uintptr_t len;
+} QgBuffer;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+struct QgContext *qg_context_new(void);
+
+/**
+ * # Safety
+ * `ctx` from qg_context_new, or NULL; not used afterwards.
+ */
+void qg_context_free(struct QgContext *ctx);
+
+/**
+ * Message of the last failed call on `ctx`, valid until the next call; "" if none.
+ *
+ * # Safety
+ * `ctx` from qg_context_new, or NULL.
+ */
+const char *qg_last_error(const struct QgContext *ctx);
+
+/**
+ * KZG params in their file encoding.
+ *
+ * # Safety
+ * `ctx` from qg_context_new; `data` readable for `len` bytes.
+ */
+enum QgStatus qg_load_params(struct QgContext *ctx, const uint8_t *data, uintptr_t len);
+
+/**
+ * Proving key file (keygen --pk-out). Its vk is used for verification unless one is loaded.
+ *
+ * # Safety
+ * `ctx` from qg_context_new; `data` readable for `len` bytes.
+ */
+enum QgStatus qg_load_proving_key(struct QgContext *ctx, const uint8_t *data, uintptr_t len);
+
+/**
+ * Verifying key file (keygen --vk-out).
+ *
+ * # Safety
+ * `ctx` from qg_context_new; `data` readable for `len` bytes.
+ */
+enum QgStatus qg_load_verifying_key(struct QgContext *ctx, const uint8_t *data, uintptr_t len);
+
+/**
+ * Proves a witness given as JSON (the CLI's witness format). `activation` ("cubic", "pwl") and
+ * `domain` may be NULL for the defaults. On success `out` holds the proof JSON.
+ *
+ * # Safety
+ * `ctx` from qg_context_new; `witness` readable for `len` bytes; strings NUL-terminated or NULL;
+ * `out` writable.
+ */
+enum QgStatus qg_prove(struct QgContext *ctx,
+                       const uint8_t *witness,
+                       uintptr_t len,
+                       const char *activation,
+                       const char *domain,
+                       struct QgBuffer *out);
+
+/**
+ * Verifies a proof JSON from qg_prove. Status Rejected means the proof is invalid.
+ *
+ * # Safety
+ * `ctx` from qg_context_new; `proof` readable for `len` bytes.
+ */
+enum QgStatus qg_verify(struct QgContext *ctx, const uint8_t *proof, uintptr_t len);
+
+/**
+ * # Safety
+ * `buf` filled by this library and not freed before.
+ */
+void qg_buffer_free(struct QgBuffer buf);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* QUANTUM_GUARD_H */
@@ -0,0 +1,127 @@
+// batch.rs
+use crate::circuit::{Layouter, SimpleFloorPlanner, StackedLayouter};
+use crate::error::{QuantumGuardError as E, Result};
+use crate::prover::tx_instances;
+use crate::{CircuitField, Config, CostEstimate, Shape, TxCircuit, INSTANCE_COLUMNS};
+use halo2_proofs::{
+    circuit as h2,
+    halo2curves::bn256::Fr,
+    plonk::{Circuit, ConstraintSystem, Error},
+};
+
+// Several transactions of one shape in one proof (the batch64 preset): TxCircuit's gates and
+// lookup tables, each transaction's regions below the previous one's, and its public inputs
+// appended to every instance column in transaction order. A short batch repeats its last
+// transaction up to the batch size, so one key serves every batch of that size.
+#[derive(Clone, Debug, Default)]
+pub struct TxBatch<F: CircuitField = Fr> {
+    pub txs: Vec<TxCircuit<F>>,
+}
+
+impl<F: CircuitField> TxBatch<F> {
+    /// Witness-free batch of `size` transactions of `shape`; use it for keygen.
+    pub fn empty(shape: Shape, size: usize) -> Self {
+        Self { txs: vec![TxCircuit::empty(shape); size] }
+    }
+
+    /// Batch of `size` from `txs` (1..=size transactions of one shape), padded with the last one.
+    pub fn new(mut txs: Vec<TxCircuit<F>>, size: usize) -> Result<Self> {
+        let last = txs.last().cloned().ok_or_else(|| E::Witness("lote vacío".into()))?;
+        if txs.len() > size {
+            return Err(E::Shape(format!("{} transacciones en un lote de {size}", txs.len())));
+        }
+        if let Some(i) = txs.iter().position(|t| t.shape() != last.shape()) {
+            return Err(E::Shape(format!("la transacción {i} del lote tiene otra forma")));
+        }
+        txs.resize(size, last);
+        Ok(Self { txs })
+    }
+
+    pub fn shape(&self) -> Shape { self.txs.first().map(TxCircuit::shape).unwrap_or_default() }
+
+    /// Public inputs: the `tx_instances` of every transaction, concatenated column by column.
+    pub fn instances(&self) -> Vec<Vec<F>> {
+        let mut columns = vec![vec![]; INSTANCE_COLUMNS];
+        for tx in &self.txs {
+            for (column, values) in columns.iter_mut().zip(tx_instances(tx)) { column.extend(values); }
+        }
+        columns
+    }
+
+    /// Estimates the layout without running keygen, so the SRS can be sized up front.
+    pub fn cost_estimate(&self) -> CostEstimate {
+        TxCircuit::<F>::cost_of(self.txs.iter().map(TxCircuit::region_rows).sum())
+    }
+}
+
+impl<F: CircuitField> Circuit<F> for TxBatch<F> {
+    type Config = Config<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self { Self::empty(self.shape(), self.txs.len()) }
+
+    // the same constraint system as TxCircuit: only the regions and instance rows multiply
+    fn configure(cs: &mut ConstraintSystem<F>) -> Self::Config { TxCircuit::<F>::configure(cs) }
+
+    fn synthesize(&self, cfg: Self::Config, layouter: impl h2::Layouter<F>) -> Result<(), Error> {
+        let mut layouter = StackedLayouter::new(layouter);
+        cfg.range.load(layouter.namespace(|| "range table"))?;
+        cfg.pwl.load_table(layouter.namespace(|| "pwl table"))?;
+        let mut rows = [0; INSTANCE_COLUMNS];
+        for (i, tx) in self.txs.iter().enumerate() {
+            tx.assign(&cfg, &mut layouter.namespace(|| format!("tx {i}")), &rows)?;
+            for (row, values) in rows.iter_mut().zip(tx_instances(tx)) { *row += values.len(); }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{NEVER_EXPIRES, ONE_Q16};
+    use halo2_proofs::{dev::MockProver, halo2curves::ff::Field};
+
+    const ONE: i64 = ONE_Q16 as i64;
+
+    fn tx(x: Vec<i64>) -> TxCircuit<Fr> {
+        let mut circ = TxCircuit::<Fr>::builder()
+            .features(x).weights(vec![ONE / 2, -ONE / 4], ONE / 8).quantum(ONE / 2, ONE / 2)
+            .domain("test").validity(0, NEVER_EXPIRES)
+            .build().unwrap();
+        circ.disclosed = 0b1;
+        circ
+    }
+
+    fn check(batch: &TxBatch<Fr>, instances: Vec<Vec<Fr>>) -> bool {
+        MockProver::run(batch.cost_estimate().min_k, batch, instances).unwrap().verify().is_ok()
+    }
+
+    #[test]
+    fn every_transaction_is_bound_to_its_own_rows() {
+        let batch = TxBatch::new(vec![tx(vec![ONE, ONE / 2]), tx(vec![-ONE / 4, ONE])], 3).unwrap();
+        assert_eq!(batch.txs.len(), 3);
+        let instances = batch.instances();
+        // the disclosure column holds x[0] of each transaction, the padding repeating the last
+        assert_eq!(instances[12], vec![batch.txs[0].x[0], batch.txs[1].x[0], batch.txs[1].x[0]]);
+        assert!(check(&batch, instances.clone()));
+
+        // the scores of the first two transactions swapped
+        let mut swapped = instances.clone();
+        swapped[2].swap(0, 1);
+        assert!(!check(&batch, swapped));
+        let mut wrong = instances;
+        wrong[3][2] += Fr::ONE;
+        assert!(!check(&batch, wrong));
+    }
+
+    #[test]
+    fn batches_take_one_shape() {
+        let mut other = tx(vec![ONE, ONE / 2]);
+        other.disclosed = 0;
+        assert!(TxBatch::new(vec![tx(vec![ONE, ONE / 2]), other], 2).is_err());
+        assert!(TxBatch::new(vec![tx(vec![ONE, ONE / 2]); 3], 2).is_err());
+        assert!(TxBatch::<Fr>::new(vec![], 2).is_err());
+    }
+}
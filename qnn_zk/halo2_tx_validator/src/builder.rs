@@ -0,0 +1,94 @@
+// builder.rs
+use crate::error::{QuantumGuardError, Result};
+use crate::fixed::Fixed16;
+use crate::{domain_from_tag, fr_to_i128, Activation, CircuitField, TxCircuit, NEVER_EXPIRES, ONE_Q16};
+
+// Checked construction of the scoring part of a TxCircuit. Bounds are what the circuit can prove:
+// features, weights and bias are signed Q16.16 in 32 bits (their products must not wrap the field
+// after rescaling), q_out and alpha are in [0, 1], and so must be the resulting score. Policy gadgets
+// (sanctions, limits, tx binding...) are set on the built circuit as before.
+const Q16_MAX: i64 = i32::MAX as i64;
+
+/// Builds a `TxCircuit` from Q16.16 integers; `score_pub` is computed when not given.
+#[derive(Clone, Debug, Default)]
+pub struct TxCircuitBuilder {
+    x: Vec<i64>,
+    w: Vec<i64>,
+    b: i64,
+    alpha: i64,
+    q_out: i64,
+    score_pub: Option<i64>,
+    activation: Activation,
+    domain: String,
+    now: Option<u64>,
+    valid_until: Option<u64>,
+}
+
+impl TxCircuitBuilder {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn features(mut self, x: Vec<i64>) -> Self { self.x = x; self }
+
+    pub fn weights(mut self, w: Vec<i64>, b: i64) -> Self { self.w = w; self.b = b; self }
+
+    pub fn quantum(mut self, q_out: i64, alpha: i64) -> Self { self.q_out = q_out; self.alpha = alpha; self }
+
+    /// Expected score; `build` fails if the inputs produce another one.
+    pub fn score_pub(mut self, score: i64) -> Self { self.score_pub = Some(score); self }
+
+    pub fn activation(mut self, activation: Activation) -> Self { self.activation = activation; self }
+
+    /// Deployment tag, hashed as by `domain_from_tag`.
+    pub fn domain(mut self, tag: &str) -> Self { self.domain = tag.to_string(); self }
+
+    /// Unix seconds; default the current time and no expiry.
+    pub fn validity(mut self, now: u64, valid_until: u64) -> Self { self.now = Some(now); self.valid_until = Some(valid_until); self }
+
+    pub fn build<F: CircuitField>(&self) -> Result<TxCircuit<F>> {
+        let invalid = |msg: String| Err(QuantumGuardError::Witness(msg));
+        if self.x.is_empty() { return invalid("x está vacío".into()); }
+        if self.w.len() != self.x.len() {
+            return Err(QuantumGuardError::Shape(format!("{} features y {} pesos", self.x.len(), self.w.len())));
+        }
+        let signed = [("x", &self.x), ("w", &self.w)].into_iter()
+            .flat_map(|(name, v)| v.iter().enumerate().map(move |(i, v)| (format!("{name}[{i}]"), *v)))
+            .chain([("b".to_string(), self.b)]);
+        for (name, v) in signed {
+            if v.abs() > Q16_MAX { return invalid(format!("{name} = {v} fuera de Q16.16 con signo (|v| <= {Q16_MAX})")); }
+        }
+        for (name, v) in [("q_out", self.q_out), ("alpha", self.alpha)] {
+            if !(0..=ONE_Q16 as i64).contains(&v) { return invalid(format!("{name} = {v} fuera de [0, {ONE_Q16}]")); }
+        }
+        if let (Some(now), Some(until)) = (self.now, self.valid_until) {
+            if now > until { return invalid(format!("now = {now} posterior a valid_until = {until}")); }
+        }
+
+        let q16 = |v: i64| Fixed16::from_raw(v).to_field::<F>();
+        let mut circ = TxCircuit {
+            x: self.x.iter().copied().map(q16).collect(),
+            w: self.w.iter().copied().map(q16).collect(),
+            b: q16(self.b),
+            alpha: q16(self.alpha),
+            q_out: q16(self.q_out),
+            now: F::from(self.now.unwrap_or_else(|| std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0))),
+            valid_until: F::from(self.valid_until.unwrap_or(NEVER_EXPIRES)),
+            domain: domain_from_tag(&self.domain),
+            activation: self.activation,
+            ..Default::default()
+        };
+        let score = circ.score();
+        let score_q16 = fr_to_i128(score);
+        if !(0..=ONE_Q16 as i128).contains(&score_q16) {
+            return invalid(format!("el score resultante ({score_q16}) no está en [0, {ONE_Q16}]: el circuito no puede probarlo"));
+        }
+        if let Some(expected) = self.score_pub.filter(|s| q16(*s) != score) {
+            return invalid(format!("score_pub = {expected}, las entradas dan {score_q16}"));
+        }
+        circ.score_pub = score;
+        Ok(circ)
+    }
+}
+
+impl<F: CircuitField> TxCircuit<F> {
+    pub fn builder() -> TxCircuitBuilder { TxCircuitBuilder::new() }
+}
@@ -0,0 +1,273 @@
+// calibrate.rs
+use crate::range::{self, RangeConfig};
+use crate::{commit_in_domain, CircuitField, Tag, FRAC_BITS, ONE_Q16};
+use crate::{Hash, PoseidonChip, PoseidonConfig};
+use crate::circuit::{AssignedCell, Layouter, Value};
+use crate::FieldExt;
+use halo2_proofs::{
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Selector},
+    poly::Rotation,
+};
+use serde::{Deserialize, Serialize};
+
+// Platt-style calibration as a piecewise-linear map on [0, 1]: 16 buckets of width 2^-4 with
+// knots c_0..c_16 (Q16.16). raw = seg·2^12 + t, p = c[seg] + floor((c[seg+1] - c[seg])·t / 2^12).
+// The knots are a witness: they are laid out as advice rows (seg, c[seg], c[seg+1]) that the
+// calibration row looks up, and committed as Poseidon(Tag::Calibration, domain, c_0..c_16) so a
+// verifier can pin the exact map behind the public probability.
+pub const BUCKETS: usize = 16;
+const T_BITS: usize = FRAC_BITS as usize - 4;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Calibration {
+    // BUCKETS + 1 probabilities in Q16.16, at raw = 0, 1/16, ..., 1
+    pub knots: Vec<u64>,
+}
+
+impl Calibration {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.knots.len() != BUCKETS + 1 {
+            return Err(format!("la calibración necesita {} nodos, tiene {}", BUCKETS + 1, self.knots.len()));
+        }
+        match self.knots.iter().find(|k| **k > ONE_Q16) {
+            Some(k) => Err(format!("nodo de calibración {k} fuera de [0, {ONE_Q16}]")),
+            None => Ok(()),
+        }
+    }
+
+    /// Native evaluation with exactly the circuit's rounding. `raw` is a Q16.16 probability.
+    pub fn apply(&self, raw: u64) -> u64 {
+        assert!(raw <= ONE_Q16, "score fuera de [0, 1]");
+        let (seg, t) = ((raw >> T_BITS) as usize, (raw & ((1 << T_BITS) - 1)) as i64);
+        let (lo, hi) = (self.knots[seg] as i64, self.knots[(seg + 1).min(BUCKETS)] as i64);
+        (lo + ((hi - lo) * t).div_euclid(1 << T_BITS)) as u64
+    }
+
+    /// Public commitment to the knots, as computed in-circuit.
+    pub fn commit<F: CircuitField>(&self, domain: F) -> F {
+        let knots: Vec<F> = self.knots.iter().map(|k| F::from(*k)).collect();
+        commit_in_domain(Tag::Calibration, domain, &knots)
+    }
+}
+
+/// Rows used by `assign`: knot table, commitment, lookup row pair and the range checks.
+pub fn calibrate_rows(sponge_rows: usize) -> usize {
+    (BUCKETS + 1) + 1 + sponge_rows + 2
+        + 2 * range::check_rows(T_BITS)
+        + range::bounded_rows(FRAC_BITS as usize + 1)
+}
+
+// (probability, knot commitment)
+type Calibrated<F> = (AssignedCell<F, F>, AssignedCell<F, F>);
+
+#[derive(Clone, Debug)]
+pub struct CalibrateConfig {
+    adv: [Column<Advice>; 5],
+    q_table: Selector,
+    q_cal: Selector,
+    range: RangeConfig,
+}
+
+impl CalibrateConfig {
+    pub fn configure<F: FieldExt>(cs: &mut ConstraintSystem<F>, adv: [Column<Advice>; 5], range: RangeConfig) -> Self {
+        for c in adv { cs.enable_equality(c); }
+        let q_table = cs.complex_selector();
+        let q_cal = cs.complex_selector();
+        let [raw, seg, t, lo, hi] = adv;
+
+        // row 0: raw, seg, t, lo, hi    row 1: p, rem
+        cs.create_gate("calibration", |meta| {
+            let q = meta.query_selector(q_cal);
+            let raw = meta.query_advice(raw, Rotation::cur());
+            let seg = meta.query_advice(seg, Rotation::cur());
+            let t = meta.query_advice(t, Rotation::cur());
+            let lo = meta.query_advice(lo, Rotation::cur());
+            let hi = meta.query_advice(hi, Rotation::cur());
+            let p = meta.query_advice(adv[0], Rotation::next());
+            let rem = meta.query_advice(adv[1], Rotation::next());
+            let width = Expression::Constant(F::from(1u64 << T_BITS));
+            vec![
+                q.clone() * (raw - seg * width.clone() - t.clone()),
+                q * (p * width.clone() - lo.clone() * width - (hi - lo) * t + rem),
+            ]
+        });
+        // table rows: seg, c[seg], c[seg+1] in adv[0..3]. The selectors are the first column on both
+        // sides, so a calibration row (1, ...) can only match a knot row; unselected rows read all zeros.
+        cs.lookup_any("calibration knots", |meta| {
+            let q_cal = meta.query_selector(q_cal);
+            let q_table = meta.query_selector(q_table);
+            let input = [seg, lo, hi].map(|c| q_cal.clone() * meta.query_advice(c, Rotation::cur()));
+            let table = [adv[0], adv[1], adv[2]].map(|c| q_table.clone() * meta.query_advice(c, Rotation::cur()));
+            std::iter::once((q_cal, q_table)).chain(input.into_iter().zip(table)).collect()
+        });
+
+        Self { adv, q_table, q_cal, range }
+    }
+
+    /// Maps `raw` through the calibration; returns (probability, knot commitment).
+    pub fn assign<F: CircuitField>(
+        &self,
+        poseidon: &PoseidonConfig<F, 3, 2>,
+        mut layouter: impl Layouter<F>,
+        domain: &AssignedCell<F, F>,
+        raw: &AssignedCell<F, F>,
+        calibration: &Calibration,
+    ) -> Result<Calibrated<F>, Error> {
+        let knots = layouter.assign_region(
+            || "calibration table",
+            |mut region| {
+                let mut knots = Vec::with_capacity(BUCKETS + 1);
+                let mut prev_hi: Option<AssignedCell<F, F>> = None;
+                for i in 0..=BUCKETS {
+                    region.enable_selector(|| "q_table", &self.q_table, i)?;
+                    region.assign_advice_from_constant(|| "seg", self.adv[0], i, F::from(i as u64))?;
+                    let lo = region.assign_advice(|| format!("c_{i}"), self.adv[1], i, || Value::known(F::from(calibration.knots[i])))?;
+                    let hi = region.assign_advice(|| "next", self.adv[2], i, || Value::known(F::from(calibration.knots[(i + 1).min(BUCKETS)])))?;
+                    // consecutive rows share their knot; the last bucket is flat (raw = 1 exactly)
+                    if let Some(prev) = prev_hi { region.constrain_equal(prev.cell(), lo.cell())?; }
+                    if i == BUCKETS { region.constrain_equal(lo.cell(), hi.cell())?; }
+                    prev_hi = Some(hi);
+                    knots.push(lo);
+                }
+                Ok(knots)
+            },
+        )?;
+
+        let commitment = {
+            let tag = Tag::Calibration.assign(layouter.namespace(|| "tag_cal"), self.adv[0])?;
+            let mut hasher = Hash::<F, PoseidonChip<F>, 3, 2>::init(poseidon.clone(), layouter.namespace(|| "poseidon_cal"))?;
+            let mut inputs = vec![tag, domain.clone()];
+            inputs.extend(knots);
+            hasher.update(layouter.namespace(|| "absorb_cal"), inputs)?;
+            hasher.squeeze(layouter.namespace(|| "squeeze_cal"))?
+        };
+
+        let (t, rem, p) = layouter.assign_region(
+            || "calibration",
+            |mut region| {
+                region.enable_selector(|| "q_cal", &self.q_cal, 0)?;
+                raw.copy_advice(|| "raw", &mut region, self.adv[0], 0)?;
+                let raw_v = raw.value().map(|r| r.get_lower_128() as u64);
+                let seg_v = raw_v.map(|r| ((r >> T_BITS) as usize).min(BUCKETS));
+                let t_v = raw_v.map(|r| r & ((1 << T_BITS) - 1));
+                let lo_v = seg_v.map(|s| calibration.knots[s] as i64);
+                let hi_v = seg_v.map(|s| calibration.knots[(s + 1).min(BUCKETS)] as i64);
+                region.assign_advice(|| "seg", self.adv[1], 0, || seg_v.map(|s| F::from(s as u64)))?;
+                let t = region.assign_advice(|| "t", self.adv[2], 0, || t_v.map(F::from))?;
+                region.assign_advice(|| "lo", self.adv[3], 0, || lo_v.map(|v| F::from(v as u64)))?;
+                region.assign_advice(|| "hi", self.adv[4], 0, || hi_v.map(|v| F::from(v as u64)))?;
+                let prod = lo_v.zip(hi_v).zip(t_v).map(|((lo, hi), t)| (hi - lo) * t as i64);
+                let p = region.assign_advice(|| "p", self.adv[0], 1, || lo_v.zip(prod).map(|(lo, d)| F::from((lo + d.div_euclid(1 << T_BITS)) as u64)))?;
+                let rem = region.assign_advice(|| "rem", self.adv[1], 1, || prod.map(|d| F::from(d.rem_euclid(1 << T_BITS) as u64)))?;
+                Ok((t, rem, p))
+            },
+        )?;
+        self.range.decompose.range_check(layouter.namespace(|| "t bits"), &t, T_BITS)?;
+        self.range.decompose.range_check(layouter.namespace(|| "rem bits"), &rem, T_BITS)?;
+        self.range.assign_bounded(layouter.namespace(|| "calibrated in [0,1]"), &p, ONE_Q16, FRAC_BITS as usize + 1)?;
+        Ok((p, commitment))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit::{pin_degree, SimpleFloorPlanner, StackedLayouter};
+    use crate::{fr_to_i128, prover::tx_instances, TxCircuit, NEVER_EXPIRES};
+    use halo2_proofs::{circuit as h2, dev::MockProver, halo2curves::bn256::Fr, plonk::Circuit};
+
+    const ONE: i64 = ONE_Q16 as i64;
+
+    fn convex() -> Calibration { Calibration { knots: (0..=BUCKETS as u64).map(|i| i * i * 256).collect() } }
+
+    #[test]
+    fn apply_interpolates_between_knots() {
+        let cal = convex();
+        assert_eq!(cal.apply(0), 0);
+        assert_eq!(cal.apply(ONE_Q16), ONE_Q16);
+        assert_eq!(cal.apply(ONE_Q16 / 2), 8 * 8 * 256);
+        // halfway through bucket 1: (256 + 1024) / 2
+        assert_eq!(cal.apply(3 << (T_BITS - 1)), 640);
+    }
+
+    #[test]
+    fn calibrated_circuit_is_satisfied() {
+        let mut circ = TxCircuit::<Fr>::builder()
+            .features(vec![ONE / 2, ONE / 4]).weights(vec![ONE / 2, -ONE / 4], 0).quantum(ONE / 2, ONE / 2)
+            .domain("test").validity(0, NEVER_EXPIRES)
+            .build().unwrap();
+        let cal = convex();
+        circ.score_pub = Fr::from(cal.apply(fr_to_i128(circ.score_pub) as u64));
+        circ.calibration = Some(cal);
+        let k = circ.cost_estimate().min_k;
+        assert_eq!(MockProver::run(k, &circ, tx_instances(&circ)).unwrap().verify(), Ok(()));
+    }
+
+    // knot table plus one hand-written calibration row (raw, seg, t, lo, hi; p, rem)
+    #[derive(Clone, Default)]
+    struct KnotRow {
+        knots: Vec<u64>,
+        row: [u64; 5],
+        p: u64,
+        rem: u64,
+    }
+
+    impl Circuit<Fr> for KnotRow {
+        type Config = CalibrateConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+        type Params = ();
+
+        fn without_witnesses(&self) -> Self { self.clone() }
+
+        fn configure(cs: &mut ConstraintSystem<Fr>) -> CalibrateConfig {
+            let adv = [(); 5].map(|_| cs.advice_column());
+            let constants = cs.fixed_column();
+            cs.enable_constant(constants);
+            let range = RangeConfig::configure(cs, adv[3], adv[4], constants);
+            let cfg = CalibrateConfig::configure(cs, adv, range);
+            pin_degree(cs);
+            cfg
+        }
+
+        fn synthesize(&self, cfg: CalibrateConfig, layouter: impl h2::Layouter<Fr>) -> Result<(), Error> {
+            let mut layouter = StackedLayouter::new(layouter);
+            cfg.range.load(layouter.namespace(|| "range table"))?;
+            layouter.assign_region(
+                || "calibration table",
+                |mut region| {
+                    for i in 0..=BUCKETS {
+                        region.enable_selector(|| "q_table", &cfg.q_table, i)?;
+                        for (c, v) in [i as u64, self.knots[i], self.knots[(i + 1).min(BUCKETS)]].into_iter().enumerate() {
+                            region.assign_advice(|| "knot", cfg.adv[c], i, || Value::known(Fr::from(v)))?;
+                        }
+                    }
+                    Ok(())
+                },
+            )?;
+            layouter.assign_region(
+                || "calibration",
+                |mut region| {
+                    region.enable_selector(|| "q_cal", &cfg.q_cal, 0)?;
+                    for (c, v) in self.row.into_iter().enumerate() {
+                        region.assign_advice(|| "cal", cfg.adv[c], 0, || Value::known(Fr::from(v)))?;
+                    }
+                    region.assign_advice(|| "p", cfg.adv[0], 1, || Value::known(Fr::from(self.p)))?;
+                    region.assign_advice(|| "rem", cfg.adv[1], 1, || Value::known(Fr::from(self.rem)))?;
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    fn verify(circ: &KnotRow) -> bool { MockProver::run(10, circ, vec![]).unwrap().verify().is_ok() }
+
+    #[test]
+    fn calibration_row_must_match_a_knot_row() {
+        let knots = (0..=BUCKETS as u64).map(|i| i << T_BITS).collect::<Vec<_>>();
+        let honest = KnotRow { knots: knots.clone(), row: [1000, 0, 1000, 0, 1 << T_BITS], p: 1000, rem: 0 };
+        assert!(verify(&honest));
+        // (seg, lo, hi) = (0, 0, 0) is not a knot row, but it is what every unselected row reads
+        let zeros = KnotRow { knots, row: [1000, 0, 1000, 0, 0], p: 0, rem: 0 };
+        assert!(!verify(&zeros));
+    }
+}
@@ -0,0 +1,152 @@
+// ceremony.rs
+use halo2_proofs::{
+    arithmetic::best_multiexp,
+    halo2curves::bn256::{Bn256, Fr, G1Affine, G2Affine, G1},
+    halo2curves::ff::{Field, FromUniformBytes, PrimeField},
+    halo2curves::group::{prime::PrimeCurveAffine, Curve, GroupEncoding},
+    poly::{commitment::{Params, ParamsProver}, kzg::commitment::ParamsKZG},
+};
+use halo2_proofs::halo2curves::pairing::Engine;
+use rand::RngCore;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+// Sequential multi-party setup of the KZG SRS (powers of tau). `init` starts from tau = 1; each
+// contributor multiplies tau by a secret r (g_i <- r^i·g_i, s_g2 <- r·s_g2) and throws r away. The
+// final tau is the product of every r, so it stays unknown as long as one contributor was honest.
+// Each contribution records r·G1 with a Schnorr proof of knowledge of r (bound to the name and the
+// previous params) and the s_g2 it produced, so `verify` checks the whole chain from the final
+// params and the transcript alone:
+//   - r·G1 is known to its contributor (the proof) and s_g2_j = r_j·s_g2_{j-1} (pairing);
+//   - the final params are powers of the last s_g2 (random linear combination, one pairing).
+
+const DOMAIN: &[u8] = b"quantum-guard/ceremony/1";
+
+/// Transcript written next to the params as `<params>.ceremony.json`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Ceremony {
+    pub k: u32,
+    pub contributions: Vec<Contribution>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Contribution {
+    pub name: String,
+    // SHA-256 of the params contributed to and of the result
+    pub prev_sha256: String,
+    pub sha256: String,
+    // compressed points and scalars, hex
+    pub r_g1: String,
+    pub s_g2: String,
+    pub pok_r: String,
+    pub pok_s: String,
+}
+
+fn point<P: GroupEncoding>(hex_str: &str, what: &str) -> Result<P, String> {
+    let bytes = hex::decode(hex_str).map_err(|_| format!("{what}: hex inválido"))?;
+    let mut repr = P::Repr::default();
+    if repr.as_ref().len() != bytes.len() { return Err(format!("{what}: longitud inválida")); }
+    repr.as_mut().copy_from_slice(&bytes);
+    Option::from(P::from_bytes(&repr)).ok_or_else(|| format!("{what}: punto inválido"))
+}
+
+// Raw little-endian repr, like the points' encodings (not fe_to_hex's big-endian integer).
+fn scalar(hex_str: &str, what: &str) -> Result<Fr, String> {
+    let bytes = hex::decode(hex_str).map_err(|_| format!("{what}: hex inválido"))?;
+    let repr: [u8; 32] = bytes.try_into().map_err(|_| format!("{what}: longitud inválida"))?;
+    Option::from(Fr::from_repr(repr)).ok_or_else(|| format!("{what}: escalar no canónico"))
+}
+
+fn challenge(name: &str, prev_sha256: &str, r_g1: &G1Affine, nonce: &G1Affine) -> Fr {
+    let mut wide = [0u8; 64];
+    for (i, half) in wide.chunks_mut(32).enumerate() {
+        let mut h = Sha256::new();
+        h.update(DOMAIN);
+        h.update([i as u8]);
+        h.update(name.as_bytes());
+        h.update(prev_sha256.as_bytes());
+        h.update(r_g1.to_bytes());
+        h.update(nonce.to_bytes());
+        half.copy_from_slice(&h.finalize());
+    }
+    Fr::from_uniform_bytes(&wide)
+}
+
+fn params_sha256(params: &ParamsKZG<Bn256>) -> String {
+    let mut bytes = vec![];
+    params.write(&mut bytes).expect("escritura en memoria");
+    hex::encode(Sha256::digest(&bytes))
+}
+
+/// Params for 2^k points with tau = 1: every G1 point is the generator.
+pub fn init(k: u32) -> ParamsKZG<Bn256> {
+    let receiver = ParamsKZG::<Bn256>::setup(1, rand::thread_rng());
+    receiver.from_parts(k, vec![G1Affine::generator(); 1 << k], None, G2Affine::generator(), G2Affine::generator())
+}
+
+/// Multiplies tau by a fresh secret drawn from `rng` and returns the new params and their record.
+pub fn contribute(params: &ParamsKZG<Bn256>, name: &str, mut rng: impl RngCore) -> (ParamsKZG<Bn256>, Contribution) {
+    let r = Fr::random(&mut rng);
+    let mut powers = Vec::with_capacity(params.get_g().len());
+    let mut acc = Fr::ONE;
+    for _ in 0..params.get_g().len() {
+        powers.push(acc);
+        acc *= r;
+    }
+    let scaled: Vec<G1> = params.get_g().par_iter().zip(powers.par_iter()).map(|(g, p)| g * p).collect();
+    let mut g = vec![G1Affine::identity(); scaled.len()];
+    G1::batch_normalize(&scaled, &mut g);
+    let s_g2 = (params.s_g2() * r).to_affine();
+    let next = params.from_parts(params.k(), g, None, params.g2(), s_g2);
+
+    let prev_sha256 = params_sha256(params);
+    let r_g1 = (G1Affine::generator() * r).to_affine();
+    let k = Fr::random(&mut rng);
+    let nonce = (G1Affine::generator() * k).to_affine();
+    let s = k + challenge(name, &prev_sha256, &r_g1, &nonce) * r;
+    let record = Contribution {
+        name: name.to_string(),
+        prev_sha256,
+        sha256: params_sha256(&next),
+        r_g1: hex::encode(r_g1.to_bytes()),
+        s_g2: hex::encode(s_g2.to_bytes()),
+        pok_r: hex::encode(nonce.to_bytes()),
+        pok_s: hex::encode(s.to_repr()),
+    };
+    (next, record)
+}
+
+/// Checks the transcript and that `params` is its final result.
+pub fn verify(ceremony: &Ceremony, params: &ParamsKZG<Bn256>) -> Result<(), String> {
+    if params.k() != ceremony.k { return Err(format!("los params tienen k = {}, la ceremonia k = {}", params.k(), ceremony.k)); }
+    let last = ceremony.contributions.last().ok_or("la ceremonia no tiene contribuciones")?;
+    let (g1, g2) = (G1Affine::generator(), G2Affine::generator());
+    let mut prev_s_g2 = g2;
+    let mut prev_sha256: Option<&str> = None;
+    for (i, c) in ceremony.contributions.iter().enumerate() {
+        let at = |e: String| format!("contribución {} ({}): {e}", i + 1, c.name);
+        if prev_sha256.is_some_and(|p| p != c.prev_sha256) { return Err(at("no parte del resultado de la anterior".into())); }
+        let r_g1: G1Affine = point(&c.r_g1, "r_g1").map_err(at)?;
+        let s_g2: G2Affine = point(&c.s_g2, "s_g2").map_err(at)?;
+        let nonce: G1Affine = point(&c.pok_r, "pok_r").map_err(at)?;
+        let s = scalar(&c.pok_s, "pok_s").map_err(at)?;
+        if bool::from(r_g1.is_identity()) { return Err(at("r = 0".into())); }
+        let e = challenge(&c.name, &c.prev_sha256, &r_g1, &nonce);
+        if (g1 * s).to_affine() != (nonce + r_g1 * e).to_affine() { return Err(at("prueba de conocimiento inválida".into())); }
+        if Bn256::pairing(&r_g1, &prev_s_g2) != Bn256::pairing(&g1, &s_g2) { return Err(at("s_g2 no es r·s_g2 anterior".into())); }
+        prev_s_g2 = s_g2;
+        prev_sha256 = Some(&c.sha256);
+    }
+
+    if params_sha256(params) != last.sha256 { return Err("los params no son el resultado de la última contribución".into()); }
+    let g = params.get_g();
+    if g[0] != g1 || params.g2() != g2 { return Err("generadores inesperados".into()); }
+    if params.s_g2() != prev_s_g2 { return Err("s_g2 de los params no coincide con la ceremonia".into()); }
+    // sum rho_i·g_{i+1} = tau·sum rho_i·g_i
+    let rho: Vec<Fr> = (1..g.len()).map(|_| Fr::random(rand::rngs::OsRng)).collect();
+    let lhs = best_multiexp(&rho, &g[1..]).to_affine();
+    let rhs = best_multiexp(&rho, &g[..g.len() - 1]).to_affine();
+    if Bn256::pairing(&lhs, &g2) != Bn256::pairing(&rhs, &params.s_g2()) { return Err("los puntos G1 no son potencias de tau".into()); }
+    Ok(())
+}
@@ -0,0 +1,301 @@
+// circuit.rs
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit as h2,
+    plonk::{Advice, Assigned, Column, ConstraintSystem, Error, Fixed, Instance, Selector},
+};
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::marker::PhantomData;
+use std::rc::Rc;
+
+pub use halo2_proofs::circuit::{Cell, SimpleFloorPlanner, Table, Value};
+
+// PSE halo2's circuit API (annotated, fallible assignments into relative regions) on top of
+// halo2-axiom, whose layouter puts every region at row 0 and hands back unannotated cells.
+// Regions go one after another in synthesis order, which is what the row estimates in
+// lib.rs assume. Each `Circuit::synthesize` wraps the layouter it gets in `StackedLayouter`.
+
+/// Pins the constraint system's degree to that of its highest gate or lookup. halo2-axiom caps
+/// the degree it derives at $MAX_DEGREE (5 by default), which leaves the quotient of any higher
+/// gate on too small a domain; call last in `Circuit::configure`.
+pub fn pin_degree<F: Field>(cs: &mut ConstraintSystem<F>) {
+    let gates = cs.gates().iter().flat_map(|g| g.polynomials()).map(|p| p.degree()).max().unwrap_or(0);
+    // as lookup::Argument::required_degree: 2 + input degree + table degree
+    let lookups = cs.lookups().iter().map(|l| {
+        let input = l.input_expressions().iter().map(|e| e.degree()).max().unwrap_or(1).max(1);
+        let table = l.table_expressions().iter().map(|e| e.degree()).max().unwrap_or(1).max(1);
+        2 + input + table
+    }).max().unwrap_or(1);
+    // the permutation argument needs 3
+    cs.set_minimum_degree(gates.max(lookups).max(3));
+}
+
+/// Rows and columns a region took, as seen by `record_regions`.
+#[derive(Clone, Debug, Default)]
+pub struct RegionSpan {
+    pub name: String,
+    pub start: usize,
+    pub height: usize,
+    pub advice: BTreeSet<usize>,
+    pub fixed: BTreeSet<usize>,
+}
+
+thread_local! {
+    static RECORDING: RefCell<Option<Vec<RegionSpan>>> = const { RefCell::new(None) };
+}
+
+/// Runs `f` (a MockProver run, a keygen) and returns the regions synthesized meanwhile on this thread.
+pub fn record_regions<R>(f: impl FnOnce() -> R) -> (R, Vec<RegionSpan>) {
+    RECORDING.with(|r| *r.borrow_mut() = Some(vec![]));
+    let out = f();
+    (out, RECORDING.with(|r| r.borrow_mut().take().unwrap_or_default()))
+}
+
+/// An assigned advice or fixed cell with its value, as in PSE halo2.
+#[derive(Clone, Debug)]
+pub struct AssignedCell<V, F: Field> {
+    value: Value<V>,
+    cell: Cell,
+    _marker: PhantomData<F>,
+}
+
+impl<V, F: Field> AssignedCell<V, F> {
+    pub fn value(&self) -> Value<&V> { self.value.as_ref() }
+
+    pub fn cell(&self) -> Cell { self.cell }
+}
+
+impl<V: Clone, F: Field> AssignedCell<V, F>
+where
+    for<'v> Assigned<F>: From<&'v V>,
+{
+    /// Copies the value into `column` at `offset` and constrains both cells to be equal.
+    pub fn copy_advice<A, AR>(&self, annotation: A, region: &mut Region<'_, F>, column: Column<Advice>, offset: usize) -> Result<Self, Error>
+    where
+        A: Fn() -> AR,
+        AR: Into<String>,
+    {
+        let copy = region.assign_advice(annotation, column, offset, || self.value.clone())?;
+        region.constrain_equal(copy.cell(), self.cell)?;
+        Ok(copy)
+    }
+}
+
+/// A region placed at `start`; offsets are relative to it.
+pub struct Region<'r, F: Field> {
+    inner: h2::Region<'r, F>,
+    start: usize,
+    // rows (so the next region starts below this one) and columns used so far
+    span: Rc<RefCell<RegionSpan>>,
+}
+
+impl<F: Field> Region<'_, F> {
+    fn row(&mut self, offset: usize) -> usize {
+        let mut span = self.span.borrow_mut();
+        span.height = span.height.max(offset + 1);
+        self.start + offset
+    }
+
+    pub fn enable_selector<A, AR>(&mut self, _annotation: A, selector: &Selector, offset: usize) -> Result<(), Error>
+    where
+        A: Fn() -> AR,
+        AR: Into<String>,
+    {
+        let row = self.row(offset);
+        selector.enable(&mut self.inner, row)
+    }
+
+    pub fn assign_advice<V, VR, A, AR>(&mut self, _annotation: A, column: Column<Advice>, offset: usize, mut to: V) -> Result<AssignedCell<VR, F>, Error>
+    where
+        V: FnMut() -> Value<VR>,
+        for<'vr> Assigned<F>: From<&'vr VR>,
+        A: Fn() -> AR,
+        AR: Into<String>,
+    {
+        let row = self.row(offset);
+        self.span.borrow_mut().advice.insert(column.index());
+        let value = to();
+        let cell = self.inner.assign_advice(column, row, value.as_ref().map(Assigned::from)).cell();
+        Ok(AssignedCell { value, cell, _marker: PhantomData })
+    }
+
+    /// Assigns `constant` through the circuit's constants column (see `enable_constant`).
+    pub fn assign_advice_from_constant<VR, A, AR>(&mut self, annotation: A, column: Column<Advice>, offset: usize, constant: VR) -> Result<AssignedCell<VR, F>, Error>
+    where
+        VR: Clone,
+        for<'vr> Assigned<F>: From<&'vr VR>,
+        A: Fn() -> AR,
+        AR: Into<String>,
+    {
+        let row = self.row(offset);
+        self.span.borrow_mut().advice.insert(column.index());
+        let cell = self.inner.assign_advice_from_constant(annotation, column, row, constant.clone())?.cell();
+        Ok(AssignedCell { value: Value::known(constant), cell, _marker: PhantomData })
+    }
+
+    /// Fixed values must be known at keygen as well, so an unknown value is a synthesis error.
+    pub fn assign_fixed<V, VR, A, AR>(&mut self, _annotation: A, column: Column<Fixed>, offset: usize, mut to: V) -> Result<AssignedCell<VR, F>, Error>
+    where
+        V: FnMut() -> Value<VR>,
+        VR: Clone,
+        for<'vr> Assigned<F>: From<&'vr VR>,
+        A: Fn() -> AR,
+        AR: Into<String>,
+    {
+        let row = self.row(offset);
+        self.span.borrow_mut().fixed.insert(column.index());
+        let value = to();
+        let mut known = None;
+        value.as_ref().map(|v| known = Some(Assigned::from(v)));
+        let cell = self.inner.assign_fixed(column, row, known.ok_or(Error::Synthesis)?);
+        Ok(AssignedCell { value, cell, _marker: PhantomData })
+    }
+
+    pub fn constrain_constant<VR: Into<Assigned<F>>>(&mut self, cell: Cell, constant: VR) -> Result<(), Error> {
+        self.inner.constrain_constant(cell, constant)
+    }
+
+    pub fn constrain_equal(&mut self, left: Cell, right: Cell) -> Result<(), Error> {
+        self.inner.constrain_equal(left, right);
+        Ok(())
+    }
+}
+
+/// PSE halo2's `Layouter`, over `Region` above.
+pub trait Layouter<F: Field> {
+    type Root: Layouter<F>;
+
+    fn assign_region<A, AR, N, NR>(&mut self, name: N, assignment: A) -> Result<AR, Error>
+    where
+        A: FnMut(Region<'_, F>) -> Result<AR, Error>,
+        N: Fn() -> NR,
+        NR: Into<String>;
+
+    fn assign_table<A, N, NR>(&mut self, name: N, assignment: A) -> Result<(), Error>
+    where
+        A: FnMut(Table<'_, F>) -> Result<(), Error>,
+        N: Fn() -> NR,
+        NR: Into<String>;
+
+    fn constrain_instance(&mut self, cell: Cell, column: Column<Instance>, row: usize) -> Result<(), Error>;
+
+    fn get_root(&mut self) -> &mut Self::Root;
+
+    fn push_namespace<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR;
+
+    fn pop_namespace(&mut self, gadget_name: Option<String>);
+
+    fn namespace<NR, N>(&mut self, name_fn: N) -> NamespacedLayouter<'_, F, Self::Root>
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.get_root().push_namespace(name_fn);
+        NamespacedLayouter(self.get_root(), PhantomData)
+    }
+}
+
+/// Root layouter: every region starts on the first row below the previous one.
+pub struct StackedLayouter<F: Field, L: h2::Layouter<F>> {
+    inner: L,
+    next_row: usize,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field, L: h2::Layouter<F>> StackedLayouter<F, L> {
+    pub fn new(inner: L) -> Self { Self { inner, next_row: 0, _marker: PhantomData } }
+}
+
+impl<F: Field, L: h2::Layouter<F>> Layouter<F> for StackedLayouter<F, L> {
+    type Root = Self;
+
+    fn assign_region<A, AR, N, NR>(&mut self, name: N, mut assignment: A) -> Result<AR, Error>
+    where
+        A: FnMut(Region<'_, F>) -> Result<AR, Error>,
+        N: Fn() -> NR,
+        NR: Into<String>,
+    {
+        let start = self.next_row;
+        let span = Rc::new(RefCell::new(RegionSpan { name: name().into(), start, ..Default::default() }));
+        let result = self.inner.assign_region(name, |inner| assignment(Region { inner, start, span: span.clone() }))?;
+        let span = span.take();
+        self.next_row += span.height;
+        RECORDING.with(|r| if let Some(spans) = r.borrow_mut().as_mut() { spans.push(span) });
+        Ok(result)
+    }
+
+    fn assign_table<A, N, NR>(&mut self, name: N, assignment: A) -> Result<(), Error>
+    where
+        A: FnMut(Table<'_, F>) -> Result<(), Error>,
+        N: Fn() -> NR,
+        NR: Into<String>,
+    {
+        self.inner.assign_table(name, assignment)
+    }
+
+    fn constrain_instance(&mut self, cell: Cell, column: Column<Instance>, row: usize) -> Result<(), Error> {
+        self.inner.constrain_instance(cell, column, row);
+        Ok(())
+    }
+
+    fn get_root(&mut self) -> &mut Self { self }
+
+    fn push_namespace<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        h2::Layouter::push_namespace(self.inner.get_root(), name_fn)
+    }
+
+    fn pop_namespace(&mut self, gadget_name: Option<String>) { h2::Layouter::pop_namespace(self.inner.get_root(), gadget_name) }
+}
+
+/// A namespace on the root layouter, left when dropped.
+pub struct NamespacedLayouter<'a, F: Field, L: Layouter<F> + 'a>(&'a mut L, PhantomData<F>);
+
+impl<'a, F: Field, L: Layouter<F> + 'a> Layouter<F> for NamespacedLayouter<'a, F, L> {
+    type Root = L::Root;
+
+    fn assign_region<A, AR, N, NR>(&mut self, name: N, assignment: A) -> Result<AR, Error>
+    where
+        A: FnMut(Region<'_, F>) -> Result<AR, Error>,
+        N: Fn() -> NR,
+        NR: Into<String>,
+    {
+        self.0.assign_region(name, assignment)
+    }
+
+    fn assign_table<A, N, NR>(&mut self, name: N, assignment: A) -> Result<(), Error>
+    where
+        A: FnMut(Table<'_, F>) -> Result<(), Error>,
+        N: Fn() -> NR,
+        NR: Into<String>,
+    {
+        self.0.assign_table(name, assignment)
+    }
+
+    fn constrain_instance(&mut self, cell: Cell, column: Column<Instance>, row: usize) -> Result<(), Error> {
+        self.0.constrain_instance(cell, column, row)
+    }
+
+    fn get_root(&mut self) -> &mut Self::Root { self.0.get_root() }
+
+    fn push_namespace<NR, N>(&mut self, _name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        panic!("solo la raíz abre espacios de nombres")
+    }
+
+    fn pop_namespace(&mut self, _gadget_name: Option<String>) { panic!("solo la raíz cierra espacios de nombres") }
+}
+
+impl<'a, F: Field, L: Layouter<F> + 'a> Drop for NamespacedLayouter<'a, F, L> {
+    fn drop(&mut self) { self.get_root().pop_namespace(None) }
+}
@@ -0,0 +1,68 @@
+// codec.rs
+use crate::envelope::{circuit_hash, ProofEnvelope};
+
+// Archival encoding of a proof envelope. Points need no extra work: halo2's Blake2b transcript
+// already writes G1 points compressed (32 bytes), and an EVM (Keccak) transcript interleaves
+// uncompressed points and scalars in an order only the vk's protocol knows. What is left is the
+// envelope itself: the circuit hash is dropped when it is this build's, and the rest is zstd-coded.
+// Layout: magic, codec version u8, flags u8, zstd frame.
+pub const MAGIC: &[u8; 4] = b"QGPZ";
+const CODEC_VERSION: u8 = 1;
+// the circuit hash is circuit_hash() of the decoding build
+const ELIDED_CIRCUIT_HASH: u8 = 1;
+// in the current envelope layout: after magic, version, scheme, transcript and k
+const CIRCUIT_HASH_AT: usize = 4 + 2 + 1 + 1 + 4;
+
+pub fn compress(envelope: &ProofEnvelope, level: i32) -> Result<Vec<u8>, String> {
+    let elide = envelope.circuit_hash == circuit_hash();
+    let mut bytes = envelope.to_bytes();
+    if elide {
+        bytes.drain(CIRCUIT_HASH_AT..CIRCUIT_HASH_AT + 32);
+    }
+    let frame = zstd::bulk::compress(&bytes, level).map_err(|e| format!("compresión fallida: {e}"))?;
+    let mut out = Vec::with_capacity(6 + frame.len());
+    out.extend_from_slice(MAGIC);
+    out.push(CODEC_VERSION);
+    out.push(if elide { ELIDED_CIRCUIT_HASH } else { 0 });
+    out.extend_from_slice(&frame);
+    Ok(out)
+}
+
+pub fn decompress(bytes: &[u8]) -> Result<ProofEnvelope, String> {
+    if bytes.get(..4) != Some(&MAGIC[..]) {
+        return Err("el archivo no es una prueba comprimida".into());
+    }
+    match bytes.get(4) {
+        Some(&CODEC_VERSION) => {}
+        Some(v) => return Err(format!("versión de compresión {v} no soportada")),
+        None => return Err("prueba comprimida truncada".into()),
+    }
+    let flags = *bytes.get(5).ok_or("prueba comprimida truncada")?;
+    let mut envelope = zstd::stream::decode_all(&bytes[6..]).map_err(|e| format!("descompresión fallida: {e}"))?;
+    if flags & ELIDED_CIRCUIT_HASH != 0 {
+        envelope.splice(CIRCUIT_HASH_AT..CIRCUIT_HASH_AT, circuit_hash());
+    }
+    ProofEnvelope::from_bytes(&envelope)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::envelope::{Scheme, Transcript};
+
+    #[test]
+    fn round_trips_with_and_without_the_circuit_hash() {
+        let envelope = ProofEnvelope::new(Scheme::KzgGwc, Transcript::Blake2b, 12, [7; 32], vec![3; 512]);
+        let packed = compress(&envelope, 3).unwrap();
+        assert_eq!(packed[5], ELIDED_CIRCUIT_HASH);
+        assert_eq!(decompress(&packed).unwrap(), envelope);
+
+        // another build's proof keeps its hash
+        let foreign = ProofEnvelope { circuit_hash: [1; 32], ..envelope };
+        let packed = compress(&foreign, 3).unwrap();
+        assert_eq!(packed[5], 0);
+        assert_eq!(decompress(&packed).unwrap(), foreign);
+        assert!(decompress(&packed[..5]).is_err());
+        assert!(decompress(&foreign.to_bytes()).is_err());
+    }
+}
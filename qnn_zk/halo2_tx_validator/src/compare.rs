@@ -0,0 +1,107 @@
+// compare.rs
+use crate::decompose::{self, DecomposeConfig};
+use crate::circuit::{AssignedCell, Layouter, Value};
+use crate::FieldExt;
+use halo2_proofs::{
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, Selector},
+    poly::Rotation,
+};
+
+// For n-bit a, b: a - b + 2^n = ge * 2^n + lo with lo < 2^n, so the carry bit `ge` is 1 iff a >= b.
+// Using 2^n - 1 as the offset instead gives a > b.
+#[derive(Clone, Debug)]
+pub struct CompareConfig {
+    a: Column<Advice>,
+    b: Column<Advice>,
+    lo: Column<Advice>,
+    ge: Column<Advice>,
+    offset: Column<Fixed>,
+    two_n: Column<Fixed>,
+    q_cmp: Selector,
+    decompose: DecomposeConfig,
+}
+
+/// Rows used by one comparison of `bits`-wide operands (operand range checks included).
+pub fn compare_rows(bits: usize, word_bits: usize) -> usize { 1 + 3 * decompose::range_check_rows(bits, word_bits) }
+
+// Little-endian canonical encoding (BN256 and Pasta scalars).
+fn bit_at<F: FieldExt>(f: &F, n: usize) -> bool { (f.to_repr().as_ref()[n / 8] >> (n % 8)) & 1 == 1 }
+
+impl CompareConfig {
+    pub fn configure<F: FieldExt>(cs: &mut ConstraintSystem<F>, a: Column<Advice>, b: Column<Advice>, lo: Column<Advice>, ge: Column<Advice>, decompose: DecomposeConfig) -> Self {
+        for c in [a, b, lo, ge] { cs.enable_equality(c); }
+        let offset = cs.fixed_column();
+        let two_n = cs.fixed_column();
+        let q_cmp = cs.selector();
+
+        cs.create_gate("compare", |meta| {
+            let q = meta.query_selector(q_cmp);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let lo = meta.query_advice(lo, Rotation::cur());
+            let ge = meta.query_advice(ge, Rotation::cur());
+            let offset = meta.query_fixed(offset, Rotation::cur());
+            let two_n = meta.query_fixed(two_n, Rotation::cur());
+            let one = Expression::Constant(F::ONE);
+            vec![
+                q.clone() * ge.clone() * (one - ge.clone()),
+                q * (a - b + offset - ge * two_n - lo),
+            ]
+        });
+
+        Self { a, b, lo, ge, offset, two_n, q_cmp, decompose }
+    }
+
+    /// Returns a boolean cell equal to 1 iff `a >= b`. Both operands are range-checked to `bits`.
+    pub fn ge<F: FieldExt>(&self, layouter: impl Layouter<F>, a: &AssignedCell<F, F>, b: &AssignedCell<F, F>, bits: usize) -> Result<AssignedCell<F, F>, Error> {
+        self.carry(layouter, a, b, bits, false)
+    }
+
+    /// Returns a boolean cell equal to 1 iff `a < b`.
+    pub fn lt<F: FieldExt>(&self, layouter: impl Layouter<F>, a: &AssignedCell<F, F>, b: &AssignedCell<F, F>, bits: usize) -> Result<AssignedCell<F, F>, Error> {
+        self.carry(layouter, b, a, bits, true)
+    }
+
+    fn carry<F: FieldExt>(&self, mut layouter: impl Layouter<F>, a: &AssignedCell<F, F>, b: &AssignedCell<F, F>, bits: usize, strict: bool) -> Result<AssignedCell<F, F>, Error> {
+        // the carry must stay below the field modulus (BN256 / Pasta: > 2^253)
+        assert!(bits < 252, "operandos de comparación demasiado anchos");
+        self.decompose.range_check(layouter.namespace(|| "a bits"), a, bits)?;
+        self.decompose.range_check(layouter.namespace(|| "b bits"), b, bits)?;
+        let (lo, ge) = layouter.assign_region(
+            || "compare",
+            |mut region| {
+                let two_n = F::from(2).pow_vartime([bits as u64]);
+                let offset = if strict { two_n - F::ONE } else { two_n };
+                region.enable_selector(|| "q_cmp", &self.q_cmp, 0)?;
+                region.assign_fixed(|| "offset", self.offset, 0, || Value::known(offset))?;
+                region.assign_fixed(|| "2^n", self.two_n, 0, || Value::known(two_n))?;
+                a.copy_advice(|| "a", &mut region, self.a, 0)?;
+                b.copy_advice(|| "b", &mut region, self.b, 0)?;
+                let shifted = a.value().zip(b.value()).map(|(a, b)| *a - b + offset);
+                let carry = shifted.map(|d| if bit_at(&d, bits) { F::ONE } else { F::ZERO });
+                let lo = region.assign_advice(|| "lo", self.lo, 0, || shifted.zip(carry).map(|(d, c)| d - c * two_n))?;
+                let ge = region.assign_advice(|| "ge", self.ge, 0, || carry)?;
+                Ok((lo, ge))
+            },
+        )?;
+        self.decompose.range_check(layouter.namespace(|| "lo bits"), &lo, bits)?;
+        Ok(ge)
+    }
+
+    /// Constrains `a >= b`.
+    pub fn assert_ge<F: FieldExt>(&self, mut layouter: impl Layouter<F>, a: &AssignedCell<F, F>, b: &AssignedCell<F, F>, bits: usize) -> Result<(), Error> {
+        let ge = self.ge(layouter.namespace(|| "ge"), a, b, bits)?;
+        layouter.assign_region(|| "assert ge", |mut region| region.constrain_constant(ge.cell(), F::ONE))
+    }
+
+    /// Constrains `a <= b`.
+    pub fn assert_le<F: FieldExt>(&self, layouter: impl Layouter<F>, a: &AssignedCell<F, F>, b: &AssignedCell<F, F>, bits: usize) -> Result<(), Error> {
+        self.assert_ge(layouter, b, a, bits)
+    }
+
+    /// Constrains `a < b`.
+    pub fn assert_lt<F: FieldExt>(&self, mut layouter: impl Layouter<F>, a: &AssignedCell<F, F>, b: &AssignedCell<F, F>, bits: usize) -> Result<(), Error> {
+        let ge = self.ge(layouter.namespace(|| "ge"), a, b, bits)?;
+        layouter.assign_region(|| "assert lt", |mut region| region.constrain_constant(ge.cell(), F::ZERO))
+    }
+}
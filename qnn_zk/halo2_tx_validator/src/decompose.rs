@@ -0,0 +1,133 @@
+// decompose.rs
+use crate::circuit::{AssignedCell, Layouter, Value};
+use crate::FieldExt;
+use halo2_proofs::{
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, Selector, TableColumn},
+    poly::Rotation,
+};
+
+// Little-endian running sum: z_0 = value, z_{i+1} = (z_i - word_i) / 2^W, z_n = 0,
+// with every word looked up in a table of [0, 2^W).
+#[derive(Clone, Debug)]
+pub struct DecomposeConfig {
+    pub word_bits: usize,
+    z: Column<Advice>,
+    word: Column<Advice>,
+    shift: Column<Fixed>,
+    table: TableColumn,
+    q_running: Selector,
+    q_short: Selector,
+}
+
+/// Rows used by `range_check` for `bits` bits with `word_bits`-bit words.
+pub fn range_check_rows(bits: usize, word_bits: usize) -> usize {
+    let words = bits.div_ceil(word_bits);
+    words + 1 + usize::from(!bits.is_multiple_of(word_bits))
+}
+
+impl DecomposeConfig {
+    /// `word_bits` sets the table size (2^word_bits rows); 1 gives plain bit decomposition.
+    pub fn configure<F: FieldExt>(cs: &mut ConstraintSystem<F>, z: Column<Advice>, word: Column<Advice>, constants: Column<Fixed>, word_bits: usize) -> Self {
+        assert!(word_bits > 0 && word_bits < 32, "word_bits fuera de rango");
+        cs.enable_equality(z);
+        cs.enable_equality(word);
+        cs.enable_constant(constants);
+        let shift = cs.fixed_column();
+        let table = cs.lookup_table_column();
+        let q_running = cs.complex_selector();
+        let q_short = cs.complex_selector();
+
+        cs.create_gate("decompose running sum", |meta| {
+            let q = meta.query_selector(q_running);
+            let z_cur = meta.query_advice(z, Rotation::cur());
+            let z_next = meta.query_advice(z, Rotation::next());
+            let w = meta.query_advice(word, Rotation::cur());
+            let radix = Expression::Constant(F::from(1u64 << word_bits));
+            vec![q * (z_cur - z_next * radix - w)]
+        });
+        // A word shorter than W bits is range-checked by looking up word * 2^(W - bits).
+        cs.create_gate("decompose short word", |meta| {
+            let q = meta.query_selector(q_short);
+            let w = meta.query_advice(word, Rotation::cur());
+            let shifted = meta.query_advice(z, Rotation::cur());
+            let factor = meta.query_fixed(shift, Rotation::cur());
+            vec![q * (shifted - w * factor)]
+        });
+        cs.lookup("decompose word in table", |meta| {
+            let q_running = meta.query_selector(q_running);
+            let q_short = meta.query_selector(q_short);
+            let w = meta.query_advice(word, Rotation::cur());
+            let shifted = meta.query_advice(z, Rotation::cur());
+            vec![(q_running * w + q_short * shifted, table)]
+        });
+
+        Self { word_bits, z, word, shift, table, q_running, q_short }
+    }
+
+    /// Must be called once per synthesis.
+    pub fn load_table<F: FieldExt>(&self, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "decompose table",
+            |mut table| {
+                for v in 0..(1u64 << self.word_bits) {
+                    table.assign_cell(|| "word", self.table, v as usize, || Value::known(F::from(v)))?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// Splits `value` into `words` little-endian words. The running sum must end at zero,
+    /// so this also proves `value < 2^(words * word_bits)`.
+    pub fn decompose<F: FieldExt>(&self, mut layouter: impl Layouter<F>, value: &AssignedCell<F, F>, words: usize) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        layouter.assign_region(
+            || "decompose",
+            |mut region| {
+                let mask = (1u128 << self.word_bits) - 1;
+                let radix_inv = crate::score::inv_pow2::<F>(self.word_bits as u32);
+                let mut z = value.copy_advice(|| "z_0", &mut region, self.z, 0)?;
+                let mut out = Vec::with_capacity(words);
+                for i in 0..words {
+                    region.enable_selector(|| "q_running", &self.q_running, i)?;
+                    let w = z.value().map(|z| F::from_u128(z.get_lower_128() & mask));
+                    out.push(region.assign_advice(|| format!("word_{i}"), self.word, i, || w)?);
+                    let next = z.value().zip(w).map(|(z, w)| (*z - w) * radix_inv);
+                    z = region.assign_advice(|| format!("z_{}", i + 1), self.z, i + 1, || next)?;
+                }
+                region.constrain_constant(z.cell(), F::ZERO)?;
+                Ok(out)
+            },
+        )
+    }
+
+    /// Constrains `value < 2^bits`.
+    pub fn range_check<F: FieldExt>(&self, mut layouter: impl Layouter<F>, value: &AssignedCell<F, F>, bits: usize) -> Result<(), Error> {
+        let words = bits.div_ceil(self.word_bits);
+        let out = self.decompose(layouter.namespace(|| "words"), value, words)?;
+        let top_bits = bits - words.saturating_sub(1) * self.word_bits;
+        match out.last() {
+            Some(top) if top_bits < self.word_bits => self.short_check(layouter.namespace(|| "top word"), top, top_bits),
+            _ => Ok(()),
+        }
+    }
+
+    /// Decomposes into individual bits (little-endian). Needs a 1-bit word configuration.
+    pub fn to_bits<F: FieldExt>(&self, layouter: impl Layouter<F>, value: &AssignedCell<F, F>, bits: usize) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        assert_eq!(self.word_bits, 1, "to_bits requiere word_bits = 1");
+        self.decompose(layouter, value, bits)
+    }
+
+    fn short_check<F: FieldExt>(&self, mut layouter: impl Layouter<F>, word: &AssignedCell<F, F>, bits: usize) -> Result<(), Error> {
+        layouter.assign_region(
+            || "short word",
+            |mut region| {
+                let factor = F::from(1u64 << (self.word_bits - bits));
+                region.enable_selector(|| "q_short", &self.q_short, 0)?;
+                region.assign_fixed(|| "shift", self.shift, 0, || Value::known(factor))?;
+                word.copy_advice(|| "word", &mut region, self.word, 0)?;
+                region.assign_advice(|| "shifted", self.z, 0, || word.value().map(|w| *w * factor))?;
+                Ok(())
+            },
+        )
+    }
+}
@@ -0,0 +1,183 @@
+// div.rs
+use crate::compare::CompareConfig;
+use crate::decompose::DecomposeConfig;
+use crate::circuit::{AssignedCell, Layouter};
+use crate::FieldExt;
+use halo2_proofs::{
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Selector},
+    poly::Rotation,
+};
+
+// Fixed-point quotient q = floor(a * 2^FRAC / b), enforced as a * 2^FRAC = q * b + r with 0 <= r < b.
+// Operands are non-negative Q(FRAC) values; r < b also rules out b = 0. EnsembleCircuit proves
+// its mean with it, and TxCircuit its ratio features (x[feature] = x[num] / x[den]).
+#[derive(Clone, Debug)]
+pub struct DivConfig {
+    pub frac_bits: u32,
+    a: Column<Advice>,
+    b: Column<Advice>,
+    q: Column<Advice>,
+    r: Column<Advice>,
+    q_div: Selector,
+    compare: CompareConfig,
+    decompose: DecomposeConfig,
+}
+
+impl DivConfig {
+    pub fn configure<F: FieldExt>(
+        cs: &mut ConstraintSystem<F>,
+        cols: [Column<Advice>; 4],
+        frac_bits: u32,
+        compare: CompareConfig,
+        decompose: DecomposeConfig,
+    ) -> Self {
+        let [a, b, q, r] = cols;
+        for c in cols { cs.enable_equality(c); }
+        let q_div = cs.selector();
+
+        cs.create_gate("fixed-point division", |meta| {
+            let s = meta.query_selector(q_div);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let q = meta.query_advice(q, Rotation::cur());
+            let r = meta.query_advice(r, Rotation::cur());
+            let scale = Expression::Constant(F::from(1u64 << frac_bits));
+            vec![s * (a * scale - q * b - r)]
+        });
+
+        Self { frac_bits, a, b, q, r, q_div, compare, decompose }
+    }
+
+    /// Returns the Q(FRAC) quotient a / b. `a` and `b` must fit in `bits` bits; the quotient is
+    /// range-checked to `bits + frac_bits`.
+    pub fn div<F: FieldExt>(&self, mut layouter: impl Layouter<F>, a: &AssignedCell<F, F>, b: &AssignedCell<F, F>, bits: usize) -> Result<AssignedCell<F, F>, Error> {
+        assert!(bits + (self.frac_bits as usize) < 128, "operandos de división demasiado anchos");
+        let (q, r) = layouter.assign_region(
+            || "div",
+            |mut region| {
+                region.enable_selector(|| "q_div", &self.q_div, 0)?;
+                a.copy_advice(|| "a", &mut region, self.a, 0)?;
+                b.copy_advice(|| "b", &mut region, self.b, 0)?;
+                let qr = a.value().zip(b.value()).map(|(a, b)| {
+                    let num = a.get_lower_128() << self.frac_bits;
+                    match b.get_lower_128() {
+                        0 => (0, num), // unsatisfiable: r < b fails
+                        den => (num / den, num % den),
+                    }
+                });
+                let q = region.assign_advice(|| "q", self.q, 0, || qr.map(|(q, _)| F::from_u128(q)))?;
+                let r = region.assign_advice(|| "r", self.r, 0, || qr.map(|(_, r)| F::from_u128(r)))?;
+                Ok((q, r))
+            },
+        )?;
+        // r < b also range-checks both to `bits`; the quotient gets its own check so q*b cannot wrap.
+        self.compare.assert_lt(layouter.namespace(|| "r < b"), &r, b, bits)?;
+        self.decompose.range_check(layouter.namespace(|| "a bits"), a, bits)?;
+        self.decompose.range_check(layouter.namespace(|| "q bits"), &q, bits + self.frac_bits as usize)?;
+        Ok(q)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit::{pin_degree, SimpleFloorPlanner, StackedLayouter, Value};
+    use crate::range::RangeConfig;
+    use crate::FRAC_BITS;
+    use halo2_proofs::{circuit as h2, dev::MockProver, halo2curves::{bn256::Fr, ff::PrimeField}, plonk::{Circuit, Instance}};
+
+    const BITS: usize = 24;
+    const ONE: u128 = 1 << FRAC_BITS;
+
+    // a / b published at instance row 0; `forged` replaces the chip's (q, r) with a hand-written row
+    #[derive(Clone, Default)]
+    struct Quotient {
+        a: u128,
+        b: u128,
+        forged: Option<(u128, u128)>,
+    }
+
+    impl Circuit<Fr> for Quotient {
+        type Config = (DivConfig, RangeConfig, [Column<Advice>; 6], Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+        type Params = ();
+
+        fn without_witnesses(&self) -> Self { self.clone() }
+
+        fn configure(cs: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let adv = [(); 6].map(|_| cs.advice_column());
+            for a in adv { cs.enable_equality(a); }
+            let instance = cs.instance_column();
+            cs.enable_equality(instance);
+            let constants = cs.fixed_column();
+            let range = RangeConfig::configure(cs, adv[3], adv[4], constants);
+            let compare = CompareConfig::configure(cs, adv[0], adv[1], adv[2], adv[5], range.decompose.clone());
+            let div = DivConfig::configure(cs, [adv[0], adv[1], adv[2], adv[5]], FRAC_BITS, compare, range.decompose.clone());
+            pin_degree(cs);
+            (div, range, adv, instance)
+        }
+
+        fn synthesize(&self, (div, range, adv, instance): Self::Config, layouter: impl h2::Layouter<Fr>) -> Result<(), Error> {
+            let mut layouter = StackedLayouter::new(layouter);
+            range.load(layouter.namespace(|| "range table"))?;
+            let (a, b) = layouter.assign_region(
+                || "operands",
+                |mut region| Ok((
+                    region.assign_advice(|| "a", adv[0], 0, || Value::known(Fr::from_u128(self.a)))?,
+                    region.assign_advice(|| "b", adv[1], 0, || Value::known(Fr::from_u128(self.b)))?,
+                )),
+            )?;
+            let q = match self.forged {
+                None => div.div(layouter.namespace(|| "a / b"), &a, &b, BITS)?,
+                // the same gate and checks as `div`, on chosen (q, r)
+                Some((q, r)) => {
+                    let (q, r) = layouter.assign_region(
+                        || "forged div",
+                        |mut region| {
+                            region.enable_selector(|| "q_div", &div.q_div, 0)?;
+                            a.copy_advice(|| "a", &mut region, div.a, 0)?;
+                            b.copy_advice(|| "b", &mut region, div.b, 0)?;
+                            Ok((
+                                region.assign_advice(|| "q", div.q, 0, || Value::known(Fr::from_u128(q)))?,
+                                region.assign_advice(|| "r", div.r, 0, || Value::known(Fr::from_u128(r)))?,
+                            ))
+                        },
+                    )?;
+                    div.compare.assert_lt(layouter.namespace(|| "r < b"), &r, &b, BITS)?;
+                    div.decompose.range_check(layouter.namespace(|| "a bits"), &a, BITS)?;
+                    div.decompose.range_check(layouter.namespace(|| "q bits"), &q, BITS + FRAC_BITS as usize)?;
+                    q
+                }
+            };
+            layouter.constrain_instance(q.cell(), instance, 0)
+        }
+    }
+
+    fn check(circ: Quotient, q: u128) -> bool {
+        MockProver::run(11, &circ, vec![vec![Fr::from_u128(q)]]).unwrap().verify().is_ok()
+    }
+
+    #[test]
+    fn quotient_is_floored() {
+        assert!(check(Quotient { a: 3 * ONE, b: 2 * ONE, forged: None }, 3 * ONE / 2));
+        // 1/3 = 21845.33.. in Q16
+        assert!(check(Quotient { a: ONE, b: 3 * ONE, forged: None }, 21845));
+        assert!(!check(Quotient { a: ONE, b: 3 * ONE, forged: None }, 21846));
+    }
+
+    #[test]
+    fn remainder_must_be_below_divisor() {
+        let (a, b) = (ONE, 3 * ONE);
+        let (q, r) = (a * ONE / b, a * ONE % b);
+        assert!(check(Quotient { a, b, forged: Some((q, r)) }, q));
+        // q - 1 with r + b satisfies the product gate
+        assert!(!check(Quotient { a, b, forged: Some((q - 1, r + b)) }, q - 1));
+    }
+
+    #[test]
+    fn zero_divisor_is_rejected() {
+        assert!(!check(Quotient { a: ONE, b: 0, forged: None }, 0));
+        // a·2^16 = q·0 + r holds with r = a·2^16, for any q
+        assert!(!check(Quotient { a: ONE, b: 0, forged: Some((7, ONE * ONE)) }, 7));
+    }
+}
@@ -0,0 +1,489 @@
+// eddsa.rs
+use crate::{poseidon_commit, CircuitField, Tag};
+use crate::{Hash, PoseidonChip, PoseidonConfig};
+use crate::circuit::{AssignedCell, Layouter, Value};
+use crate::FieldExt;
+use halo2_proofs::{
+    halo2curves::{bn256::Fr, group::ff::PrimeField},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Selector},
+    poly::Rotation,
+};
+
+// EdDSA over a twisted Edwards curve embedded in the circuit field (Baby Jubjub over BN254):
+// the quantum oracle signs (q_out, epoch) and the circuit checks S·B = R + 8·h·A with
+// h = Poseidon(Tag::Oracle, R, A, q_out, epoch). The sponge differs from circomlib's, so
+// signatures must come from a signer using `message_hash`.
+// S is not reduced mod the subgroup order: (R, S + l) also verifies, which only matters if
+// signatures themselves are used as identifiers.
+
+// Scalars: S is below the subgroup order l < 2^251; h is a full field element.
+pub const S_BITS: usize = 251;
+pub const H_BITS: usize = 254;
+
+#[derive(Clone, Copy, Debug)]
+pub struct EdwardsCurve<F> {
+    pub a: F,
+    pub d: F,
+    // generator of the prime-order subgroup
+    pub base: (F, F),
+}
+
+/// Baby Jubjub (EIP-2494) with its prime-order generator Base8.
+pub fn baby_jubjub() -> EdwardsCurve<Fr> {
+    let n = |s: &str| Fr::from_str_vartime(s).unwrap();
+    EdwardsCurve {
+        a: Fr::from(168700),
+        d: Fr::from(168696),
+        base: (
+            n("5299619240641551281634865583518297030282874472190772894086521144482721001553"),
+            n("16950150798460657717958625567821834550301663161624707787222815936182638968203"),
+        ),
+    }
+}
+
+/// Oracle signature over (q_out, epoch), plus the public key it verifies under.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct OracleSignature<F> {
+    pub key: (F, F),
+    pub r: (F, F),
+    pub s: F,
+    pub epoch: F,
+}
+
+impl<F: CircuitField> EdwardsCurve<F> {
+    pub fn is_on_curve(&self, (x, y): (F, F)) -> bool {
+        let (x2, y2) = (x * x, y * y);
+        self.a * x2 + y2 == F::ONE + self.d * x2 * y2
+    }
+
+    // t = d·x1·x2·y1·y2, witnessed next to every in-circuit addition
+    fn cross(&self, (x1, y1): (F, F), (x2, y2): (F, F)) -> F { self.d * x1 * x2 * y1 * y2 }
+
+    /// Complete on curve points (a is a square and d is not); None when a denominator vanishes,
+    /// which takes an off-curve input.
+    pub fn add(&self, p: (F, F), q: (F, F)) -> Option<(F, F)> {
+        let ((x1, y1), (x2, y2), t) = (p, q, self.cross(p, q));
+        let x3 = (x1 * y2 + y1 * x2) * Option::<F>::from((F::ONE + t).invert())?;
+        let y3 = (y1 * y2 - self.a * x1 * x2) * Option::<F>::from((F::ONE - t).invert())?;
+        Some((x3, y3))
+    }
+
+    /// Double-and-add over the low `bits` bits of `k`, most significant first (as in-circuit).
+    pub fn mul(&self, p: (F, F), k: F, bits: usize) -> Option<(F, F)> {
+        scalar_bits(k, bits).into_iter().try_fold((F::ZERO, F::ONE), |acc, bit| {
+            let acc = self.add(acc, acc)?;
+            if bit { self.add(acc, p) } else { Some(acc) }
+        })
+    }
+
+    pub fn verify(&self, sig: &OracleSignature<F>, q_out: F) -> bool {
+        if !self.is_on_curve(sig.key) || !self.is_on_curve(sig.r) || scalar_bits(sig.s, 256)[..256 - S_BITS].iter().any(|b| *b) {
+            return false;
+        }
+        let h = message_hash(sig, q_out);
+        let check = || {
+            let mut ha = self.mul(sig.key, h, H_BITS)?;
+            for _ in 0..3 { ha = self.add(ha, ha)?; }
+            Some(self.mul(self.base, sig.s, S_BITS)? == self.add(sig.r, ha)?)
+        };
+        check().unwrap_or(false)
+    }
+}
+
+/// The challenge hash the oracle signs: Poseidon(Tag::Oracle, Rx, Ry, Ax, Ay, q_out, epoch).
+pub fn message_hash<F: CircuitField>(sig: &OracleSignature<F>, q_out: F) -> F {
+    poseidon_commit(&[Tag::Oracle.value(), sig.r.0, sig.r.1, sig.key.0, sig.key.1, q_out, sig.epoch])
+}
+
+// Most significant bit first.
+fn scalar_bits<F: FieldExt>(k: F, bits: usize) -> Vec<bool> {
+    let repr = k.to_repr();
+    (0..bits).rev().map(|i| (repr.as_ref()[i / 8] >> (i % 8)) & 1 == 1).collect()
+}
+
+/// Rows used by `verify`, given the rows of the 7-input challenge sponge: witnesses, tag, base,
+/// two scalar multiplications (bits + 3 rows per bit), the cofactor and the final sum (t row + sum).
+pub fn verify_rows(sponge_rows: usize) -> usize {
+    2 + 1 + sponge_rows + 1
+        + [S_BITS, H_BITS].iter().map(|bits| (bits + 1) + (1 + 3 * bits)).sum::<usize>()
+        + 3 + 2
+}
+
+// Sum of witnessed points; a vanishing denominator (off-curve input) fails synthesis.
+fn add_values<F: CircuitField>(curve: &EdwardsCurve<F>, p: Value<(F, F)>, q: Value<(F, F)>) -> Result<Value<(F, F)>, Error> {
+    let s = p.zip(q).map(|(p, q)| curve.add(p, q));
+    s.error_if_known_and(Option::is_none)?;
+    Ok(s.map(|s| s.unwrap_or((F::ZERO, F::ONE))))
+}
+
+type Point<F> = (AssignedCell<F, F>, AssignedCell<F, F>);
+
+fn point_value<F: CircuitField>(p: &Point<F>) -> Value<(F, F)> { p.0.value().zip(p.1.value()).map(|(x, y)| (*x, *y)) }
+
+#[derive(Clone, Debug)]
+pub struct EddsaConfig {
+    adv: [Column<Advice>; 6],
+    q_add: Selector,
+    q_double: Selector,
+    q_select: Selector,
+    q_bits: Selector,
+    q_curve: Selector,
+}
+
+impl EddsaConfig {
+    /// Gates are only created when the field has an embedded curve; `verify` fails otherwise.
+    pub fn configure<F: CircuitField>(cs: &mut ConstraintSystem<F>, adv: [Column<Advice>; 6]) -> Self {
+        for c in adv { cs.enable_equality(c); }
+        let config = Self {
+            adv,
+            q_add: cs.selector(),
+            q_double: cs.selector(),
+            q_select: cs.selector(),
+            q_bits: cs.selector(),
+            q_curve: cs.selector(),
+        };
+        let Some(curve) = F::edwards() else { return config };
+        let one = || Expression::Constant(F::ONE);
+        // t is witnessed so the sum constraints stay at degree 3 and the gate at 5
+        let add = move |[x1, y1, x2, y2, x3, y3]: [Expression<F>; 6], t: Expression<F>| {
+            vec![
+                t.clone() - Expression::Constant(curve.d) * x1.clone() * x2.clone() * y1.clone() * y2.clone(),
+                x3 * (one() + t.clone()) - (x1.clone() * y2.clone() + y1.clone() * x2.clone()),
+                y3 * (one() - t) - (y1 * y2 - Expression::Constant(curve.a) * x1 * x2),
+            ]
+        };
+
+        // row: x1, y1, x2, y2, x3, y3 with (x3, y3) = (x1, y1) + (x2, y2); t in adv[5] of the row above
+        cs.create_gate("edwards add", |meta| {
+            let q = meta.query_selector(config.q_add);
+            let t = meta.query_advice(adv[5], Rotation::prev());
+            add(adv.map(|c| meta.query_advice(c, Rotation::cur())), t).into_iter().map(|e| q.clone() * e).collect::<Vec<_>>()
+        });
+        // row: x1, y1, t, -, x3, y3 with (x3, y3) = 2·(x1, y1)
+        cs.create_gate("edwards double", |meta| {
+            let q = meta.query_selector(config.q_double);
+            let [x1, y1, t, _, x3, y3] = adv.map(|c| meta.query_advice(c, Rotation::cur()));
+            add([x1.clone(), y1.clone(), x1, y1, x3, y3], t).into_iter().map(|e| q.clone() * e).collect::<Vec<_>>()
+        });
+        // row: bit, px, py, sx, sy with (sx, sy) = bit ? (px, py) : identity
+        cs.create_gate("edwards select", |meta| {
+            let q = meta.query_selector(config.q_select);
+            let [bit, px, py, sx, sy, _] = adv.map(|c| meta.query_advice(c, Rotation::cur()));
+            vec![
+                q.clone() * (sx - bit.clone() * px),
+                q * (sy - (one() - bit.clone() + bit * py)),
+            ]
+        });
+        // rows: k_i, b_i with k_{i+1} = 2·k_i + b_i and b_i boolean (most significant bit first)
+        cs.create_gate("scalar bits", |meta| {
+            let q = meta.query_selector(config.q_bits);
+            let k = meta.query_advice(adv[0], Rotation::cur());
+            let b = meta.query_advice(adv[1], Rotation::cur());
+            let k_next = meta.query_advice(adv[0], Rotation::next());
+            vec![
+                q.clone() * b.clone() * (one() - b.clone()),
+                q * (k_next - k * Expression::Constant(F::from(2)) - b),
+            ]
+        });
+        cs.create_gate("edwards on curve", |meta| {
+            let q = meta.query_selector(config.q_curve);
+            let x = meta.query_advice(adv[0], Rotation::cur());
+            let y = meta.query_advice(adv[1], Rotation::cur());
+            let (x2, y2) = (x.clone() * x, y.clone() * y);
+            vec![q * (Expression::Constant(curve.a) * x2.clone() + y2.clone() - one() - Expression::Constant(curve.d) * x2 * y2)]
+        });
+        config
+    }
+
+    /// Checks `sig` over (q_out, epoch); returns the key and epoch cells for the host to publish.
+    pub fn verify<F: CircuitField>(
+        &self,
+        poseidon: &PoseidonConfig<F, 3, 2>,
+        mut layouter: impl Layouter<F>,
+        q_out: &AssignedCell<F, F>,
+        sig: &OracleSignature<F>,
+    ) -> Result<(Point<F>, AssignedCell<F, F>), Error> {
+        let curve = F::edwards().ok_or(Error::Synthesis)?;
+        let (key, r, s, epoch) = layouter.assign_region(
+            || "oracle signature",
+            |mut region| {
+                let mut point = |row: usize, (x, y): (F, F)| -> Result<Point<F>, Error> {
+                    region.enable_selector(|| "q_curve", &self.q_curve, row)?;
+                    Ok((
+                        region.assign_advice(|| "x", self.adv[0], row, || Value::known(x))?,
+                        region.assign_advice(|| "y", self.adv[1], row, || Value::known(y))?,
+                    ))
+                };
+                let key = point(0, sig.key)?;
+                let r = point(1, sig.r)?;
+                let s = region.assign_advice(|| "s", self.adv[2], 0, || Value::known(sig.s))?;
+                let epoch = region.assign_advice(|| "epoch", self.adv[3], 0, || Value::known(sig.epoch))?;
+                Ok((key, r, s, epoch))
+            },
+        )?;
+
+        let h = {
+            let tag = Tag::Oracle.assign(layouter.namespace(|| "tag_oracle"), self.adv[0])?;
+            let mut hasher = Hash::<F, PoseidonChip<F>, 3, 2>::init(poseidon.clone(), layouter.namespace(|| "poseidon_oracle"))?;
+            let inputs = vec![tag, r.0.clone(), r.1.clone(), key.0.clone(), key.1.clone(), q_out.clone(), epoch.clone()];
+            hasher.update(layouter.namespace(|| "absorb_oracle"), inputs)?;
+            hasher.squeeze(layouter.namespace(|| "squeeze_oracle"))?
+        };
+
+        let base = layouter.assign_region(
+            || "edwards base",
+            |mut region| Ok((
+                region.assign_advice_from_constant(|| "bx", self.adv[0], 0, curve.base.0)?,
+                region.assign_advice_from_constant(|| "by", self.adv[1], 0, curve.base.1)?,
+            )),
+        )?;
+        let lhs = self.mul(layouter.namespace(|| "S·B"), &curve, &base, &s, S_BITS)?;
+        let mut ha = self.mul(layouter.namespace(|| "h·A"), &curve, &key, &h, H_BITS)?;
+        for i in 0..3 {
+            ha = self.double(layouter.namespace(|| format!("cofactor {i}")), &curve, &ha)?;
+        }
+        let rhs = self.add(layouter.namespace(|| "R + 8·h·A"), &curve, &r, &ha)?;
+        layouter.assign_region(
+            || "S·B == R + 8·h·A",
+            |mut region| {
+                region.constrain_equal(lhs.0.cell(), rhs.0.cell())?;
+                region.constrain_equal(lhs.1.cell(), rhs.1.cell())
+            },
+        )?;
+        Ok((key, epoch))
+    }
+
+    // row 0: t in adv[5]; row 1: the sum
+    fn add<F: CircuitField>(&self, mut layouter: impl Layouter<F>, curve: &EdwardsCurve<F>, p: &Point<F>, q: &Point<F>) -> Result<Point<F>, Error> {
+        layouter.assign_region(
+            || "edwards add",
+            |mut region| {
+                let (pv, qv) = (point_value(p), point_value(q));
+                region.assign_advice(|| "t", self.adv[5], 0, || pv.zip(qv).map(|(p, q)| curve.cross(p, q)))?;
+                region.enable_selector(|| "q_add", &self.q_add, 1)?;
+                p.0.copy_advice(|| "x1", &mut region, self.adv[0], 1)?;
+                p.1.copy_advice(|| "y1", &mut region, self.adv[1], 1)?;
+                q.0.copy_advice(|| "x2", &mut region, self.adv[2], 1)?;
+                q.1.copy_advice(|| "y2", &mut region, self.adv[3], 1)?;
+                let sum = add_values(curve, pv, qv)?;
+                Ok((
+                    region.assign_advice(|| "x3", self.adv[4], 1, || sum.map(|s| s.0))?,
+                    region.assign_advice(|| "y3", self.adv[5], 1, || sum.map(|s| s.1))?,
+                ))
+            },
+        )
+    }
+
+    fn double<F: CircuitField>(&self, mut layouter: impl Layouter<F>, curve: &EdwardsCurve<F>, p: &Point<F>) -> Result<Point<F>, Error> {
+        layouter.assign_region(
+            || "edwards double",
+            |mut region| {
+                region.enable_selector(|| "q_double", &self.q_double, 0)?;
+                p.0.copy_advice(|| "x1", &mut region, self.adv[0], 0)?;
+                p.1.copy_advice(|| "y1", &mut region, self.adv[1], 0)?;
+                let pv = point_value(p);
+                region.assign_advice(|| "t", self.adv[2], 0, || pv.map(|p| curve.cross(p, p)))?;
+                let sum = add_values(curve, pv, pv)?;
+                Ok((
+                    region.assign_advice(|| "x3", self.adv[4], 0, || sum.map(|s| s.0))?,
+                    region.assign_advice(|| "y3", self.adv[5], 0, || sum.map(|s| s.1))?,
+                ))
+            },
+        )
+    }
+
+    // Binds `k` to `bits` boolean cells (most significant first): k must be below 2^bits,
+    // except for bits >= 254 where the sum may wrap (harmless for a hash output).
+    fn bits<F: CircuitField>(&self, mut layouter: impl Layouter<F>, k: &AssignedCell<F, F>, bits: usize) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        layouter.assign_region(
+            || "scalar bits",
+            |mut region| {
+                let values = k.value().map(|k| scalar_bits(*k, bits));
+                let mut acc = region.assign_advice_from_constant(|| "k_0", self.adv[0], 0, F::ZERO)?;
+                let mut cells = Vec::with_capacity(bits);
+                for i in 0..bits {
+                    region.enable_selector(|| "q_bits", &self.q_bits, i)?;
+                    let bit = values.as_ref().map(|b| F::from(b[i] as u64));
+                    cells.push(region.assign_advice(|| format!("b_{i}"), self.adv[1], i, || bit)?);
+                    let next = acc.value().zip(bit).map(|(a, b)| *a * F::from(2) + b);
+                    acc = region.assign_advice(|| format!("k_{}", i + 1), self.adv[0], i + 1, || next)?;
+                }
+                region.constrain_equal(acc.cell(), k.cell())?;
+                Ok(cells)
+            },
+        )
+    }
+
+    fn mul<F: CircuitField>(&self, mut layouter: impl Layouter<F>, curve: &EdwardsCurve<F>, p: &Point<F>, k: &AssignedCell<F, F>, bits: usize) -> Result<Point<F>, Error> {
+        let bit_cells = self.bits(layouter.namespace(|| "bits"), k, bits)?;
+        layouter.assign_region(
+            || "double and add",
+            |mut region| {
+                let mut acc = (
+                    region.assign_advice_from_constant(|| "0x", self.adv[0], 0, F::ZERO)?,
+                    region.assign_advice_from_constant(|| "0y", self.adv[1], 0, F::ONE)?,
+                );
+                // three rows per bit: double (with its t), select (with the add's t), add
+                for (i, bit) in bit_cells.iter().enumerate() {
+                    let row = 1 + 3 * i;
+                    region.enable_selector(|| "q_double", &self.q_double, row)?;
+                    acc.0.copy_advice(|| "x", &mut region, self.adv[0], row)?;
+                    acc.1.copy_advice(|| "y", &mut region, self.adv[1], row)?;
+                    let av = point_value(&acc);
+                    region.assign_advice(|| "t", self.adv[2], row, || av.map(|a| curve.cross(a, a)))?;
+                    let doubled = add_values(curve, av, av)?;
+                    let dx = region.assign_advice(|| "2x", self.adv[4], row, || doubled.map(|d| d.0))?;
+                    let dy = region.assign_advice(|| "2y", self.adv[5], row, || doubled.map(|d| d.1))?;
+
+                    region.enable_selector(|| "q_select", &self.q_select, row + 1)?;
+                    let b = bit.copy_advice(|| "bit", &mut region, self.adv[0], row + 1)?;
+                    p.0.copy_advice(|| "px", &mut region, self.adv[1], row + 1)?;
+                    p.1.copy_advice(|| "py", &mut region, self.adv[2], row + 1)?;
+                    let selected = b.value().zip(p.0.value().zip(p.1.value()))
+                        .map(|(b, (px, py))| if *b == F::ONE { (*px, *py) } else { (F::ZERO, F::ONE) });
+                    let sx = region.assign_advice(|| "sx", self.adv[3], row + 1, || selected.map(|s| s.0))?;
+                    let sy = region.assign_advice(|| "sy", self.adv[4], row + 1, || selected.map(|s| s.1))?;
+                    region.assign_advice(|| "t", self.adv[5], row + 1, || doubled.zip(selected).map(|(d, s)| curve.cross(d, s)))?;
+
+                    region.enable_selector(|| "q_add", &self.q_add, row + 2)?;
+                    dx.copy_advice(|| "x1", &mut region, self.adv[0], row + 2)?;
+                    dy.copy_advice(|| "y1", &mut region, self.adv[1], row + 2)?;
+                    sx.copy_advice(|| "x2", &mut region, self.adv[2], row + 2)?;
+                    sy.copy_advice(|| "y2", &mut region, self.adv[3], row + 2)?;
+                    let sum = add_values(curve, doubled, selected)?;
+                    acc = (
+                        region.assign_advice(|| "x3", self.adv[4], row + 2, || sum.map(|s| s.0))?,
+                        region.assign_advice(|| "y3", self.adv[5], row + 2, || sum.map(|s| s.1))?,
+                    );
+                }
+                Ok(acc)
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{prover::tx_instances, TxCircuit, NEVER_EXPIRES, ONE_Q16};
+    use halo2_proofs::{dev::MockProver, halo2curves::ff::Field};
+    use num_bigint::BigUint;
+
+    // order l of the subgroup generated by Base8
+    const SUBGROUP_ORDER: &str = "2736030358979909402780800718157159386076813972158567259200215660948447373041";
+    const ONE: i64 = ONE_Q16 as i64;
+
+    // S = r + 8·h·a mod l, with A = a·B and R = r·B
+    fn sign(secret: u64, nonce: u64, q_out: Fr, epoch: u64) -> OracleSignature<Fr> {
+        let curve = baby_jubjub();
+        let key = curve.mul(curve.base, Fr::from(secret), 64).unwrap();
+        let r = curve.mul(curve.base, Fr::from(nonce), 64).unwrap();
+        let mut sig = OracleSignature { key, r, s: Fr::ZERO, epoch: Fr::from(epoch) };
+        let h = BigUint::from_bytes_le(&message_hash(&sig, q_out).to_repr());
+        let l = BigUint::parse_bytes(SUBGROUP_ORDER.as_bytes(), 10).unwrap();
+        let s = (BigUint::from(nonce) + h * 8u32 * secret) % l;
+        let mut repr = [0u8; 32];
+        let s = s.to_bytes_le();
+        repr[..s.len()].copy_from_slice(&s);
+        sig.s = Fr::from_repr(repr).unwrap();
+        sig
+    }
+
+    fn circuit(q_out: i64, sig: OracleSignature<Fr>) -> TxCircuit<Fr> {
+        let mut circ = TxCircuit::<Fr>::builder()
+            .features(vec![ONE / 2, ONE / 4]).weights(vec![ONE / 2, -ONE / 4], 0).quantum(q_out, ONE / 2)
+            .domain("test").validity(0, NEVER_EXPIRES)
+            .build().unwrap();
+        circ.oracle = Some(sig);
+        circ
+    }
+
+    fn check(circ: &TxCircuit<Fr>) -> bool {
+        MockProver::run(circ.cost_estimate().min_k, circ, tx_instances(circ)).unwrap().verify().is_ok()
+    }
+
+    #[test]
+    fn base_generates_the_prime_order_subgroup() {
+        let curve = baby_jubjub();
+        assert!(curve.is_on_curve(curve.base));
+        let l = Fr::from_str_vartime(SUBGROUP_ORDER).unwrap();
+        assert_eq!(curve.mul(curve.base, l, S_BITS), Some((Fr::ZERO, Fr::ONE)));
+    }
+
+    // circomlib's babyjub test vectors
+    #[test]
+    fn addition_matches_reference_vectors() {
+        let curve = baby_jubjub();
+        let n = |s: &str| Fr::from_str_vartime(s).unwrap();
+        let p1 = (
+            n("17777552123799933955779906779655732241715742912184938656739573121738514868268"),
+            n("2626589144620713026669568689430873010625803728049924121243784502389097019475"),
+        );
+        let p2 = (
+            n("16540640123574156134436876038791482806971768689494387082833631921987005038935"),
+            n("20819045374670962167435360035096875258406992893633759881276124905556507972311"),
+        );
+        assert_eq!(curve.add(p1, p2), Some((
+            n("7916061937171219682591368294088513039687205273691143098332585753343424131937"),
+            n("14035240266687799601661095864649209771790948434046947201833777492504781204499"),
+        )));
+        assert_eq!(curve.add(p1, p1), Some((
+            n("6890855772600357754907169075114257697580319025794532037257385534741338397365"),
+            n("4338620300185947561074059802482547481416142213883829469920100239455078257889"),
+        )));
+        assert_eq!(curve.mul(p1, Fr::from(3), 2), Some((
+            n("19372461775513343691590086534037741906533799473648040012278229434133483800898"),
+            n("9458658722007214007257525444427903161243386465067105737478306991484593958249"),
+        )));
+    }
+
+    #[test]
+    fn vanishing_denominator_is_none() {
+        let curve = baby_jubjub();
+        // d·x1·x2·y1·y2 = -1
+        let q = (Fr::ONE, -curve.d.invert().unwrap());
+        assert_eq!(curve.add((Fr::ONE, Fr::ONE), q), None);
+    }
+
+    #[test]
+    fn edwards_gates_stay_below_degree_6() {
+        let stats = TxCircuit::<Fr>::empty(circuit(ONE / 2, OracleSignature::default()).shape()).stats();
+        for g in stats.gates.iter().filter(|g| g.name.starts_with("edwards")) {
+            assert!(g.degree <= 5, "{}: grado {}", g.name, g.degree);
+        }
+    }
+
+    #[test]
+    fn signature_vectors() {
+        let curve = baby_jubjub();
+        let q_out = Fr::from(ONE as u64 / 2);
+        for (secret, nonce, epoch) in [(1, 1, 0), (0x5eed, 0x1234_5678, 7), (u64::MAX, u64::MAX - 1, u64::MAX)] {
+            let sig = sign(secret, nonce, q_out, epoch);
+            assert!(curve.verify(&sig, q_out), "a = {secret}");
+            assert!(!curve.verify(&OracleSignature { epoch: sig.epoch + Fr::ONE, ..sig }, q_out));
+            assert!(!curve.verify(&OracleSignature { s: sig.s + Fr::ONE, ..sig }, q_out));
+            assert!(!curve.verify(&OracleSignature { r: (sig.r.0, sig.r.1 + Fr::ONE), ..sig }, q_out));
+        }
+        // (R, S + l) verifies whenever S + l still fits in S_BITS (see the module note)
+        let sig = sign(1, 1, q_out, 0);
+        let malleated = OracleSignature { s: sig.s + Fr::from_str_vartime(SUBGROUP_ORDER).unwrap(), ..sig };
+        let fits = scalar_bits(malleated.s, 256)[..256 - S_BITS].iter().all(|b| !b);
+        assert_eq!(curve.verify(&malleated, q_out), fits);
+        assert!(!curve.verify(&OracleSignature { s: Fr::from(2).pow([S_BITS as u64]), ..sig }, q_out));
+    }
+
+    #[test]
+    fn signed_q_out_is_accepted() {
+        let q_out = ONE / 2;
+        let sig = sign(0x5eed, 0x1234_5678, Fr::from(q_out as u64), 7);
+        assert!(baby_jubjub().verify(&sig, Fr::from(q_out as u64)));
+        assert!(check(&circuit(q_out, sig)));
+    }
+
+    #[test]
+    fn other_q_out_is_rejected() {
+        let sig = sign(0x5eed, 0x1234_5678, Fr::from(ONE as u64 / 2), 7);
+        assert!(!baby_jubjub().verify(&sig, Fr::from(ONE as u64 / 4)));
+        assert!(!check(&circuit(ONE / 4, sig)));
+    }
+}
@@ -0,0 +1,248 @@
+// ensemble.rs
+use crate::compare::CompareConfig;
+use crate::div::DivConfig;
+use crate::pwl::PwlConfig;
+use crate::range::RangeConfig;
+use crate::score::{ScoreCells, ScoreConfig};
+use crate::{commit_in_domain, fr_from_qi128, fr_to_i128, Activation, Tag, TxCircuit, FRAC_BITS};
+use crate::{Hash, PoseidonChip, PoseidonConfig};
+use crate::circuit::{pin_degree, Layouter, SimpleFloorPlanner, StackedLayouter, Value};
+use halo2_proofs::{
+    circuit as h2,
+    halo2curves::bn256::Fr,
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance, Selector},
+    poly::Rotation,
+};
+
+// K models scored on the same features; the public score is floor(sum(score_k) / K),
+// proven with the division chip against the constant K. Each member runs the full scorer
+// (see score.rs) with its x, alpha and q_out cells tied to the first member's, and gets its own
+// commitment row over the w and b cells it scored with, so the verifier pins every member.
+pub const MAX_MODELS: usize = 16;
+// sum of up to 16 scores in [0, 2^16]
+const SUM_BITS: usize = FRAC_BITS as usize + 5;
+
+#[derive(Clone, Debug)]
+pub struct EnsembleConfig {
+    adv: [Column<Advice>; 6],
+    q_sum: Selector,
+    poseidon: PoseidonConfig<Fr, 3, 2>,
+    range: RangeConfig,
+    pwl: PwlConfig,
+    score: ScoreConfig,
+    div: DivConfig,
+    instance: [Column<Instance>; 4], // commit_wb (one row per model), commit_x, score_pub, domain
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct EnsembleCircuit {
+    pub x: Vec<Fr>,
+    // (w, b) per model
+    pub models: Vec<(Vec<Fr>, Fr)>,
+    pub alpha: Fr,
+    pub q_out: Fr,
+    pub domain: Fr,
+    pub activation: Activation,
+}
+
+impl EnsembleCircuit {
+    /// Witness-free circuit for `models` models over `features` features; use it for keygen.
+    pub fn empty(features: usize, models: usize, activation: Activation) -> Self {
+        Self {
+            x: vec![Fr::from(0); features],
+            models: vec![(vec![Fr::from(0); features], Fr::from(0)); models],
+            activation,
+            ..Default::default()
+        }
+    }
+
+    /// Model `k` as a single-model circuit, for its native score and pre-activation.
+    pub fn member(&self, k: usize) -> TxCircuit {
+        let (w, b) = &self.models[k];
+        TxCircuit { x: self.x.clone(), w: w.clone(), b: *b, alpha: self.alpha, q_out: self.q_out, activation: self.activation, ..Default::default() }
+    }
+
+    /// The published score: floor of the mean member score, in Q16.16.
+    pub fn score_q16(&self) -> i64 {
+        let sum: i128 = (0..self.models.len()).map(|k| fr_to_i128(self.member(k).score())).sum();
+        sum.div_euclid(self.models.len().max(1) as i128) as i64
+    }
+
+    /// Public inputs: commit_wb per model, commit_x, score_pub, domain.
+    pub fn instances(&self) -> Vec<Vec<Fr>> {
+        let commit_wb = self.models.iter()
+            .map(|(w, b)| commit_in_domain(Tag::Wb, self.domain, &w.iter().copied().chain([*b]).collect::<Vec<_>>()))
+            .collect();
+        let commit_x = commit_in_domain(Tag::X, self.domain, &self.x);
+        vec![commit_wb, vec![commit_x], vec![fr_from_qi128(self.score_q16() as i128)], vec![self.domain]]
+    }
+}
+
+impl Circuit<Fr> for EnsembleCircuit {
+    type Config = EnsembleConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self { Self::empty(self.x.len(), self.models.len(), self.activation) }
+
+    fn configure(cs: &mut ConstraintSystem<Fr>) -> Self::Config {
+        let adv = [0,1,2,3,4,5].map(|_| cs.advice_column());
+        for a in &adv { cs.enable_equality(*a); }
+        let instance = [0,1,2,3].map(|_| cs.instance_column());
+        for i in &instance { cs.enable_equality(*i); }
+        let poseidon = PoseidonChip::configure(cs, adv[0], adv[1], adv[2], adv[3], adv[4], adv[5]);
+        let constants = cs.fixed_column();
+        let range = RangeConfig::configure(cs, adv[3], adv[4], constants);
+        let compare = CompareConfig::configure(cs, adv[0], adv[1], adv[2], adv[5], range.decompose.clone());
+        let pwl = PwlConfig::configure(cs, [adv[0], adv[1], adv[2], adv[3], adv[4]], range.decompose.clone());
+        let score = ScoreConfig::configure(cs, adv, range.clone(), pwl.clone());
+        let div = DivConfig::configure(cs, [adv[0], adv[1], adv[2], adv[5]], FRAC_BITS, compare, range.decompose.clone());
+
+        // total_k = total_{k-1} + score_k
+        let q_sum = cs.selector();
+        cs.create_gate("ensemble sum", |meta| {
+            let q = meta.query_selector(q_sum);
+            let score = meta.query_advice(adv[0], Rotation::cur());
+            let total = meta.query_advice(adv[1], Rotation::cur());
+            let prev = meta.query_advice(adv[1], Rotation::prev());
+            vec![q * (total - prev - score)]
+        });
+
+        pin_degree(cs);
+        EnsembleConfig { adv, q_sum, poseidon, range, pwl, score, div, instance }
+    }
+
+    fn synthesize(&self, cfg: Self::Config, layouter: impl h2::Layouter<Fr>) -> Result<(), Error> {
+        let mut layouter = StackedLayouter::new(layouter);
+        let k = self.models.len();
+        assert!(k > 0 && k <= MAX_MODELS, "número de modelos fuera de rango");
+        cfg.range.load(layouter.namespace(|| "range table"))?;
+        cfg.pwl.load_table(layouter.namespace(|| "pwl table"))?;
+
+        let domain_cell = layouter.assign_region(
+            || "domain",
+            |mut region| region.assign_advice(|| "domain", cfg.adv[0], 0, || Value::known(self.domain))
+        )?;
+        layouter.constrain_instance(domain_cell.cell(), cfg.instance[3], 0)?;
+
+        let scored = (0..k)
+            .map(|m| cfg.score.assign(layouter.namespace(|| format!("score_{m}")), &self.member(m).score_inputs()))
+            .collect::<Result<Vec<ScoreCells<Fr>>, _>>()?;
+        layouter.assign_region(
+            || "shared inputs",
+            |mut region| {
+                let first = &scored[0];
+                for member in &scored[1..] {
+                    let pairs = member.x.iter().zip(&first.x).chain([(&member.alpha, &first.alpha), (&member.q_out, &first.q_out)]);
+                    for (a, b) in pairs { region.constrain_equal(a.cell(), b.cell())?; }
+                }
+                Ok(())
+            }
+        )?;
+
+        for (m, member) in scored.iter().enumerate() {
+            let commit_wb = {
+                let mut hasher = Hash::<Fr, PoseidonChip<Fr>, 3, 2>::init(cfg.poseidon.clone(), layouter.namespace(|| format!("poseidon_wb_{m}")))?;
+                let tag = Tag::Wb.assign(layouter.namespace(|| format!("tag_wb_{m}")), cfg.adv[0])?;
+                let mut inputs = vec![tag, domain_cell.clone()];
+                inputs.extend(member.w.iter().cloned());
+                inputs.push(member.b.clone());
+                hasher.update(layouter.namespace(|| format!("absorb_wb_{m}")), inputs)?;
+                hasher.squeeze(layouter.namespace(|| format!("squeeze_wb_{m}")))?
+            };
+            layouter.constrain_instance(commit_wb.cell(), cfg.instance[0], m)?;
+        }
+        let commit_x = {
+            let mut hasher = Hash::<Fr, PoseidonChip<Fr>, 3, 2>::init(cfg.poseidon.clone(), layouter.namespace(|| "poseidon_x"))?;
+            let tag = Tag::X.assign(layouter.namespace(|| "tag_x"), cfg.adv[0])?;
+            let mut inputs = vec![tag, domain_cell.clone()];
+            inputs.extend(scored[0].x.iter().cloned());
+            hasher.update(layouter.namespace(|| "absorb_x"), inputs)?;
+            hasher.squeeze(layouter.namespace(|| "squeeze_x"))?
+        };
+        layouter.constrain_instance(commit_x.cell(), cfg.instance[1], 0)?;
+
+        // the scorer already bounds each score to [0, 1]
+        let total = layouter.assign_region(
+            || "ensemble sum",
+            |mut region| {
+                let mut total = region.assign_advice(|| "total_0", cfg.adv[1], 0, || Value::known(Fr::from(0)))?;
+                region.constrain_constant(total.cell(), Fr::from(0))?;
+                for (m, member) in scored.iter().enumerate() {
+                    region.enable_selector(|| "q_sum", &cfg.q_sum, m + 1)?;
+                    let score = member.score.copy_advice(|| format!("score_{m}"), &mut region, cfg.adv[0], m + 1)?;
+                    let next = total.value().zip(score.value()).map(|(t, s)| *t + s);
+                    total = region.assign_advice(|| format!("total_{}", m + 1), cfg.adv[1], m + 1, || next)?;
+                }
+                Ok(total)
+            }
+        )?;
+
+        // mean = floor(total * 2^16 / (K * 2^16))
+        let divisor = layouter.assign_region(
+            || "ensemble size",
+            |mut region| {
+                let d = Fr::from((k as u64) << FRAC_BITS);
+                let cell = region.assign_advice(|| "K", cfg.adv[1], 0, || Value::known(d))?;
+                region.constrain_constant(cell.cell(), d)?;
+                Ok(cell)
+            }
+        )?;
+        let mean = cfg.div.div(layouter.namespace(|| "mean"), &total, &divisor, SUM_BITS)?;
+        layouter.constrain_instance(mean.cell(), cfg.instance[2], 0)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fixed::Fixed16, ONE_Q16};
+    use halo2_proofs::dev::MockProver;
+
+    const ONE: i64 = ONE_Q16 as i64;
+
+    fn ensemble(activation: Activation) -> EnsembleCircuit {
+        let q = |v: i64| Fixed16::from_raw(v).to_field::<Fr>();
+        EnsembleCircuit {
+            x: [ONE / 2, -ONE / 4, ONE].map(q).to_vec(),
+            models: vec![
+                ([ONE / 2, ONE / 3, -ONE / 8].map(q).to_vec(), q(ONE / 16)),
+                ([-ONE / 4, ONE, ONE / 5].map(q).to_vec(), q(-ONE / 3)),
+            ],
+            alpha: q(ONE / 4),
+            q_out: q(3 * ONE / 4),
+            domain: Fr::from(1),
+            activation,
+        }
+    }
+
+    fn check(circ: &EnsembleCircuit, instances: Vec<Vec<Fr>>) -> bool {
+        MockProver::run(15, circ, instances).unwrap().verify().is_ok()
+    }
+
+    #[test]
+    fn mean_of_member_scores_is_satisfied() {
+        for activation in [Activation::CubicSigmoid, Activation::PwlSigmoid] {
+            let circ = ensemble(activation);
+            assert!(check(&circ, circ.instances()), "{activation:?}");
+        }
+    }
+
+    #[test]
+    fn forged_member_score_is_rejected() {
+        for activation in [Activation::CubicSigmoid, Activation::PwlSigmoid] {
+            let circ = ensemble(activation);
+            // the mean as if member 1 had scored 1.0
+            let forged = (fr_to_i128(circ.member(0).score()) + ONE as i128).div_euclid(2);
+            let mut instances = circ.instances();
+            instances[2] = vec![fr_from_qi128(forged)];
+            assert!(!check(&circ, instances.clone()), "{activation:?}");
+            // nor can another model produce it under member 1's commitment
+            let mut other = circ.clone();
+            other.models[1].1 = Fixed16::from_raw(ONE * 8).to_field();
+            instances[2] = other.instances()[2].clone();
+            assert!(!check(&other, instances), "{activation:?}");
+        }
+    }
+}
@@ -0,0 +1,218 @@
+// envelope.rs
+use crate::{ensemble::EnsembleCircuit, tree::TreeCircuit, velocity::VelocityCircuit, TxCircuit};
+use halo2_proofs::{
+    halo2curves::bn256::Fr,
+    plonk::{Circuit, ConstraintSystem},
+};
+use sha2::{Digest, Sha256};
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Self-describing proof file: what the proof was made against travels with it, so a verifier
+// with the wrong params, key or build fails with a precise error instead of a bare "invalid proof".
+// Layout (little-endian): magic, version u16, scheme u8, transcript u8, k u32, circuit hash [32],
+// vk digest [32], created_at u64 (Unix seconds), proof length u32, proof.
+// Version 1 had no transcript byte (always Blake2b).
+pub const MAGIC: &[u8; 4] = b"QGPF";
+pub const FORMAT_VERSION: u16 = 2;
+const HEADER_LEN: usize = 4 + 2 + 1 + 1 + 4 + 32 + 32 + 8 + 4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scheme {
+    KzgGwc = 1,
+    Ipa = 2,
+}
+
+impl TryFrom<u8> for Scheme {
+    type Error = String;
+    fn try_from(v: u8) -> Result<Self, Self::Error> {
+        match v {
+            1 => Ok(Scheme::KzgGwc),
+            2 => Ok(Scheme::Ipa),
+            _ => Err(format!("esquema de apertura desconocido: {v}")),
+        }
+    }
+}
+
+// Fiat-Shamir hash. Keccak is snark-verifier's EVM transcript, the one the Solidity verifier replays.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Transcript {
+    #[default]
+    Blake2b = 1,
+    Keccak = 2,
+}
+
+impl TryFrom<u8> for Transcript {
+    type Error = String;
+    fn try_from(v: u8) -> Result<Self, Self::Error> {
+        match v {
+            1 => Ok(Transcript::Blake2b),
+            2 => Ok(Transcript::Keccak),
+            _ => Err(format!("transcript desconocido: {v}")),
+        }
+    }
+}
+
+impl std::str::FromStr for Transcript {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "blake2b" => Ok(Transcript::Blake2b),
+            "keccak" => Ok(Transcript::Keccak),
+            _ => Err(format!("transcript desconocido: {s} (blake2b, keccak)")),
+        }
+    }
+}
+
+fn pinned<C: Circuit<Fr>>() -> String {
+    let mut cs = ConstraintSystem::<Fr>::default();
+    C::configure(&mut cs);
+    format!("{:?}", cs.pinned())
+}
+
+// Gate set identity: SHA-256 over the pinned constraint systems (columns, gates, queries,
+// permutation, lookups, degree) of every circuit this build proves, so it changes exactly when
+// one of them does, whatever the crate version. Fixed-column contents are covered by the vk digest.
+pub fn circuit_hash() -> [u8; 32] {
+    static HASH: OnceLock<[u8; 32]> = OnceLock::new();
+    *HASH.get_or_init(|| {
+        let mut hasher = Sha256::new();
+        for cs in [pinned::<TxCircuit<Fr>>(), pinned::<EnsembleCircuit>(), pinned::<TreeCircuit>(), pinned::<VelocityCircuit>()] {
+            hasher.update((cs.len() as u64).to_le_bytes());
+            hasher.update(cs);
+        }
+        hasher.finalize().into()
+    })
+}
+
+/// `circuit_hash` in hex, as key headers and vk exports record it.
+pub fn circuit_version() -> String { hex::encode(circuit_hash()) }
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProofEnvelope {
+    pub version: u16,
+    pub scheme: Scheme,
+    pub transcript: Transcript,
+    pub k: u32,
+    pub circuit_hash: [u8; 32],
+    pub vk_digest: [u8; 32],
+    pub created_at: u64,
+    pub proof: Vec<u8>,
+}
+
+impl ProofEnvelope {
+    pub fn new(scheme: Scheme, transcript: Transcript, k: u32, vk_digest: [u8; 32], proof: Vec<u8>) -> Self {
+        let created_at = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        Self { version: FORMAT_VERSION, scheme, transcript, k, circuit_hash: circuit_hash(), vk_digest, created_at, proof }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(HEADER_LEN + self.proof.len());
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        out.push(self.scheme as u8);
+        out.push(self.transcript as u8);
+        out.extend_from_slice(&self.k.to_le_bytes());
+        out.extend_from_slice(&self.circuit_hash);
+        out.extend_from_slice(&self.vk_digest);
+        out.extend_from_slice(&self.created_at.to_le_bytes());
+        out.extend_from_slice(&(self.proof.len() as u32).to_le_bytes());
+        out.extend_from_slice(&self.proof);
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.get(..4) != Some(&MAGIC[..]) {
+            return Err("el archivo no es un sobre de prueba (¿prueba en formato anterior? vuelve a generarla)".into());
+        }
+        let version = u16::from_le_bytes(bytes.get(4..6).ok_or("sobre de prueba truncado")?.try_into().unwrap());
+        // v1: no transcript byte
+        let (transcript, at) = match version {
+            1 => (Transcript::Blake2b, 7),
+            FORMAT_VERSION => (Transcript::try_from(*bytes.get(7).ok_or("sobre de prueba truncado")?)?, 8),
+            _ => return Err(format!("versión de sobre {version} no soportada (se espera {FORMAT_VERSION})")),
+        };
+        let header = at + 4 + 32 + 32 + 8 + 4;
+        if bytes.len() < header {
+            return Err("sobre de prueba truncado".into());
+        }
+        let scheme = Scheme::try_from(bytes[6])?;
+        let k = u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+        let circuit_hash = bytes[at + 4..at + 36].try_into().unwrap();
+        let vk_digest = bytes[at + 36..at + 68].try_into().unwrap();
+        let created_at = u64::from_le_bytes(bytes[at + 68..at + 76].try_into().unwrap());
+        let len = u32::from_le_bytes(bytes[at + 76..header].try_into().unwrap()) as usize;
+        let proof = bytes.get(header..header + len).ok_or("sobre de prueba truncado")?.to_vec();
+        Ok(Self { version, scheme, transcript, k, circuit_hash, vk_digest, created_at, proof })
+    }
+
+    /// Rejects an envelope made for another scheme, params size, verifying key or circuit build.
+    pub fn check(&self, scheme: Scheme, k: u32, vk_digest: &[u8; 32]) -> Result<(), String> {
+        if self.scheme != scheme {
+            return Err(format!("la prueba usa {:?}, el verificador {scheme:?}", self.scheme));
+        }
+        if self.k != k {
+            return Err(format!("la prueba es para k = {}, los params tienen k = {k}", self.k));
+        }
+        if &self.vk_digest != vk_digest {
+            return Err(format!("vk de la prueba {} != vk cargada {}", hex::encode(self.vk_digest), hex::encode(vk_digest)));
+        }
+        if self.circuit_hash != circuit_hash() {
+            return Err(format!("la prueba es de otra versión del circuito ({}, esta: {})", hex::encode(self.circuit_hash), circuit_version()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn envelope() -> ProofEnvelope {
+        ProofEnvelope::new(Scheme::KzgGwc, Transcript::Keccak, 12, [7; 32], (0..=255).collect())
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let envelope = envelope();
+        let bytes = envelope.to_bytes();
+        assert_eq!(bytes.len(), HEADER_LEN + 256);
+        assert_eq!(ProofEnvelope::from_bytes(&bytes).unwrap(), envelope);
+    }
+
+    // version 1: no transcript byte, always Blake2b
+    #[test]
+    fn version_1_reads_as_blake2b() {
+        let mut bytes = envelope().to_bytes();
+        bytes[4..6].copy_from_slice(&1u16.to_le_bytes());
+        bytes.remove(7);
+        let read = ProofEnvelope::from_bytes(&bytes).unwrap();
+        assert_eq!((read.version, read.transcript), (1, Transcript::Blake2b));
+        assert_eq!(read.proof, envelope().proof);
+    }
+
+    #[test]
+    fn rejects_damaged_files() {
+        let bytes = envelope().to_bytes();
+        assert!(ProofEnvelope::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(ProofEnvelope::from_bytes(&bytes[..HEADER_LEN - 1]).is_err());
+        assert!(ProofEnvelope::from_bytes(&bytes[4..]).is_err());
+        let mut newer = bytes.clone();
+        newer[4..6].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+        assert!(ProofEnvelope::from_bytes(&newer).is_err());
+        let mut scheme = bytes;
+        scheme[6] = 9;
+        assert!(ProofEnvelope::from_bytes(&scheme).is_err());
+    }
+
+    #[test]
+    fn check_names_the_mismatch() {
+        let envelope = envelope();
+        assert!(envelope.check(Scheme::KzgGwc, 12, &[7; 32]).is_ok());
+        assert!(envelope.check(Scheme::Ipa, 12, &[7; 32]).unwrap_err().contains("Ipa"));
+        assert!(envelope.check(Scheme::KzgGwc, 13, &[7; 32]).unwrap_err().contains("k = 13"));
+        assert!(envelope.check(Scheme::KzgGwc, 12, &[8; 32]).unwrap_err().contains("vk"));
+        let other = ProofEnvelope { circuit_hash: [0; 32], ..envelope };
+        assert!(other.check(Scheme::KzgGwc, 12, &[7; 32]).unwrap_err().contains("otra versión del circuito"));
+    }
+}
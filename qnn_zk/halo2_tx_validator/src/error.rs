@@ -0,0 +1,45 @@
+// error.rs
+use thiserror::Error;
+
+/// Errors of the library API, by what the caller can do about them.
+#[derive(Debug, Error)]
+pub enum QuantumGuardError {
+    // the witness can't be read or can't be proven as given
+    #[error("witness inválido: {0}")]
+    Witness(String),
+    // params file unreadable or for another k / curve
+    #[error("params inválidos: {0}")]
+    Params(String),
+    // witness, key and proof disagree on the circuit shape
+    #[error("forma incompatible: {0}")]
+    Shape(String),
+    // key file unreadable, or from another build
+    #[error("clave inválida: {0}")]
+    Key(String),
+    #[error("keygen fallido: {0}")]
+    Keygen(String),
+    #[error("no se pudo crear la prueba: {0}")]
+    Proving(String),
+    // the proof does not verify, or was made for other params, key or circuit version
+    #[error("prueba rechazada: {0}")]
+    Verification(String),
+    // needs a Cargo feature this build lacks
+    #[error("{0}")]
+    Unavailable(&'static str),
+}
+
+impl std::error::Error for crate::verifier::VerifyError {}
+
+impl From<crate::verifier::VerifyError> for QuantumGuardError {
+    fn from(e: crate::verifier::VerifyError) -> Self {
+        use crate::verifier::VerifyError as V;
+        match e {
+            V::Envelope(e) | V::Rejected(e) => QuantumGuardError::Verification(e),
+            V::Key(e) => QuantumGuardError::Key(e),
+            V::Params(e) => QuantumGuardError::Params(e),
+            V::Unsupported(e) => QuantumGuardError::Unavailable(e),
+        }
+    }
+}
+
+pub type Result<T, E = QuantumGuardError> = std::result::Result<T, E>;
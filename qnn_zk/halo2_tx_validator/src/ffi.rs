@@ -0,0 +1,210 @@
+// ffi.rs
+use crate::error::QuantumGuardError as E;
+use crate::keys::{ProvingKeyFile, VerifyingKeyFile};
+use crate::prover::{self, Proof, ProveOptions};
+use crate::witness::Witness;
+use crate::TxCircuit;
+use halo2_proofs::halo2curves::bn256::{Bn256, Fr};
+use halo2_proofs::poly::kzg::commitment::ParamsKZG;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+// C ABI over the library's prover and verifier (header: include/quantum_guard.h, regenerated by
+// build.rs with --features ffi). A context owns params and keys; functions return a QgStatus and
+// leave the message of the last failure in the context. Buffers returned to C are freed with
+// qg_buffer_free, contexts with qg_context_free. Proofs cross the boundary as the JSON of
+// prover::Proof. A context is not thread-safe; use one per thread.
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QgStatus {
+    Ok = 0,
+    // null pointer, invalid UTF-8, missing params or key
+    InvalidArgument = 1,
+    Witness = 2,
+    Params = 3,
+    Key = 4,
+    Shape = 5,
+    Proving = 6,
+    Rejected = 7,
+    Unavailable = 8,
+    Panic = 9,
+}
+
+/// Opaque to C.
+pub struct QgContext {
+    params: Option<ParamsKZG<Bn256>>,
+    pk: Option<ProvingKeyFile>,
+    vk: Option<VerifyingKeyFile>,
+    last_error: CString,
+}
+
+#[repr(C)]
+pub struct QgBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+enum Failure {
+    Invalid(String),
+    Lib(E),
+}
+
+impl From<E> for Failure {
+    fn from(e: E) -> Self { Failure::Lib(e) }
+}
+
+fn status(e: &E) -> QgStatus {
+    match e {
+        E::Witness(_) => QgStatus::Witness,
+        E::Params(_) => QgStatus::Params,
+        E::Key(_) | E::Keygen(_) => QgStatus::Key,
+        E::Shape(_) => QgStatus::Shape,
+        E::Proving(_) => QgStatus::Proving,
+        E::Verification(_) => QgStatus::Rejected,
+        E::Unavailable(_) => QgStatus::Unavailable,
+    }
+}
+
+// Runs `f` on the context, records its error and never lets a panic cross into C.
+unsafe fn guard(ctx: *mut QgContext, f: impl FnOnce(&mut QgContext) -> Result<(), Failure>) -> QgStatus {
+    let Some(ctx) = ctx.as_mut() else { return QgStatus::InvalidArgument };
+    let (code, msg) = match catch_unwind(AssertUnwindSafe(|| f(ctx))) {
+        Ok(Ok(())) => return QgStatus::Ok,
+        Ok(Err(Failure::Invalid(msg))) => (QgStatus::InvalidArgument, msg),
+        Ok(Err(Failure::Lib(e))) => (status(&e), e.to_string()),
+        Err(_) => (QgStatus::Panic, "pánico interno".to_string()),
+    };
+    ctx.last_error = CString::new(msg.replace('\0', " ")).unwrap_or_default();
+    code
+}
+
+unsafe fn bytes<'a>(data: *const u8, len: usize) -> Result<&'a [u8], Failure> {
+    if data.is_null() {
+        return if len == 0 { Ok(&[]) } else { Err(Failure::Invalid("puntero nulo".into())) };
+    }
+    Ok(std::slice::from_raw_parts(data, len))
+}
+
+// NULL is None
+unsafe fn opt_str<'a>(s: *const c_char) -> Result<Option<&'a str>, Failure> {
+    if s.is_null() { return Ok(None); }
+    CStr::from_ptr(s).to_str().map(Some).map_err(|_| Failure::Invalid("cadena no UTF-8".into()))
+}
+
+#[no_mangle]
+pub extern "C" fn qg_context_new() -> *mut QgContext {
+    Box::into_raw(Box::new(QgContext { params: None, pk: None, vk: None, last_error: CString::default() }))
+}
+
+/// # Safety
+/// `ctx` from qg_context_new, or NULL; not used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn qg_context_free(ctx: *mut QgContext) {
+    if !ctx.is_null() { drop(Box::from_raw(ctx)); }
+}
+
+/// Message of the last failed call on `ctx`, valid until the next call; "" if none.
+///
+/// # Safety
+/// `ctx` from qg_context_new, or NULL.
+#[no_mangle]
+pub unsafe extern "C" fn qg_last_error(ctx: *const QgContext) -> *const c_char {
+    match ctx.as_ref() {
+        Some(ctx) => ctx.last_error.as_ptr(),
+        None => c"contexto nulo".as_ptr(),
+    }
+}
+
+/// KZG params in their file encoding.
+///
+/// # Safety
+/// `ctx` from qg_context_new; `data` readable for `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn qg_load_params(ctx: *mut QgContext, data: *const u8, len: usize) -> QgStatus {
+    guard(ctx, |ctx| {
+        ctx.params = Some(prover::read_params(bytes(data, len)?)?);
+        Ok(())
+    })
+}
+
+/// Proving key file (keygen --pk-out). Its vk is used for verification unless one is loaded.
+///
+/// # Safety
+/// `ctx` from qg_context_new; `data` readable for `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn qg_load_proving_key(ctx: *mut QgContext, data: *const u8, len: usize) -> QgStatus {
+    guard(ctx, |ctx| {
+        ctx.pk = Some(ProvingKeyFile::read::<TxCircuit<Fr>>(&mut bytes(data, len)?)?);
+        Ok(())
+    })
+}
+
+/// Verifying key file (keygen --vk-out).
+///
+/// # Safety
+/// `ctx` from qg_context_new; `data` readable for `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn qg_load_verifying_key(ctx: *mut QgContext, data: *const u8, len: usize) -> QgStatus {
+    guard(ctx, |ctx| {
+        ctx.vk = Some(VerifyingKeyFile::read::<TxCircuit<Fr>>(&mut bytes(data, len)?)?);
+        Ok(())
+    })
+}
+
+/// Proves a witness given as JSON (the CLI's witness format). `activation` ("cubic", "pwl") and
+/// `domain` may be NULL for the defaults. On success `out` holds the proof JSON.
+///
+/// # Safety
+/// `ctx` from qg_context_new; `witness` readable for `len` bytes; strings NUL-terminated or NULL;
+/// `out` writable.
+#[no_mangle]
+pub unsafe extern "C" fn qg_prove(
+    ctx: *mut QgContext, witness: *const u8, len: usize, activation: *const c_char, domain: *const c_char, out: *mut QgBuffer,
+) -> QgStatus {
+    guard(ctx, |ctx| {
+        let out = out.as_mut().ok_or(Failure::Invalid("out nulo".into()))?;
+        let wit: Witness = serde_json::from_slice(bytes(witness, len)?).map_err(|e| E::Witness(e.to_string()))?;
+        let options = ProveOptions {
+            activation: opt_str(activation)?.map(str::parse).transpose().map_err(Failure::Invalid)?.unwrap_or_default(),
+            domain: opt_str(domain)?.unwrap_or_default().to_string(),
+            ..Default::default()
+        };
+        let params = ctx.params.as_ref().ok_or(Failure::Invalid("faltan los params (qg_load_params)".into()))?;
+        let pk = ctx.pk.as_ref().ok_or(Failure::Invalid("falta la clave de prueba (qg_load_proving_key)".into()))?;
+        let proof = prover::prove(params, &pk.pk, &wit, &options, rand::rngs::OsRng)?;
+        let json = serde_json::to_vec(&proof).map_err(|e| E::Proving(format!("serialización de la prueba: {e}")))?.into_boxed_slice();
+        out.len = json.len();
+        out.data = Box::into_raw(json) as *mut u8;
+        Ok(())
+    })
+}
+
+/// Verifies a proof JSON from qg_prove. Status Rejected means the proof is invalid.
+///
+/// # Safety
+/// `ctx` from qg_context_new; `proof` readable for `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn qg_verify(ctx: *mut QgContext, proof: *const u8, len: usize) -> QgStatus {
+    guard(ctx, |ctx| {
+        let proof: Proof = serde_json::from_slice(bytes(proof, len)?).map_err(|e| E::Verification(e.to_string()))?;
+        let params = ctx.params.as_ref().ok_or(Failure::Invalid("faltan los params (qg_load_params)".into()))?;
+        let (vk, header) = match (&ctx.vk, &ctx.pk) {
+            (Some(f), _) => (&f.vk, &f.header),
+            (None, Some(f)) => (f.pk.get_vk(), &f.header),
+            (None, None) => return Err(Failure::Invalid("falta la clave de verificación (qg_load_verifying_key)".into())),
+        };
+        if let Some(header) = header { header.check_shape(&proof.public.shape)?; }
+        prover::verify(params, vk, &proof)?;
+        Ok(())
+    })
+}
+
+/// # Safety
+/// `buf` filled by this library and not freed before.
+#[no_mangle]
+pub unsafe extern "C" fn qg_buffer_free(buf: QgBuffer) {
+    if !buf.data.is_null() {
+        drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(buf.data, buf.len)));
+    }
+}
@@ -0,0 +1,94 @@
+// fixed.rs
+use crate::FieldExt;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+
+// Signed fixed point with FRAC fractional bits, stored as the raw integer (value · 2^FRAC): the
+// representation the circuit works on. Multiplication rescales with floor division, as the
+// circuit's products do, so native code using these operators reproduces the assigned values.
+// Plain operators panic on i64 overflow like the integers they wrap; checked_* and saturating_*
+// are for input data.
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Fixed<const FRAC: u32>(i64);
+
+/// The circuit's Q16.16.
+pub type Fixed16 = Fixed<{ crate::FRAC_BITS }>;
+
+impl<const FRAC: u32> Fixed<FRAC> {
+    pub const ZERO: Self = Self(0);
+    pub const ONE: Self = Self(1 << FRAC);
+
+    pub const fn from_raw(raw: i64) -> Self { Self(raw) }
+
+    pub const fn raw(self) -> i64 { self.0 }
+
+    /// Nearest representable value; errors on NaN, infinities and out-of-range values.
+    pub fn from_f64(v: f64) -> Result<Self, String> {
+        let scaled = (v * (1u64 << FRAC) as f64).round();
+        if !scaled.is_finite() || scaled.abs() > i64::MAX as f64 {
+            return Err(format!("{v} no es representable en Q{}.{FRAC}", 64 - FRAC));
+        }
+        Ok(Self(scaled as i64))
+    }
+
+    pub fn to_f64(self) -> f64 { self.0 as f64 / (1u64 << FRAC) as f64 }
+
+    /// Field encoding: negative values are p - |v|.
+    pub fn to_field<F: FieldExt>(self) -> F {
+        let magnitude = F::from(self.0.unsigned_abs());
+        if self.0 < 0 { -magnitude } else { magnitude }
+    }
+
+    /// Inverse of `to_field`; elements above (p-1)/2 read as negative. Fails outside i64.
+    pub fn from_field<F: FieldExt>(f: F) -> Result<Self, String> {
+        i64::try_from(crate::fr_to_i128(f)).map(Self).map_err(|_| "el elemento no cabe en i64".into())
+    }
+
+    pub fn checked_add(self, rhs: Self) -> Option<Self> { self.0.checked_add(rhs.0).map(Self) }
+
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> { self.0.checked_sub(rhs.0).map(Self) }
+
+    pub fn checked_mul(self, rhs: Self) -> Option<Self> { i64::try_from(Self::mul_raw(self, rhs)).ok().map(Self) }
+
+    pub fn saturating_add(self, rhs: Self) -> Self { Self(self.0.saturating_add(rhs.0)) }
+
+    pub fn saturating_sub(self, rhs: Self) -> Self { Self(self.0.saturating_sub(rhs.0)) }
+
+    pub fn saturating_mul(self, rhs: Self) -> Self { Self(Self::mul_raw(self, rhs).clamp(i64::MIN as i128, i64::MAX as i128) as i64) }
+
+    // a·b / 2^FRAC, floored
+    fn mul_raw(a: Self, b: Self) -> i128 { (a.0 as i128 * b.0 as i128).div_euclid(1i128 << FRAC) }
+}
+
+impl<const FRAC: u32> Add for Fixed<FRAC> {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self { Self(self.0 + rhs.0) }
+}
+
+impl<const FRAC: u32> Sub for Fixed<FRAC> {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self { Self(self.0 - rhs.0) }
+}
+
+impl<const FRAC: u32> Neg for Fixed<FRAC> {
+    type Output = Self;
+    fn neg(self) -> Self { Self(-self.0) }
+}
+
+impl<const FRAC: u32> Mul for Fixed<FRAC> {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        Self(i64::try_from(Self::mul_raw(self, rhs)).expect("desbordamiento en producto de punto fijo"))
+    }
+}
+
+impl<const FRAC: u32> std::iter::Sum for Fixed<FRAC> {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self { iter.fold(Self::ZERO, Add::add) }
+}
+
+impl<const FRAC: u32> fmt::Display for Fixed<FRAC> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { write!(f, "{}", self.to_f64()) }
+}
@@ -0,0 +1,365 @@
+// keccak.rs
+use crate::circuit::{AssignedCell, Layouter, Region, Value};
+use crate::FieldExt;
+use halo2_proofs::{
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Selector},
+    poly::Rotation,
+};
+
+// Bit-level Keccak-256 (Ethereum padding 0x01 .. 0x80). Every lane bit is its own cell and every
+// xor / chi / not is one row, so a 136-byte block costs ~116k rows: fine for one transaction,
+// not for bulk data. Lanes are little-endian: bit z of lane (x, y) is state[(x + 5y) * 64 + z].
+pub const RATE_BYTES: usize = 136;
+
+const ROTATIONS: [[u32; 5]; 5] = [
+    [0, 36, 3, 41, 18],
+    [1, 44, 10, 45, 2],
+    [62, 6, 43, 15, 61],
+    [28, 55, 25, 21, 56],
+    [27, 20, 39, 8, 14],
+];
+
+const ROUND_CONSTANTS: [u64; 24] = [
+    0x0000000000000001, 0x0000000000008082, 0x800000000000808A, 0x8000000080008000,
+    0x000000000000808B, 0x0000000080000001, 0x8000000080008081, 0x8000000000008009,
+    0x000000000000008A, 0x0000000000000088, 0x0000000080008009, 0x000000008000000A,
+    0x000000008000808B, 0x800000000000008B, 0x8000000000008089, 0x8000000000008003,
+    0x8000000000008002, 0x8000000000000080, 0x000000000000800A, 0x800000008000000A,
+    0x8000000080008081, 0x8000000000008080, 0x0000000080000001, 0x8000000080008008,
+];
+
+type Bit<F> = AssignedCell<F, F>;
+
+/// Padded length of an `n`-byte message.
+pub fn padded_len(n: usize) -> usize { (n / RATE_BYTES + 1) * RATE_BYTES }
+
+fn round_rows(round: usize) -> usize { 5 * 64 * 4 + 5 * 64 + 1600 + 1600 + ROUND_CONSTANTS[round].count_ones() as usize }
+
+/// Rows used by `hash` for an `n`-byte message (inputs, absorption, permutations, output packing).
+pub fn hash_rows(n: usize) -> usize {
+    let blocks = padded_len(n) / RATE_BYTES;
+    let permutation: usize = (0..24).map(round_rows).sum();
+    1 + 8 * padded_len(n) + (blocks - 1) * RATE_BYTES * 8 + blocks * permutation + 2 * pack_rows(128)
+}
+
+/// Rows used by `pack` for `bits` bits.
+pub fn pack_rows(bits: usize) -> usize { 1 + bits }
+
+// (hi, lo) halves of a digest
+type Digest<F> = (AssignedCell<F, F>, AssignedCell<F, F>);
+
+#[derive(Clone, Debug)]
+pub struct KeccakConfig {
+    adv: [Column<Advice>; 4],
+    q_bool: Selector,
+    q_xor: Selector,
+    q_chi: Selector,
+    q_not: Selector,
+    q_pack: Selector,
+}
+
+impl KeccakConfig {
+    /// Needs a constants column enabled elsewhere (padding bits and the zero lane are constants).
+    pub fn configure<F: FieldExt>(cs: &mut ConstraintSystem<F>, adv: [Column<Advice>; 4]) -> Self {
+        for c in adv { cs.enable_equality(c); }
+        let [q_bool, q_xor, q_chi, q_not, q_pack] = [0, 1, 2, 3, 4].map(|_| cs.selector());
+        let one = || Expression::Constant(F::ONE);
+        let two = || Expression::Constant(F::from(2));
+
+        cs.create_gate("keccak bit", |meta| {
+            let q = meta.query_selector(q_bool);
+            let a = meta.query_advice(adv[0], Rotation::cur());
+            vec![q * a.clone() * (one() - a)]
+        });
+        cs.create_gate("keccak xor", |meta| {
+            let q = meta.query_selector(q_xor);
+            let a = meta.query_advice(adv[0], Rotation::cur());
+            let b = meta.query_advice(adv[1], Rotation::cur());
+            let out = meta.query_advice(adv[3], Rotation::cur());
+            vec![q * (out - (a.clone() + b.clone() - two() * a * b))]
+        });
+        // a ^ (!b & c)
+        cs.create_gate("keccak chi", |meta| {
+            let q = meta.query_selector(q_chi);
+            let a = meta.query_advice(adv[0], Rotation::cur());
+            let b = meta.query_advice(adv[1], Rotation::cur());
+            let c = meta.query_advice(adv[2], Rotation::cur());
+            let out = meta.query_advice(adv[3], Rotation::cur());
+            let t = (one() - b) * c;
+            vec![q * (out - (a.clone() + t.clone() - two() * a * t))]
+        });
+        cs.create_gate("keccak not", |meta| {
+            let q = meta.query_selector(q_not);
+            let a = meta.query_advice(adv[0], Rotation::cur());
+            let out = meta.query_advice(adv[3], Rotation::cur());
+            vec![q * (out - (one() - a))]
+        });
+        // acc' = 2 * acc + bit
+        cs.create_gate("keccak pack", |meta| {
+            let q = meta.query_selector(q_pack);
+            let bit = meta.query_advice(adv[0], Rotation::cur());
+            let acc = meta.query_advice(adv[1], Rotation::cur());
+            let out = meta.query_advice(adv[3], Rotation::cur());
+            vec![q * (out - (two() * acc + bit))]
+        });
+
+        Self { adv, q_bool, q_xor, q_chi, q_not, q_pack }
+    }
+
+    fn constant<F: FieldExt>(&self, region: &mut Region<'_, F>, row: &mut usize, v: bool) -> Result<Bit<F>, Error> {
+        let v = if v { F::ONE } else { F::ZERO };
+        let cell = region.assign_advice(|| "const", self.adv[0], *row, || Value::known(v))?;
+        region.constrain_constant(cell.cell(), v)?;
+        *row += 1;
+        Ok(cell)
+    }
+
+    fn xor<F: FieldExt>(&self, region: &mut Region<'_, F>, row: &mut usize, a: &Bit<F>, b: &Bit<F>) -> Result<Bit<F>, Error> {
+        region.enable_selector(|| "xor", &self.q_xor, *row)?;
+        a.copy_advice(|| "a", region, self.adv[0], *row)?;
+        b.copy_advice(|| "b", region, self.adv[1], *row)?;
+        let v = a.value().zip(b.value()).map(|(a, b)| *a + b - F::from(2) * a * b);
+        let out = region.assign_advice(|| "a ^ b", self.adv[3], *row, || v)?;
+        *row += 1;
+        Ok(out)
+    }
+
+    fn chi<F: FieldExt>(&self, region: &mut Region<'_, F>, row: &mut usize, a: &Bit<F>, b: &Bit<F>, c: &Bit<F>) -> Result<Bit<F>, Error> {
+        region.enable_selector(|| "chi", &self.q_chi, *row)?;
+        a.copy_advice(|| "a", region, self.adv[0], *row)?;
+        b.copy_advice(|| "b", region, self.adv[1], *row)?;
+        c.copy_advice(|| "c", region, self.adv[2], *row)?;
+        let v = a.value().zip(b.value()).zip(c.value()).map(|((a, b), c)| {
+            let t = (F::ONE - b) * c;
+            *a + t - F::from(2) * a * t
+        });
+        let out = region.assign_advice(|| "chi", self.adv[3], *row, || v)?;
+        *row += 1;
+        Ok(out)
+    }
+
+    fn not<F: FieldExt>(&self, region: &mut Region<'_, F>, row: &mut usize, a: &Bit<F>) -> Result<Bit<F>, Error> {
+        region.enable_selector(|| "not", &self.q_not, *row)?;
+        a.copy_advice(|| "a", region, self.adv[0], *row)?;
+        let out = region.assign_advice(|| "!a", self.adv[3], *row, || a.value().map(|a| F::ONE - a))?;
+        *row += 1;
+        Ok(out)
+    }
+
+    /// Assigns the message bits (little-endian per byte), boolean-constrained. `bytes` has the shape's
+    /// length even without a witness.
+    pub fn assign_bytes<F: FieldExt>(&self, mut layouter: impl Layouter<F>, bytes: Value<&[u8]>, len: usize) -> Result<Vec<Bit<F>>, Error> {
+        layouter.assign_region(
+            || "keccak input",
+            |mut region| {
+                let mut bits = Vec::with_capacity(8 * len);
+                for i in 0..8 * len {
+                    region.enable_selector(|| "q_bool", &self.q_bool, i)?;
+                    let v = bytes.map(|b| F::from(((b[i / 8] >> (i % 8)) & 1) as u64));
+                    bits.push(region.assign_advice(|| format!("bit {i}"), self.adv[0], i, || v)?);
+                }
+                Ok(bits)
+            },
+        )
+    }
+
+    /// Packs bits, most significant first, into one cell. At most 248 bits so the value stays canonical.
+    pub fn pack<F: FieldExt>(&self, mut layouter: impl Layouter<F>, bits: &[Bit<F>]) -> Result<AssignedCell<F, F>, Error> {
+        assert!(!bits.is_empty() && bits.len() <= 248, "pack: número de bits fuera de rango");
+        layouter.assign_region(
+            || "keccak pack",
+            |mut region| {
+                let mut row = 0;
+                let mut acc = self.constant(&mut region, &mut row, false)?;
+                for bit in bits {
+                    region.enable_selector(|| "q_pack", &self.q_pack, row)?;
+                    bit.copy_advice(|| "bit", &mut region, self.adv[0], row)?;
+                    acc.copy_advice(|| "acc", &mut region, self.adv[1], row)?;
+                    let v = acc.value().zip(bit.value()).map(|(acc, b)| *acc * F::from(2) + b);
+                    acc = region.assign_advice(|| "acc'", self.adv[3], row, || v)?;
+                    row += 1;
+                }
+                Ok(acc)
+            },
+        )
+    }
+
+    /// Big-endian bits of byte-aligned `data` (the order `pack` expects for integers).
+    pub fn be_bits<F: FieldExt>(data: &[Bit<F>], offset: usize, len: usize) -> Vec<Bit<F>> {
+        (offset..offset + len).flat_map(|byte| (0..8).rev().map(move |b| data[8 * byte + b].clone())).collect()
+    }
+
+    /// Keccak-256 of the message bits from `assign_bytes`; returns (hi, lo): the digest's
+    /// first and last 16 bytes as big-endian integers.
+    pub fn hash<F: FieldExt>(&self, mut layouter: impl Layouter<F>, message: &[Bit<F>]) -> Result<Digest<F>, Error> {
+        assert_eq!(message.len() % 8, 0);
+        let n = message.len() / 8;
+        let padded = padded_len(n);
+
+        // message bits followed by constant padding bits, then the zero lane
+        let (blocks, zero) = layouter.assign_region(
+            || "keccak padding",
+            |mut region| {
+                let mut row = 0;
+                let zero = self.constant(&mut region, &mut row, false)?;
+                let mut bits = message.to_vec();
+                for byte in n..padded {
+                    let mut pad = 0u8;
+                    if byte == n { pad |= 0x01; }
+                    if byte == padded - 1 { pad |= 0x80; }
+                    for b in 0..8 {
+                        bits.push(self.constant(&mut region, &mut row, (pad >> b) & 1 == 1)?);
+                    }
+                }
+                Ok((bits, zero))
+            },
+        )?;
+
+        let mut state: Vec<Bit<F>> = vec![zero; 1600];
+        for (k, block) in blocks.chunks(RATE_BYTES * 8).enumerate() {
+            if k == 0 {
+                state[..block.len()].clone_from_slice(block);
+            } else {
+                state = layouter.assign_region(
+                    || format!("keccak absorb {k}"),
+                    |mut region| {
+                        let mut row = 0;
+                        let mut next = state.clone();
+                        for (i, bit) in block.iter().enumerate() {
+                            next[i] = self.xor(&mut region, &mut row, &state[i], bit)?;
+                        }
+                        Ok(next)
+                    },
+                )?;
+            }
+            for round in 0..24 {
+                state = layouter.assign_region(|| format!("keccak block {k} round {round}"), |mut region| self.round(&mut region, &state, round))?;
+            }
+        }
+
+        // digest byte j is byte j of the little-endian lanes 0..3
+        let digest: Vec<Bit<F>> = (0..32).flat_map(|j| (0..8).rev().map(move |b| 8 * j + b)).map(|i| state[i].clone()).collect();
+        let hi = self.pack(layouter.namespace(|| "digest hi"), &digest[..128])?;
+        let lo = self.pack(layouter.namespace(|| "digest lo"), &digest[128..])?;
+        Ok((hi, lo))
+    }
+
+    fn round<F: FieldExt>(&self, region: &mut Region<'_, F>, a: &[Bit<F>], round: usize) -> Result<Vec<Bit<F>>, Error> {
+        let idx = |x: usize, y: usize, z: usize| (x + 5 * y) * 64 + z;
+        let mut row = 0;
+
+        // theta
+        let mut c = Vec::with_capacity(320);
+        for x in 0..5 {
+            for z in 0..64 {
+                let mut p = self.xor(region, &mut row, &a[idx(x, 0, z)], &a[idx(x, 1, z)])?;
+                for y in 2..5 { p = self.xor(region, &mut row, &p, &a[idx(x, y, z)])?; }
+                c.push(p);
+            }
+        }
+        let mut d = Vec::with_capacity(320);
+        for x in 0..5 {
+            for z in 0..64 {
+                d.push(self.xor(region, &mut row, &c[((x + 4) % 5) * 64 + z], &c[((x + 1) % 5) * 64 + (z + 63) % 64])?);
+            }
+        }
+        let mut t = Vec::with_capacity(1600);
+        for y in 0..5 {
+            for x in 0..5 {
+                for z in 0..64 { t.push(self.xor(region, &mut row, &a[idx(x, y, z)], &d[x * 64 + z])?); }
+            }
+        }
+
+        // rho + pi: pure rewiring
+        let mut b = t.clone();
+        for x in 0..5 {
+            for y in 0..5 {
+                let r = ROTATIONS[x][y] as usize;
+                for z in 0..64 { b[idx(y, (2 * x + 3 * y) % 5, z)] = t[idx(x, y, (z + 64 - r) % 64)].clone(); }
+            }
+        }
+
+        // chi
+        let mut out = Vec::with_capacity(1600);
+        for y in 0..5 {
+            for x in 0..5 {
+                for z in 0..64 {
+                    out.push(self.chi(region, &mut row, &b[idx(x, y, z)], &b[idx((x + 1) % 5, y, z)], &b[idx((x + 2) % 5, y, z)])?);
+                }
+            }
+        }
+
+        // iota
+        for (z, lane) in out.iter_mut().enumerate().take(64) {
+            if (ROUND_CONSTANTS[round] >> z) & 1 == 1 { *lane = self.not(region, &mut row, lane)?; }
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit::{pin_degree, SimpleFloorPlanner, StackedLayouter};
+    use crate::prover::tx_hash_instances;
+    use halo2_proofs::{circuit as h2, dev::MockProver, halo2curves::bn256::Fr, plonk::{Circuit, Instance}};
+    use sha3::{Digest as _, Keccak256};
+
+    // keccak256 of the message, published as (hi, lo)
+    #[derive(Clone, Default)]
+    struct Hashed(Vec<u8>);
+
+    impl Circuit<Fr> for Hashed {
+        type Config = (KeccakConfig, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+        type Params = ();
+
+        fn without_witnesses(&self) -> Self { self.clone() }
+
+        fn configure(cs: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let adv = [(); 4].map(|_| cs.advice_column());
+            let constants = cs.fixed_column();
+            cs.enable_constant(constants);
+            let instance = cs.instance_column();
+            cs.enable_equality(instance);
+            let keccak = KeccakConfig::configure(cs, adv);
+            pin_degree(cs);
+            (keccak, instance)
+        }
+
+        fn synthesize(&self, (keccak, instance): Self::Config, layouter: impl h2::Layouter<Fr>) -> Result<(), Error> {
+            let mut layouter = StackedLayouter::new(layouter);
+            let bits = keccak.assign_bytes(layouter.namespace(|| "message"), Value::known(&self.0[..]), self.0.len())?;
+            let (hi, lo) = keccak.hash(layouter.namespace(|| "keccak"), &bits)?;
+            layouter.constrain_instance(hi.cell(), instance, 0)?;
+            layouter.constrain_instance(lo.cell(), instance, 1)
+        }
+    }
+
+    fn check(message: &[u8], digest: &[u8]) -> bool {
+        let k = (hash_rows(message.len()) + 8 * message.len() + 16).next_power_of_two().ilog2();
+        MockProver::run(k, &Hashed(message.to_vec()), vec![tx_hash_instances(digest)]).unwrap().verify().is_ok()
+    }
+
+    #[test]
+    fn padded_length_leaves_room_for_the_padding() {
+        assert_eq!(padded_len(0), RATE_BYTES);
+        assert_eq!(padded_len(RATE_BYTES - 1), RATE_BYTES);
+        assert_eq!(padded_len(RATE_BYTES), 2 * RATE_BYTES);
+    }
+
+    #[test]
+    fn empty_message_matches_the_reference_digest() {
+        let digest = hex::decode("c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470").unwrap();
+        assert_eq!(Keccak256::digest(b"")[..], digest[..]);
+        assert!(check(b"", &digest));
+    }
+
+    #[test]
+    fn other_digest_is_rejected() {
+        let mut digest = Keccak256::digest(b"abc").to_vec();
+        assert!(check(b"abc", &digest));
+        digest[31] ^= 1;
+        assert!(!check(b"abc", &digest));
+    }
+}
@@ -0,0 +1,198 @@
+// keys.rs
+use crate::envelope::circuit_version;
+use crate::error::{QuantumGuardError as E, Result};
+use crate::Shape;
+use halo2_proofs::{
+    halo2curves::bn256::{Fr, G1Affine},
+    plonk::{Circuit, ProvingKey, VerifyingKey},
+    SerdeFormat,
+};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+
+// Key files: magic, version u16, header length u32, JSON header, then the key in halo2's raw
+// (uncompressed) encoding: larger files, but loading skips point decompression. Files without
+// the magic are bare raw keys from older releases and read with no header.
+pub const PK_MAGIC: &[u8; 4] = b"QGPK";
+pub const VK_MAGIC: &[u8; 4] = b"QGVK";
+pub const KEY_VERSION: u16 = 1;
+
+/// What a key was generated for.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyHeader {
+    // circuit_version() of the build that ran keygen
+    pub circuit_version: String,
+    pub k: u32,
+    // TxCircuit keys only
+    #[serde(default)]
+    pub shape: Option<Shape>,
+}
+
+impl KeyHeader {
+    fn new(k: u32, shape: Option<Shape>) -> Self { Self { circuit_version: circuit_version(), k, shape } }
+
+    /// Rejects a TxCircuit key made for another shape; keys without one pass.
+    pub fn check_shape(&self, shape: &Shape) -> Result<()> {
+        match &self.shape {
+            Some(s) if s != shape => Err(E::Shape(format!("la clave es para {} features, se esperan {}", s.features, shape.features))),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn write_framed(w: &mut impl Write, magic: &[u8; 4], header: &KeyHeader, key: &[u8]) -> Result<()> {
+    let header = serde_json::to_vec(header).expect("serialización en memoria");
+    let io = |e: std::io::Error| E::Key(e.to_string());
+    w.write_all(magic).map_err(io)?;
+    w.write_all(&KEY_VERSION.to_le_bytes()).map_err(io)?;
+    w.write_all(&(header.len() as u32).to_le_bytes()).map_err(io)?;
+    w.write_all(&header).map_err(io)?;
+    w.write_all(key).map_err(io)
+}
+
+// (header, raw key) of a framed or legacy file
+fn split<'a>(bytes: &'a [u8], magic: &[u8; 4], other: &[u8; 4]) -> Result<(Option<KeyHeader>, &'a [u8])> {
+    let truncated = || E::Key("archivo de clave truncado".into());
+    match bytes.get(..4) {
+        Some(m) if m == other => return Err(E::Key(format!("se esperaba una clave {}, el archivo es {}", String::from_utf8_lossy(magic), String::from_utf8_lossy(other)))),
+        Some(m) if m == magic => {}
+        _ => return Ok((None, bytes)),
+    }
+    let version = u16::from_le_bytes(bytes.get(4..6).ok_or_else(truncated)?.try_into().unwrap());
+    if version != KEY_VERSION {
+        return Err(E::Key(format!("versión de clave {version} no soportada (se espera {KEY_VERSION})")));
+    }
+    let len = u32::from_le_bytes(bytes.get(6..10).ok_or_else(truncated)?.try_into().unwrap()) as usize;
+    let header: KeyHeader = serde_json::from_slice(bytes.get(10..10 + len).ok_or_else(truncated)?)
+        .map_err(|e| E::Key(format!("cabecera de clave ilegible: {e}")))?;
+    if header.circuit_version != circuit_version() {
+        return Err(E::Key(format!("clave generada por el circuito {}, este es {}: vuelve a ejecutar keygen", header.circuit_version, circuit_version())));
+    }
+    Ok((Some(header), &bytes[10 + len..]))
+}
+
+fn read_all(r: &mut impl Read) -> Result<Vec<u8>> {
+    let mut bytes = vec![];
+    r.read_to_end(&mut bytes).map_err(|e| E::Key(e.to_string()))?;
+    Ok(bytes)
+}
+
+pub struct ProvingKeyFile {
+    pub header: Option<KeyHeader>,
+    pub pk: ProvingKey<G1Affine>,
+}
+
+impl ProvingKeyFile {
+    /// `shape` for TxCircuit keys; k is read from the key.
+    pub fn new(pk: ProvingKey<G1Affine>, shape: Option<Shape>) -> Self {
+        Self { header: Some(KeyHeader::new(pk.get_vk().get_domain().k(), shape)), pk }
+    }
+
+    pub fn write(&self, w: &mut impl Write) -> Result<()> {
+        let mut key = vec![];
+        self.pk.write(&mut key, SerdeFormat::RawBytes).map_err(|e| E::Key(e.to_string()))?;
+        let header = self.header.clone().unwrap_or_else(|| KeyHeader::new(self.pk.get_vk().get_domain().k(), None));
+        write_framed(w, PK_MAGIC, &header, &key)
+    }
+
+    pub fn read<C: Circuit<Fr, Params = ()>>(r: &mut impl Read) -> Result<Self> {
+        let bytes = read_all(r)?;
+        let (header, mut key) = split(&bytes, PK_MAGIC, VK_MAGIC)?;
+        let pk = ProvingKey::read::<_, C>(&mut key, SerdeFormat::RawBytes, ()).map_err(|e| E::Key(format!("clave de prueba ilegible: {e}")))?;
+        Ok(Self { header, pk })
+    }
+}
+
+pub struct VerifyingKeyFile {
+    pub header: Option<KeyHeader>,
+    pub vk: VerifyingKey<G1Affine>,
+}
+
+impl VerifyingKeyFile {
+    pub fn new(vk: VerifyingKey<G1Affine>, shape: Option<Shape>) -> Self {
+        Self { header: Some(KeyHeader::new(vk.get_domain().k(), shape)), vk }
+    }
+
+    pub fn write(&self, w: &mut impl Write) -> Result<()> {
+        let mut key = vec![];
+        self.vk.write(&mut key, SerdeFormat::RawBytes).map_err(|e| E::Key(e.to_string()))?;
+        let header = self.header.clone().unwrap_or_else(|| KeyHeader::new(self.vk.get_domain().k(), None));
+        write_framed(w, VK_MAGIC, &header, &key)
+    }
+
+    pub fn read<C: Circuit<Fr, Params = ()>>(r: &mut impl Read) -> Result<Self> {
+        let bytes = read_all(r)?;
+        let (header, mut key) = split(&bytes, VK_MAGIC, PK_MAGIC)?;
+        let vk = VerifyingKey::read::<_, C>(&mut key, SerdeFormat::RawBytes, ()).map_err(|e| E::Key(format!("clave de verificación ilegible: {e}")))?;
+        Ok(Self { header, vk })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{prover::keygen, TxCircuit};
+    use halo2_proofs::{halo2curves::bn256::Bn256, poly::kzg::commitment::ParamsKZG};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    fn shape() -> Shape { Shape { features: 2, ..Default::default() } }
+
+    fn pk() -> ProvingKey<G1Affine> {
+        let circ = TxCircuit::<Fr>::empty(shape());
+        let params = ParamsKZG::<Bn256>::setup(circ.cost_estimate().min_k, ChaCha20Rng::seed_from_u64(0));
+        keygen(&params, &circ).unwrap()
+    }
+
+    fn written(file: &VerifyingKeyFile) -> Vec<u8> {
+        let mut bytes = vec![];
+        file.write(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn key_files_round_trip() {
+        let pk = pk();
+        let vk_file = VerifyingKeyFile::new(pk.get_vk().clone(), Some(shape()));
+        let read = VerifyingKeyFile::read::<TxCircuit<Fr>>(&mut &written(&vk_file)[..]).unwrap();
+        assert_eq!(read.header, vk_file.header);
+        assert_eq!(read.vk.transcript_repr(), pk.get_vk().transcript_repr());
+
+        let mut bytes = vec![];
+        ProvingKeyFile::new(pk.clone(), Some(shape())).write(&mut bytes).unwrap();
+        let read = ProvingKeyFile::read::<TxCircuit<Fr>>(&mut &bytes[..]).unwrap();
+        assert_eq!(read.header.unwrap().shape, Some(shape()));
+        assert_eq!(read.pk.get_vk().transcript_repr(), pk.get_vk().transcript_repr());
+        // a proving key is not a verifying key
+        assert!(VerifyingKeyFile::read::<TxCircuit<Fr>>(&mut &bytes[..]).is_err());
+    }
+
+    // bare raw keys from older releases
+    #[test]
+    fn reads_legacy_keys_without_header() {
+        let pk = pk();
+        let mut raw = vec![];
+        pk.get_vk().write(&mut raw, SerdeFormat::RawBytes).unwrap();
+        let read = VerifyingKeyFile::read::<TxCircuit<Fr>>(&mut &raw[..]).unwrap();
+        assert!(read.header.is_none());
+        assert_eq!(read.vk.transcript_repr(), pk.get_vk().transcript_repr());
+    }
+
+    #[test]
+    fn rejects_other_circuits_and_shapes() {
+        let vk_file = VerifyingKeyFile::new(pk().get_vk().clone(), Some(shape()));
+        let mut header = vk_file.header.clone().unwrap();
+        header.circuit_version = "00".repeat(32);
+        let mut key = vec![];
+        vk_file.vk.write(&mut key, SerdeFormat::RawBytes).unwrap();
+        let mut bytes = vec![];
+        write_framed(&mut bytes, VK_MAGIC, &header, &key).unwrap();
+        assert!(VerifyingKeyFile::read::<TxCircuit<Fr>>(&mut &bytes[..]).is_err());
+
+        let bytes = written(&vk_file);
+        assert!(VerifyingKeyFile::read::<TxCircuit<Fr>>(&mut &bytes[..20]).is_err());
+        let header = vk_file.header.unwrap();
+        assert!(header.check_shape(&shape()).is_ok());
+        assert!(header.check_shape(&Shape { features: 3, ..shape() }).is_err());
+    }
+}
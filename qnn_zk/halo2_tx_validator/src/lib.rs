@@ -1,111 +1,889 @@
 // lib.rs
+extern crate alloc;
+
+use crate::circuit::{pin_degree, AssignedCell, Layouter, SimpleFloorPlanner, StackedLayouter, Value};
 use halo2_proofs::{
-    arithmetic::FieldExt,
-    circuit::{Layouter, SimpleFloorPlanner, Value},
-    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance, Selector},
+    circuit as h2,
+    halo2curves::{bn256::Fr, ff::{FromUniformBytes, PrimeField}},
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance},
 };
-use halo2_gadgets::poseidon::{Hash, Pow5Chip, Pow5Config};
-use halo2_proofs::pairing::bn256::Fr;
+use poseidon_primitives::poseidon::primitives::Spec;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+pub mod batch;
+pub mod builder;
+pub mod calibrate;
+pub mod ceremony;
+pub mod circuit;
+pub mod codec;
+pub mod compare;
+pub mod decompose;
+pub mod div;
+pub mod eddsa;
+pub mod envelope;
+pub mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod fixed;
+pub mod keccak;
+pub mod keys;
+pub mod ensemble;
+pub mod merkle;
+pub mod numeric;
+#[cfg(feature = "pasta")]
+pub mod pasta;
+pub mod poseidon;
+#[cfg(feature = "poseidon2")]
+pub mod poseidon2;
+pub mod preset;
+pub mod prover;
+pub mod public;
+pub mod pwl;
+pub mod range;
+#[cfg(feature = "remote")]
+pub mod remote;
+pub mod rlp;
+pub mod score;
+pub mod srs;
+pub mod store;
+pub mod tree;
+pub mod velocity;
+pub mod verifier;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod witness;
+use calibrate::{CalibrateConfig, Calibration};
+use compare::CompareConfig;
+use div::DivConfig;
+use eddsa::{EddsaConfig, EdwardsCurve, OracleSignature};
+use keccak::KeccakConfig;
+use merkle::{MerkleConfig, MerkleWitness, ADDRESS_BITS};
+use pwl::PwlConfig;
+use range::RangeConfig;
+// Every commitment goes through these; `poseidon2` swaps the chip behind the same sponge calls.
+#[cfg(not(feature = "poseidon2"))]
+pub(crate) use poseidon::{Hash, Input, Pow5Chip as PoseidonChip, Pow5Config as PoseidonConfig};
+#[cfg(feature = "poseidon2")]
+pub(crate) use poseidon2::{Hash, Input, Poseidon2Chip as PoseidonChip, Poseidon2Config as PoseidonConfig};
+use rlp::{RlpConfig, RlpFeatures};
+use score::{ScoreConfig, ScoreInputs};
 
-const FRAC_BITS: u32 = 16;
+/// Prime fields with a 32-byte little-endian representation (BN256 and Pasta scalars).
+pub trait FieldExt: PrimeField<Repr = [u8; 32]> {
+    /// Low 128 bits of the canonical representation.
+    fn get_lower_128(&self) -> u128 { u128::from_le_bytes(self.to_repr()[..16].try_into().unwrap()) }
+}
 
-fn sigmoid_poly(x: Fr) -> Fr {
-    let scale = Fr::from(1u64 << FRAC_BITS);
-    let c0 = Fr::from(((0.5f64 * (1u64<<FRAC_BITS) as f64).round()) as u64);
-    let c1 = Fr::from(((0.25f64 * (1u64<<FRAC_BITS) as f64).round()) as u64);
-    let c3 = Fr::from(((-0.0208333333333f64 * (1u64<<FRAC_BITS) as f64).round() as i64) as u64);
+impl<F: PrimeField<Repr = [u8; 32]>> FieldExt for F {}
 
-    let x1 = x;
-    let x2 = x1 * x1;
-    let x3 = x2 * x1;
-    let term1 = (c1 * x1) * scale.invert().unwrap();
-    let term3 = (c3 * x3) * (scale * scale).invert().unwrap();
+/// Fields the transaction circuit can be instantiated over; selects the Poseidon parameters per field.
+pub trait CircuitField: FieldExt + FromUniformBytes<64> + Ord {
+    type PoseidonSpec: Spec<Self, 3, 2>;
 
-    c0 + term1 + term3
+    /// Twisted Edwards curve embedded in this field, for in-circuit oracle signatures.
+    fn edwards() -> Option<EdwardsCurve<Self>> { None }
 }
 
+impl CircuitField for Fr {
+    type PoseidonSpec = poseidon::P128Pow5T3<Self>;
+
+    fn edwards() -> Option<EdwardsCurve<Self>> { Some(eddsa::baby_jubjub()) }
+}
+
+#[cfg(feature = "pasta")]
+impl CircuitField for pasta::Fp {
+    type PoseidonSpec = poseidon::P128Pow5T3<Self>;
+}
+
+// Purpose tag absorbed first by every commitment, ahead of the deployment domain, so commitments
+// of different kinds never share a preimage space (a commit_wb can never equal a commit_q).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tag {
+    Wb = 1,
+    Q = 2,
+    X = 3,
+    Nullifier = 4,
+    Now = 5,
+    Counterparty = 6,
+    Destination = 7,
+    Amounts = 8,
+    Oracle = 9,
+    Calibration = 10,
+}
+
+impl Tag {
+    pub fn value<F: FieldExt>(self) -> F { F::from(self as u64) }
+
+    /// Constant cell holding the tag, for sponges that absorb cells.
+    pub fn assign<F: FieldExt>(self, mut layouter: impl Layouter<F>, column: Column<Advice>) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || format!("tag {self:?}"),
+            |mut region| region.assign_advice_from_constant(|| "tag", column, 0, self.value())
+        )
+    }
+}
+
+pub const FRAC_BITS: u32 = 16;
+// 1.0 in Q16.16: upper bound for probabilities (q_out) and mixing weights (alpha).
+pub const ONE_Q16: u64 = 1 << FRAC_BITS;
+// Unix seconds fit in 40 bits until year 36812.
+const TIME_BITS: usize = 40;
+pub const NEVER_EXPIRES: u64 = (1 << TIME_BITS) - 1;
+// Amounts (Q16.16, non-negative) and their public limit fit in 64 bits.
+pub const AMOUNT_BITS: usize = 64;
+// TxCircuit public input columns (see `Config::instance`).
+pub const INSTANCE_COLUMNS: usize = 18;
+
 #[derive(Clone, Debug)]
-pub struct Config {
+pub struct Config<F: FieldExt> {
     adv: [Column<Advice>; 6],
-    sel: Selector,
-    poseidon: Pow5Config<Fr, 3, 2>,
-    instance: [Column<Instance>; 3], // commit_wb, commit_q, score_pub
+    poseidon: PoseidonConfig<F, 3, 2>,
+    range: RangeConfig,
+    compare: CompareConfig,
+    pwl: PwlConfig,
+    score: ScoreConfig,
+    merkle: MerkleConfig,
+    keccak: KeccakConfig,
+    rlp: RlpConfig,
+    eddsa: EddsaConfig,
+    calibrate: CalibrateConfig,
+    div: DivConfig,
+    // Witnesses (x, w, b, q_out, alpha) never appear here: only commitments, the score and
+    // policy bounds. The prover blinds every advice column, so proofs add nothing beyond these.
+    // commit_wb, commit_q, score_pub, commit_x, valid_until, commit_now, domain, sanctions_root, commit_counterparty,
+    // allowlist_root, commit_destination, amount_limit, disclosed features, registry_root, model_id,
+    // tx_hash (hi, lo), oracle key and epoch (x, y, epoch), commit_calibration
+    instance: [Column<Instance>; INSTANCE_COLUMNS],
 }
 
 #[derive(Clone, Debug, Default)]
-pub struct TxCircuit {
-    pub x: Vec<Fr>,
-    pub w: Vec<Fr>,
-    pub b: Fr,
-    pub alpha: Fr,
-    pub q_out: Fr,
-    pub score_pub: Fr,
+// Generic over the field so the scoring gadgets can be reused on other curves (see `pasta`);
+// the CLI and the other circuits stay on BN256.
+pub struct TxCircuit<F: CircuitField = Fr> {
+    pub x: Vec<F>,
+    pub w: Vec<F>,
+    pub b: F,
+    pub alpha: F,
+    pub q_out: F,
+    pub score_pub: F,
+    // Proof expiry: now <= valid_until, with `now` committed via Poseidon.
+    pub now: F,
+    pub valid_until: F,
+    // Deployment tag (chain id or hashed name), absorbed first by every commitment.
+    pub domain: F,
+    pub activation: Activation,
+    // Counterparty is absent from the committed sanctions tree (sorted-leaf gap proof).
+    pub sanctions: Option<MerkleWitness<F>>,
+    // Destination is a leaf of the committed allowlist tree (leaf low == destination != 0).
+    pub allowlist: Option<MerkleWitness<F>>,
+    // x[amount_index] <= amount_limit (public).
+    pub amount_index: Option<usize>,
+    pub amount_limit: F,
+    // Random salt absorbed into commit_x in hiding mode, so low-entropy features
+    // cannot be recovered by hashing candidate vectors.
+    pub blinding: Option<F>,
+    // Features revealed as public inputs, as a bitmask over the first 64 indices.
+    pub disclosed: u64,
+    // commit_wb is registered under model_id in a public registry root (low = id, next = commit_wb).
+    pub registry: Option<MerkleWitness<F>>,
+    // Raw transaction bytes whose keccak256 is the public tx hash; `tx_fields` ties features to them.
+    pub tx: Option<Vec<u8>>,
+    pub tx_fields: Vec<TxField>,
+    // Legacy transaction fields decoded in-circuit from `tx` and bound to features.
+    pub rlp: Option<RlpFeatures>,
+    // x[feature] = x[num] / x[den] in Q16.16, proven with the division chip.
+    pub ratios: Vec<Ratio>,
+    // q_out signed by the quantum oracle under a public key (see eddsa.rs).
+    pub oracle: Option<OracleSignature<F>>,
+    // Committed calibration applied to the score; score_pub is then the calibrated probability.
+    pub calibration: Option<Calibration>,
+}
+
+// Everything keygen depends on. Proving and verifying keys are valid for every witness of the same shape.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Shape {
+    pub features: usize,
+    #[serde(default)]
+    pub activation: Activation,
+    // 0 = no sanctions screening
+    #[serde(default)]
+    pub sanctions_depth: usize,
+    // 0 = no allowlist
+    #[serde(default)]
+    pub allowlist_depth: usize,
+    // feature holding the transaction amount, if a limit is enforced
+    #[serde(default)]
+    pub amount_index: Option<usize>,
+    // commit_x = H(domain, r, x) instead of H(domain, x)
+    #[serde(default)]
+    pub hiding: bool,
+    // bit i set: x[i] is published (in index order) in the disclosure column
+    #[serde(default)]
+    pub disclosed: u64,
+    // 0 = single committed model, no registry
+    #[serde(default)]
+    pub registry_depth: usize,
+    // 0 = no tx hash binding; otherwise the raw transaction length in bytes
+    #[serde(default)]
+    pub tx_bytes: usize,
+    #[serde(default)]
+    pub tx_fields: Vec<TxField>,
+    // features fed by the RLP decoder (requires tx_bytes > 0)
+    #[serde(default)]
+    pub rlp: Option<RlpFeatures>,
+    // features proven to be the Q16.16 quotient of two others
+    #[serde(default)]
+    pub ratios: Vec<Ratio>,
+    // q_out carries an oracle signature checked in-circuit
+    #[serde(default)]
+    pub oracle: bool,
+    // score_pub is the raw score mapped through a committed calibration
+    #[serde(default)]
+    pub calibrated: bool,
+}
+
+// x[feature] equals the big-endian integer in tx bytes [offset, offset + len), taken as is (not Q16).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TxField {
+    pub feature: usize,
+    pub offset: usize,
+    pub len: usize,
+}
+
+impl std::str::FromStr for TxField {
+    type Err = String;
+    /// `feature:offset:len`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<usize> = s.split(':').map(|p| p.parse().map_err(|_| format!("campo tx inválido: {s}"))).collect::<Result<_, _>>()?;
+        match parts[..] {
+            [feature, offset, len] if (1..=31).contains(&len) => Ok(TxField { feature, offset, len }),
+            _ => Err(format!("campo tx inválido: {s} (feature:offset:len, len 1..=31)")),
+        }
+    }
+}
+
+// x[feature] = floor(x[num] * 2^16 / x[den]); operands are non-negative Q16.16 below 2^AMOUNT_BITS.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Ratio {
+    pub feature: usize,
+    pub num: usize,
+    pub den: usize,
+}
+
+impl Ratio {
+    /// The quotient the circuit assigns, or None if the denominator is not positive.
+    pub fn quotient_q16(num: i64, den: i64) -> Option<i64> {
+        (num >= 0 && den > 0).then(|| (((num as i128) << FRAC_BITS) / den as i128) as i64)
+    }
+}
+
+impl std::str::FromStr for Ratio {
+    type Err = String;
+    /// `feature:num:den`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<usize> = s.split(':').map(|p| p.parse().map_err(|_| format!("ratio inválido: {s}"))).collect::<Result<_, _>>()?;
+        match parts[..] {
+            [feature, num, den] => Ok(Ratio { feature, num, den }),
+            _ => Err(format!("ratio inválido: {s} (feature:num:den)")),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Activation {
+    // c0 + c1*z + c3*z^3, products floored as in score.rs
+    #[default]
+    CubicSigmoid,
+    // 16-segment piecewise-linear sigmoid, fully constrained (see pwl.rs)
+    PwlSigmoid,
+}
+
+impl std::str::FromStr for Activation {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "cubic" => Ok(Activation::CubicSigmoid),
+            "pwl" => Ok(Activation::PwlSigmoid),
+            _ => Err(format!("activación desconocida: {s} (cubic, pwl)")),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CostEstimate {
+    pub rows: usize,
+    pub advice_columns: usize,
+    pub fixed_columns: usize,
+    pub instance_columns: usize,
+    pub selectors: usize,
+    pub lookups: usize,
+    pub min_k: u32,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct GateStats {
+    pub name: String,
+    pub constraints: usize,
+    pub degree: usize,
+}
+
+// Constraint-system view for audits. Copy constraints (and hence permutation cycles) exist only
+// once a witness is laid out; the configuration fixes which columns take part and how the
+// permutation argument is chunked.
+#[derive(Clone, Debug, Serialize)]
+pub struct CircuitStats {
+    pub cost: CostEstimate,
+    pub degree: usize,
+    pub gates: Vec<GateStats>,
+    pub permutation_columns: usize,
+    pub permutation_chunks: usize,
+}
+
+// Rows taken by one sponge: the initial-state region, then one absorb row + one permutation per RATE-sized chunk.
+#[cfg(not(feature = "poseidon2"))]
+pub fn poseidon_rows<F: CircuitField>(inputs: usize) -> usize {
+    let permutations = inputs.div_ceil(2).max(1);
+    1 + permutations * (1 + poseidon::permutation_rows::<F>())
+}
+
+// Poseidon2 absorbs in the first round row: one permutation region per RATE-sized chunk.
+#[cfg(feature = "poseidon2")]
+pub fn poseidon_rows<F: CircuitField>(inputs: usize) -> usize {
+    inputs.div_ceil(2).max(1) * poseidon2::PERMUTATION_ROWS
+}
+
+impl<F: CircuitField> TxCircuit<F> {
+    /// Witness-free circuit with the same layout as any witness of `shape`; use it for keygen.
+    pub fn empty(shape: Shape) -> Self {
+        Self {
+            x: vec![F::from(0); shape.features],
+            w: vec![F::from(0); shape.features],
+            activation: shape.activation,
+            sanctions: (shape.sanctions_depth > 0).then(|| MerkleWitness::empty(shape.sanctions_depth)),
+            allowlist: (shape.allowlist_depth > 0).then(|| MerkleWitness::empty(shape.allowlist_depth)),
+            amount_index: shape.amount_index,
+            blinding: shape.hiding.then(|| F::from(0)),
+            disclosed: shape.disclosed,
+            registry: (shape.registry_depth > 0).then(|| MerkleWitness::empty(shape.registry_depth)),
+            tx: (shape.tx_bytes > 0).then(|| vec![0; shape.tx_bytes]),
+            tx_fields: shape.tx_fields,
+            rlp: shape.rlp,
+            ratios: shape.ratios,
+            oracle: shape.oracle.then(OracleSignature::default),
+            calibration: shape.calibrated.then(|| Calibration { knots: vec![0; calibrate::BUCKETS + 1] }),
+            ..Default::default()
+        }
+    }
+
+    pub fn shape(&self) -> Shape {
+        Shape {
+            features: self.x.len(),
+            activation: self.activation,
+            sanctions_depth: self.sanctions.as_ref().map_or(0, |s| s.siblings.len()),
+            allowlist_depth: self.allowlist.as_ref().map_or(0, |s| s.siblings.len()),
+            amount_index: self.amount_index,
+            hiding: self.blinding.is_some(),
+            disclosed: self.disclosed,
+            registry_depth: self.registry.as_ref().map_or(0, |s| s.siblings.len()),
+            tx_bytes: self.tx.as_ref().map_or(0, Vec::len),
+            tx_fields: self.tx_fields.clone(),
+            rlp: self.rlp,
+            ratios: self.ratios.clone(),
+            oracle: self.oracle.is_some(),
+            calibrated: self.calibration.is_some(),
+        }
+    }
+
+    /// The scoring part of the witness, as consumed by `ScoreConfig`.
+    pub fn score_inputs(&self) -> ScoreInputs<F> {
+        ScoreInputs { x: self.x.clone(), w: self.w.clone(), b: self.b, alpha: self.alpha, q_out: self.q_out, activation: self.activation }
+    }
+
+    /// Integer pre-activation z in Q16.16, with floor rescaling after each product.
+    pub fn preactivation_q16(&self) -> i64 { self.score_inputs().preactivation_q16() }
+
+    /// Native commit_wb (instance 0): Poseidon over the tag, the domain, w and b.
+    pub fn commit_wb(&self) -> F {
+        let mut inputs = self.w.clone();
+        inputs.push(self.b);
+        commit_in_domain(Tag::Wb, self.domain, &inputs)
+    }
+
+    /// Native commit_q (instance 1): Poseidon over the tag, the domain and q_out.
+    pub fn commit_q(&self) -> F { commit_in_domain(Tag::Q, self.domain, &[self.q_out]) }
+
+    /// Native score for the configured activation, exactly as assigned in the circuit.
+    pub fn score(&self) -> F { self.score_inputs().score() }
+
+    /// The score as published: the raw score, or its calibrated probability.
+    pub fn public_score(&self) -> F {
+        let raw = self.score();
+        match &self.calibration {
+            Some(c) => F::from(c.apply(raw.get_lower_128() as u64)),
+            None => raw,
+        }
+    }
+
+    /// Estimates the layout without running keygen, so the SRS can be sized up front.
+    pub fn cost_estimate(&self) -> CostEstimate { Self::cost_of(self.region_rows()) }
+
+    // Rows `assign` takes, without the lookup tables.
+    pub(crate) fn region_rows(&self) -> usize {
+        // every sponge absorbs the purpose tag and the domain first; cell sponges add a tag row
+        let rows = 1
+            + 1 + poseidon_rows::<F>(2 + self.w.len() + 1)
+            + 1 + poseidon_rows::<F>(2 + 1)
+            + 1 + poseidon_rows::<F>(2 + usize::from(self.blinding.is_some()) + self.x.len())
+            + score::score_rows(self.x.len(), self.activation)
+            + 2 + poseidon_rows::<F>(2 + 1) + compare::compare_rows(TIME_BITS, range::RANGE_WORD_BITS);
+        let rows = rows + match &self.sanctions {
+            Some(s) => 2 + 2 * compare::compare_rows(ADDRESS_BITS, range::RANGE_WORD_BITS)
+                + merkle::path_rows(s.siblings.len(), poseidon_rows::<F>(2)) + poseidon_rows::<F>(3),
+            None => 0,
+        };
+        let rows = rows + match &self.allowlist {
+            Some(s) => 2 + compare::compare_rows(ADDRESS_BITS, range::RANGE_WORD_BITS)
+                + merkle::path_rows(s.siblings.len(), poseidon_rows::<F>(2)) + poseidon_rows::<F>(3),
+            None => 0,
+        };
+        let rows = rows + match &self.registry {
+            Some(s) => 1 + merkle::path_rows(s.siblings.len(), poseidon_rows::<F>(2)),
+            None => 0,
+        };
+        let rows = rows + match &self.tx {
+            Some(tx) => keccak::hash_rows(tx.len()) + self.tx_fields.iter().map(|f| keccak::pack_rows(8 * f.len)).sum::<usize>(),
+            None => 0,
+        };
+        let rows = rows + match (&self.tx, self.rlp) {
+            (Some(tx), Some(_)) => {
+                let w = tx.len().min(rlp::WINDOW);
+                w * keccak::pack_rows(8) + rlp::decode_rows(tx.len(), range::RANGE_WORD_BITS)
+            }
+            _ => 0,
+        };
+        let rows = rows + match self.calibration {
+            Some(_) => calibrate::calibrate_rows(poseidon_rows::<F>(2 + calibrate::BUCKETS + 1)),
+            None => 0,
+        };
+        let rows = rows + match self.oracle {
+            Some(_) => eddsa::verify_rows(poseidon_rows::<F>(7)),
+            None => 0,
+        };
+        let rows = rows + match self.amount_index {
+            Some(_) => 1 + compare::compare_rows(AMOUNT_BITS, range::RANGE_WORD_BITS),
+            None => 0,
+        };
+        rows + self.ratios.len() * (1 + compare::compare_rows(AMOUNT_BITS, range::RANGE_WORD_BITS)
+            + range::check_rows(AMOUNT_BITS) + range::check_rows(AMOUNT_BITS + FRAC_BITS as usize))
+    }
+
+    // Cost of a TxCircuit layout with `rows` region rows.
+    pub(crate) fn cost_of(rows: usize) -> CostEstimate {
+        let mut cs = ConstraintSystem::<F>::default();
+        <Self as Circuit<F>>::configure(&mut cs);
+        // the range and pwl lookup tables must fit as well
+        let rows = rows.max(1 << range::RANGE_WORD_BITS).max(pwl::PWL_TABLE_ROWS);
+        let usable = rows + cs.minimum_rows();
+        let mut min_k = 1;
+        while (1usize << min_k) < usable { min_k += 1; }
+
+        CostEstimate {
+            rows,
+            advice_columns: cs.num_advice_columns(),
+            fixed_columns: cs.num_fixed_columns(),
+            instance_columns: cs.num_instance_columns(),
+            selectors: cs.num_selectors(),
+            lookups: cs.lookups().len(),
+            min_k,
+        }
+    }
+
+    /// Gates, degree and permutation layout of the configured constraint system, with the cost estimate.
+    pub fn stats(&self) -> CircuitStats {
+        let mut cs = ConstraintSystem::<F>::default();
+        <Self as Circuit<F>>::configure(&mut cs);
+        let gates = cs.gates().iter().map(|g| GateStats {
+            name: g.name().to_string(),
+            constraints: g.polynomials().len(),
+            degree: g.polynomials().iter().map(|p| p.degree()).max().unwrap_or(0),
+        }).collect();
+        let degree = cs.degree();
+        let permutation_columns = cs.permutation().get_columns().len();
+        // each permutation product polynomial covers degree - 2 columns
+        let permutation_chunks = permutation_columns.div_ceil(degree - 2);
+        CircuitStats { cost: self.cost_estimate(), degree, gates, permutation_columns, permutation_chunks }
+    }
 }
 
-impl Circuit<Fr> for TxCircuit {
-    type Config = Config;
+impl<F: CircuitField> Circuit<F> for TxCircuit<F> {
+    type Config = Config<F>;
     type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
 
-    fn without_witnesses(&self) -> Self { Self::default() }
+    fn without_witnesses(&self) -> Self { Self::empty(self.shape()) }
 
-    fn configure(cs: &mut ConstraintSystem<Fr>) -> Self::Config {
+    fn configure(cs: &mut ConstraintSystem<F>) -> Self::Config {
         let adv = [0,1,2,3,4,5].map(|_| cs.advice_column());
         for a in &adv { cs.enable_equality(*a); }
-        let instance = [0,1,2].map(|_| cs.instance_column());
+        let instance = [(); INSTANCE_COLUMNS].map(|_| cs.instance_column());
         for i in &instance { cs.enable_equality(*i); }
-        let sel = cs.selector();
-        let poseidon = Pow5Chip::configure(cs, adv[0], adv[1], adv[2], adv[3], adv[4], adv[5]);
+        let poseidon = PoseidonChip::configure(cs, adv[0], adv[1], adv[2], adv[3], adv[4], adv[5]);
+        let constants = cs.fixed_column();
+        let range = RangeConfig::configure(cs, adv[3], adv[4], constants);
+        let compare = CompareConfig::configure(cs, adv[0], adv[1], adv[2], adv[5], range.decompose.clone());
+        let pwl = PwlConfig::configure(cs, [adv[0], adv[1], adv[2], adv[3], adv[4]], range.decompose.clone());
+        let score = ScoreConfig::configure(cs, adv, range.clone(), pwl.clone());
+        let merkle = MerkleConfig::configure(cs, [adv[0], adv[1], adv[2], adv[3], adv[4]]);
+        let keccak = KeccakConfig::configure(cs, [adv[0], adv[1], adv[2], adv[3]]);
+        let rlp = RlpConfig::configure(cs, adv, compare.clone(), range.decompose.clone());
+        let eddsa = EddsaConfig::configure(cs, adv);
+        let calibrate = CalibrateConfig::configure(cs, [adv[0], adv[1], adv[2], adv[3], adv[4]], range.clone());
+        let div = DivConfig::configure(cs, [adv[0], adv[1], adv[2], adv[5]], FRAC_BITS, compare.clone(), range.decompose.clone());
 
-        cs.create_gate("score equals public", |meta| {
-            let s = meta.query_selector(sel);
-            let score_calc = meta.query_advice(adv[5], 0);
-            let score_pub = meta.query_instance(instance[2], 0);
-            vec![ s * (score_calc - score_pub) ]
-        });
+        pin_degree(cs);
+        Config { adv, poseidon, range, compare, pwl, score, merkle, keccak, rlp, eddsa, calibrate, div, instance }
+    }
 
-        Config { adv, sel, poseidon, instance }
+    fn synthesize(&self, cfg: Self::Config, layouter: impl h2::Layouter<F>) -> Result<(), Error> {
+        let mut layouter = StackedLayouter::new(layouter);
+        cfg.range.load(layouter.namespace(|| "range table"))?;
+        cfg.pwl.load_table(layouter.namespace(|| "pwl table"))?;
+        self.assign(&cfg, &mut layouter, &[0; INSTANCE_COLUMNS])
     }
+}
+
+impl<F: CircuitField> TxCircuit<F> {
+    // Everything but the lookup tables, publishing instance column c from row rows[c] on, so
+    // `batch::TxBatch` can lay several transactions out over one set of tables.
+    pub(crate) fn assign(&self, cfg: &Config<F>, layouter: &mut impl Layouter<F>, rows: &[usize; INSTANCE_COLUMNS]) -> Result<(), Error> {
 
-    fn synthesize(&self, cfg: Self::Config, mut layouter: impl Layouter<Fr>) -> Result<(), Error> {
-        // Poseidon commits (dummy wiring para demo; publica cero por simplicidad)
-        let _commit_wb = {
-            let mut hasher = Hash::<Fr, Pow5Chip<Fr>, 3, 2>::init(cfg.poseidon.clone(), layouter.namespace(|| "poseidon_wb"))?;
-            let mut inputs = self.w.clone();
-            inputs.push(self.b);
+        let domain_cell = layouter.assign_region(
+            || "domain",
+            |mut region| region.assign_advice(|| "domain", cfg.adv[0], 0, || Value::known(self.domain))
+        )?;
+        layouter.constrain_instance(domain_cell.cell(), cfg.instance[6], rows[6])?;
+
+        // The scorer's cells are what the commitments below absorb.
+        let scored = cfg.score.assign(layouter.namespace(|| "score"), &self.score_inputs())?;
+        let x_cells = scored.x.clone();
+
+        // Model and quantum-output commitments over the scorer's w, b and q_out cells;
+        // `commit_wb` / `commit_q` compute them natively.
+        let commit_wb = {
+            let tag = Tag::Wb.assign(layouter.namespace(|| "tag_wb"), cfg.adv[0])?;
+            let mut hasher = Hash::<F, PoseidonChip<F>, 3, 2>::init(cfg.poseidon.clone(), layouter.namespace(|| "poseidon_wb"))?;
+            let mut inputs = vec![tag, domain_cell.clone()];
+            inputs.extend(scored.w.iter().cloned());
+            inputs.push(scored.b.clone());
             hasher.update(layouter.namespace(|| "absorb_wb"), inputs)?;
             hasher.squeeze(layouter.namespace(|| "squeeze_wb"))?
         };
-        let _commit_q = {
-            let mut hasher = Hash::<Fr, Pow5Chip<Fr>, 3, 2>::init(cfg.poseidon.clone(), layouter.namespace(|| "poseidon_q"))?;
-            hasher.update(layouter.namespace(|| "absorb_q"), vec![self.q_out])?;
+        layouter.constrain_instance(commit_wb.cell(), cfg.instance[0], rows[0])?;
+        if let Some(wit) = &self.registry {
+            let id = layouter.assign_region(
+                || "model id",
+                |mut region| region.assign_advice(|| "model_id", cfg.adv[0], 0, || Value::known(wit.low))
+            )?;
+            let root = cfg.merkle.root(&cfg.poseidon, layouter.namespace(|| "registry path"), &id, &commit_wb, wit)?;
+            layouter.constrain_instance(root.cell(), cfg.instance[13], rows[13])?;
+            layouter.constrain_instance(id.cell(), cfg.instance[14], rows[14])?;
+        }
+        let commit_q = {
+            let tag = Tag::Q.assign(layouter.namespace(|| "tag_q"), cfg.adv[0])?;
+            let mut hasher = Hash::<F, PoseidonChip<F>, 3, 2>::init(cfg.poseidon.clone(), layouter.namespace(|| "poseidon_q"))?;
+            hasher.update(layouter.namespace(|| "absorb_q"), vec![tag, domain_cell.clone(), scored.q_out.clone()])?;
             hasher.squeeze(layouter.namespace(|| "squeeze_q"))?
         };
+        layouter.constrain_instance(commit_q.cell(), cfg.instance[1], rows[1])?;
+        // the feature cells the scorer multiplies
+        let commit_x = {
+            let tag = Tag::X.assign(layouter.namespace(|| "tag_x"), cfg.adv[0])?;
+            let mut hasher = Hash::<F, PoseidonChip<F>, 3, 2>::init(cfg.poseidon.clone(), layouter.namespace(|| "poseidon_x"))?;
+            let mut inputs: Vec<Input<F>> = vec![tag.into(), domain_cell.clone().into()];
+            inputs.extend(self.blinding.map(Input::from));
+            inputs.extend(x_cells.iter().cloned().map(Input::from));
+            hasher.update(layouter.namespace(|| "absorb_x"), inputs)?;
+            hasher.squeeze(layouter.namespace(|| "squeeze_x"))?
+        };
+        layouter.constrain_instance(commit_x.cell(), cfg.instance[3], rows[3])?;
 
-        // z = sum(w_i * x_i)/2^k + b + alpha*q_out/2^k
-        let scale = Fr::from(1u64 << FRAC_BITS);
+        match &self.calibration {
+            Some(calibration) => {
+                let (p, commitment) = cfg.calibrate.assign(&cfg.poseidon, layouter.namespace(|| "calibration"), &domain_cell, &scored.score, calibration)?;
+                layouter.constrain_instance(p.cell(), cfg.instance[2], rows[2])?;
+                layouter.constrain_instance(commitment.cell(), cfg.instance[17], rows[17])?;
+            }
+            None => layouter.constrain_instance(scored.score.cell(), cfg.instance[2], rows[2])?,
+        }
 
-        let score_cell = layouter.assign_region(
-            || "affine + sigmoid",
-            |mut region| {
-                cfg.sel.enable(&mut region, 0)?;
-                let mut acc = Fr::from(0);
-                for (i, (wi, xi)) in self.w.iter().zip(self.x.iter()).enumerate() {
-                    acc += (*wi * *xi) * scale.invert().unwrap();
-                    let _ = region.assign_advice(|| format!("w_{i}"), cfg.adv[1], i, || Value::known(*wi))?;
-                    let _ = region.assign_advice(|| format!("x_{i}"), cfg.adv[0], i, || Value::known(*xi))?;
+        if let Some(sig) = &self.oracle {
+            let (key, epoch) = cfg.eddsa.verify(&cfg.poseidon, layouter.namespace(|| "oracle signature"), &scored.q_out, sig)?;
+            layouter.constrain_instance(key.0.cell(), cfg.instance[16], rows[16])?;
+            layouter.constrain_instance(key.1.cell(), cfg.instance[16], rows[16] + 1)?;
+            layouter.constrain_instance(epoch.cell(), cfg.instance[16], rows[16] + 2)?;
+        }
+
+        if let Some(tx) = &self.tx {
+            let bits = cfg.keccak.assign_bytes(layouter.namespace(|| "tx bytes"), Value::known(&tx[..]), tx.len())?;
+            let (hi, lo) = cfg.keccak.hash(layouter.namespace(|| "keccak(tx)"), &bits)?;
+            layouter.constrain_instance(hi.cell(), cfg.instance[15], rows[15])?;
+            layouter.constrain_instance(lo.cell(), cfg.instance[15], rows[15] + 1)?;
+            for f in &self.tx_fields {
+                let v = cfg.keccak.pack(layouter.namespace(|| format!("tx field {}", f.feature)), &KeccakConfig::be_bits(&bits, f.offset, f.len))?;
+                layouter.assign_region(|| "tx field", |mut region| region.constrain_equal(v.cell(), x_cells[f.feature].cell()))?;
+            }
+            if let Some(features) = self.rlp {
+                let bytes = (0..tx.len().min(rlp::WINDOW))
+                    .map(|i| cfg.keccak.pack(layouter.namespace(|| format!("tx byte {i}")), &KeccakConfig::be_bits(&bits, i, 1)))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let values = cfg.rlp.decode(layouter.namespace(|| "rlp decode"), &bytes)?;
+                for (item, feature) in features.bindings() {
+                    layouter.assign_region(|| "rlp field", |mut region| region.constrain_equal(values[item as usize].cell(), x_cells[feature].cell()))?;
                 }
-                acc += self.b;
-                acc += (self.alpha * self.q_out) * scale.invert().unwrap();
+            }
+        }
+
+        for (row, i) in disclosed_indices(self.disclosed).enumerate() {
+            layouter.constrain_instance(x_cells[i].cell(), cfg.instance[12], rows[12] + row)?;
+        }
+
+        if let Some(i) = self.amount_index {
+            let limit = layouter.assign_region(
+                || "amount limit",
+                |mut region| region.assign_advice(|| "limit", cfg.adv[1], 0, || Value::known(self.amount_limit))
+            )?;
+            cfg.compare.assert_le(layouter.namespace(|| "amount <= limit"), &x_cells[i], &limit, AMOUNT_BITS)?;
+            layouter.constrain_instance(limit.cell(), cfg.instance[11], rows[11])?;
+        }
+
+        for r in &self.ratios {
+            let q = cfg.div.div(layouter.namespace(|| format!("ratio x[{}]", r.feature)), &x_cells[r.num], &x_cells[r.den], AMOUNT_BITS)?;
+            layouter.assign_region(|| "ratio", |mut region| region.constrain_equal(q.cell(), x_cells[r.feature].cell()))?;
+        }
 
-                let score = sigmoid_poly(acc);
-                let score_cell = region.assign_advice(|| "score", cfg.adv[5], 0, || Value::known(score))?;
-                Ok(score_cell)
+        let (now_cell, until_cell) = layouter.assign_region(
+            || "expiry",
+            |mut region| {
+                let now = region.assign_advice(|| "now", cfg.adv[0], 0, || Value::known(self.now))?;
+                let until = region.assign_advice(|| "valid_until", cfg.adv[1], 0, || Value::known(self.valid_until))?;
+                Ok((now, until))
             }
         )?;
+        let commit_now = {
+            let tag = Tag::Now.assign(layouter.namespace(|| "tag_now"), cfg.adv[0])?;
+            let mut hasher = Hash::<F, PoseidonChip<F>, 3, 2>::init(cfg.poseidon.clone(), layouter.namespace(|| "poseidon_now"))?;
+            hasher.update(layouter.namespace(|| "absorb_now"), vec![tag, domain_cell.clone(), now_cell.clone()])?;
+            hasher.squeeze(layouter.namespace(|| "squeeze_now"))?
+        };
+        cfg.compare.assert_le(layouter.namespace(|| "now <= valid_until"), &now_cell, &until_cell, TIME_BITS)?;
+        layouter.constrain_instance(until_cell.cell(), cfg.instance[4], rows[4])?;
+        layouter.constrain_instance(commit_now.cell(), cfg.instance[5], rows[5])?;
+
+        if let Some(wit) = &self.sanctions {
+            let (addr, low, next) = layouter.assign_region(
+                || "sanctions gap",
+                |mut region| {
+                    let addr = region.assign_advice(|| "counterparty", cfg.adv[0], 0, || Value::known(wit.address))?;
+                    let low = region.assign_advice(|| "low", cfg.adv[1], 0, || Value::known(wit.low))?;
+                    let next = region.assign_advice(|| "next", cfg.adv[2], 0, || Value::known(wit.next))?;
+                    Ok((addr, low, next))
+                }
+            )?;
+            cfg.compare.assert_lt(layouter.namespace(|| "low < counterparty"), &low, &addr, ADDRESS_BITS)?;
+            cfg.compare.assert_lt(layouter.namespace(|| "counterparty < next"), &addr, &next, ADDRESS_BITS)?;
+            let root = cfg.merkle.root(&cfg.poseidon, layouter.namespace(|| "sanctions path"), &low, &next, wit)?;
+            layouter.constrain_instance(root.cell(), cfg.instance[7], rows[7])?;
+            let commit_cp = {
+                let tag = Tag::Counterparty.assign(layouter.namespace(|| "tag_cp"), cfg.adv[0])?;
+                let mut hasher = Hash::<F, PoseidonChip<F>, 3, 2>::init(cfg.poseidon.clone(), layouter.namespace(|| "poseidon_cp"))?;
+                hasher.update(layouter.namespace(|| "absorb_cp"), vec![tag, domain_cell.clone(), addr])?;
+                hasher.squeeze(layouter.namespace(|| "squeeze_cp"))?
+            };
+            layouter.constrain_instance(commit_cp.cell(), cfg.instance[8], rows[8])?;
+        }
 
-        layouter.constrain_instance(score_cell.cell(), cfg.instance[2], 0)?;
+        if let Some(wit) = &self.allowlist {
+            let (dest, next, zero) = layouter.assign_region(
+                || "allowlist leaf",
+                |mut region| {
+                    let dest = region.assign_advice(|| "destination", cfg.adv[0], 0, || Value::known(wit.address))?;
+                    let low = region.assign_advice(|| "low", cfg.adv[1], 0, || Value::known(wit.low))?;
+                    let next = region.assign_advice(|| "next", cfg.adv[2], 0, || Value::known(wit.next))?;
+                    let zero = region.assign_advice_from_constant(|| "0", cfg.adv[3], 0, F::ZERO)?;
+                    region.constrain_equal(dest.cell(), low.cell())?;
+                    Ok((dest, next, zero))
+                }
+            )?;
+            // the first leaf starts at the 0 sentinel, which is not a listed address
+            cfg.compare.assert_lt(layouter.namespace(|| "0 < destination"), &zero, &dest, ADDRESS_BITS)?;
+            let root = cfg.merkle.root(&cfg.poseidon, layouter.namespace(|| "allowlist path"), &dest, &next, wit)?;
+            layouter.constrain_instance(root.cell(), cfg.instance[9], rows[9])?;
+            let commit_dest = {
+                let tag = Tag::Destination.assign(layouter.namespace(|| "tag_dest"), cfg.adv[0])?;
+                let mut hasher = Hash::<F, PoseidonChip<F>, 3, 2>::init(cfg.poseidon.clone(), layouter.namespace(|| "poseidon_dest"))?;
+                hasher.update(layouter.namespace(|| "absorb_dest"), vec![tag, domain_cell.clone(), dest])?;
+                hasher.squeeze(layouter.namespace(|| "squeeze_dest"))?
+            };
+            layouter.constrain_instance(commit_dest.cell(), cfg.instance[10], rows[10])?;
+        }
         Ok(())
     }
 }
 
-pub fn fr_from_qi128(x: i128) -> Fr { Fr::from((x as i64) as u64) }
+// Signed encoding: negative values are p - |x|.
+pub fn fr_from_qi128<F: FieldExt>(x: i128) -> F {
+    let magnitude = F::from_u128(x.unsigned_abs());
+    if x < 0 { -magnitude } else { magnitude }
+}
+
+/// Canonical public-input encoding: "0x" + the value as 64 big-endian hex digits, as EVM tools write a uint256.
+pub fn fe_to_hex<F: FieldExt>(f: &F) -> String {
+    let mut be = f.to_repr();
+    be.reverse();
+    format!("0x{}", hex::encode(be))
+}
+
+/// Inverse of `fe_to_hex`; rejects wrong lengths and non-canonical (>= p) encodings.
+pub fn fe_from_hex<F: FieldExt>(s: &str) -> Result<F, String> {
+    let bytes = hex::decode(s.trim().trim_start_matches("0x")).map_err(|e| format!("hex inválido ({s}): {e}"))?;
+    let mut repr = F::Repr::default();
+    if bytes.len() != repr.as_ref().len() {
+        return Err(format!("se esperan {} bytes, {s} tiene {}", repr.as_ref().len(), bytes.len()));
+    }
+    repr.as_mut().iter_mut().zip(bytes.iter().rev()).for_each(|(dst, src)| *dst = *src);
+    Option::from(F::from_repr(repr)).ok_or_else(|| format!("{s} no es un elemento canónico del campo"))
+}
+
+// Signed reading of a field element: values above (p-1)/2 are negative.
+fn fr_to_i128<F: FieldExt>(f: F) -> i128 {
+    let v = f.get_lower_128();
+    if (-f).get_lower_128() < v { -((-f).get_lower_128() as i128) } else { v as i128 }
+}
+
+/// Native Poseidon sponge (P128Pow5T3, constant-length domain), matching the in-circuit commitments.
+#[cfg(not(feature = "poseidon2"))]
+pub fn poseidon_commit<F: CircuitField>(inputs: &[F]) -> F {
+    poseidon::hash(inputs)
+}
+
+/// Native Poseidon2 sponge, matching the in-circuit commitments.
+#[cfg(feature = "poseidon2")]
+pub fn poseidon_commit<F: CircuitField>(inputs: &[F]) -> F {
+    poseidon2::hash(inputs)
+}
+
+/// Commitment as computed in-circuit: the purpose tag, then the domain, then the inputs.
+pub fn commit_in_domain<F: CircuitField>(tag: Tag, domain: F, inputs: &[F]) -> F {
+    let mut absorbed = vec![tag.value(), domain];
+    absorbed.extend_from_slice(inputs);
+    poseidon_commit(&absorbed)
+}
+
+/// Feature indices set in a disclosure mask, ascending.
+pub fn disclosed_indices(mask: u64) -> impl Iterator<Item = usize> {
+    (0..64).filter(move |i| mask >> i & 1 == 1)
+}
+
+/// Maps a deployment tag to a field element: numeric tags are chain ids, anything else is hashed.
+pub fn domain_from_tag<F: FieldExt>(tag: &str) -> F {
+    if let Ok(chain_id) = tag.parse::<u64>() { return F::from(chain_id); }
+    let digest = Sha256::digest(tag.as_bytes());
+    // 31 bytes keep the value below the field modulus
+    let lo = u128::from_le_bytes(digest[..16].try_into().unwrap());
+    let mut hi = [0u8; 16];
+    hi[..15].copy_from_slice(&digest[16..31]);
+    let two_64 = F::from_u128(1 << 64);
+    F::from_u128(lo) + F::from_u128(u128::from_le_bytes(hi)) * two_64 * two_64
+}
+
+/// Caps the rayon pool halo2 parallelizes keygen and proving on. Call once, before the first
+/// proof; the global pool cannot be resized after it starts.
+pub fn set_prover_threads(threads: usize) -> Result<(), String> {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build_global()
+        .map_err(|e| format!("no se pudo fijar el número de hilos: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prover::tx_instances;
+    use halo2_proofs::{dev::{MockProver, VerifyFailure}, halo2curves::ff::Field};
+
+    const ONE: i64 = ONE_Q16 as i64;
+
+    fn circuit(x: Vec<i64>, disclosed: u64) -> TxCircuit<Fr> {
+        let mut circ = TxCircuit::<Fr>::builder()
+            .features(x).weights(vec![ONE / 2, -ONE / 4, ONE / 8], 0).quantum(ONE / 2, ONE / 2)
+            .domain("test").validity(0, NEVER_EXPIRES)
+            .build().unwrap();
+        circ.disclosed = disclosed;
+        circ
+    }
+
+    fn check(circ: &TxCircuit<Fr>, instances: Vec<Vec<Fr>>) -> Result<(), Vec<VerifyFailure>> {
+        MockProver::run(circ.cost_estimate().min_k, circ, instances).unwrap().verify()
+    }
+
+    #[test]
+    fn disclosed_features_and_commit_x_share_the_scored_cells() {
+        let (x, other) = (vec![ONE / 2, ONE / 4, -ONE], vec![ONE / 2, ONE / 3, -ONE]);
+        let circ = circuit(x, 0b101);
+        let instances = tx_instances(&circ);
+        assert_eq!(instances[12], vec![circ.x[0], circ.x[2]]);
+        assert_eq!(check(&circ, instances.clone()), Ok(()));
+
+        let mut wrong = instances.clone();
+        wrong[12][1] += Fr::ONE;
+        assert!(check(&circ, wrong).is_err());
+        // x[1] is not disclosed, but commit_x still has to open to the scored x
+        let mut wrong = instances;
+        wrong[3] = tx_instances(&circuit(other, 0b101))[3].clone();
+        assert!(check(&circ, wrong).is_err());
+    }
+
+    #[test]
+    fn commitments_absorb_the_public_domain() {
+        let circ = circuit(vec![ONE / 2, ONE / 4, -ONE], 0);
+        let mut other = circ.clone();
+        other.domain = domain_from_tag("other");
+        let instances = tx_instances(&circ);
+        // the public domain alone cannot be swapped under the same commitments
+        let mut wrong = instances.clone();
+        wrong[6] = vec![other.domain];
+        assert!(check(&circ, wrong).is_err());
+        // nor can the commitments be replaced by the other domain's
+        let mut wrong = instances;
+        wrong[0] = vec![other.commit_wb()];
+        assert!(check(&circ, wrong).is_err());
+    }
+
+    #[test]
+    fn blinded_commit_x_is_satisfied() {
+        let mut circ = circuit(vec![ONE / 2, ONE / 4, -ONE], 0b010);
+        circ.blinding = Some(Fr::from(0xdead_beef));
+        assert_eq!(check(&circ, tx_instances(&circ)), Ok(()));
+    }
+
+    #[test]
+    fn ratio_features_are_constrained() {
+        let ratio = Ratio { feature: 2, num: 0, den: 1 };
+        // 1 / 3 = 21845.33.. in Q16, floored
+        let q = Ratio::quotient_q16(ONE, 3 * ONE).unwrap();
+        assert_eq!(q, 21845);
+        let mut circ = circuit(vec![ONE, 3 * ONE, q], 0);
+        circ.ratios = vec![ratio];
+        assert_eq!(check(&circ, tx_instances(&circ)), Ok(()));
+        // a ratio feature off by one ulp fails the division, even with commit_x opened to it
+        let mut forged = circuit(vec![ONE, 3 * ONE, q + 1], 0);
+        forged.ratios = vec![ratio];
+        assert!(check(&forged, tx_instances(&forged)).is_err());
+        // zero denominators have no quotient
+        let mut zero = circuit(vec![ONE, 0, 0], 0);
+        zero.ratios = vec![ratio];
+        assert!(check(&zero, tx_instances(&zero)).is_err());
+    }
+}
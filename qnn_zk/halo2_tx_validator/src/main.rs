@@ -1,110 +1,3515 @@
 // main.rs
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
 use halo2_proofs::{
-    dev::MockProver,
-    plonk::{keygen_pk, keygen_vk},
+    dev::{FailureLocation, MockProver, VerifyFailure},
+    plonk::{keygen_pk, keygen_vk, Circuit, ConstraintSystem, ProvingKey, VerifyingKey},
+    poly::commitment::{Params, ParamsProver},
     poly::kzg::{
         commitment::ParamsKZG,
         multiopen::{ProverGWC, VerifierGWC},
         strategy::SingleStrategy,
     },
-    transcript::{Blake2bWrite, Blake2bRead, Challenge255},
-    pairing::bn256::{Bn256, Fr},
+    transcript::{Blake2bWrite, Blake2bRead, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer},
+    halo2curves::{bn256::{Bn256, Fr, G1Affine}, group::{ff::{Field, PrimeField}, GroupEncoding}},
+    SerdeFormat,
 };
-use halo2_tx_validator::{TxCircuit, fr_from_qi128};
-use serde::{Deserialize, Serialize};
-use std::{fs, path::Path};
+#[cfg(feature = "remote")]
+use halo2_tx_validator::remote;
+use halo2_tx_validator::{Ratio, TxField, batch::TxBatch, ceremony::{self, Ceremony}, codec, srs, numeric::{flexible_i64, parse_fe, FlexI64}, witness::{dense_or_sparse, BinaryWitness, SparseVector, Witness}, prover::{self, base_circuit, tx_hash_instances, tx_instances}, store::{self, Store}, envelope::{ProofEnvelope, Scheme, Transcript}, error::QuantumGuardError, fixed::Fixed16, keys::{ProvingKeyFile, VerifyingKeyFile}, public::{hex_instances, Audit, BatchPublicInputs, PublicInputs, Quantization, PUBLIC_VERSION}, calibrate::Calibration, eddsa::{baby_jubjub, OracleSignature}, ensemble::{self, EnsembleCircuit}, merkle::{MerkleWitness, ModelRegistry, SortedMerkleTree}, preset::Preset, rlp::{self, RlpFeatures}, tree::{stump_from_q16, TreeCircuit, TreeShape}, velocity::{self, VelocityCircuit}, Activation, Shape, Tag, TxCircuit, commit_in_domain, disclosed_indices, fe_to_hex, set_prover_threads, domain_from_tag, FRAC_BITS, NEVER_EXPIRES, ONE_Q16};
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{collections::{BTreeMap, BTreeSet}, fs, io::{Read, Write}, path::Path, process::ExitCode, sync::{atomic::{AtomicBool, Ordering}, mpsc::RecvTimeoutError, Mutex}, time::{Duration, Instant, SystemTime, UNIX_EPOCH}};
 
 #[derive(Parser)]
 #[command(author, version, about)]
-struct Cli { #[command(subcommand)] cmd: Cmd }
+struct Cli {
+    #[command(subcommand)] cmd: Cmd,
+    // one JSON summary on stdout (status, exit_code, elapsed_ms, outputs with sha256, result, error)
+    #[arg(long, global = true)] json: bool,
+    // no progress or phase timings on stderr
+    #[arg(long, global = true)] quiet: bool,
+    // defaults for flags (see FileConfig); otherwise $QUANTUM_GUARD_CONFIG or ./quantum-guard.toml.
+    // Read from argv by FileConfig::load, before clap runs.
+    #[allow(dead_code)]
+    #[arg(long, global = true)] config: Option<String>,
+    // prover thread pool size (default: all cores)
+    #[arg(long, global = true)] threads: Option<usize>,
+}
+
+// quantum-guard.toml: a value becomes the default of the same-named flag on every subcommand
+// that has one, so flags given on the command line still win.
+//   params = "srs/k18.params"
+//   pk = "keys/tx.pk"
+//   vk = "keys/tx.vk"
+//   k = 18
+//   backend = "kzg"
+//   transcript = "keccak"
+//   multiopen = "gwc"
+//   threads = 4
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileConfig {
+    params: Option<String>,
+    pk: Option<String>,
+    vk: Option<String>,
+    k: Option<u32>,
+    backend: Option<String>,
+    transcript: Option<String>,
+    // only GWC is implemented; accepted so scripts can pin it
+    multiopen: Option<String>,
+    // a global flag, applied in main
+    threads: Option<usize>,
+}
+
+impl FileConfig {
+    fn load() -> Result<Self, Box<dyn std::error::Error>> {
+        let args: Vec<String> = std::env::args().collect();
+        let flag = args.iter().position(|a| a == "--config").and_then(|i| args.get(i + 1).cloned())
+            .or_else(|| args.iter().find_map(|a| a.strip_prefix("--config=").map(str::to_string)));
+        let path = match flag.or_else(|| std::env::var("QUANTUM_GUARD_CONFIG").ok()) {
+            Some(path) => path,
+            None if Path::new("quantum-guard.toml").exists() => "quantum-guard.toml".into(),
+            None => return Ok(Self::default()),
+        };
+        let cfg: Self = toml::from_str(&fs::read_to_string(&path).map_err(|e| format!("{path}: {e}"))?)
+            .map_err(|e| format!("{path}: {e}"))?;
+        if let Some(m) = cfg.multiopen.as_deref().filter(|m| *m != "gwc") {
+            return Err(format!("{path}: multiopen '{m}' no disponible (gwc)").into());
+        }
+        Ok(cfg)
+    }
+
+    fn defaults(&self) -> Vec<(&'static str, String)> {
+        [
+            ("params", self.params.clone()),
+            ("pk", self.pk.clone()),
+            ("vk", self.vk.clone()),
+            ("k", self.k.map(|k| k.to_string())),
+            ("backend", self.backend.clone()),
+            ("transcript", self.transcript.clone()),
+        ].into_iter().filter_map(|(id, v)| Some((id, v?))).collect()
+    }
 
+    fn apply(&self, mut cmd: clap::Command) -> clap::Command {
+        let defaults = self.defaults();
+        let names: Vec<String> = cmd.get_subcommands().map(|s| s.get_name().to_string()).collect();
+        for name in names {
+            for (id, value) in &defaults {
+                let has = cmd.find_subcommand(&name).is_some_and(|s| s.get_arguments().any(|a| a.get_id() == *id));
+                if has {
+                    cmd = cmd.mut_subcommand(&name, |s| s.mut_arg(*id, |a| a.required(false).default_value(value.clone())));
+                }
+            }
+        }
+        cmd
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Backend { Kzg, Ipa }
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Rounding { Nearest, Floor, Ceil, Trunc }
+
+// parsed once per run, so the size of the largest variant does not matter
 #[derive(Subcommand)]
+#[allow(clippy::large_enum_variant)]
 enum Cmd {
-    GenParams { #[arg(long)] k: u32, #[arg(long)] out: String },
+    GenParams {
+        #[arg(long, required_unless_present_any = ["preset", "auto_k"])] k: Option<u32>,
+        #[arg(long)] preset: Option<Preset>,
+        // smallest k the cost model fits for --features-n features
+        #[arg(long, requires = "features_n", conflicts_with = "k")] auto_k: bool,
+        #[arg(long)] features_n: Option<usize>,
+        #[arg(long, default_value = "cubic")] activation: Activation,
+        #[arg(long)] out: String,
+        #[arg(long, value_enum, default_value_t = Backend::Kzg)] backend: Backend,
+        // deterministic setup (ChaCha20): the toxic waste is derivable, for tests only
+        #[arg(long, conflicts_with = "beacon")] seed: Option<u64>,
+        // public randomness mixed with OS entropy (e.g. a block hash announced in advance); its
+        // SHA-256 goes to <out>.json
+        #[arg(long)] beacon: Option<String>
+    },
+    // Multi-party KZG setup: each contributor runs `contribute` on the previous params; the
+    // transcript travels next to them as <params>.ceremony.json.
+    Ceremony { #[command(subcommand)] cmd: CeremonyCmd },
+    // KZG params file utilities.
+    Params { #[command(subcommand)] cmd: ParamsCmd },
+    // Downloads a ceremony SRS, checks its published SHA-256 and writes it as KZG params for k.
+    FetchParams {
+        #[arg(long, required_unless_present = "preset")] k: Option<u32>,
+        #[arg(long)] preset: Option<Preset>,
+        // hermez | ppot
+        #[arg(long, default_value = "hermez")] source: srs::Source,
+        // overrides the source's URL (mirror or local copy served over http)
+        #[arg(long)] url: Option<String>,
+        // SHA-256 of the downloaded file, as published for the ceremony
+        #[arg(long)] sha256: String,
+        #[arg(long)] out: String
+    },
     Prove {
+        #[arg(long, value_enum, default_value_t = Backend::Kzg)] backend: Backend,
         #[arg(long)] params: String,
+        #[arg(long)] preset: Option<Preset>,
+        // cubic | pwl
+        #[arg(long, default_value = "cubic")] activation: Activation,
+        #[arg(long, required_unless_present = "stdin")] witness: Option<String>,
+        #[arg(long, required_unless_present = "stdout")] proof: Option<String>,
+        #[arg(long, required_unless_present = "stdout")] public: Option<String>,
+        // witness JSON from stdin instead of --witness
+        #[arg(long, conflicts_with = "witness")] stdin: bool,
+        // proof and public JSON to stdout as two length-prefixed frames (see write_frame); messages go to stderr
+        #[arg(long, conflicts_with_all = ["proof", "public"])] stdout: bool,
+        #[arg(long)] policy_version: Option<String>,
+        #[arg(long)] feature_schema: Option<String>,
+        // Unix seconds; the proof attests now <= valid_until
+        #[arg(long)] valid_until: Option<u64>,
+        #[arg(long)] now: Option<u64>,
+        // chain id or deployment tag (e.g. "testnet"); must match the verifier's
+        #[arg(long, default_value = "0")] domain: String,
+        // sanctions tree (sanctions-build output); proves --counterparty is not listed
+        #[arg(long, requires = "counterparty")] sanctions: Option<String>,
+        #[arg(long, requires = "sanctions")] counterparty: Option<String>,
+        // allowlist tree (allowlist-build output); proves --destination is listed
+        #[arg(long, requires = "destination")] allowlist: Option<String>,
+        #[arg(long, requires = "allowlist")] destination: Option<String>,
+        // proves x[amount_index] <= amount_limit (Q16.16); the limit is public
+        #[arg(long, requires = "amount_limit")] amount_index: Option<usize>,
+        #[arg(long, requires = "amount_index")] amount_limit: Option<i64>,
+        // salt commit_x with a fresh random r, written to --opening (keep it private)
+        #[arg(long, requires = "opening")] hiding: bool,
+        #[arg(long)] opening: Option<String>,
+        // feature indices to publish, e.g. --disclose 0,3
+        #[arg(long, value_delimiter = ',')] disclose: Vec<usize>,
+        // model registry (registry-build output); the witness model must be registered under --model-id
+        #[arg(long, requires = "model_id")] registry: Option<String>,
+        #[arg(long, requires = "registry")] model_id: Option<usize>,
+        // raw signed transaction (0x hex); its keccak256 becomes a public input
+        #[arg(long)] tx_raw: Option<String>,
+        // features read from the raw tx: feature:offset:len (big-endian, not scaled)
+        #[arg(long, value_delimiter = ',', requires = "tx_raw")] tx_field: Vec<TxField>,
+        // features decoded in-circuit from a legacy (RLP) tx; `to` needs a 160-bit feature, which i64 witnesses cannot hold
+        #[arg(long, requires = "tx_raw")] rlp_nonce: Option<usize>,
+        #[arg(long, requires = "tx_raw")] rlp_gas_price: Option<usize>,
+        #[arg(long, requires = "tx_raw")] rlp_value: Option<usize>,
+        // features proven in-circuit as the Q16.16 quotient of two others: feature:num:den (e.g. amount / account_age)
+        #[arg(long, value_delimiter = ',')] ratio: Vec<Ratio>,
+        // oracle signature over (q_out, epoch), checked in-circuit; key and epoch become public
+        #[arg(long)] oracle_sig: Option<String>,
+        // calibration knots ({"knots": [...]}, Q16.16); score_pub must be the calibrated probability
+        #[arg(long)] calibration: Option<String>,
+        // proving key from `keygen`; written here on first use if the file does not exist
+        #[arg(long)] pk: Option<String>,
+        // seeded ChaCha20 RNG for blinding and the hiding salt: same inputs, byte-identical proof
+        // (the envelope's created_at is zeroed); regression tests only
+        #[arg(long)] seed: Option<u64>,
+        // keccak: the EVM transcript, for the export-verifier contract (needs --features evm)
+        #[arg(long, default_value = "blake2b")] transcript: Transcript,
+        // also archive the proof in this SQLite store (needs --features store)
+        #[arg(long)] store: Option<String>
+    },
+    Verify {
+        #[arg(long, value_enum, default_value_t = Backend::Kzg)] backend: Backend,
+        #[arg(long)] params: String,
+        #[arg(long, required_unless_present = "stdin")] proof: Option<String>,
+        #[arg(long, required_unless_present = "stdin")] public: Option<String>,
+        // proof and public frames from stdin, as written by `prove --stdout`
+        #[arg(long, conflicts_with_all = ["proof", "public"])] stdin: bool,
+        // --strict: every audit field must match the local configuration
+        #[arg(long)] strict: bool,
+        // approved model: commit_wb must be its commitment in the verifier's domain
+        #[arg(long)] model: Option<String>,
+        #[arg(long)] policy_version: Option<String>,
+        #[arg(long)] feature_schema: Option<String>,
+        // reject proofs whose valid_until is before now (or --now)
+        #[arg(long)] check_expiry: bool,
+        #[arg(long)] now: Option<u64>,
+        #[arg(long, default_value = "0")] domain: String,
+        // require the proof to be against this sanctions tree's root
+        #[arg(long)] sanctions: Option<String>,
+        #[arg(long)] allowlist: Option<String>,
+        // require the proof to enforce exactly this amount limit (Q16.16)
+        #[arg(long)] amount_limit: Option<i64>,
+        // require the proof to use a model from this registry
+        #[arg(long)] registry: Option<String>,
+        // require the proof to be bound to this Ethereum tx hash
+        #[arg(long)] tx_hash: Option<String>,
+        // require q_out to be signed by this oracle key (decimal x,y)
+        #[arg(long, value_delimiter = ',', num_args = 2)] oracle_key: Vec<String>,
+        // require the score to be calibrated with exactly these knots
+        #[arg(long)] calibration: Option<String>,
+        // verifying key from `keygen`; written here on first use if the file does not exist
+        #[arg(long)] vk: Option<String>,
+        // require this transcript; by default the one recorded in the proof envelope is used
+        #[arg(long)] transcript: Option<Transcript>,
+        // policy decision on a valid proof: ACCEPT iff score_pub >= threshold, else REJECT (exit code 6)
+        #[arg(long)] threshold: Option<f64>,
+        // require proofs made with this vk (fingerprint printed by keygen)
+        #[arg(long)] vk_fingerprint: Option<String>
+    },
+    // Writes the proving and verifying keys for a circuit shape, so prove/verify can skip keygen.
+    Keygen {
+        #[arg(long, value_enum, default_value_t = Backend::Kzg)] backend: Backend,
+        #[arg(long)] params: String,
+        // Shape JSON (as in a public file's "shape") or a preset
+        #[arg(long, required_unless_present_any = ["preset", "features_n"])] shape: Option<String>,
+        #[arg(long, conflicts_with = "shape")] preset: Option<Preset>,
+        #[arg(long, conflicts_with_all = ["shape", "preset"])] features_n: Option<usize>,
+        #[arg(long)] pk_out: String,
+        #[arg(long)] vk_out: String,
+        // downsize --params to the shape's minimal k (KZG) and write them to --params-out for proving
+        #[arg(long, requires = "params_out")] auto_k: bool,
+        #[arg(long)] params_out: Option<String>
+    },
+    // Builds a witness from a transaction and a model with feature specs; q_out is given or fetched.
+    WitnessGen {
+        #[arg(long)] tx: String,
+        #[arg(long)] model: String,
+        // quantum layer output as a probability in [0, 1]
+        #[arg(long, required_unless_present = "q_out_url", conflicts_with = "q_out_url")] q_out: Option<f64>,
+        // oracle endpoint answering {"q_out": <probability>}
+        #[arg(long)] q_out_url: Option<String>,
+        #[arg(long, default_value = "pwl")] activation: Activation,
+        #[arg(long)] out: String
+    },
+    // Converts a float witness to fixed point; score_pub is recomputed unless given.
+    Quantize {
+        #[arg(long = "in")] input: String,
+        // the circuit uses Q16.16; other values are for external consumers
+        #[arg(long, default_value_t = FRAC_BITS)] frac_bits: u32,
+        #[arg(long, value_enum, default_value_t = Rounding::Nearest)] rounding: Rounding,
+        #[arg(long, default_value = "pwl")] activation: Activation,
+        #[arg(long)] out: String
+    },
+    // Verifying key utilities.
+    Vk {
+        #[command(subcommand)] cmd: VkCmd
+    },
+    // Witness file utilities.
+    Witness {
+        #[command(subcommand)] cmd: WitnessCmd
+    },
+    // Runs MockProver on a witness: reports every unsatisfied constraint without building a proof.
+    Mock {
+        #[arg(long)] witness: String,
+        // defaults to the cost model's minimum
+        #[arg(long)] k: Option<u32>,
+        #[arg(long, default_value = "cubic")] activation: Activation,
+        #[arg(long, default_value = "0")] domain: String
+    },
+    // Dumps the intermediate values of the score (per-feature products, running dot product,
+    // pre-activation z, score) for a witness, after checking it with MockProver, to diagnose
+    // mismatches against the reference pipeline.
+    Trace {
         #[arg(long)] witness: String,
+        #[arg(long)] k: Option<u32>,
+        #[arg(long, default_value = "cubic")] activation: Activation,
+        #[arg(long, default_value = "0")] domain: String,
+        // trace JSON (default: stdout)
+        #[arg(long)] out: Option<String>
+    },
+    // Renders region/column usage of the circuit for a feature count (needs --features dev-graph).
+    Layout {
+        #[arg(long, required_unless_present = "preset")] features_n: Option<usize>,
+        #[arg(long)] preset: Option<Preset>,
+        #[arg(long, default_value = "cubic")] activation: Activation,
+        #[arg(long)] k: Option<u32>,
+        #[arg(long, default_value = "layout.png")] out: String
+    },
+    // Times keygen, proving and verification (KZG, fresh params) for every features × k pair; prints JSON.
+    Bench {
+        #[arg(long, value_delimiter = ',', default_value = "16")] features_n: Vec<usize>,
+        // defaults to the cost model's minimum for each feature count
+        #[arg(long, value_delimiter = ',')] k: Vec<u32>,
+        #[arg(long, default_value = "cubic")] activation: Activation,
+        #[arg(long)] out: Option<String>
+    },
+    // Predicts proving time and peak memory for a k (or the minimum k of a feature count) from a
+    // local calibration table; --calibrate builds the table by proving at each --calibrate-k.
+    Estimate {
+        #[arg(long)] k: Option<u32>,
+        #[arg(long)] features_n: Option<usize>,
+        #[arg(long, default_value = "cubic")] activation: Activation,
+        // prover threads on the target machine (default: as calibrated)
+        #[arg(long)] cores: Option<usize>,
+        #[arg(long, default_value = "quantum-guard-estimate.json")] table: String,
+        #[arg(long)] calibrate: bool,
+        #[arg(long, value_delimiter = ',', default_value = "12,14,16")] calibrate_k: Vec<u32>
+    },
+    // Proves every *.json witness in a directory with one proving key (KZG); writes <name>.proof,
+    // <name>.public.json and summary.json to --out-dir. All witnesses must share a shape.
+    ProveBatch {
+        #[arg(long)] params: String,
+        #[arg(long, required_unless_present_any = ["batch", "parquet"])] witness_dir: Option<String>,
+        // batch witness file (JSON Lines of {"id", ...witness}), as written by witness from-csv
+        #[arg(long, conflicts_with = "witness_dir")] batch: Option<String>,
+        // Parquet/Arrow feature table read as in witness from-parquet, with no intermediate files
+        #[arg(long, conflicts_with_all = ["witness_dir", "batch"], requires = "model")] parquet: Option<String>,
+        #[arg(long)] model: Option<String>,
+        #[arg(long)] q_out: Option<f64>,
+        #[arg(long)] out_dir: String,
+        #[arg(long)] pk: Option<String>,
+        #[arg(long, default_value = "cubic")] activation: Activation,
+        #[arg(long, default_value = "0")] domain: String,
+        // concurrent proofs sharing the params and proving key; the prover threads (--threads) are
+        // split evenly among them. Each holds one proof's working memory.
+        #[arg(long, default_value_t = 1)] jobs: usize,
+        // skip witnesses already proven according to <out_dir>/jobs.json (the manifest every
+        // run keeps up to date), e.g. after a crash or preemption
+        #[arg(long)] resume: bool,
+        // batch64: one proof (batch-<first stem>.proof) per 64 witnesses in file order; a job is
+        // then a whole batch. The witnesses must have the preset's feature count.
+        #[arg(long)] preset: Option<Preset>
+    },
+    // Proves an archive of historical transactions under one model: witnesses as in witness-gen,
+    // proofs as in prove-batch (--jobs, checkpointed in <out_dir>/jobs.json for --resume), each
+    // proof also archived in --store. A transaction without a valid witness is reported as failed.
+    Backfill {
+        #[arg(long)] params: String,
+        // JSON Lines, one transaction per line as witness-gen --tx takes it; a "q_out" field
+        // overrides --q-out
+        #[arg(long)] archive: String,
+        #[arg(long)] model: String,
+        // model version: --model must be registered under --model-id (registry-build output)
+        #[arg(long, requires = "model_id")] registry: Option<String>,
+        #[arg(long, requires = "registry")] model_id: Option<usize>,
+        #[arg(long)] q_out: Option<f64>,
+        // transaction field naming its outputs (line number if absent); a 0x tx hash is also
+        // stored with the proof
+        #[arg(long, default_value = "hash")] id_field: String,
+        #[arg(long)] out_dir: String,
+        #[arg(long)] pk: Option<String>,
+        #[arg(long, default_value = "pwl")] activation: Activation,
+        #[arg(long, default_value = "0")] domain: String,
+        #[arg(long, default_value_t = 1)] jobs: usize,
+        #[arg(long)] resume: bool,
+        // SQLite proof store (needs --features store)
+        #[arg(long)] store: Option<String>
+    },
+    // Long-running prove-batch: keeps params and the proving key in memory and proves every
+    // witness .json that appears in --watch, writing <name>.proof and <name>.public.json to --out
+    // (or <name>.error). Files are picked up once their size has been stable for one poll.
+    Daemon {
+        #[arg(long)] params: String,
+        #[arg(long)] watch: String,
+        #[arg(long)] out: String,
+        #[arg(long)] pk: Option<String>,
+        // the one shape this daemon proves (policy-free, as in prove-batch)
+        #[arg(long, required_unless_present_any = ["preset", "features_n"])] shape: Option<String>,
+        #[arg(long, conflicts_with = "shape")] preset: Option<Preset>,
+        #[arg(long, conflicts_with_all = ["shape", "preset"])] features_n: Option<usize>,
+        #[arg(long, default_value = "0")] domain: String,
+        #[arg(long, default_value_t = 1000)] poll_ms: u64
+    },
+    // Verifies (proof, public) pairs (KZG): <name>.proof + <name>.public.json in --dir, or a JSON
+    // manifest [{"proof": .., "public": ..}]. Prints a JSON summary; exits non-zero if any fails.
+    VerifyBatch {
+        #[arg(long)] params: String,
+        #[arg(long, required_unless_present = "manifest")] dir: Option<String>,
+        #[arg(long, conflicts_with = "dir")] manifest: Option<String>,
+        // one verifying key for every pair; otherwise keygen once per distinct shape
+        #[arg(long)] vk: Option<String>,
+        #[arg(long, default_value = "0")] domain: String,
+        // aggregated: every proof is folded into one KZG accumulator and checked with a single
+        // pairing; only if that fails is each one checked alone, to name the bad ones
+        #[arg(long)] preset: Option<Preset>
+    },
+    // Writes the Solidity source (and, with solc on PATH, the deployable bytecode) of an on-chain KZG
+    // verifier for a shape's vk (needs --features evm). It checks proofs made with `prove --transcript keccak`.
+    ExportVerifier {
+        #[arg(long)] params: String,
+        #[arg(long, required_unless_present_any = ["preset", "features_n"])] shape: Option<String>,
+        #[arg(long, conflicts_with = "shape")] preset: Option<Preset>,
+        #[arg(long, conflicts_with_all = ["shape", "preset"])] features_n: Option<usize>,
+        #[arg(long)] vk: Option<String>,
+        // --yul-out from releases that emitted Yul
+        #[arg(long, alias = "yul-out", default_value = "verifier.sol")] sol_out: String,
+        #[arg(long)] bytecode_out: Option<String>
+    },
+    // Prints the constraint system of a shape: columns, gates, lookups, permutation and rows.
+    Stats {
+        #[arg(long, required_unless_present_any = ["preset", "features_n"])] shape: Option<String>,
+        #[arg(long, conflicts_with = "shape")] preset: Option<Preset>,
+        #[arg(long, conflicts_with_all = ["shape", "preset"])] features_n: Option<usize>,
+        // one line per gate: name, constraints, degree
+        #[arg(long)] gates: bool
+    },
+    // End-to-end smoke test: params (generated, or --params downsized), keygen, prove and verify a
+    // canned witness, then expect a tampered proof and a tampered public input to be rejected.
+    Selftest {
+        #[arg(long)] params: Option<String>
+    },
+    // Proof archive (see store.rs).
+    Store {
+        #[command(subcommand)] cmd: StoreCmd
+    },
+    // Shell completion script on stdout, e.g. `completions bash > /etc/bash_completion.d/halo2_tx_validator`.
+    Completions {
+        shell: clap_complete::Shell
+    },
+    // Prints the proof envelope and, given the vk, the transcript sections (KZG) with their sizes.
+    InspectProof {
         #[arg(long)] proof: String,
-        #[arg(long)] public: String
+        #[arg(long)] vk: Option<String>
     },
-    Verify {
+    // Archival proof codec (see codec.rs); decompress restores the envelope in the current layout.
+    Compress {
+        #[arg(long = "in")] input: String,
+        #[arg(long)] out: String,
+        // zstd level, 1..=22
+        #[arg(long, default_value_t = 19)] level: i32
+    },
+    Decompress {
+        #[arg(long = "in")] input: String,
+        #[arg(long)] out: String
+    },
+    // Writes the calldata for the export-verifier contract as hex: the flattened instances, then the proof.
+    EncodeCalldata {
+        #[arg(long)] proof: String,
+        #[arg(long)] public: String,
+        #[arg(long, default_value = "calldata.hex")] out: String
+    },
+    // One JSON artifact for relays and block builders: raw proof, instances flattened as 32-byte
+    // big-endian words (with per-column counts), vk fingerprint and, for KZG + keccak proofs,
+    // the export-verifier calldata.
+    ExportBundle {
+        #[arg(long)] proof: String,
+        #[arg(long)] public: String,
+        #[arg(long, default_value = "bundle.json")] out: String
+    },
+    // Deploys the export-verifier bytecode in a local EVM (revm) and calls it with the calldata;
+    // reports gas used (needs --features evm).
+    VerifyEvm {
+        #[arg(long)] bytecode: String,
+        #[arg(long)] calldata: String
+    },
+    Cost {
+        #[arg(long, required_unless_present = "preset")] features_n: Option<usize>,
+        #[arg(long)] preset: Option<Preset>,
+        #[arg(long, default_value = "cubic")] activation: Activation,
+        #[arg(long, default_value_t = 0)] sanctions_depth: usize,
+        #[arg(long, default_value_t = 0)] allowlist_depth: usize,
+        #[arg(long)] amount_index: Option<usize>
+    },
+    // Builds the sorted Merkle tree from a file with one 0x address per line.
+    SanctionsBuild {
+        #[arg(long)] list: String,
+        #[arg(long, default_value_t = 20)] depth: usize,
+        #[arg(long)] out: String
+    },
+    // Builds the sorted allowlist tree from a CSV whose first column is the 0x address.
+    AllowlistBuild {
+        #[arg(long)] csv: String,
+        #[arg(long, default_value_t = 20)] depth: usize,
+        #[arg(long)] out: String
+    },
+    // Proves that a window of amounts (JSON array, Q16.16) sums to at most --cap.
+    ProveVelocity {
+        #[arg(long, value_enum, default_value_t = Backend::Kzg)] backend: Backend,
+        #[arg(long)] params: String,
+        #[arg(long)] amounts: String,
+        #[arg(long)] cap: i64,
+        #[arg(long)] proof: String,
+        #[arg(long)] public: String,
+        #[arg(long, default_value = "0")] domain: String
+    },
+    VerifyVelocity {
+        #[arg(long, value_enum, default_value_t = Backend::Kzg)] backend: Backend,
+        #[arg(long)] params: String,
+        #[arg(long)] proof: String,
+        #[arg(long)] public: String,
+        // cap the verifier requires; defaults to the one in the public file
+        #[arg(long)] cap: Option<i64>,
+        #[arg(long, default_value = "0")] domain: String
+    },
+    // Scores the features with every model of the witness and proves the public mean.
+    ProveEnsemble {
+        #[arg(long, value_enum, default_value_t = Backend::Kzg)] backend: Backend,
         #[arg(long)] params: String,
+        #[arg(long, default_value = "cubic")] activation: Activation,
+        #[arg(long)] witness: String,
+        #[arg(long)] proof: String,
+        #[arg(long)] public: String,
+        #[arg(long, default_value = "0")] domain: String
+    },
+    VerifyEnsemble {
+        #[arg(long, value_enum, default_value_t = Backend::Kzg)] backend: Backend,
+        #[arg(long)] params: String,
+        #[arg(long)] proof: String,
+        #[arg(long)] public: String,
+        #[arg(long, default_value = "0")] domain: String
+    },
+    // Gradient-boosted stumps: --model holds base and stumps, --witness the features.
+    ProveTree {
+        #[arg(long, value_enum, default_value_t = Backend::Kzg)] backend: Backend,
+        #[arg(long)] params: String,
+        #[arg(long)] model: String,
+        #[arg(long)] witness: String,
+        #[arg(long)] proof: String,
+        #[arg(long)] public: String,
+        #[arg(long)] valid_until: Option<u64>,
+        #[arg(long)] now: Option<u64>,
+        #[arg(long, default_value = "0")] domain: String
+    },
+    VerifyTree {
+        #[arg(long, value_enum, default_value_t = Backend::Kzg)] backend: Backend,
+        #[arg(long)] params: String,
+        #[arg(long)] proof: String,
+        #[arg(long)] public: String,
+        #[arg(long, default_value = "0")] domain: String
+    },
+    // Builds the model registry from model files ({w, b}); a model's id is its position.
+    RegistryBuild {
+        #[arg(long = "model", required = true)] models: Vec<String>,
+        #[arg(long, default_value_t = 8)] depth: usize,
+        #[arg(long, default_value = "0")] domain: String,
+        #[arg(long)] out: String
+    },
+    Presets,
+}
+
+#[derive(Subcommand)]
+enum StoreCmd {
+    // Archives an existing proof; the tx hash is taken from the public inputs when the proof binds one.
+    Add {
+        #[arg(long)] db: String,
+        #[arg(long)] proof: String,
+        #[arg(long)] public: String,
+        // for proofs made without --tx-raw
+        #[arg(long)] tx_hash: Option<String>
+    },
+    // Lists matching proofs, newest first.
+    Query {
+        #[arg(long)] db: String,
+        #[arg(long)] tx_hash: Option<String>,
+        #[arg(long)] vk: Option<String>,
+        // UTC day the proof was created, AAAA-MM-DD
+        #[arg(long, conflicts_with_all = ["since", "until"])] date: Option<String>,
+        // Unix seconds, [since, until)
+        #[arg(long)] since: Option<u64>,
+        #[arg(long)] until: Option<u64>,
+        #[arg(long)] limit: Option<usize>
+    },
+    // Writes a stored proof and its public JSON back out, ready for `verify`.
+    Get {
+        #[arg(long)] db: String,
+        #[arg(long)] id: i64,
         #[arg(long)] proof: String,
         #[arg(long)] public: String
     },
 }
 
+#[derive(Subcommand)]
+enum VkCmd {
+    // Rewrites a KZG verifying key (keygen --vk-out) as JSON (see vk_json) or as halo2's raw bytes without
+    // the key file header (see keys.rs), for tools that read bare keys.
+    Export {
+        #[arg(long)] vk: String,
+        #[arg(long, value_enum, default_value_t = VkFormat::Json)] format: VkFormat,
+        #[arg(long)] out: String
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum VkFormat { Json, Raw }
+
+#[derive(Subcommand)]
+enum WitnessCmd {
+    // Checks a witness before proving: field types, lengths against the shape, Q16.16 ranges and
+    // score_pub against the native score. Every problem is reported with its JSON pointer.
+    Check {
+        #[arg(long)] witness: String,
+        // expected shape; without one, only w and x must agree in length
+        #[arg(long)] shape: Option<String>,
+        #[arg(long, conflicts_with = "shape")] preset: Option<Preset>,
+        #[arg(long, conflicts_with_all = ["shape", "preset"])] features_n: Option<usize>,
+        #[arg(long, default_value = "cubic")] activation: Activation
+    },
+    // One witness per CSV row (a header names the columns): the model's features are read by
+    // column name, as witness-gen reads tx fields. Writes <id>.json per row into --out-dir or
+    // one batch witness file (JSON Lines, for prove-batch --batch) to --out.
+    FromCsv {
+        #[arg(long)] csv: String,
+        #[arg(long)] model: String,
+        // names each witness; the row number (from 1) if the CSV has no such column
+        #[arg(long, default_value = "id")] id_column: String,
+        // per-row q_out column, falling back to --q-out
+        #[arg(long, default_value = "q_out")] q_out_column: String,
+        #[arg(long)] q_out: Option<f64>,
+        #[arg(long, default_value = "pwl")] activation: Activation,
+        #[arg(long, required_unless_present = "out", conflicts_with = "out")] out_dir: Option<String>,
+        #[arg(long)] out: Option<String>
+    },
+    // As from-csv, from a Parquet file or an Arrow IPC file (.arrow, .feather), record batch by
+    // record batch; feature columns may be any numeric type (needs --features parquet).
+    FromParquet {
+        #[arg(long = "in")] input: String,
+        #[arg(long)] model: String,
+        #[arg(long, default_value = "id")] id_column: String,
+        #[arg(long, default_value = "q_out")] q_out_column: String,
+        #[arg(long)] q_out: Option<f64>,
+        #[arg(long, default_value = "pwl")] activation: Activation,
+        #[arg(long, required_unless_present = "out", conflicts_with = "out")] out_dir: Option<String>,
+        #[arg(long)] out: Option<String>
+    },
+    // Rewrites a witness in the format of --out's extension: .qgw (binary, see witness.rs),
+    // .yaml/.yml, .toml or JSON.
+    Convert {
+        #[arg(long = "in")] input: String,
+        #[arg(long)] out: String,
+        // write x and w as sparse {"len", "values"} (not for .qgw)
+        #[arg(long)] sparse: bool,
+        // for a values_f64 witness without score_pub
+        #[arg(long, default_value = "pwl")] activation: Activation
+    },
+}
+
+#[derive(Subcommand)]
+enum CeremonyCmd {
+    // Starting params (tau = 1) and an empty transcript.
+    Init {
+        #[arg(long, required_unless_present = "preset")] k: Option<u32>,
+        #[arg(long)] preset: Option<Preset>,
+        #[arg(long)] out: String
+    },
+    // Adds a contribution: the secret comes from OS entropy mixed with --beacon and is never stored.
+    Contribute {
+        #[arg(long)] params: String,
+        #[arg(long)] out: String,
+        // recorded in the transcript and bound to the proof of knowledge
+        #[arg(long)] name: String,
+        #[arg(long)] beacon: Option<String>
+    },
+    // Checks every contribution and that the params are the final result.
+    Verify {
+        #[arg(long)] params: String
+    },
+}
+
+#[derive(Subcommand)]
+enum ParamsCmd {
+    // Converts a snarkjs phase-1 .ptau (bn128) into KZG params for k <= its power.
+    ImportPtau {
+        #[arg(long)] ptau: String,
+        #[arg(long, required_unless_present = "preset")] k: Option<u32>,
+        #[arg(long)] preset: Option<Preset>,
+        #[arg(long)] out: String
+    },
+    // Prints k, curve and fingerprints of a KZG params file; the SHA-256 is the audit's params_sha256.
+    Inspect {
+        #[arg(long)] params: String
+    },
+    // Downsizes KZG params to a smaller k (deterministic: the first 2^k points are kept) and writes
+    // <out>.json with the fingerprints of both files.
+    Trim {
+        #[arg(long)] params: String,
+        #[arg(long, required_unless_present = "preset")] k: Option<u32>,
+        #[arg(long)] preset: Option<Preset>,
+        #[arg(long)] out: String
+    },
+}
+
 #[derive(Deserialize)]
-struct Witness {
-    x: Vec<i64>, w: Vec<i64>, b: i64, alpha: i64, q_out: i64, score_pub: i64,
+struct FloatWitness {
+    x: Vec<f64>, w: Vec<f64>, b: f64, alpha: f64, q_out: f64,
+    #[serde(default)] score_pub: Option<f64>,
 }
+#[derive(Deserialize)]
+struct Model {
+    w: Vec<i64>, b: i64,
+    // used by witness-gen only: quantum mixing weight (Q16.16) and how each feature is read from a tx
+    #[serde(default)] alpha: i64,
+    #[serde(default)] features: Vec<FeatureSpec>,
+}
+// x = round(tx[field] * scale * 2^16); numbers, decimal strings and 0x hex are accepted.
+#[derive(Deserialize)]
+struct FeatureSpec {
+    field: String,
+    #[serde(default = "unit_scale")] scale: f64,
+}
+// Baby Jubjub points and scalars as decimal strings, as most EdDSA tooling prints them.
+#[derive(Deserialize)]
+struct OracleSigFile { key: [String; 2], r: [String; 2], s: String, epoch: u64 }
+#[derive(Deserialize)]
+struct TreeModel { base: i64, stumps: Vec<TreeStump> }
+#[derive(Deserialize)]
+struct TreeStump { feature: usize, threshold: i64, left: i64, right: i64 }
+#[derive(Deserialize)]
+struct TreeWitness { #[serde(deserialize_with = "dense_or_sparse")] x: Vec<i64> }
 #[derive(Serialize, Deserialize)]
-struct Public {
-    commit_wb: String, commit_q: String, score_pub: String,
-    instances: Vec<Vec<Fr>>,
+struct TreePublic {
+    shape: TreeShape,
+    score_pub: String,
+    commit_wb: String,
+    commit_x: String,
+    valid_until: u64,
+    domain: String,
+    #[serde(with = "hex_instances")] instances: Vec<Vec<Fr>>,
+}
+#[derive(Deserialize)]
+struct EnsembleWitness {
+    #[serde(deserialize_with = "dense_or_sparse")] x: Vec<i64>,
+    models: Vec<Model>,
+    #[serde(deserialize_with = "flexible_i64")] alpha: i64,
+    #[serde(deserialize_with = "flexible_i64")] q_out: i64,
+    #[serde(deserialize_with = "flexible_i64")] score_pub: i64,
+}
+#[derive(Serialize, Deserialize)]
+struct EnsemblePublic {
+    features: usize,
+    activation: Activation,
+    score_pub: String,
+    commit_wb: Vec<String>,
+    commit_x: String,
+    domain: String,
+    #[serde(with = "hex_instances")] instances: Vec<Vec<Fr>>,
+}
+#[derive(Serialize, Deserialize)]
+struct VelocityPublic {
+    window: usize,
+    cap: i64,
+    commit_amounts: String,
+    domain: String,
+    #[serde(with = "hex_instances")] instances: Vec<Vec<Fr>>,
+}
+// Instance columns as nested arrays of canonical hex strings (see `fe_to_hex`).
+#[derive(Serialize)]
+struct BenchResult {
+    features: usize,
+    k: u32,
+    rows: usize,
+    keygen_ms: u128,
+    prove_ms: u128,
+    verify_ms: u128,
+    proof_bytes: usize,
+    // process high-water mark so far (VmHWM), Linux only
+    peak_rss_kb: Option<u64>,
 }
 
-fn to_fr_q16(v: i64) -> Fr { fr_from_qi128(v as i128) }
+// estimate --calibrate output: bench runs on this machine.
+#[derive(Serialize, Deserialize)]
+struct EstimateTable {
+    cpu: String,
+    threads: usize,
+    runs: Vec<EstimateRun>,
+}
+#[derive(Serialize, Deserialize)]
+struct EstimateRun { k: u32, prove_ms: u128, peak_rss_kb: Option<u64> }
+
+fn cpu_model() -> String {
+    fs::read_to_string("/proc/cpuinfo").ok()
+        .and_then(|info| info.lines().find(|l| l.starts_with("model name")).and_then(|l| l.split(':').nth(1)).map(|m| m.trim().to_string()))
+        .unwrap_or_else(|| std::env::consts::ARCH.to_string())
+}
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let cli = Cli::parse();
-    match cli.cmd {
-        Cmd::GenParams { k, out } => {
-            let params = ParamsKZG::<Bn256>::setup(k, rand::thread_rng());
-            fs::write(out, params.to_bytes())?;
-            println!("Params KZG generados.");
+// Proving is dominated by FFTs and MSMs: time ~ n log n for n = 2^k rows, divided among threads
+// (assumed to scale linearly); memory ~ a + b·n. Both fitted to the table's runs.
+fn estimate(table: &EstimateTable, k: u32, threads: usize) -> Result<(f64, Option<f64>), String> {
+    if table.runs.is_empty() { return Err("la tabla de calibración está vacía".into()); }
+    let work = |k: u32| (1u64 << k) as f64 * k as f64;
+    let per_unit = table.runs.iter().map(|r| r.prove_ms as f64 / work(r.k)).sum::<f64>() / table.runs.len() as f64;
+    let ms = per_unit * work(k) * table.threads as f64 / threads.max(1) as f64;
+    let mem: Vec<(f64, f64)> = table.runs.iter().filter_map(|r| Some(((1u64 << r.k) as f64, r.peak_rss_kb? as f64))).collect();
+    let rss_kb = match mem.len() {
+        0 => None,
+        1 => Some(mem[0].1 * (1u64 << k) as f64 / mem[0].0),
+        m => {
+            let (mx, my) = (mem.iter().map(|p| p.0).sum::<f64>() / m as f64, mem.iter().map(|p| p.1).sum::<f64>() / m as f64);
+            let b = mem.iter().map(|p| (p.0 - mx) * (p.1 - my)).sum::<f64>() / mem.iter().map(|p| (p.0 - mx).powi(2)).sum::<f64>().max(f64::EPSILON);
+            Some(my + b * ((1u64 << k) as f64 - mx))
         }
-        Cmd::Prove { params, witness, proof, public } => {
-            let params_bytes = fs::read(params)?;
-            let params = ParamsKZG::<Bn256>::read(&mut &params_bytes[..]).unwrap();
+    };
+    Ok((ms, rss_kb))
+}
 
-            let wit: Witness = serde_json::from_str(&fs::read_to_string(&witness)?)?;
-            let circ = TxCircuit {
-                x: wit.x.into_iter().map(to_fr_q16).collect(),
-                w: wit.w.into_iter().map(to_fr_q16).collect(),
-                b: to_fr_q16(wit.b),
-                alpha: to_fr_q16(wit.alpha),
-                q_out: to_fr_q16(wit.q_out),
-                score_pub: to_fr_q16(wit.score_pub),
-            };
+#[derive(Deserialize)]
+struct ManifestEntry { proof: String, public: String }
+#[derive(Serialize)]
+struct VerifyEntry {
+    proof: String,
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+// One line of a batch witness file (JSON Lines): a witness plus the name its outputs get.
+#[derive(Serialize, Deserialize)]
+struct BatchWitness {
+    id: String,
+    // archived with the proof by prove-batch --store / backfill
+    #[serde(default, skip_serializing_if = "Option::is_none")] tx_hash: Option<String>,
+    #[serde(flatten)] witness: Witness,
+}
 
-            let vk = keygen_vk(&params, &circ)?;
-            let pk = keygen_pk(&params, vk, &circ)?;
-
-            // Públicos simplificados: solo score_pub
-            let instances: Vec<Vec<Fr>> = vec![ vec![Fr::from(0)], vec![Fr::from(0)], vec![to_fr_q16(wit.score_pub)] ];
-
-            let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
-            halo2_proofs::plonk::create_proof::<
-                halo2_proofs::poly::kzg::commitment::KZGCommitmentScheme<Bn256>,
-                ProverGWC<_>, _, _, _, _
-            >(&params, &pk, &[circ], &[&instances], rand::thread_rng(), &mut transcript)?;
-            let proof_bytes = transcript.finalize();
-            fs::write(&proof, &proof_bytes)?;
-
-            let pub_json = Public {
-                commit_wb: "0x00".into(),
-                commit_q: "0x00".into(),
-                score_pub: format!("{:?}", to_fr_q16(wit.score_pub)),
-                instances,
-            };
-            fs::write(&public, serde_json::to_vec_pretty(&pub_json)?)?;
-            println!("Prueba creada.");
+// A prove-batch input: a witness file, one line of a batch witness file, or (backfill) a
+// transaction whose witness could not be built, reported as failed with the reason.
+enum BatchItem {
+    File(std::path::PathBuf),
+    Line(BatchWitness),
+    Invalid(String, String),
+}
+
+impl BatchItem {
+    // (name in summaries and the manifest, stem of its output files)
+    fn names(&self) -> (String, String) {
+        match self {
+            BatchItem::File(path) => (path.display().to_string(), path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default()),
+            BatchItem::Line(line) => (line.id.clone(), line.id.clone()),
+            BatchItem::Invalid(id, _) => (id.clone(), id.clone()),
         }
-        Cmd::Verify { params, proof, public } => {
-            let params_bytes = fs::read(params)?;
-            let params = ParamsKZG::<Bn256>::read(&mut &params_bytes[..]).unwrap();
-            let proof_bytes = fs::read(proof)?;
-            let pub_json: Public = serde_json::from_slice(&fs::read(public)?)?;
-            let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof_bytes[..]);
-            let strategy = SingleStrategy::<halo2_proofs::poly::kzg::commitment::KZGCommitmentScheme<Bn256>>::new(&params);
-            halo2_proofs::plonk::verify_proof::<
-                halo2_proofs::poly::kzg::commitment::KZGCommitmentScheme<Bn256>,
-                VerifierGWC<_>, _, _
-            >(&params, &[], strategy, &[&pub_json.instances], &mut transcript)?;
-            println!("¡Prueba verificada!");
+    }
+}
+
+// prove-batch progress, rewritten (atomically) after every item so a killed run can --resume.
+#[derive(Default, Serialize, Deserialize)]
+struct JobManifest {
+    source: String,
+    items: BTreeMap<String, JobItem>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct JobItem {
+    // pending | done | failed
+    status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")] error: Option<String>,
+    #[serde(default)] ms: u128,
+    // stem of the batch proof holding a done item; absent when it is the item's own
+    #[serde(default, skip_serializing_if = "Option::is_none")] proof: Option<String>,
+}
+
+impl JobManifest {
+    fn save(&self, path: &Path) -> std::io::Result<()> {
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(self)?)?;
+        fs::rename(tmp, path)
+    }
+}
+
+// witness from-csv/from-parquet output: <id>.json files in out_dir, or one batch witness file.
+fn write_batch(batch: &[BatchWitness], out_dir: Option<String>, out: Option<String>) -> Result<(), Box<dyn std::error::Error>> {
+    match (out_dir, out) {
+        (Some(dir), _) => {
+            fs::create_dir_all(&dir)?;
+            for b in batch { write_witness(&Path::new(&dir).join(format!("{}.json", b.id)).display().to_string(), &b.witness)?; }
         }
+        (None, Some(out)) => {
+            let mut lines = vec![];
+            for b in batch { lines.extend(serde_json::to_vec(b)?); lines.push(b'\n'); }
+            write_out(&out, lines)?;
+        }
+        (None, None) => return Err("falta --out-dir o --out".into()),
+    }
+    Ok(())
+}
+
+fn read_batch(path: &str) -> Result<Vec<BatchWitness>, Box<dyn std::error::Error>> {
+    let text = String::from_utf8(read_input(path)?)?;
+    text.lines().enumerate().filter(|(_, l)| !l.trim().is_empty())
+        .map(|(i, l)| serde_json::from_str(l).map_err(|e| format!("{path}:{}: {e}", i + 1).into()))
+        .collect()
+}
+
+#[derive(Serialize)]
+struct BatchEntry {
+    witness: String,
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    ms: u128,
+}
+
+fn unit_scale() -> f64 { 1.0 }
+
+fn tx_number(tx: &serde_json::Value, field: &str) -> Result<f64, String> {
+    match tx.get(field) {
+        Some(serde_json::Value::Number(n)) => n.as_f64().ok_or(format!("campo {field} no numérico")),
+        Some(serde_json::Value::String(s)) => match s.strip_prefix("0x") {
+            Some(h) => u128::from_str_radix(h, 16).map(|v| v as f64).map_err(|_| format!("campo {field} hex inválido: {s}")),
+            None => s.trim().parse().map_err(|_| format!("campo {field} no numérico: {s}")),
+        },
+        Some(_) => Err(format!("campo {field} no numérico")),
+        None => Err(format!("la tx no tiene el campo {field}")),
+    }
+}
+
+fn quantize_witness(f: &FloatWitness, frac_bits: u32, rounding: Rounding, activation: Activation) -> Result<(Witness, Quantization), Box<dyn std::error::Error>> {
+    let one = (1u64 << frac_bits) as f64;
+    let mut max_error: BTreeMap<String, f64> = ["x", "w", "escalares"].map(|g| (g.to_string(), 0.0)).into();
+    let mut q = |v: f64, group: &str| -> Result<i64, String> {
+        let q = quantize(v, frac_bits, rounding)?;
+        let err = max_error.get_mut(group).unwrap();
+        *err = err.max((v - q as f64 / one).abs());
+        Ok(q)
+    };
+    let x = f.x.iter().map(|v| q(*v, "x")).collect::<Result<Vec<_>, _>>()?;
+    let w = f.w.iter().map(|v| q(*v, "w")).collect::<Result<Vec<_>, _>>()?;
+    let (b, alpha, q_out) = (q(f.b, "escalares")?, q(f.alpha, "escalares")?, q(f.q_out, "escalares")?);
+    let given = f.score_pub.map(|s| q(s, "escalares")).transpose()?;
+    let mut wit = Witness { x, w, b, alpha, q_out, score_pub: 0 };
+    wit.score_pub = match given {
+        Some(s) => s,
+        None if frac_bits == FRAC_BITS => fr_to_u64(&base_circuit(&wit, activation, "0").score()).map(|s| s as i64)
+            .ok_or("el score no es un Q16.16 exacto; usa --activation pwl")?,
+        None => return Err("sin score_pub en la entrada solo se puede calcular con frac_bits = 16".into()),
+    };
+    let rounding = rounding.to_possible_value().map(|v| v.get_name().to_string()).unwrap_or_default();
+    Ok((wit, Quantization { frac_bits, rounding, max_error }))
+}
+
+fn quantize(v: f64, frac_bits: u32, rounding: Rounding) -> Result<i64, String> {
+    let scaled = v * (1u64 << frac_bits) as f64;
+    let q = match rounding {
+        Rounding::Nearest => scaled.round(),
+        Rounding::Floor => scaled.floor(),
+        Rounding::Ceil => scaled.ceil(),
+        Rounding::Trunc => scaled.trunc(),
+    };
+    if !q.is_finite() || q.abs() >= i64::MAX as f64 { return Err(format!("{v} no cabe en punto fijo con {frac_bits} bits")); }
+    Ok(q as i64)
+}
+
+fn quantize_q16(v: f64) -> Result<i64, String> { Fixed16::from_f64(v).map(Fixed16::raw) }
+
+// Witness of one transaction under a model with a feature mapping; score_pub is computed.
+fn witness_from_tx(tx: &serde_json::Value, model: &Model, q_out: f64, activation: Activation) -> Result<Witness, Box<dyn std::error::Error>> {
+    check_feature_map(model)?;
+    let x = model.features.iter().map(|f| quantize_q16(tx_number(tx, &f.field)? * f.scale)).collect::<Result<Vec<_>, _>>()?;
+    witness_from_features(x, model, q_out, activation)
+}
+
+fn check_feature_map(model: &Model) -> Result<(), String> {
+    if model.features.len() != model.w.len() {
+        return Err(format!("el modelo tiene {} pesos y {} features", model.w.len(), model.features.len()));
+    }
+    Ok(())
+}
+
+// x already quantized to Q16.16, in the model's feature order.
+fn witness_from_features(x: Vec<i64>, model: &Model, q_out: f64, activation: Activation) -> Result<Witness, Box<dyn std::error::Error>> {
+    if !(0.0..=1.0).contains(&q_out) { return Err(format!("q_out = {q_out} fuera de [0, 1]").into()); }
+    let mut wit = Witness { x, w: model.w.clone(), b: model.b, alpha: model.alpha, q_out: quantize_q16(q_out)?, score_pub: 0 };
+    let circ = base_circuit(&wit, activation, "0");
+    wit.score_pub = fr_to_u64(&circ.score()).map(|s| s as i64)
+        .ok_or("el score no es un Q16.16 exacto; usa --activation pwl")?;
+    Ok(wit)
+}
+
+fn peak_rss_kb() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|l| l.starts_with("VmHWM:"))?;
+    line.split_whitespace().nth(1)?.parse().ok()
+}
+
+// All-zero features and weights: the score is sigmoid(0), which is enough to exercise every gate of the shape.
+fn bench_one(features: usize, k: u32, activation: Activation) -> Result<BenchResult, Box<dyn std::error::Error>> {
+    let mut circ = TxCircuit::<Fr>::empty(Shape { features, activation, ..Default::default() });
+    circ.valid_until = Fr::from(NEVER_EXPIRES);
+    circ.score_pub = circ.score();
+    let rows = circ.cost_estimate().rows;
+    let instances = tx_instances(&circ);
+    let instances: Vec<&[Fr]> = instances.iter().map(|v| &v[..]).collect();
+    let params = ParamsKZG::<Bn256>::setup(k, rand::thread_rng());
+
+    let start = Instant::now();
+    let vk = keygen_vk(&params, &circ)?;
+    let pk = keygen_pk(&params, vk, &circ)?;
+    let keygen_ms = start.elapsed().as_millis();
+
+    let start = Instant::now();
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    halo2_proofs::plonk::create_proof::<Kzg, ProverGWC<_>, _, _, _, _>(
+        &params, &pk, &[circ], &[&instances[..]], rand::thread_rng(), &mut transcript
+    )?;
+    let proof = transcript.finalize();
+    let prove_ms = start.elapsed().as_millis();
+
+    let start = Instant::now();
+    let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]);
+    halo2_proofs::plonk::verify_proof::<Kzg, VerifierGWC<_>, _, _, _>(
+        &params, pk.get_vk(), SingleStrategy::<Bn256>::new(&params), &[&instances[..]], &mut transcript
+    )?;
+    let verify_ms = start.elapsed().as_millis();
+
+    Ok(BenchResult { features, k, rows, keygen_ms, prove_ms, verify_ms, proof_bytes: proof.len(), peak_rss_kb: peak_rss_kb() })
+}
+
+fn unix_now() -> u64 { SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0) }
+
+fn sha256_hex(bytes: &[u8]) -> String { hex::encode(Sha256::digest(bytes)) }
+
+fn model_sha256(w: &[i64], b: i64) -> String {
+    let mut h = Sha256::new();
+    for wi in w { h.update(wi.to_le_bytes()); }
+    h.update(b.to_le_bytes());
+    hex::encode(h.finalize())
+}
+
+// VK fingerprint: halo2's transcript_repr, a Blake2b hash of the canonical (pinned) key: domain,
+// constraint system, fixed and permutation commitments. Keygen prints it, the proof envelope and
+// the public file carry it, and verify refuses a proof whose fingerprints disagree.
+fn vk_digest(repr: Fr) -> String { hex::encode(repr.to_repr()) }
+
+fn check_audit(audit: &Option<Audit>, expected: &Audit) -> Result<(), String> {
+    let audit = audit.as_ref().ok_or("el archivo público no contiene campos de auditoría")?;
+    let checks = [
+        ("params", &audit.params_sha256, &expected.params_sha256),
+        ("vk", &audit.vk_digest, &expected.vk_digest),
+        ("modelo", &audit.model_sha256, &expected.model_sha256),
+    ];
+    for (name, got, want) in checks {
+        if got != want { return Err(format!("digest de {name} no coincide: {got} != {want}")); }
     }
+    if audit.policy_version != expected.policy_version { return Err("versión de política no coincide".into()); }
+    if audit.feature_schema != expected.feature_schema { return Err("esquema de features no coincide".into()); }
     Ok(())
 }
+
+fn to_fr_q16(v: i64) -> Fr { Fixed16::from_raw(v).to_field() }
+
+// Small non-negative field elements only (e.g. a Q16.16 score).
+fn fr_to_u64(f: &Fr) -> Option<u64> {
+    let repr = f.to_repr();
+    let bytes = repr.as_ref();
+    bytes[8..].iter().all(|b| *b == 0).then(|| u64::from_le_bytes(bytes[..8].try_into().unwrap()))
+}
+
+fn fr_from_dec(s: &str) -> Result<Fr, String> {
+    Fr::from_str_vartime(s.trim()).ok_or_else(|| format!("elemento de campo inválido: {s}"))
+}
+
+fn load_oracle_sig(path: &str) -> Result<OracleSignature<Fr>, Box<dyn std::error::Error>> {
+    let f: OracleSigFile = serde_json::from_str(&fs::read_to_string(path)?)?;
+    Ok(OracleSignature {
+        key: (fr_from_dec(&f.key[0])?, fr_from_dec(&f.key[1])?),
+        r: (fr_from_dec(&f.r[0])?, fr_from_dec(&f.r[1])?),
+        s: fr_from_dec(&f.s)?,
+        epoch: Fr::from(f.epoch),
+    })
+}
+
+fn load_calibration(path: &str) -> Result<Calibration, Box<dyn std::error::Error>> {
+    let calibration: Calibration = serde_json::from_str(&fs::read_to_string(path)?)?;
+    calibration.validate()?;
+    Ok(calibration)
+}
+
+fn load_tree(path: &str) -> Result<SortedMerkleTree, Box<dyn std::error::Error>> {
+    let tree: SortedMerkleTree = serde_json::from_str(&fs::read_to_string(path)?)?;
+    Ok(tree.rebuild()?)
+}
+
+// Inverse of tx_hash_instances: the bound tx hash, if the proof has one.
+fn tx_hash_from_instances(instances: &[Vec<Fr>]) -> Option<String> {
+    let halves = instances.get(15).filter(|c| c.len() == 2)?;
+    let bytes: Vec<u8> = halves.iter().flat_map(|h| {
+        let mut half = h.to_repr()[..16].to_vec();
+        half.reverse();
+        half
+    }).collect();
+    Some(format!("0x{}", hex::encode(bytes)))
+}
+
+// Archives a proof with its public JSON; returns the row id.
+fn store_proof(db: &str, proof: &[u8], public: &PublicInputs, tx_hash: Option<String>) -> Result<i64, Box<dyn std::error::Error>> {
+    let envelope = ProofEnvelope::from_bytes(proof)?;
+    let tx_hash = tx_hash.or_else(|| tx_hash_from_instances(&public.instances));
+    let id = Store::open(db)?.insert(tx_hash.as_deref(), &hex::encode(envelope.vk_digest), envelope.created_at, unix_now(), proof, &serde_json::to_string(public)?)?;
+    Ok(id)
+}
+
+fn load_registry(path: &str) -> Result<ModelRegistry, Box<dyn std::error::Error>> {
+    let registry: ModelRegistry = serde_json::from_str(&fs::read_to_string(path)?)?;
+    Ok(registry.rebuild()?)
+}
+
+fn model_commit(domain: Fr, w: &[i64], b: i64) -> Fr {
+    let inputs: Vec<Fr> = w.iter().chain([&b]).map(|v| to_fr_q16(*v)).collect();
+    commit_in_domain(Tag::Wb, domain, &inputs)
+}
+
+fn read_address_csv(path: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    Ok(fs::read_to_string(path)?
+        .lines()
+        .filter_map(|l| l.split(',').next().map(|c| c.trim().trim_matches('"').to_string()))
+        .filter(|a| a.starts_with("0x"))
+        .collect())
+}
+
+type Kzg = halo2_proofs::poly::kzg::commitment::KZGCommitmentScheme<Bn256>;
+
+// Keys cached by path: loaded when the file exists, otherwise generated and written there.
+fn cached_pk<C: Circuit<Fr, Params = ()>>(path: Option<&str>, keygen: impl FnOnce() -> Result<ProvingKey<G1Affine>, Box<dyn std::error::Error>>) -> Result<ProvingKey<G1Affine>, Box<dyn std::error::Error>> {
+    match path {
+        Some(p) if is_input(p) => Ok(ProvingKeyFile::read::<C>(&mut &read_input(p)?[..])?.pk),
+        Some(p) => {
+            let file = ProvingKeyFile::new(keygen()?, None);
+            let mut bytes = vec![];
+            file.write(&mut bytes)?;
+            write_out(p, bytes)?;
+            Ok(file.pk)
+        }
+        None => keygen(),
+    }
+}
+
+fn cached_vk<C: Circuit<Fr, Params = ()>>(path: Option<&str>, keygen: impl FnOnce() -> Result<VerifyingKey<G1Affine>, Box<dyn std::error::Error>>) -> Result<VerifyingKey<G1Affine>, Box<dyn std::error::Error>> {
+    match path {
+        Some(p) if is_input(p) => Ok(VerifyingKeyFile::read::<C>(&mut &read_input(p)?[..])?.vk),
+        Some(p) => {
+            let file = VerifyingKeyFile::new(keygen()?, None);
+            let mut bytes = vec![];
+            file.write(&mut bytes)?;
+            write_out(p, bytes)?;
+            Ok(file.vk)
+        }
+        None => keygen(),
+    }
+}
+
+// The library's proving and verification, with phase timings on stderr.
+fn create_kzg_proof<C: Circuit<Fr, Params = ()>>(params: &ParamsKZG<Bn256>, pk: &ProvingKey<G1Affine>, circ: C, instances: &[&[Fr]], rng: impl RngCore, transcript: Transcript) -> Result<ProofEnvelope, Box<dyn std::error::Error>> {
+    Ok(timed(PROVE_PHASE, || prover::create_kzg_proof(params, pk, circ, instances, rng, transcript))?)
+}
+
+fn check_kzg_proof(params: &ParamsKZG<Bn256>, vk: &VerifyingKey<G1Affine>, envelope: &ProofEnvelope, instances: &[&[Fr]]) -> Result<(), Box<dyn std::error::Error>> {
+    Ok(timed("verificación", || prover::check_kzg_proof(params, vk, envelope, instances))?)
+}
+
+fn prove_kzg<C: Circuit<Fr, Params = ()>>(params_bytes: &[u8], circ: C, instances: &[Vec<Fr>], pk: Option<&str>, rng: impl RngCore, transcript: Transcript) -> Result<(Vec<u8>, Fr), Box<dyn std::error::Error>> {
+    let params = timed("carga de params", || prover::read_params(params_bytes))?;
+    let pk = timed("clave de prueba", || cached_pk::<C>(pk, || {
+        let vk = keygen_vk(&params, &circ)?;
+        Ok(keygen_pk(&params, vk, &circ)?)
+    }))?;
+    let instances: Vec<&[Fr]> = instances.iter().map(|v| &v[..]).collect();
+    let envelope = create_kzg_proof(&params, &pk, circ, &instances, rng, transcript)?;
+    Ok((envelope.to_bytes(), pk.get_vk().transcript_repr()))
+}
+
+fn verify_kzg<C: Circuit<Fr, Params = ()>>(params_bytes: &[u8], empty: &C, proof_bytes: &[u8], instances: &[Vec<Fr>], vk: Option<&str>) -> Result<Fr, Box<dyn std::error::Error>> {
+    let params = timed("carga de params", || prover::read_params(params_bytes))?;
+    let vk = timed("clave de verificación", || cached_vk::<C>(vk, || Ok(keygen_vk(&params, empty)?)))?;
+    let envelope = ProofEnvelope::from_bytes(proof_bytes)?;
+    let instances: Vec<&[Fr]> = instances.iter().map(|v| &v[..]).collect();
+    check_kzg_proof(&params, &vk, &envelope, &instances)?;
+    Ok(vk.transcript_repr())
+}
+
+// --shape file, --preset or a bare feature count, as accepted by keygen and export-verifier.
+fn resolve_shape(shape: Option<String>, preset: Option<Preset>, features_n: Option<usize>) -> Result<Shape, Box<dyn std::error::Error>> {
+    Ok(match (shape, preset, features_n) {
+        (Some(path), _, _) => serde_json::from_str(&fs::read_to_string(path)?)?,
+        (None, Some(p), _) if p.spec().batch_size > 1 => return Err(format!("el preset {p} prueba lotes: úsalo con prove-batch, verify-batch o cost").into()),
+        (None, Some(p), _) => p.shape(),
+        (None, None, Some(features)) => Shape { features, ..Default::default() },
+        (None, None, None) => return Err("falta --shape, --preset o --features-n".into()),
+    })
+}
+
+// Layout read by the snark-verifier contract (no selector): every instance value, column by column,
+// as a 32-byte big-endian word, followed by the raw proof transcript.
+fn evm_calldata(instances: &[Vec<Fr>], proof: &[u8]) -> Vec<u8> {
+    let mut out: Vec<u8> = instances.iter().flatten().flat_map(evm_word).collect();
+    out.extend_from_slice(proof);
+    out
+}
+
+fn evm_word(v: &Fr) -> [u8; 32] {
+    let mut word: [u8; 32] = v.to_repr().as_ref().try_into().expect("Fr de 32 bytes");
+    word.reverse();
+    word
+}
+
+// --json: prose goes to stderr and a single summary object to stdout (see main); plain mode prints as before.
+static JSON_MODE: AtomicBool = AtomicBool::new(false);
+static STDOUT_TAKEN: AtomicBool = AtomicBool::new(false);
+// (path, sha256) of every file written, in order
+static OUTPUTS: Mutex<Vec<(String, String)>> = Mutex::new(Vec::new());
+// structured result of commands that produce one (bench, verify-batch)
+static RESULT: Mutex<Option<serde_json::Value>> = Mutex::new(None);
+
+macro_rules! say {
+    ($($arg:tt)*) => {
+        if JSON_MODE.load(Ordering::Relaxed) { eprintln!($($arg)*) } else { println!($($arg)*) }
+    };
+}
+// Always stderr: for commands whose stdout carries data (frames, generated files).
+macro_rules! esay {
+    ($($arg:tt)*) => { eprintln!($($arg)*) };
+}
+
+fn report(value: serde_json::Value) {
+    if JSON_MODE.load(Ordering::Relaxed) {
+        *RESULT.lock().unwrap() = Some(value);
+    } else {
+        println!("{}", serde_json::to_string_pretty(&value).unwrap_or_default());
+    }
+}
+
+// One MockProver failure, flattened for people who don't read halo2's Debug output.
+#[derive(Serialize)]
+struct FailureReport {
+    kind: &'static str,
+    // constraint or lookup with its gate, as "Constraint i ('name') in gate j ('gate')"
+    what: String,
+    region: Option<String>,
+    // offset within the region, or absolute row outside any region
+    row: Option<usize>,
+    // cell -> value, for unsatisfied constraints
+    cells: Vec<(String, String)>,
+}
+
+fn locate(location: &FailureLocation) -> (Option<String>, Option<usize>) {
+    match location {
+        FailureLocation::InRegion { region, offset } => (Some(region.to_string()), Some(*offset)),
+        FailureLocation::OutsideRegion { row } => (None, Some(*row)),
+    }
+}
+
+fn failure_report(f: &VerifyFailure) -> FailureReport {
+    let (kind, what, (region, row), cells) = match f {
+        VerifyFailure::ConstraintNotSatisfied { constraint, location, cell_values } => (
+            "restricción", constraint.to_string(), locate(location),
+            cell_values.iter().map(|(cell, v)| (cell.to_string(), v.clone())).collect(),
+        ),
+        VerifyFailure::CellNotAssigned { gate, region, gate_offset, column, offset } => (
+            "celda sin asignar", format!("{gate}, columna {column:?} rotación {offset}"), (Some(region.to_string()), Some(*gate_offset)), vec![],
+        ),
+        VerifyFailure::Lookup { name, lookup_index, location } => ("lookup", format!("lookup {lookup_index} ('{name}')"), locate(location), vec![]),
+        VerifyFailure::Permutation { column, location } => ("permutación", format!("columna {column}"), locate(location), vec![]),
+        other => ("otro", other.to_string(), (None, None), vec![]),
+    };
+    FailureReport { kind, what, region, row, cells }
+}
+
+// Table on stdout (or the JSON result with --json).
+fn print_failures(failures: &[VerifyFailure]) {
+    let reports: Vec<FailureReport> = failures.iter().map(failure_report).collect();
+    if JSON_MODE.load(Ordering::Relaxed) {
+        report(serde_json::json!({ "failures": reports }));
+        return;
+    }
+    say!("{:<4} {:<18} {:<44} {:>6}  {}", "#", "tipo", "región", "fila", "detalle");
+    for (i, f) in reports.iter().enumerate() {
+        let row = f.row.map_or("-".into(), |r| r.to_string());
+        say!("{:<4} {:<18} {:<44} {:>6}  {}", i + 1, f.kind, f.region.as_deref().unwrap_or("-"), row, f.what);
+        for (cell, value) in &f.cells { say!("{:<4} {:<18} {:<44} {:>6}    {cell} = {value}", "", "", "", ""); }
+    }
+}
+
+// A local path or, with the `remote` feature, an https/s3/gs URI (see remote.rs).
+fn read_input(path: &str) -> std::io::Result<Vec<u8>> {
+    #[cfg(feature = "remote")]
+    if remote::is_remote(path) {
+        return remote::fetch(path).map_err(std::io::Error::other);
+    }
+    #[cfg(not(feature = "remote"))]
+    if path.contains("://") {
+        return Err(std::io::Error::other(format!("{path}: las URIs requieren compilar con --features remote")));
+    }
+    fs::read(path)
+}
+// Whether a cached key can be loaded rather than generated.
+fn is_input(path: &str) -> bool {
+    #[cfg(feature = "remote")]
+    if remote::is_remote(path) { return true; }
+    Path::new(path).exists()
+}
+// Witness and public files: YAML (.yaml, .yml), TOML (.toml) or JSON (any other name), all
+// through the same serde model. Frames on stdin/stdout and batch outputs stay JSON.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum DocFormat { Json, Yaml, Toml }
+
+impl DocFormat {
+    fn of(path: impl AsRef<Path>) -> Self {
+        match path.as_ref().extension().and_then(|e| e.to_str()) {
+            Some("yaml" | "yml") => DocFormat::Yaml,
+            Some("toml") => DocFormat::Toml,
+            _ => DocFormat::Json,
+        }
+    }
+
+    fn parse<T: DeserializeOwned>(self, bytes: &[u8]) -> Result<T, Box<dyn std::error::Error>> {
+        Ok(match self {
+            DocFormat::Json => serde_json::from_slice(bytes)?,
+            DocFormat::Yaml => serde_yaml::from_slice(bytes)?,
+            DocFormat::Toml => toml::from_str(std::str::from_utf8(bytes)?)?,
+        })
+    }
+
+    fn render<T: Serialize>(self, value: &T) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        Ok(match self {
+            DocFormat::Json => serde_json::to_vec_pretty(value)?,
+            DocFormat::Yaml => serde_yaml::to_string(value)?.into_bytes(),
+            DocFormat::Toml => toml::to_string_pretty(value)?.into_bytes(),
+        })
+    }
+}
+
+fn read_doc<T: DeserializeOwned>(path: &str) -> Result<T, Box<dyn std::error::Error>> {
+    DocFormat::of(path).parse(&read_input(path)?)
+}
+
+fn write_doc<T: Serialize>(path: impl AsRef<Path>, value: &T) -> Result<(), Box<dyn std::error::Error>> {
+    write_out(&path, DocFormat::of(&path).render(value)?)?;
+    Ok(())
+}
+
+// A witness file as prove-batch and the daemon pick them up.
+fn is_doc(path: &Path) -> bool {
+    path.extension().and_then(|e| e.to_str()).is_some_and(|e| ["json", "yaml", "yml", "toml", "qgw"].contains(&e))
+}
+
+fn is_binary_witness(path: impl AsRef<Path>) -> bool { path.as_ref().extension().is_some_and(|e| e == "qgw") }
+
+// Witness files: binary (.qgw) or any DocFormat. A document may instead hold real numbers,
+// {"values_f64": {x, w, b, alpha, q_out, score_pub?}}, quantized here to Q16.16 (nearest);
+// score_pub, when absent, is computed with `activation`.
+fn read_witness(path: &str, activation: Activation) -> Result<Witness, Box<dyn std::error::Error>> {
+    Ok(read_witness_quantized(path, activation)?.0)
+}
+
+fn read_witness_quantized(path: &str, activation: Activation) -> Result<(Witness, Option<Quantization>), Box<dyn std::error::Error>> {
+    if is_binary_witness(path) {
+        let bytes = read_input(path)?;
+        let bin = BinaryWitness::decode(&bytes)?;
+        return Ok((Witness { x: bin.x().collect(), w: bin.w().collect(), b: bin.b, alpha: bin.alpha, q_out: bin.q_out, score_pub: bin.score_pub }, None));
+    }
+    witness_from_doc(read_doc(path)?, activation)
+}
+
+fn witness_from_doc(doc: serde_json::Value, activation: Activation) -> Result<(Witness, Option<Quantization>), Box<dyn std::error::Error>> {
+    match doc.get("values_f64") {
+        Some(f) => {
+            let f: FloatWitness = serde_json::from_value(f.clone())?;
+            let (wit, q) = quantize_witness(&f, FRAC_BITS, Rounding::Nearest, activation)?;
+            Ok((wit, Some(q)))
+        }
+        None => Ok((serde_json::from_value(doc)?, None)),
+    }
+}
+
+fn write_witness(path: &str, wit: &Witness) -> Result<(), Box<dyn std::error::Error>> {
+    if !is_binary_witness(path) { return write_doc(path, wit); }
+    write_out(path, BinaryWitness::encode(&wit.x, &wit.w, wit.b, wit.alpha, wit.q_out, wit.score_pub))?;
+    Ok(())
+}
+
+fn write_out(path: impl AsRef<Path>, bytes: impl AsRef<[u8]>) -> std::io::Result<()> {
+    fs::write(&path, &bytes)?;
+    OUTPUTS.lock().unwrap().push((path.as_ref().display().to_string(), sha256_hex(bytes.as_ref())));
+    Ok(())
+}
+
+// A valid proof whose score fails `verify --threshold`.
+#[derive(Debug)]
+struct PolicyReject(String);
+
+impl std::fmt::Display for PolicyReject {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result { write!(f, "REJECT: {}", self.0) }
+}
+
+impl std::error::Error for PolicyReject {}
+
+// Exit codes: 0 ok, 1 other failure, 2 usage (clap), 3 proof rejected, 4 I/O, 5 malformed input,
+// 6 valid proof rejected by --threshold.
+fn exit_code(command: &str, e: &(dyn std::error::Error + 'static)) -> u8 {
+    if e.is::<PolicyReject>() { return 6; }
+    match e.downcast_ref::<QuantumGuardError>() {
+        Some(QuantumGuardError::Witness(_) | QuantumGuardError::Params(_)) => return 5,
+        Some(QuantumGuardError::Verification(_)) => return 3,
+        _ => {}
+    }
+    if e.is::<std::io::Error>() { return 4; }
+    if e.is::<serde_json::Error>() || e.is::<serde_yaml::Error>() || e.is::<toml::de::Error>() || e.is::<hex::FromHexError>() { return 5; }
+    if command.starts_with("verify") { 3 } else { 1 }
+}
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+// create_proof is one call; build with --features profile for halo2's own breakdown of it
+// (witness synthesis, commitments, opening) on stderr.
+const PROVE_PHASE: &str = "prueba (síntesis, compromisos, apertura)";
+
+// Runs one phase with a start line, a heartbeat every 10 s and its duration on stderr; --quiet silences it.
+fn timed<T>(phase: &str, f: impl FnOnce() -> T) -> T {
+    if QUIET.load(Ordering::Relaxed) { return f(); }
+    eprintln!("> {phase}...");
+    let start = Instant::now();
+    let out = std::thread::scope(|s| {
+        let (done, ticks) = std::sync::mpsc::channel::<()>();
+        s.spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = ticks.recv_timeout(Duration::from_secs(10)) {
+                eprintln!("  {phase}: {} s", start.elapsed().as_secs());
+            }
+        });
+        let out = f();
+        drop(done);
+        out
+    });
+    eprintln!("  {phase}: {:.2} s", start.elapsed().as_secs_f64());
+    out
+}
+
+// Stream framing for --stdin/--stdout: u32 little-endian length, then the bytes.
+fn write_frame(w: &mut impl Write, bytes: &[u8]) -> std::io::Result<()> {
+    w.write_all(&(bytes.len() as u32).to_le_bytes())?;
+    w.write_all(bytes)
+}
+
+fn read_frame(r: &mut impl Read) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let mut len = [0u8; 4];
+    r.read_exact(&mut len).map_err(|e| format!("marco de entrada ilegible: {e}"))?;
+    let mut bytes = vec![0; u32::from_le_bytes(len) as usize];
+    r.read_exact(&mut bytes).map_err(|e| format!("marco de entrada truncado: {e}"))?;
+    Ok(bytes)
+}
+
+// Length of every instance column for `shape`; fixed per vk, so the EVM verifier hardcodes it.
+fn num_instances(shape: Shape) -> Vec<usize> {
+    tx_instances(&TxCircuit::<Fr>::empty(shape)).iter().map(Vec::len).collect()
+}
+
+// ChaCha20 from --seed, otherwise from OS entropy.
+fn seeded_rng(seed: Option<u64>) -> ChaCha20Rng {
+    seed.map_or_else(ChaCha20Rng::from_entropy, ChaCha20Rng::seed_from_u64)
+}
+
+// Setup randomness: SHA-256 over 32 bytes of OS entropy and the beacon. The beacon can't weaken a
+// sound OS source; with a broken one it still leaves the toxic waste unknown to whoever did not
+// know the beacon when the params were made. Nothing here is verifiable after the fact: the
+// record only says which beacon was claimed (see `ceremony` for params nobody has to trust).
+fn setup_rng(beacon: Option<&str>) -> ChaCha20Rng {
+    let mut os = [0u8; 32];
+    rand::rngs::OsRng.fill_bytes(&mut os);
+    let mut h = Sha256::new();
+    h.update(b"quantum-guard/setup-entropy/1");
+    h.update(os);
+    if let Some(beacon) = beacon { h.update(beacon.as_bytes()); }
+    ChaCha20Rng::from_seed(h.finalize().into())
+}
+
+// (JSON pointer, message) for every problem in a witness document; empty if it can be proven.
+fn check_witness(doc: &serde_json::Value, features: Option<usize>, activation: Activation) -> Vec<(String, String)> {
+    // signed Q16.16 in 32 bits; probabilities in [0, 1]
+    const Q16_MAX: i64 = i32::MAX as i64;
+    let mut errors = vec![];
+    let Some(obj) = doc.as_object() else {
+        return vec![("".into(), "se espera un objeto".into())];
+    };
+    for key in obj.keys().filter(|k| !["x", "w", "b", "alpha", "q_out", "score_pub"].contains(&k.as_str())) {
+        errors.push((format!("/{key}"), "campo desconocido".into()));
+    }
+    fn int(errors: &mut Vec<(String, String)>, ptr: String, v: Option<&serde_json::Value>, range: std::ops::RangeInclusive<i64>) -> Option<i64> {
+        match v.map(|v| serde_json::from_value::<FlexI64>(v.clone()).ok().map(|v| v.0)) {
+            None => errors.push((ptr, "falta".into())),
+            Some(None) => errors.push((ptr, "se espera un entero (Q16.16) o una cadena numérica".into())),
+            Some(Some(n)) if !range.contains(&n) => errors.push((ptr, format!("{n} fuera de [{}, {}]", range.start(), range.end()))),
+            Some(Some(n)) => return Some(n),
+        }
+        None
+    }
+    let unit = 0..=ONE_Q16 as i64;
+    let b = int(&mut errors, "/b".into(), obj.get("b"), -Q16_MAX..=Q16_MAX);
+    let alpha = int(&mut errors, "/alpha".into(), obj.get("alpha"), unit.clone());
+    let q_out = int(&mut errors, "/q_out".into(), obj.get("q_out"), unit.clone());
+    let score_pub = int(&mut errors, "/score_pub".into(), obj.get("score_pub"), unit);
+    // sparse vectors are checked in their dense expansion
+    let mut dense = serde_json::Map::new();
+    for name in ["x", "w"] {
+        if let Some(v @ serde_json::Value::Object(_)) = obj.get(name) {
+            match serde_json::from_value::<SparseVector>(v.clone()).map_err(|e| e.to_string()).and_then(|s| s.to_dense()) {
+                Ok(d) => { dense.insert(name.to_string(), serde_json::json!(d)); }
+                Err(e) => errors.push((format!("/{name}"), format!("vector disperso inválido: {e}"))),
+            }
+        }
+    }
+    let obj = &obj.clone().into_iter().chain(dense).collect::<serde_json::Map<_, _>>();
+    let mut vector = |name: &str| -> Option<Vec<i64>> {
+        let items = match obj.get(name) {
+            Some(serde_json::Value::Array(items)) => items,
+            Some(_) => { errors.push((format!("/{name}"), "se espera un array".into())); return None; }
+            None => { errors.push((format!("/{name}"), "falta".into())); return None; }
+        };
+        let values: Vec<Option<i64>> = items.iter().enumerate().map(|(i, v)| int(&mut errors, format!("/{name}/{i}"), Some(v), -Q16_MAX..=Q16_MAX)).collect();
+        values.into_iter().collect()
+    };
+    let (x, w) = (vector("x"), vector("w"));
+    let x_len = obj.get("x").and_then(|v| v.as_array()).map(Vec::len);
+    let w_len = obj.get("w").and_then(|v| v.as_array()).map(Vec::len);
+    if let Some(n) = features {
+        for (ptr, len) in [("/x", x_len), ("/w", w_len)] {
+            if let Some(len) = len.filter(|len| *len != n) { errors.push((ptr.into(), format!("{len} elementos, la forma espera {n}"))); }
+        }
+    } else if let (Some(xl), Some(wl)) = (x_len, w_len) {
+        if xl != wl { errors.push(("/w".into(), format!("{wl} pesos para {xl} features"))); }
+    }
+    // the raw score; a calibrated proof publishes calibrate(score) instead
+    if let (true, Some(x), Some(w), Some(b), Some(alpha), Some(q_out), Some(score_pub)) = (errors.is_empty(), x, w, b, alpha, q_out, score_pub) {
+        let circ = base_circuit(&Witness { x, w, b, alpha, q_out, score_pub }, activation, "0");
+        let expected = circ.score();
+        if to_fr_q16(score_pub) != expected {
+            errors.push(("/score_pub".into(), format!("{score_pub} no coincide con el score recalculado ({})", fr_to_u64(&expected).map_or("fuera de rango".into(), |v| v.to_string()))));
+        }
+    }
+    errors
+}
+
+// Portable verifying key, for third-party verifiers and audits. Field elements are 0x + 64
+// big-endian hex digits (fe_to_hex); G1 points are 0x + halo2curves' 32-byte compressed encoding
+// (x little-endian, sign of y in the top bit). Columns are {"type", "index"} in halo2's numbering;
+// queries are [column index, rotation]. transcript_repr is the vk digest every transcript absorbs
+// first, so a verifier that recomputes it from these fields knows it read them right.
+fn vk_json(vk: &VerifyingKey<G1Affine>) -> serde_json::Value {
+    use halo2_proofs::plonk::Any;
+    let point = |p: &G1Affine| format!("0x{}", hex::encode(p.to_bytes()));
+    let cs = vk.cs();
+    let column = |c: &halo2_proofs::plonk::Column<Any>| serde_json::json!({
+        "type": match c.column_type() { Any::Advice(_) => "advice", Any::Fixed => "fixed", Any::Instance => "instance" },
+        "index": c.index(),
+    });
+    let queries = |q: Vec<(usize, i32)>| q.into_iter().map(|(c, r)| serde_json::json!([c, r])).collect::<Vec<_>>();
+    serde_json::json!({
+        "format": "quantum-guard-vk/2",
+        "circuit": halo2_tx_validator::envelope::circuit_version(),
+        "curve": "bn256",
+        "commitment": "kzg",
+        "k": vk.get_domain().k(),
+        "transcript_repr": fe_to_hex(&vk.transcript_repr()),
+        "columns": {
+            "advice": cs.num_advice_columns(),
+            "fixed": cs.num_fixed_columns(),
+            "instance": cs.num_instance_columns(),
+        },
+        "degree": cs.degree(),
+        "blinding_factors": cs.blinding_factors(),
+        "fixed_commitments": vk.fixed_commitments().iter().map(point).collect::<Vec<_>>(),
+        "permutation": {
+            "columns": cs.permutation().get_columns().iter().map(column).collect::<Vec<_>>(),
+            "commitments": vk.permutation().commitments().iter().map(point).collect::<Vec<_>>(),
+        },
+        "gates": cs.gates().iter().map(|g| serde_json::json!({
+            "name": g.name(),
+            "constraints": g.polynomials().iter().enumerate().map(|(i, p)| serde_json::json!({
+                "name": g.constraint_name(i),
+                "degree": p.degree(),
+            })).collect::<Vec<_>>(),
+        })).collect::<Vec<_>>(),
+        "lookups": cs.lookups().len(),
+        "queries": {
+            "advice": queries(cs.advice_queries().iter().map(|(c, r)| (c.index(), r.0)).collect()),
+            "fixed": queries(cs.fixed_queries().iter().map(|(c, r)| (c.index(), r.0)).collect()),
+            "instance": queries(cs.instance_queries().iter().map(|(c, r)| (c.index(), r.0)).collect()),
+        },
+    })
+}
+
+// Sections of a KZG/GWC transcript in create_proof's write order, as (name, points, scalars), for
+// one circuit and a single advice phase. A size mismatch with the proof usually means another vk
+// or halo2 version.
+fn transcript_layout(cs: &ConstraintSystem<Fr>) -> Vec<(&'static str, usize, usize)> {
+    let lookups = cs.lookups().len();
+    let perm_columns = cs.permutation().get_columns().len();
+    let chunks = perm_columns.div_ceil(cs.degree() - 2);
+    // GWC opens once per distinct rotation
+    let mut points: BTreeSet<i32> = cs.advice_queries().iter().map(|(_, r)| r.0)
+        .chain(cs.fixed_queries().iter().map(|(_, r)| r.0))
+        .chain(cs.instance_queries().iter().map(|(_, r)| r.0))
+        .chain([0])
+        .collect();
+    if chunks > 0 { points.insert(1); }
+    if chunks > 1 { points.insert(-(cs.blinding_factors() as i32 + 1)); }
+    if lookups > 0 { points.extend([-1, 1]); }
+    vec![
+        ("compromisos advice", cs.num_advice_columns(), 0),
+        ("lookups: columnas permutadas", 2 * lookups, 0),
+        ("permutación: productos", chunks, 0),
+        ("lookups: productos", lookups, 0),
+        ("vanishing: polinomio aleatorio", 1, 0),
+        ("vanishing: trozos del cociente", cs.degree() - 1, 0),
+        ("evaluaciones instance", 0, cs.instance_queries().len()),
+        ("evaluaciones advice", 0, cs.advice_queries().len()),
+        ("evaluaciones fixed", 0, cs.fixed_queries().len()),
+        ("vanishing: evaluación aleatoria", 0, 1),
+        ("permutación: evaluaciones sigma", 0, perm_columns),
+        ("permutación: evaluaciones de productos", 0, (3 * chunks).saturating_sub(1)),
+        ("lookups: evaluaciones", 0, 5 * lookups),
+        ("apertura GWC: un testigo por punto", points.len(), 0),
+    ]
+}
+
+// g[0], g2 and s·g2 (compressed) pin the ceremony independently of k.
+fn generators_digest(params: &ParamsKZG<Bn256>) -> String {
+    let mut h = Sha256::new();
+    h.update(params.get_g()[0].to_bytes());
+    h.update(params.g2().to_bytes());
+    h.update(params.s_g2().to_bytes());
+    hex::encode(h.finalize())
+}
+
+// Params in their file encoding.
+fn params_to_bytes(params: &ParamsKZG<Bn256>) -> Vec<u8> {
+    let mut bytes = vec![];
+    params.write(&mut bytes).expect("escritura en memoria");
+    bytes
+}
+
+// Smallest k whose 2^k rows fit `shape` according to the cost model.
+fn min_k(shape: Shape) -> u32 { TxCircuit::<Fr>::empty(shape).cost_estimate().min_k }
+
+fn keygen_kzg<C: Circuit<Fr, Params = ()>>(params_bytes: &[u8], empty: &C) -> Result<ProvingKey<G1Affine>, Box<dyn std::error::Error>> {
+    Ok(prover::keygen(&prover::read_params(params_bytes)?, empty)?)
+}
+
+// Both key files of a TxCircuit shape (see keys.rs); hands the pk back.
+fn write_keys(pk: ProvingKey<G1Affine>, shape: Shape, pk_out: &str, vk_out: &str) -> Result<ProvingKey<G1Affine>, Box<dyn std::error::Error>> {
+    let mut bytes = vec![];
+    VerifyingKeyFile::new(pk.get_vk().clone(), Some(shape.clone())).write(&mut bytes)?;
+    write_out(vk_out, &bytes)?;
+    bytes.clear();
+    let file = ProvingKeyFile::new(pk, Some(shape));
+    file.write(&mut bytes)?;
+    write_out(pk_out, &bytes)?;
+    Ok(file.pk)
+}
+
+// IPA over the BN256 G1 curve: same scalar field as the KZG build, no trusted setup.
+#[cfg_attr(not(feature = "ipa"), allow(dead_code))]
+const IPA_BLAKE2B: &str = "el backend IPA solo admite el transcript blake2b";
+#[cfg(feature = "ipa")]
+mod ipa {
+    use super::*;
+    use halo2_proofs::poly::ipa::{
+        commitment::{IPACommitmentScheme, ParamsIPA},
+        multiopen::{ProverIPA, VerifierIPA},
+        strategy::SingleStrategy,
+    };
+    use halo2_proofs::poly::VerificationStrategy;
+
+    pub fn gen_params(k: u32) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let params = ParamsIPA::<G1Affine>::new(k);
+        let mut bytes = vec![];
+        params.write(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    pub fn keygen<C: Circuit<Fr, Params = ()>>(params_bytes: &[u8], empty: &C) -> Result<ProvingKey<G1Affine>, Box<dyn std::error::Error>> {
+        let params = ParamsIPA::<G1Affine>::read(&mut &params_bytes[..])?;
+        let vk = keygen_vk(&params, empty)?;
+        Ok(keygen_pk(&params, vk, empty)?)
+    }
+
+    pub fn prove<C: Circuit<Fr, Params = ()>>(params_bytes: &[u8], circ: C, instances: &[Vec<Fr>], pk: Option<&str>, rng: impl RngCore, transcript: Transcript) -> Result<(Vec<u8>, Fr), Box<dyn std::error::Error>> {
+        if transcript != Transcript::Blake2b { return Err(IPA_BLAKE2B.into()); }
+        let params = timed("carga de params", || ParamsIPA::<G1Affine>::read(&mut &params_bytes[..]))?;
+        let pk = timed("clave de prueba", || cached_pk::<C>(pk, || {
+            let vk = keygen_vk(&params, &circ)?;
+            Ok(keygen_pk(&params, vk, &circ)?)
+        }))?;
+        let vk_repr = pk.get_vk().transcript_repr();
+        let instances: Vec<&[Fr]> = instances.iter().map(|v| &v[..]).collect();
+        let mut writer = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
+        timed(PROVE_PHASE, || halo2_proofs::plonk::create_proof::<IPACommitmentScheme<G1Affine>, ProverIPA<_>, _, _, _, _>(
+            &params, &pk, &[circ], &[&instances[..]], rng, &mut writer
+        ))?;
+        let envelope = ProofEnvelope::new(Scheme::Ipa, transcript, params.k(), vk_repr.to_repr(), writer.finalize());
+        Ok((envelope.to_bytes(), vk_repr))
+    }
+
+    pub fn verify<C: Circuit<Fr, Params = ()>>(params_bytes: &[u8], empty: &C, proof_bytes: &[u8], instances: &[Vec<Fr>], vk: Option<&str>) -> Result<Fr, Box<dyn std::error::Error>> {
+        let params = timed("carga de params", || ParamsIPA::<G1Affine>::read(&mut &params_bytes[..]))?;
+        let vk = timed("clave de verificación", || cached_vk::<C>(vk, || Ok(keygen_vk(&params, empty)?)))?;
+        let envelope = ProofEnvelope::from_bytes(proof_bytes)?;
+        envelope.check(Scheme::Ipa, params.k(), &vk.transcript_repr().to_repr())?;
+        if envelope.transcript != Transcript::Blake2b { return Err(IPA_BLAKE2B.into()); }
+        let instances: Vec<&[Fr]> = instances.iter().map(|v| &v[..]).collect();
+        let mut transcript = Blake2bRead::<_, G1Affine, Challenge255<_>>::init(&envelope.proof[..]);
+        let strategy = SingleStrategy::new(&params);
+        timed("verificación", || halo2_proofs::plonk::verify_proof::<IPACommitmentScheme<G1Affine>, VerifierIPA<_>, _, _, _>(
+            &params, &vk, strategy, &[&instances[..]], &mut transcript
+        ))?;
+        Ok(vk.transcript_repr())
+    }
+}
+
+#[cfg(not(feature = "ipa"))]
+mod ipa {
+    use super::*;
+    const MSG: &str = "backend IPA no disponible: compila con --features ipa";
+    pub fn gen_params(_: u32) -> Result<Vec<u8>, Box<dyn std::error::Error>> { Err(MSG.into()) }
+    pub fn keygen<C: Circuit<Fr, Params = ()>>(_: &[u8], _: &C) -> Result<ProvingKey<G1Affine>, Box<dyn std::error::Error>> { Err(MSG.into()) }
+    pub fn prove<C: Circuit<Fr, Params = ()>>(_: &[u8], _: C, _: &[Vec<Fr>], _: Option<&str>, _: impl RngCore, _: Transcript) -> Result<(Vec<u8>, Fr), Box<dyn std::error::Error>> { Err(MSG.into()) }
+    pub fn verify<C: Circuit<Fr, Params = ()>>(_: &[u8], _: &C, _: &[u8], _: &[Vec<Fr>], _: Option<&str>) -> Result<Fr, Box<dyn std::error::Error>> { Err(MSG.into()) }
+}
+
+// Solidity-side verification via snark-verifier: the verifier is generated by running the PLONK
+// verification with an EVM loader, which records it as Solidity instead of evaluating it.
+#[cfg(feature = "evm")]
+mod evm {
+    use super::*;
+    use halo2_proofs::halo2curves::bn256::Fq;
+    use snark_verifier::{
+        loader::evm::{compile_solidity, EvmLoader},
+        pcs::kzg::{Gwc19, KzgAs, KzgDecidingKey},
+        system::halo2::{compile, transcript::evm::EvmTranscript, Config},
+        verifier::{self, SnarkVerifier},
+    };
+    use std::rc::Rc;
+
+    // GWC, as in prove_kzg
+    type PlonkVerifier = verifier::plonk::PlonkVerifier<KzgAs<Bn256, Gwc19>>;
+
+    pub fn verifier_solidity(params: &ParamsKZG<Bn256>, vk: &VerifyingKey<G1Affine>, num_instance: Vec<usize>) -> Result<String, Box<dyn std::error::Error>> {
+        let protocol = compile(params, vk, Config::kzg().with_num_instance(num_instance.clone()));
+        let dk: KzgDecidingKey<Bn256> = (params.get_g()[0], params.g2(), params.s_g2()).into();
+        let loader = EvmLoader::new::<Fq, Fr>();
+        let protocol = protocol.loaded(&loader);
+        let mut transcript = EvmTranscript::<_, Rc<EvmLoader>, _, _>::new(&loader);
+        let instances = transcript.load_instances(num_instance);
+        let proof = PlonkVerifier::read_proof(&dk, &protocol, &instances, &mut transcript)
+            .map_err(|e| format!("verificador EVM: {e:?}"))?;
+        PlonkVerifier::verify(&dk, &protocol, &instances, &proof).map_err(|e| format!("verificador EVM: {e:?}"))?;
+        Ok(loader.solidity_code())
+    }
+
+    // shells out to solc
+    pub fn bytecode(solidity: &str) -> Result<Vec<u8>, Box<dyn std::error::Error>> { Ok(compile_solidity(solidity)) }
+
+    /// Gas used by a successful call; a revert is an error.
+    pub fn deploy_and_call(code: Vec<u8>, calldata: Vec<u8>) -> Result<u64, Box<dyn std::error::Error>> {
+        Ok(snark_verifier::loader::evm::deploy_and_call(code, calldata).map_err(|e| format!("el verificador EVM rechazó la prueba: {e}"))?)
+    }
+}
+
+#[cfg(not(feature = "evm"))]
+mod evm {
+    use super::*;
+    const MSG: &str = "verificador EVM no disponible: compila con --features evm";
+    pub fn verifier_solidity(_: &ParamsKZG<Bn256>, _: &VerifyingKey<G1Affine>, _: Vec<usize>) -> Result<String, Box<dyn std::error::Error>> { Err(MSG.into()) }
+    pub fn bytecode(_: &str) -> Result<Vec<u8>, Box<dyn std::error::Error>> { Err(MSG.into()) }
+    pub fn deploy_and_call(_: Vec<u8>, _: Vec<u8>) -> Result<u64, Box<dyn std::error::Error>> { Err(MSG.into()) }
+}
+
+// Feature tables for witness from-parquet and prove-batch --parquet.
+#[cfg(feature = "parquet")]
+mod columnar {
+    use super::*;
+    use arrow::{array::{Array, Float64Array, StringArray}, compute::cast, datatypes::DataType, record_batch::RecordBatch};
+
+    type Batches = Box<dyn Iterator<Item = Result<RecordBatch, arrow::error::ArrowError>>>;
+
+    fn batches(path: &str) -> Result<Batches, Box<dyn std::error::Error>> {
+        let file = fs::File::open(path)?;
+        Ok(match Path::new(path).extension().and_then(|e| e.to_str()) {
+            Some("arrow" | "feather" | "ipc") => Box::new(arrow::ipc::reader::FileReader::try_new(file, None)?),
+            _ => Box::new(parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder::try_new(file)?.build()?),
+        })
+    }
+
+    /// One witness per row; features by column name, as witness from-csv.
+    pub fn read(path: &str, model: &Model, id_column: &str, q_out_column: &str, q_out: Option<f64>, activation: Activation) -> Result<Vec<BatchWitness>, Box<dyn std::error::Error>> {
+        check_feature_map(model)?;
+        let mut out = vec![];
+        for batch in batches(path)? {
+            let batch = batch?;
+            let numeric = |name: &str| -> Result<Option<Float64Array>, Box<dyn std::error::Error>> {
+                let Some(col) = batch.column_by_name(name) else { return Ok(None) };
+                let col = cast(col, &DataType::Float64).map_err(|e| format!("columna {name}: {e}"))?;
+                Ok(Some(col.as_any().downcast_ref::<Float64Array>().expect("cast a Float64").clone()))
+            };
+            let features = model.features.iter().map(|f| numeric(&f.field)?.ok_or_else(|| format!("{path} no tiene la columna {}", f.field).into()))
+                .collect::<Result<Vec<_>, Box<dyn std::error::Error>>>()?;
+            let q_col = numeric(q_out_column)?;
+            let ids = match batch.column_by_name(id_column) {
+                Some(col) => Some(cast(col, &DataType::Utf8)?.as_any().downcast_ref::<StringArray>().expect("cast a Utf8").clone()),
+                None => None,
+            };
+            for i in 0..batch.num_rows() {
+                let row = out.len() + 1;
+                let value = |col: &Float64Array, name: &str| if col.is_null(i) { Err(format!("fila {row}: {name} vacío")) } else { Ok(col.value(i)) };
+                let x = features.iter().zip(&model.features)
+                    .map(|(col, f)| quantize_q16(value(col, &f.field)? * f.scale))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let q = match &q_col {
+                    Some(col) => value(col, q_out_column)?,
+                    None => q_out.ok_or(format!("fila {row}: sin columna {q_out_column} ni --q-out"))?,
+                };
+                let id = ids.as_ref().filter(|ids| !ids.is_null(i)).map_or_else(|| row.to_string(), |ids| ids.value(i).to_string());
+                let witness = witness_from_features(x, model, q, activation).map_err(|e| format!("fila {row}: {e}"))?;
+                out.push(BatchWitness { id, tx_hash: None, witness });
+            }
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(not(feature = "parquet"))]
+mod columnar {
+    use super::*;
+    pub fn read(_: &str, _: &Model, _: &str, _: &str, _: Option<f64>, _: Activation) -> Result<Vec<BatchWitness>, Box<dyn std::error::Error>> {
+        Err("entrada Parquet/Arrow no disponible: compila con --features parquet".into())
+    }
+}
+
+#[cfg(feature = "dev-graph")]
+fn render_layout<C: Circuit<Fr, Params = ()>>(circ: &C, k: u32, out: &str) -> Result<(), Box<dyn std::error::Error>> {
+    use halo2_proofs::{dev::MockProver, plonk::ConstraintSystem};
+    use plotters::prelude::*;
+    // halo2-axiom ships no CircuitLayout: regions come from our layouter during a mock run,
+    // drawn as advice columns, then fixed columns, over 2^k rows.
+    let mut cs = ConstraintSystem::<Fr>::default();
+    C::configure(&mut cs);
+    let instances = vec![vec![]; cs.num_instance_columns()];
+    let (_, regions) = halo2_tx_validator::circuit::record_regions(|| MockProver::run(k, circ, instances));
+    let columns = (cs.num_advice_columns() + cs.num_fixed_columns()) as i32;
+    let root = BitMapBackend::new(out, (1024, 3072)).into_drawing_area();
+    root.fill(&WHITE)?;
+    let root = root.titled("TxCircuit", ("sans-serif", 40))?;
+    let area = root.apply_coord_spec(Cartesian2d::<plotters::coord::types::RangedCoordi32, plotters::coord::types::RangedCoordi32>::new(
+        0..columns, 0..1 << k, (0..root.dim_in_pixel().0 as i32, 0..root.dim_in_pixel().1 as i32),
+    ));
+    for (i, r) in regions.iter().enumerate() {
+        let color = Palette99::pick(i).mix(0.6).filled();
+        let fixed = r.fixed.iter().map(|c| c + cs.num_advice_columns());
+        for col in r.advice.iter().copied().chain(fixed) {
+            let (x, y) = (col as i32, r.start as i32);
+            area.draw(&Rectangle::new([(x, y), (x + 1, y + r.height.max(1) as i32)], color))?;
+        }
+    }
+    root.present()?;
+    Ok(())
+}
+
+#[cfg(not(feature = "dev-graph"))]
+fn render_layout<C: Circuit<Fr, Params = ()>>(_: &C, _: u32, _: &str) -> Result<(), Box<dyn std::error::Error>> {
+    Err("layout no disponible: compila con --features dev-graph".into())
+}
+
+struct BatchProver<'a> {
+    params: &'a ParamsKZG<Bn256>,
+    params_sha256: &'a str,
+    pk: &'a ProvingKey<G1Affine>,
+    shape: &'a Shape,
+    activation: Activation,
+    domain: &'a str,
+    out_dir: &'a Path,
+    // every witness model must be the one registered at this leaf (prove --registry --model-id)
+    registry: Option<MerkleWitness>,
+    // SQLite proof store each proof is also archived in (single proofs only)
+    store: Option<&'a str>,
+    // witnesses per proof: above 1, `pk` is a TxBatch key of this size
+    batch_size: usize,
+}
+
+// A witness ready to prove: its circuit and public file, named by its output stem.
+struct Prepared {
+    stem: String,
+    circ: TxCircuit<Fr>,
+    public: PublicInputs,
+    tx_hash: Option<String>,
+}
+
+impl BatchProver<'_> {
+    fn prove(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let prepared = self.prepare(&BatchItem::File(path.to_path_buf()))?;
+        self.prove_single(prepared)
+    }
+
+    fn prepare(&self, item: &BatchItem) -> Result<Prepared, Box<dyn std::error::Error>> {
+        let (stem, (wit, quantization), tx_hash) = match item {
+            BatchItem::File(path) => (
+                path.file_stem().and_then(|s| s.to_str()).ok_or("nombre de witness inválido")?.to_string(),
+                read_witness_quantized(path.to_str().ok_or("ruta de witness no UTF-8")?, self.activation)?,
+                None,
+            ),
+            BatchItem::Line(line) => (line.id.clone(), (line.witness.clone(), None), line.tx_hash.clone()),
+            BatchItem::Invalid(_, e) => return Err(e.clone().into()),
+        };
+        let mut circ = base_circuit(&wit, self.activation, self.domain);
+        if let Some(reg) = &self.registry {
+            if reg.next != model_commit(circ.domain, &wit.w, wit.b) {
+                return Err("el modelo del witness no es el registrado".into());
+            }
+            circ.registry = Some(reg.clone());
+        }
+        if &circ.shape() != self.shape {
+            return Err(format!("el witness tiene {} features, la clave espera {}", circ.shape().features, self.shape.features).into());
+        }
+        let instances = tx_instances(&circ);
+        let public = PublicInputs {
+            version: PUBLIC_VERSION,
+            commit_wb: fe_to_hex(&instances[0][0]),
+            commit_q: fe_to_hex(&instances[1][0]),
+            score_pub: fe_to_hex(&to_fr_q16(wit.score_pub)),
+            commit_x: fe_to_hex(&instances[3][0]),
+            commit_now: fe_to_hex(&instances[5][0]),
+            valid_until: NEVER_EXPIRES,
+            domain: self.domain.to_string(),
+            shape: self.shape.clone(),
+            vk_fingerprint: vk_digest(self.pk.get_vk().transcript_repr()),
+            quantization,
+            instances,
+            audit: Some(Audit {
+                params_sha256: self.params_sha256.to_string(),
+                vk_digest: vk_digest(self.pk.get_vk().transcript_repr()),
+                model_sha256: model_sha256(&wit.w, wit.b),
+                policy_version: None,
+                feature_schema: None,
+            }),
+        };
+        Ok(Prepared { stem, circ, public, tx_hash })
+    }
+
+    // Writes <stem>.proof and <stem>.public.json to out_dir.
+    fn prove_single(&self, p: Prepared) -> Result<(), Box<dyn std::error::Error>> {
+        let proof = create_kzg_proof(self.params, self.pk, p.circ, &p.public.instance_refs(), rand::thread_rng(), Transcript::Blake2b)?.to_bytes();
+        write_out(self.out_dir.join(format!("{}.proof", p.stem)), &proof)?;
+        write_out(self.out_dir.join(format!("{}.public.json", p.stem)), serde_json::to_vec_pretty(&p.public)?)?;
+        if let Some(db) = self.store { store_proof(db, &proof, &p.public, p.tx_hash)?; }
+        Ok(())
+    }
+
+    // One TxBatch proof over `items`, written as batch-<first stem>.proof with a BatchPublicInputs
+    // file; returns that stem.
+    fn prove_batched(&self, items: Vec<Prepared>) -> Result<String, Box<dyn std::error::Error>> {
+        let stem = format!("batch-{}", items.first().ok_or("lote vacío")?.stem);
+        let public = BatchPublicInputs { batch_size: self.batch_size, items: items.iter().map(|p| p.public.clone()).collect() };
+        let batch = TxBatch::new(items.into_iter().map(|p| p.circ).collect(), self.batch_size)?;
+        let instances = public.instances()?;
+        let refs: Vec<&[Fr]> = instances.iter().map(|v| &v[..]).collect();
+        let proof = create_kzg_proof(self.params, self.pk, batch, &refs, rand::thread_rng(), Transcript::Blake2b)?.to_bytes();
+        write_out(self.out_dir.join(format!("{stem}.proof")), &proof)?;
+        write_out(self.out_dir.join(format!("{stem}.public.json")), serde_json::to_vec_pretty(&public)?)?;
+        Ok(stem)
+    }
+
+    // Proves a work unit: one item, or up to batch_size items in one TxBatch proof, leaving out
+    // those without a valid witness. Per item: the stem of the proof holding it, or the error.
+    fn prove_unit(&self, items: &[&BatchItem]) -> Vec<Result<String, String>> {
+        let prepared: Vec<_> = items.iter().map(|item| self.prepare(item).map_err(|e| e.to_string())).collect();
+        if self.batch_size == 1 {
+            return prepared.into_iter().map(|p| {
+                let p = p?;
+                let stem = p.stem.clone();
+                self.prove_single(p).map(|_| stem).map_err(|e| e.to_string())
+            }).collect();
+        }
+        let mut results: Vec<Result<String, String>> = vec![];
+        let mut valid = vec![];
+        for p in prepared {
+            match p {
+                Ok(p) => { results.push(Ok(String::new())); valid.push(p); }
+                Err(e) => results.push(Err(e)),
+            }
+        }
+        let proven = match valid.is_empty() {
+            true => Ok(String::new()),
+            false => self.prove_batched(valid).map_err(|e| e.to_string()),
+        };
+        results.into_iter().map(|r| r.and_then(|_| proven.clone())).collect()
+    }
+}
+
+// prove-batch and backfill: proves `files` with `jobs` workers, keeping <out_dir>/jobs.json
+// current for --resume, and writes summary.json; fails if any item has no proof.
+fn run_batch(prover: &BatchProver, files: Vec<BatchItem>, source: String, out_dir: &str, jobs: usize, resume: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let manifest_path = Path::new(out_dir).join("jobs.json");
+    let mut manifest: JobManifest = match resume {
+        true => fs::read(&manifest_path).ok().map(|b| serde_json::from_slice(&b)).transpose()?.unwrap_or_default(),
+        false => JobManifest::default(),
+    };
+    if resume && !manifest.source.is_empty() && manifest.source != source {
+        return Err(format!("{} es de otro lote ({})", manifest_path.display(), manifest.source).into());
+    }
+    manifest.source = source.clone();
+    // done means done only while its proof is still there
+    let (mut skipped, mut todo) = (vec![], vec![]);
+    for (i, item) in files.into_iter().enumerate() {
+        let (name, stem) = item.names();
+        match manifest.items.get(&name) {
+            Some(j) if j.status == "done" && Path::new(out_dir).join(format!("{}.proof", j.proof.as_deref().unwrap_or(&stem))).exists() => {
+                skipped.push((i, BatchEntry { witness: name, ok: true, error: None, ms: j.ms }));
+            }
+            _ => {
+                manifest.items.insert(name, JobItem { status: "pending".into(), error: None, ms: 0, proof: None });
+                todo.push((i, item));
+            }
+        }
+    }
+    if !skipped.is_empty() { say!("Reanudando: {} ya probados, {} pendientes.", skipped.len(), todo.len()); }
+    manifest.save(&manifest_path)?;
+    let manifest = Mutex::new(manifest);
+    // work units: one item, or batch_size items sharing a proof
+    let units: Vec<_> = todo.chunks(prover.batch_size.max(1)).collect();
+
+    // each worker takes the next unproven unit and proves it in its own rayon pool, so N jobs
+    // don't each spread their FFTs and MSMs over every core; results are restored to file order
+    let jobs = jobs.max(1).min(units.len().max(1));
+    let threads_per_job = (rayon::current_num_threads() / jobs).max(1);
+    let next = std::sync::atomic::AtomicUsize::new(0);
+    let mut entries: Vec<(usize, BatchEntry)> = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..jobs).map(|_| {
+            let (units, next, manifest, manifest_path) = (&units, &next, &manifest, &manifest_path);
+            scope.spawn(move || {
+                let pool = rayon::ThreadPoolBuilder::new().num_threads(threads_per_job).build().expect("pool de prueba");
+                let claim = || Some(next.fetch_add(1, Ordering::Relaxed)).filter(|u| *u < units.len());
+                pool.install(|| std::iter::from_fn(claim).flat_map(|u| {
+                    let unit = units[u];
+                    let start = Instant::now();
+                    let results = prover.prove_unit(&unit.iter().map(|(_, item)| item).collect::<Vec<_>>());
+                    let ms = start.elapsed().as_millis();
+                    let mut m = manifest.lock().unwrap();
+                    let entries: Vec<_> = unit.iter().zip(results).map(|((i, item), result)| {
+                        let (witness, stem) = item.names();
+                        let proof = result.as_ref().ok().filter(|p| **p != stem).cloned();
+                        let entry = BatchEntry { witness, ok: result.is_ok(), error: result.err(), ms };
+                        let status = if entry.ok { "done" } else { "failed" };
+                        m.items.insert(entry.witness.clone(), JobItem { status: status.into(), error: entry.error.clone(), ms, proof });
+                        (*i, entry)
+                    }).collect();
+                    // a manifest that can't be written only costs re-proving on resume
+                    if let Err(e) = m.save(manifest_path) { esay!("aviso: {}: {e}", manifest_path.display()); }
+                    entries
+                }).collect::<Vec<_>>())
+            })
+        }).collect();
+        workers.into_iter().flat_map(|w| w.join().expect("hilo de prueba abortado")).collect()
+    });
+    entries.extend(skipped);
+    entries.sort_by_key(|(i, _)| *i);
+    let entries: Vec<BatchEntry> = entries.into_iter().map(|(_, e)| e).collect();
+    let failed = entries.iter().filter(|e| !e.ok).count();
+    for e in entries.iter().filter(|e| !e.ok) {
+        esay!("{}: {}", e.witness, e.error.as_deref().unwrap_or(""));
+    }
+    write_out(Path::new(out_dir).join("summary.json"), serde_json::to_vec_pretty(&entries)?)?;
+    say!("Pruebas: {} correctas, {failed} fallidas.", entries.len() - failed);
+    if failed > 0 { return Err(format!("{failed} witness sin prueba").into()); }
+    Ok(())
+}
+
+// A verify-batch proof checked against its public file, ready for the pairing: the instances it
+// is verified with and its vk (an index into the per-shape cache, None for --vk).
+struct LoadedProof {
+    envelope: ProofEnvelope,
+    instances: Vec<Vec<Fr>>,
+    vk: Option<usize>,
+}
+
+fn load_pair(params: &ParamsKZG<Bn256>, vks: &mut Vec<((Shape, usize), VerifyingKey<G1Affine>)>, fixed_vk: Option<&VerifyingKeyFile>, domain: &str, entry: &ManifestEntry) -> Result<LoadedProof, Box<dyn std::error::Error>> {
+    let proof_bytes = fs::read(&entry.proof)?;
+    let doc: serde_json::Value = read_doc(&entry.public)?;
+    // a batch proof's public file lists the transactions it covers
+    let (items, batch_size, instances) = match doc.get("items") {
+        Some(_) => {
+            let batch: BatchPublicInputs = serde_json::from_value(doc)?;
+            let instances = batch.instances()?;
+            (batch.items, batch.batch_size, instances)
+        }
+        None => {
+            let pub_json: PublicInputs = serde_json::from_value(doc)?;
+            let instances = pub_json.instances.clone();
+            (vec![pub_json], 1, instances)
+        }
+    };
+    for pub_json in &items {
+        match pub_json.instances.get(2).and_then(|v| v.first()).and_then(fr_to_u64) {
+            Some(score) if score <= ONE_Q16 => {}
+            _ => return Err("score_pub fuera de [0, 1]".into()),
+        }
+        if pub_json.instances.get(6).and_then(|v| v.first()) != Some(&domain_from_tag(domain)) {
+            return Err(format!("la prueba no pertenece al dominio '{domain}'").into());
+        }
+    }
+    let shape = items[0].shape.clone();
+    let vk = match fixed_vk {
+        Some(file) => {
+            if let Some(header) = &file.header { header.check_shape(&shape)?; }
+            None
+        }
+        None => Some(match vks.iter().position(|(key, _)| *key == (shape.clone(), batch_size)) {
+            Some(i) => i,
+            None => {
+                let vk = match batch_size {
+                    1 => keygen_vk(params, &TxCircuit::<Fr>::empty(shape.clone()))?,
+                    n => keygen_vk(params, &TxBatch::<Fr>::empty(shape.clone(), n))?,
+                };
+                vks.push(((shape, batch_size), vk));
+                vks.len() - 1
+            }
+        }),
+    };
+    Ok(LoadedProof { envelope: ProofEnvelope::from_bytes(&proof_bytes)?, instances, vk })
+}
+
+fn main() -> ExitCode {
+    let file_config = match FileConfig::load() {
+        Ok(cfg) => cfg,
+        Err(e) => {
+            eprintln!("error: {e}");
+            return ExitCode::from(2);
+        }
+    };
+    let matches = file_config.apply(Cli::command()).get_matches();
+    let command = matches.subcommand_name().unwrap_or_default().to_string();
+    let cli = match Cli::from_arg_matches(&matches) {
+        Ok(cli) => cli,
+        Err(e) => e.exit(),
+    };
+    JSON_MODE.store(cli.json, Ordering::Relaxed);
+    QUIET.store(cli.quiet, Ordering::Relaxed);
+    if let Some(threads) = cli.threads.or(file_config.threads) {
+        if let Err(e) = set_prover_threads(threads) {
+            eprintln!("error: {e}");
+            return ExitCode::from(2);
+        }
+    }
+    let start = Instant::now();
+    let result = run(cli.cmd);
+    let code = match &result {
+        Ok(()) => 0,
+        Err(e) => exit_code(&command, e.as_ref()),
+    };
+    if cli.json {
+        let summary = serde_json::json!({
+            "command": command,
+            "status": if code == 0 { "ok" } else { "error" },
+            "exit_code": code,
+            "elapsed_ms": start.elapsed().as_millis() as u64,
+            "outputs": OUTPUTS.lock().unwrap().iter().map(|(path, sha)| serde_json::json!({ "path": path, "sha256": sha })).collect::<Vec<_>>(),
+            "result": RESULT.lock().unwrap().take(),
+            "error": result.as_ref().err().map(|e| e.to_string()),
+        });
+        // prove --stdout owns stdout for its frames
+        if STDOUT_TAKEN.load(Ordering::Relaxed) { eprintln!("{summary}"); } else { println!("{summary}"); }
+    } else if let Err(e) = &result {
+        eprintln!("error: {e}");
+    }
+    ExitCode::from(code)
+}
+
+fn run(cmd: Cmd) -> Result<(), Box<dyn std::error::Error>> {
+    match cmd {
+        Cmd::GenParams { k, preset, auto_k, features_n, activation, out, backend, seed, beacon } => {
+            let auto = auto_k.then(|| features_n.map(|features| min_k(Shape { features, activation, ..Default::default() }))).flatten();
+            let k = k.or(auto).or(preset.map(|p| p.spec().k)).ok_or("falta --k, --preset o --auto-k --features-n")?;
+            if auto_k { say!("k mínimo según el modelo de coste: {k}"); }
+            match backend {
+                Backend::Kzg => {
+                    let rng = match seed {
+                        Some(_) => seeded_rng(seed),
+                        None => setup_rng(beacon.as_deref()),
+                    };
+                    let params = ParamsKZG::<Bn256>::setup(k, rng);
+                    let bytes = params_to_bytes(&params);
+                    write_out(&out, &bytes)?;
+                    let record = serde_json::json!({
+                        "k": k,
+                        "sha256": sha256_hex(&bytes),
+                        "generators": generators_digest(&params),
+                        "entropy": match (seed, &beacon) { (Some(_), _) => "seed", (None, Some(_)) => "os+beacon", (None, None) => "os" },
+                        "beacon": beacon,
+                        "beacon_sha256": beacon.as_deref().map(|b| sha256_hex(b.as_bytes())),
+                    });
+                    write_out(format!("{out}.json"), serde_json::to_vec_pretty(&record)?)?;
+                    say!("Params KZG generados.");
+                }
+                Backend::Ipa => {
+                    write_out(out, ipa::gen_params(k)?)?;
+                    say!("Params IPA generados (sin trusted setup).");
+                }
+            }
+        }
+        Cmd::Ceremony { cmd } => match cmd {
+            CeremonyCmd::Init { k, preset, out } => {
+                let k = k.or(preset.map(|p| p.spec().k)).ok_or("falta --k o --preset")?;
+                write_out(&out, params_to_bytes(&ceremony::init(k)))?;
+                write_out(format!("{out}.ceremony.json"), serde_json::to_vec_pretty(&Ceremony { k, contributions: vec![] })?)?;
+                say!("Ceremonia iniciada (k = {k}) en {out}.");
+            }
+            CeremonyCmd::Contribute { params, out, name, beacon } => {
+                let mut transcript: Ceremony = serde_json::from_slice(&read_input(&format!("{params}.ceremony.json"))?)?;
+                let bytes = read_input(&params)?;
+                let p = ParamsKZG::<Bn256>::read(&mut &bytes[..])?;
+                if transcript.contributions.last().is_some_and(|c| c.sha256 != sha256_hex(&bytes)) {
+                    return Err(format!("{params} no es el resultado de la última contribución del transcript").into());
+                }
+                let (next, contribution) = ceremony::contribute(&p, &name, setup_rng(beacon.as_deref()));
+                write_out(&out, params_to_bytes(&next))?;
+                say!("Contribución {} ({name}) escrita en {out}; sha256 {}.", transcript.contributions.len() + 1, contribution.sha256);
+                transcript.contributions.push(contribution);
+                write_out(format!("{out}.ceremony.json"), serde_json::to_vec_pretty(&transcript)?)?;
+            }
+            CeremonyCmd::Verify { params } => {
+                let transcript: Ceremony = serde_json::from_slice(&read_input(&format!("{params}.ceremony.json"))?)?;
+                let p = ParamsKZG::<Bn256>::read(&mut &read_input(&params)?[..])?;
+                ceremony::verify(&transcript, &p)?;
+                for (i, c) in transcript.contributions.iter().enumerate() { say!("{:>3}. {} ({})", i + 1, c.name, c.sha256); }
+                say!("Ceremonia válida: {} contribuciones.", transcript.contributions.len());
+            }
+        },
+        Cmd::Params { cmd } => match cmd {
+            ParamsCmd::ImportPtau { ptau, k, preset, out } => {
+                let k = k.or(preset.map(|p| p.spec().k)).ok_or("falta --k o --preset")?;
+                let params = srs::params_from_ptau(&fs::read(&ptau)?, k)?;
+                write_out(&out, params_to_bytes(&params))?;
+                say!("Params KZG (k = {k}) importados de {ptau}.");
+            }
+            ParamsCmd::Inspect { params } => {
+                let bytes = read_input(&params)?;
+                let p = ParamsKZG::<Bn256>::read(&mut &bytes[..])?;
+                say!("k:            {}", p.k());
+                say!("curva:        BN256 (KZG)");
+                say!("puntos G1:    {}", p.get_g().len());
+                say!("generadores:  {}", generators_digest(&p));
+                say!("sha256:       {}", sha256_hex(&bytes));
+                // record written next to the file by gen-params or params trim
+                if let Some(record) = fs::read(format!("{params}.json")).ok().and_then(|b| serde_json::from_slice::<serde_json::Value>(&b).ok()) {
+                    if let Some(entropy) = record["entropy"].as_str() { say!("entropía:     {entropy}"); }
+                    if let Some(beacon) = record["beacon_sha256"].as_str() { say!("beacon:       sha256 {beacon}"); }
+                    if record["sha256"].as_str().is_some_and(|h| h != sha256_hex(&bytes)) { esay!("aviso: {params}.json describe otro archivo"); }
+                }
+            }
+            ParamsCmd::Trim { params, k, preset, out } => {
+                let k = k.or(preset.map(|p| p.spec().k)).ok_or("falta --k o --preset")?;
+                let source = read_input(&params)?;
+                let mut p = ParamsKZG::<Bn256>::read(&mut &source[..])?;
+                let source_k = p.k();
+                if source_k < k { return Err(format!("los params tienen k = {source_k}, se pidió k = {k}").into()); }
+                p.downsize(k);
+                let trimmed = params_to_bytes(&p);
+                write_out(&out, &trimmed)?;
+                let record = serde_json::json!({
+                    "source": params,
+                    "source_k": source_k,
+                    "source_sha256": sha256_hex(&source),
+                    "k": k,
+                    "sha256": sha256_hex(&trimmed),
+                    "generators": generators_digest(&p),
+                });
+                write_out(format!("{out}.json"), serde_json::to_vec_pretty(&record)?)?;
+                say!("Params reducidos de k = {source_k} a k = {k} en {out}.");
+            }
+        },
+        Cmd::FetchParams { k, preset, source, url, sha256, out } => {
+            let k = k.or(preset.map(|p| p.spec().k)).ok_or("falta --k o --preset")?;
+            let url = url.unwrap_or_else(|| source.url(k));
+            let params = srs::params_from_raw(&srs::download(&url, &sha256)?, k)?;
+            write_out(&out, params_to_bytes(&params))?;
+            say!("Params KZG (k = {k}) importados de {url}.");
+        }
+        Cmd::Prove { backend, params, preset, activation, witness, proof, public, stdin: _, stdout: _, policy_version, feature_schema, valid_until, now, domain, sanctions, counterparty, allowlist, destination, amount_index, amount_limit, hiding, opening, disclose, registry, model_id, tx_raw, tx_field, rlp_nonce, rlp_gas_price, rlp_value, ratio, oracle_sig, calibration, pk, seed, transcript, store } => {
+            let params_bytes = read_input(&params)?;
+            let mut rng = seeded_rng(seed);
+
+            let (wit, quantization) = match witness {
+                Some(path) => read_witness_quantized(&path, activation)?,
+                None => witness_from_doc(serde_json::from_reader(std::io::stdin().lock())?, activation)?,
+            };
+            let now = now.unwrap_or_else(unix_now);
+            let valid_until = valid_until.unwrap_or(NEVER_EXPIRES);
+            if now > valid_until || valid_until > NEVER_EXPIRES {
+                return Err(format!("valid_until = {valid_until} inválido para now = {now}").into());
+            }
+            let model_sha256 = model_sha256(&wit.w, wit.b);
+            for (name, v) in [("q_out", wit.q_out), ("alpha", wit.alpha), ("score_pub", wit.score_pub)] {
+                if !(0..=ONE_Q16 as i64).contains(&v) {
+                    return Err(format!("{name} = {v} fuera de rango [0, {ONE_Q16}]").into());
+                }
+            }
+            if let (Some(i), Some(limit)) = (amount_index, amount_limit) {
+                let amount = *wit.x.get(i).ok_or_else(|| format!("amount_index {i} fuera de rango"))?;
+                if amount < 0 || limit < 0 || amount > limit {
+                    return Err(format!("importe {amount} no cumple el límite {limit}").into());
+                }
+            }
+            if let Some(i) = disclose.iter().find(|i| **i >= wit.x.len().min(64)) {
+                return Err(format!("no se puede revelar la feature {i}").into());
+            }
+            let disclosed = disclose.iter().fold(0u64, |m, i| m | 1 << i);
+            let tx = tx_raw.map(|h| hex::decode(h.trim().trim_start_matches("0x"))).transpose()?;
+            if let Some(tx) = &tx {
+                for f in &tx_field {
+                    let bytes = tx.get(f.offset..f.offset + f.len).ok_or(format!("campo tx fuera de la transacción: {f:?}"))?;
+                    // witness features are i64, so wider fields cannot match
+                    let v = (f.len <= 8).then(|| bytes.iter().fold(0i128, |acc, b| acc << 8 | *b as i128));
+                    if v.is_none() || wit.x.get(f.feature).map(|x| *x as i128) != v {
+                        return Err(format!("x[{}] no coincide con los bytes {}..{} de la tx", f.feature, f.offset, f.offset + f.len).into());
+                    }
+                }
+            }
+            let rlp_features = (rlp_nonce.is_some() || rlp_gas_price.is_some() || rlp_value.is_some())
+                .then_some(RlpFeatures { nonce: rlp_nonce, gas_price: rlp_gas_price, to: None, value: rlp_value });
+            if let (Some(tx), Some(features)) = (&tx, rlp_features) {
+                let items = rlp::parse_legacy(tx)?;
+                for (item, feature) in features.bindings() {
+                    let bytes = &items[item as usize];
+                    let v = (bytes.len() <= 8).then(|| bytes.iter().fold(0i128, |acc, b| acc << 8 | *b as i128));
+                    if v.is_none() || wit.x.get(feature).map(|x| *x as i128) != v {
+                        return Err(format!("x[{feature}] no coincide con el campo RLP {item:?} de la tx").into());
+                    }
+                }
+            }
+            for r in &ratio {
+                let x = |i: usize| wit.x.get(i).copied().ok_or_else(|| format!("ratio {r:?}: feature {i} fuera de rango"));
+                if Ratio::quotient_q16(x(r.num)?, x(r.den)?) != Some(x(r.feature)?) {
+                    return Err(format!("x[{}] no es x[{}] / x[{}] en Q16.16", r.feature, r.num, r.den).into());
+                }
+            }
+            let oracle = oracle_sig.map(|path| load_oracle_sig(&path)).transpose()?;
+            if let Some(sig) = &oracle {
+                if !baby_jubjub().verify(sig, to_fr_q16(wit.q_out)) {
+                    return Err("la firma del oráculo no es válida para q_out".into());
+                }
+            }
+            let registry = match (registry, model_id) {
+                (Some(path), Some(id)) => {
+                    let wit_reg = load_registry(&path)?.witness(id)?;
+                    if wit_reg.next != model_commit(domain_from_tag(&domain), &wit.w, wit.b) {
+                        return Err(format!("el modelo del witness no es el registrado como model_id {id}").into());
+                    }
+                    Some(wit_reg)
+                }
+                _ => None,
+            };
+            let sanctions = match (sanctions, counterparty) {
+                (Some(tree), Some(cp)) => Some(load_tree(&tree)?.non_membership(&cp)?),
+                _ => None,
+            };
+            let allowlist = match (allowlist, destination) {
+                (Some(tree), Some(dest)) => Some(load_tree(&tree)?.membership(&dest)?),
+                _ => None,
+            };
+            let circ = TxCircuit {
+                x: wit.x.into_iter().map(to_fr_q16).collect(),
+                w: wit.w.into_iter().map(to_fr_q16).collect(),
+                b: to_fr_q16(wit.b),
+                alpha: to_fr_q16(wit.alpha),
+                q_out: to_fr_q16(wit.q_out),
+                score_pub: to_fr_q16(wit.score_pub),
+                now: Fr::from(now),
+                valid_until: Fr::from(valid_until),
+                domain: domain_from_tag(&domain),
+                activation,
+                sanctions,
+                allowlist,
+                amount_index,
+                amount_limit: to_fr_q16(amount_limit.unwrap_or(0)),
+                blinding: hiding.then(|| Fr::random(&mut rng)),
+                disclosed,
+                registry,
+                tx,
+                tx_fields: tx_field,
+                rlp: rlp_features,
+                ratios: ratio,
+                oracle,
+                calibration: calibration.map(|path| load_calibration(&path)).transpose()?,
+            };
+            if circ.calibration.is_some() && circ.public_score() != to_fr_q16(wit.score_pub) {
+                return Err(format!("score_pub = {} no es el score calibrado ({:?})", wit.score_pub, circ.public_score()).into());
+            }
+            let shape = circ.shape();
+            if let Some(p) = preset {
+                if p.spec().batch_size > 1 {
+                    return Err(format!("el preset {p} prueba lotes: usa prove-batch --preset {p}").into());
+                }
+                if shape.features != p.shape().features {
+                    return Err(format!("el witness tiene {} features, el preset {p} espera {}", shape.features, p.shape().features).into());
+                }
+            }
+
+            let instances = tx_instances(&circ);
+            let (commit_x, commit_now) = (instances[3][0], instances[5][0]);
+            if let (Some(r), Some(path)) = (circ.blinding, &opening) {
+                write_out(path, serde_json::to_vec_pretty(&serde_json::json!({ "blinding": fe_to_hex(&r), "commit_x": fe_to_hex(&commit_x) }))?)?;
+            }
+
+            let (proof_bytes, vk_repr) = match backend {
+                Backend::Kzg => prove_kzg(&params_bytes, circ, &instances, pk.as_deref(), &mut rng, transcript)?,
+                Backend::Ipa => ipa::prove(&params_bytes, circ, &instances, pk.as_deref(), &mut rng, transcript)?,
+            };
+            let proof_bytes = match seed {
+                Some(_) => ProofEnvelope { created_at: 0, ..ProofEnvelope::from_bytes(&proof_bytes)? }.to_bytes(),
+                None => proof_bytes,
+            };
+            let pub_json = PublicInputs {
+                version: PUBLIC_VERSION,
+                commit_wb: fe_to_hex(&instances[0][0]),
+                commit_q: fe_to_hex(&instances[1][0]),
+                score_pub: fe_to_hex(&to_fr_q16(wit.score_pub)),
+                commit_x: fe_to_hex(&commit_x),
+                commit_now: fe_to_hex(&commit_now),
+                valid_until,
+                domain,
+                shape,
+                vk_fingerprint: vk_digest(vk_repr),
+                quantization,
+                instances,
+                audit: Some(Audit {
+                    params_sha256: sha256_hex(&params_bytes),
+                    vk_digest: vk_digest(vk_repr),
+                    model_sha256,
+                    policy_version,
+                    feature_schema,
+                }),
+            };
+            match (proof, public) {
+                (Some(proof), Some(public)) => {
+                    write_out(&proof, &proof_bytes)?;
+                    write_doc(&public, &pub_json)?;
+                    say!("Prueba creada.");
+                }
+                _ => {
+                    let mut out = std::io::stdout().lock();
+                    write_frame(&mut out, &proof_bytes)?;
+                    write_frame(&mut out, &serde_json::to_vec(&pub_json)?)?;
+                    out.flush()?;
+                    STDOUT_TAKEN.store(true, Ordering::Relaxed);
+                    esay!("Prueba creada.");
+                }
+            }
+            if let Some(db) = store {
+                let id = store_proof(&db, &proof_bytes, &pub_json, None)?;
+                esay!("Prueba archivada en {db} (id {id}).");
+            }
+        }
+        Cmd::Verify { backend, params, proof, public, stdin: _, strict, model, policy_version, feature_schema, check_expiry, now, domain, sanctions, allowlist, amount_limit, registry, tx_hash, oracle_key, calibration, vk, transcript, threshold, vk_fingerprint } => {
+            if let Some(t) = threshold.filter(|t| !(0.0..=1.0).contains(t)) {
+                return Err(format!("--threshold {t} fuera de [0, 1]").into());
+            }
+            let params_bytes = read_input(&params)?;
+            let (proof_bytes, pub_json): (_, PublicInputs) = match (proof, public) {
+                (Some(proof), Some(public)) => (fs::read(proof)?, read_doc(&public)?),
+                _ => {
+                    let mut input = std::io::stdin().lock();
+                    (read_frame(&mut input)?, serde_json::from_slice(&read_frame(&mut input)?)?)
+                }
+            };
+            let envelope = ProofEnvelope::from_bytes(&proof_bytes)?;
+            if let Some(t) = transcript {
+                if envelope.transcript != t { return Err(format!("la prueba usa el transcript {:?}, se exige {t:?}", envelope.transcript).into()); }
+            }
+            // fingerprints first: a proof for another circuit version fails here, not as an opaque invalid proof
+            let proof_fp = hex::encode(envelope.vk_digest);
+            if !pub_json.vk_fingerprint.is_empty() && !pub_json.vk_fingerprint.eq_ignore_ascii_case(&proof_fp) {
+                return Err(format!("fingerprint vk del archivo público {} != el de la prueba {proof_fp}", pub_json.vk_fingerprint).into());
+            }
+            if let Some(fp) = vk_fingerprint.filter(|fp| !fp.trim_start_matches("0x").eq_ignore_ascii_case(&proof_fp)) {
+                return Err(format!("la prueba usa la vk {proof_fp}, se exige {fp}").into());
+            }
+            // the named commitments are for readers; only the instances are proven
+            for (name, value, column) in [("commit_wb", &pub_json.commit_wb, 0), ("commit_q", &pub_json.commit_q, 1), ("commit_x", &pub_json.commit_x, 3)] {
+                if !value.is_empty() && pub_json.instances.get(column).and_then(|v| v.first()) != Some(&parse_fe::<Fr>(value)?) {
+                    return Err(format!("{name} del archivo público no coincide con la instancia {column}").into());
+                }
+            }
+            match pub_json.instances.get(2).and_then(|v| v.first()).and_then(fr_to_u64) {
+                Some(score) if score <= ONE_Q16 => say!("score = {:.6}", score as f64 / ONE_Q16 as f64),
+                _ => return Err("score_pub fuera de [0, 1]".into()),
+            }
+            if pub_json.instances.get(4).and_then(|v| v.first()) != Some(&Fr::from(pub_json.valid_until)) {
+                return Err("valid_until no coincide con la instancia pública".into());
+            }
+            // The verifier's own domain, not the file's, decides which deployment it accepts.
+            if pub_json.instances.get(6).and_then(|v| v.first()) != Some(&domain_from_tag(&domain)) {
+                return Err(format!("la prueba no pertenece al dominio '{domain}' (archivo: '{}')", pub_json.domain).into());
+            }
+            if let Some(tree) = sanctions {
+                if pub_json.instances.get(7).and_then(|v| v.first()) != Some(&load_tree(&tree)?.root()) {
+                    return Err("la prueba no usa la raíz de sanciones esperada".into());
+                }
+            }
+            if let Some(tree) = allowlist {
+                if pub_json.instances.get(9).and_then(|v| v.first()) != Some(&load_tree(&tree)?.root()) {
+                    return Err("la prueba no usa la raíz de allowlist esperada".into());
+                }
+            }
+            if let Some(limit) = amount_limit {
+                if pub_json.instances.get(11).and_then(|v| v.first()) != Some(&to_fr_q16(limit)) {
+                    return Err(format!("la prueba no aplica el límite de importe {limit}").into());
+                }
+            }
+            let vk_repr = match backend {
+                Backend::Kzg => verify_kzg(&params_bytes, &TxCircuit::<Fr>::empty(pub_json.shape.clone()), &proof_bytes, &pub_json.instances, vk.as_deref())?,
+                Backend::Ipa => ipa::verify(&params_bytes, &TxCircuit::<Fr>::empty(pub_json.shape.clone()), &proof_bytes, &pub_json.instances, vk.as_deref())?,
+            };
+            let model: Option<Model> = model.map(|path| read_doc(&path)).transpose()?;
+            if let Some(m) = &model {
+                let inputs: Vec<Fr> = m.w.iter().chain([&m.b]).map(|v| to_fr_q16(*v)).collect();
+                if pub_json.instances.first().and_then(|v| v.first()) != Some(&commit_in_domain(Tag::Wb, domain_from_tag(&domain), &inputs)) {
+                    return Err("la prueba no usa el modelo indicado (commit_wb distinto)".into());
+                }
+                say!("Modelo verificado: commit_wb coincide.");
+            }
+            if let Some(path) = registry {
+                if pub_json.instances.get(13).and_then(|v| v.first()) != Some(&load_registry(&path)?.root()) {
+                    return Err("la prueba no usa el registro de modelos esperado".into());
+                }
+            }
+            if let Some(h) = tx_hash {
+                let digest = hex::decode(h.trim().trim_start_matches("0x"))?;
+                if digest.len() != 32 || pub_json.instances.get(15) != Some(&tx_hash_instances(&digest)) {
+                    return Err(format!("la prueba no está ligada a la tx {h}").into());
+                }
+            }
+            if !oracle_key.is_empty() {
+                let key = oracle_key.iter().map(|c| fr_from_dec(c)).collect::<Result<Vec<_>, _>>()?;
+                if pub_json.instances.get(16).map(|v| &v[..v.len().min(2)]) != Some(&key[..]) {
+                    return Err("q_out no está firmado por la clave de oráculo esperada".into());
+                }
+            }
+            if let Some(path) = calibration {
+                if pub_json.instances.get(17).and_then(|v| v.first()) != Some(&load_calibration(&path)?.commit(domain_from_tag(&domain))) {
+                    return Err("la prueba no usa la calibración esperada".into());
+                }
+            }
+            if let Some(id) = pub_json.instances.get(14).and_then(|v| v.first()).and_then(fr_to_u64) {
+                say!("model_id = {id}");
+            }
+            if let Some(epoch) = pub_json.instances.get(16).and_then(|v| v.get(2)).and_then(fr_to_u64) {
+                say!("oracle epoch = {epoch}");
+            }
+            for (i, v) in disclosed_indices(pub_json.shape.disclosed).zip(pub_json.instances.get(12).into_iter().flatten()) {
+                say!("x[{i}] = {}", fe_to_hex(v));
+            }
+            if strict {
+                let model = model.ok_or("--strict requiere --model")?;
+                let expected = Audit {
+                    params_sha256: sha256_hex(&params_bytes),
+                    vk_digest: vk_digest(vk_repr),
+                    model_sha256: model_sha256(&model.w, model.b),
+                    policy_version,
+                    feature_schema,
+                };
+                check_audit(&pub_json.audit, &expected)?;
+                say!("Auditoría estricta OK.");
+            }
+            if check_expiry {
+                let now = now.unwrap_or_else(unix_now);
+                if now > pub_json.valid_until {
+                    return Err(format!("prueba expirada: valid_until = {} < now = {now}", pub_json.valid_until).into());
+                }
+            }
+            say!("¡Prueba verificada!");
+            if let Some(t) = threshold {
+                // compared in Q16.16, rounding the threshold up so 0.85 never admits a lower score
+                let score = pub_json.instances[2].first().and_then(fr_to_u64).unwrap_or(0);
+                let accept = score >= (t * ONE_Q16 as f64).ceil() as u64;
+                let decision = if accept { "ACCEPT" } else { "REJECT" };
+                if JSON_MODE.load(Ordering::Relaxed) {
+                    report(serde_json::json!({ "decision": decision, "score": score as f64 / ONE_Q16 as f64, "threshold": t }));
+                } else {
+                    say!("{decision}");
+                }
+                if !accept { return Err(PolicyReject(format!("score {:.6} < umbral {t}", score as f64 / ONE_Q16 as f64)).into()); }
+            }
+        }
+        Cmd::Keygen { backend, params, shape, preset, features_n, pk_out, vk_out, auto_k, params_out } => {
+            let mut params_bytes = read_input(&params)?;
+            let shape = resolve_shape(shape, preset, features_n)?;
+            if let (true, Some(out)) = (auto_k, params_out) {
+                if backend != Backend::Kzg { return Err("--auto-k solo está disponible con KZG".into()); }
+                let k = min_k(shape.clone());
+                let mut p = ParamsKZG::<Bn256>::read(&mut &params_bytes[..])?;
+                if p.k() < k { return Err(format!("los params tienen k = {}, la forma necesita k = {k}", p.k()).into()); }
+                p.downsize(k);
+                params_bytes = params_to_bytes(&p);
+                write_out(&out, &params_bytes)?;
+                say!("Params reducidos a k = {k} en {out}.");
+            }
+            let empty = TxCircuit::<Fr>::empty(shape.clone());
+            let pk = match backend {
+                Backend::Kzg => keygen_kzg(&params_bytes, &empty)?,
+                Backend::Ipa => ipa::keygen(&params_bytes, &empty)?,
+            };
+            let pk = write_keys(pk, shape, &pk_out, &vk_out)?;
+            say!("Claves generadas (fingerprint vk {}).", vk_digest(pk.get_vk().transcript_repr()));
+        }
+        Cmd::WitnessGen { tx, model, q_out, q_out_url, activation, out } => {
+            let tx: serde_json::Value = serde_json::from_str(&fs::read_to_string(tx)?)?;
+            let model: Model = serde_json::from_str(&fs::read_to_string(model)?)?;
+            let q_out = match (q_out, q_out_url) {
+                (Some(q), _) => q,
+                (None, Some(url)) => {
+                    let body: serde_json::Value = serde_json::from_reader(ureq::get(&url).call()?.into_reader())?;
+                    body.get("q_out").and_then(|v| v.as_f64()).ok_or(format!("respuesta del oráculo sin q_out: {body}"))?
+                }
+                (None, None) => return Err("falta --q-out o --q-out-url".into()),
+            };
+            let wit = witness_from_tx(&tx, &model, q_out, activation)?;
+            write_witness(&out, &wit)?;
+            say!("Witness escrito en {out} (score = {:.6}).", wit.score_pub as f64 / ONE_Q16 as f64);
+        }
+        Cmd::Quantize { input, frac_bits, rounding, activation, out } => {
+            if !(1..=32).contains(&frac_bits) { return Err(format!("frac_bits = {frac_bits} fuera de 1..=32").into()); }
+            let f: FloatWitness = read_doc(&input)?;
+            let (wit, quantization) = quantize_witness(&f, frac_bits, rounding, activation)?;
+            if frac_bits != FRAC_BITS { esay!("aviso: el circuito espera Q16.16; este witness usa {frac_bits} bits"); }
+            write_witness(&out, &wit)?;
+            for (group, err) in quantization.max_error {
+                say!("error máximo {group:<10} {err:.3e}");
+            }
+        }
+        Cmd::Vk { cmd: VkCmd::Export { vk, format, out } } => {
+            let file = VerifyingKeyFile::read::<TxCircuit<Fr>>(&mut &read_input(&vk)?[..])?;
+            match format {
+                VkFormat::Json => write_out(&out, serde_json::to_vec_pretty(&vk_json(&file.vk))?)?,
+                VkFormat::Raw => {
+                    let mut bytes = vec![];
+                    file.vk.write(&mut bytes, SerdeFormat::RawBytes)?;
+                    write_out(&out, bytes)?;
+                }
+            }
+            say!("vk {} exportada en {out}.", vk_digest(file.vk.transcript_repr()));
+        }
+        Cmd::Witness { cmd: WitnessCmd::FromCsv { csv, model, id_column, q_out_column, q_out, activation, out_dir, out } } => {
+            let model: Model = read_doc(&model)?;
+            let input = read_input(&csv)?;
+            let mut reader = csv::Reader::from_reader(&input[..]);
+            let headers = reader.headers()?.clone();
+            let mut batch = vec![];
+            for (i, record) in reader.records().enumerate() {
+                let record = record?;
+                let row = i + 1;
+                let tx: serde_json::Map<String, serde_json::Value> = headers.iter().zip(record.iter())
+                    .map(|(h, v)| (h.to_string(), serde_json::Value::String(v.to_string())))
+                    .collect();
+                let tx = serde_json::Value::Object(tx);
+                let q = match tx.get(&q_out_column) {
+                    Some(_) => tx_number(&tx, &q_out_column)?,
+                    None => q_out.ok_or(format!("fila {row}: sin columna {q_out_column} ni --q-out"))?,
+                };
+                let id = tx.get(&id_column).and_then(|v| v.as_str()).map_or_else(|| row.to_string(), str::to_string);
+                let witness = witness_from_tx(&tx, &model, q, activation).map_err(|e| format!("fila {row}: {e}"))?;
+                batch.push(BatchWitness { id, tx_hash: None, witness });
+            }
+            write_batch(&batch, out_dir, out)?;
+            say!("{} witness generados de {csv}.", batch.len());
+        }
+        Cmd::Witness { cmd: WitnessCmd::FromParquet { input, model, id_column, q_out_column, q_out, activation, out_dir, out } } => {
+            let model: Model = read_doc(&model)?;
+            let batch = columnar::read(&input, &model, &id_column, &q_out_column, q_out, activation)?;
+            write_batch(&batch, out_dir, out)?;
+            say!("{} witness generados de {input}.", batch.len());
+        }
+        Cmd::Witness { cmd: WitnessCmd::Convert { input, out, sparse, activation } } => {
+            let wit = read_witness(&input, activation)?;
+            if !sparse {
+                write_witness(&out, &wit)?;
+            } else if is_binary_witness(&out) {
+                return Err("el witness binario no tiene forma dispersa".into());
+            } else {
+                let mut doc = serde_json::to_value(&wit)?;
+                doc["x"] = serde_json::to_value(SparseVector::from_dense(&wit.x))?;
+                doc["w"] = serde_json::to_value(SparseVector::from_dense(&wit.w))?;
+                write_doc(&out, &doc)?;
+            }
+            say!("Witness de {} features escrito en {out}.", wit.x.len());
+        }
+        Cmd::Witness { cmd: WitnessCmd::Check { witness, shape, preset, features_n, activation } } => {
+            let doc: serde_json::Value = match is_binary_witness(&witness) {
+                true => serde_json::to_value(read_witness(&witness, activation)?)?,
+                false => read_doc(&witness)?,
+            };
+            // a float witness is checked as it will be proven
+            let doc = match doc.get("values_f64") {
+                Some(_) => serde_json::to_value(witness_from_doc(doc, activation)?.0)?,
+                None => doc,
+            };
+            let features = match (&shape, preset, features_n) {
+                (None, None, None) => None,
+                _ => Some(resolve_shape(shape, preset, features_n)?.features),
+            };
+            let errors = check_witness(&doc, features, activation);
+            for (ptr, msg) in &errors { say!("{witness}#{ptr}: {msg}"); }
+            if JSON_MODE.load(Ordering::Relaxed) {
+                report(serde_json::json!(errors.iter().map(|(p, m)| serde_json::json!({ "pointer": p, "error": m })).collect::<Vec<_>>()));
+            }
+            if !errors.is_empty() { return Err(format!("witness inválido: {} errores", errors.len()).into()); }
+            say!("Witness válido.");
+        }
+        Cmd::Mock { witness, k, activation, domain } => {
+            let wit: Witness = read_witness(&witness, activation)?;
+            let circ = base_circuit(&wit, activation, &domain);
+            let k = k.unwrap_or_else(|| circ.cost_estimate().min_k);
+            let instances = tx_instances(&circ);
+            let prover = MockProver::run(k, &circ, instances)?;
+            match prover.verify() {
+                Ok(()) => say!("Todas las restricciones se cumplen (k = {k})."),
+                Err(failures) => {
+                    print_failures(&failures);
+                    return Err(format!("{} restricciones fallidas", failures.len()).into());
+                }
+            }
+        }
+        Cmd::Trace { witness, k, activation, domain, out } => {
+            let wit: Witness = read_witness(&witness, activation)?;
+            let circ = base_circuit(&wit, activation, &domain);
+            let k = k.unwrap_or_else(|| circ.cost_estimate().min_k);
+            let failures = MockProver::run(k, &circ, tx_instances(&circ))?.verify().err().map_or(0, |f| f.len());
+            let trace = circ.score_inputs().trace();
+            let q = |v: i64| v as f64 / ONE_Q16 as f64;
+            let doc = serde_json::json!({
+                "k": k,
+                "activation": activation,
+                "satisfied": failures == 0,
+                "q16": trace,
+                "real": {
+                    "dot": q(trace.dot), "bias": q(trace.bias), "mix": q(trace.mix), "z": q(trace.z), "score": q(trace.score),
+                    "partial_sums": trace.partial_sums.iter().map(|v| q(*v)).collect::<Vec<_>>(),
+                },
+                "score_pub": wit.score_pub,
+            });
+            match &out {
+                Some(path) => {
+                    write_out(path, serde_json::to_vec_pretty(&doc)?)?;
+                    say!("Traza escrita en {path}.");
+                }
+                None => println!("{}", serde_json::to_string_pretty(&doc)?),
+            }
+            say!("z = {:.6}, score = {:.6} (score_pub del witness = {:.6})", q(trace.z), q(trace.score), q(wit.score_pub));
+            if trace.score != wit.score_pub { esay!("aviso: el score del circuito no coincide con score_pub del witness"); }
+            if failures > 0 { return Err(format!("{failures} restricciones fallidas (ver `mock`)").into()); }
+        }
+        Cmd::Layout { features_n, preset, activation, k, out } => {
+            let features = features_n.or(preset.map(|p| p.spec().features)).ok_or("falta --features-n o --preset")?;
+            let circ = TxCircuit::<Fr>::empty(Shape { features, activation, ..Default::default() });
+            let k = k.unwrap_or_else(|| circ.cost_estimate().min_k);
+            render_layout(&circ, k, &out)?;
+            say!("Layout (k = {k}) escrito en {out}.");
+        }
+        Cmd::Bench { features_n, k, activation, out } => {
+            let mut results = vec![];
+            for features in features_n {
+                let ks = if k.is_empty() {
+                    vec![TxCircuit::<Fr>::empty(Shape { features, activation, ..Default::default() }).cost_estimate().min_k]
+                } else {
+                    k.clone()
+                };
+                for k in ks {
+                    esay!("bench: features={features} k={k}");
+                    results.push(bench_one(features, k, activation)?);
+                }
+            }
+            match out {
+                Some(path) => write_out(path, serde_json::to_string_pretty(&results)?)?,
+                None => report(serde_json::to_value(&results)?),
+            }
+        }
+        Cmd::Estimate { k, features_n, activation, cores, table, calibrate, calibrate_k } => {
+            if calibrate {
+                // runs in increasing k, so the process high-water mark is the latest run's
+                let mut ks = calibrate_k.clone();
+                ks.sort_unstable();
+                let features = 16;
+                let min_k = TxCircuit::<Fr>::empty(Shape { features, activation, ..Default::default() }).cost_estimate().min_k;
+                let mut runs = vec![];
+                for k in ks.into_iter().map(|k| k.max(min_k)).collect::<BTreeSet<_>>() {
+                    esay!("calibración: k={k}");
+                    let r = bench_one(features, k, activation)?;
+                    runs.push(EstimateRun { k, prove_ms: r.prove_ms, peak_rss_kb: r.peak_rss_kb });
+                }
+                let t = EstimateTable { cpu: cpu_model(), threads: rayon::current_num_threads(), runs };
+                write_out(&table, serde_json::to_vec_pretty(&t)?)?;
+                say!("Tabla de calibración escrita en {table} ({} ejecuciones).", t.runs.len());
+            }
+            let k = match (k, features_n) {
+                (Some(k), _) => k,
+                (None, Some(features)) => TxCircuit::<Fr>::empty(Shape { features, activation, ..Default::default() }).cost_estimate().min_k,
+                (None, None) if calibrate => return Ok(()),
+                (None, None) => return Err("falta --k o --features-n".into()),
+            };
+            let t: EstimateTable = serde_json::from_slice(&fs::read(&table).map_err(|e| format!("{table}: {e} (genera la tabla con estimate --calibrate)"))?)?;
+            let threads = cores.unwrap_or(t.threads);
+            if t.cpu != cpu_model() { esay!("aviso: tabla calibrada en otra CPU ({})", t.cpu); }
+            let (lo, hi) = (t.runs.iter().map(|r| r.k).min().unwrap_or(0), t.runs.iter().map(|r| r.k).max().unwrap_or(0));
+            if k < lo || k > hi { esay!("aviso: k = {k} fuera del rango calibrado {lo}..={hi}; extrapolando"); }
+            let (ms, rss_kb) = estimate(&t, k, threads)?;
+            if JSON_MODE.load(Ordering::Relaxed) {
+                report(serde_json::json!({ "k": k, "threads": threads, "prove_ms": ms.round() as u64, "peak_rss_kb": rss_kb.map(|v| v.round() as u64) }));
+            } else {
+                say!("k:              {k}");
+                say!("hilos:          {threads}");
+                say!("prueba:         {:.1} s", ms / 1000.0);
+                say!("memoria máxima: {}", rss_kb.map_or("desconocida".into(), |kb| format!("{:.0} MiB", kb / 1024.0)));
+            }
+        }
+        Cmd::ProveBatch { params, witness_dir, batch, parquet, model, q_out, out_dir, pk, activation, domain, jobs, resume, preset } => {
+            let params_bytes = read_input(&params)?;
+            let params_kzg = ParamsKZG::<Bn256>::read(&mut &params_bytes[..])?;
+            let (files, source): (Vec<BatchItem>, String) = match (witness_dir, batch.or(parquet.clone())) {
+                (None, Some(path)) if parquet.is_some() => {
+                    let model: Model = read_doc(model.as_deref().ok_or("--parquet requiere --model")?)?;
+                    (columnar::read(&path, &model, "id", "q_out", q_out, activation)?.into_iter().map(BatchItem::Line).collect(), path)
+                }
+                (Some(dir), _) => {
+                    let mut files: Vec<_> = fs::read_dir(&dir)?
+                        .map(|e| e.map(|e| e.path()))
+                        .collect::<Result<Vec<_>, _>>()?
+                        .into_iter()
+                        .filter(|p| is_doc(p))
+                        .collect();
+                    files.sort();
+                    (files.into_iter().map(BatchItem::File).collect(), dir)
+                }
+                (None, Some(path)) => (read_batch(&path)?.into_iter().map(BatchItem::Line).collect(), path),
+                (None, None) => return Err("falta --witness-dir o --batch".into()),
+            };
+            let first = match files.first().ok_or(format!("no hay witness en {source}"))? {
+                BatchItem::File(path) => read_witness(path.to_str().ok_or("ruta de witness no UTF-8")?, activation)?,
+                BatchItem::Line(line) => line.witness.clone(),
+                BatchItem::Invalid(id, e) => return Err(format!("{id}: {e}").into()),
+            };
+            let shape = base_circuit(&first, activation, &domain).shape();
+            if let Some(p) = preset.filter(|p| p.shape().features != shape.features) {
+                return Err(format!("el witness tiene {} features, el preset {p} espera {}", shape.features, p.shape().features).into());
+            }
+            let batch_size = preset.map_or(1, |p| p.spec().batch_size);
+            let pk = match batch_size {
+                1 => {
+                    let empty = TxCircuit::<Fr>::empty(shape.clone());
+                    cached_pk::<TxCircuit<Fr>>(pk.as_deref(), || Ok(keygen_pk(&params_kzg, keygen_vk(&params_kzg, &empty)?, &empty)?))?
+                }
+                n => {
+                    let empty = TxBatch::<Fr>::empty(shape.clone(), n);
+                    timed("keygen", || cached_pk::<TxBatch<Fr>>(pk.as_deref(), || Ok(keygen_pk(&params_kzg, keygen_vk(&params_kzg, &empty)?, &empty)?)))?
+                }
+            };
+            fs::create_dir_all(&out_dir)?;
+            let prover = BatchProver {
+                params: &params_kzg,
+                params_sha256: &sha256_hex(&params_bytes),
+                pk: &pk,
+                shape: &shape,
+                activation,
+                domain: &domain,
+                out_dir: Path::new(&out_dir),
+                registry: None,
+                store: None,
+                batch_size,
+            };
+
+            run_batch(&prover, files, source, &out_dir, jobs, resume)?;
+        }
+        Cmd::Backfill { params, archive, model, registry, model_id, q_out, id_field, out_dir, pk, activation, domain, jobs, resume, store } => {
+            let params_bytes = read_input(&params)?;
+            let params_kzg = ParamsKZG::<Bn256>::read(&mut &params_bytes[..])?;
+            let model: Model = read_doc(&model)?;
+            check_feature_map(&model)?;
+            let registry = match (registry, model_id) {
+                (Some(path), Some(id)) => {
+                    let wit_reg = load_registry(&path)?.witness(id)?;
+                    if wit_reg.next != model_commit(domain_from_tag(&domain), &model.w, model.b) {
+                        return Err(format!("el modelo no es el registrado como model_id {id}").into());
+                    }
+                    Some(wit_reg)
+                }
+                _ => None,
+            };
+            // fail before proving anything rather than on every item
+            if let Some(db) = &store { Store::open(db)?; }
+            let text = String::from_utf8(read_input(&archive)?)?;
+            let (mut items, mut ids) = (vec![], BTreeSet::new());
+            for (i, line) in text.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+                let tx: serde_json::Value = serde_json::from_str(line).map_err(|e| format!("{archive}:{}: {e}", i + 1))?;
+                let id = tx.get(&id_field).and_then(|v| v.as_str()).map_or_else(|| (i + 1).to_string(), str::to_string);
+                if !ids.insert(id.clone()) { return Err(format!("{archive}:{}: {id_field} repetido: {id}", i + 1).into()); }
+                let q = match tx.get("q_out") {
+                    Some(_) => tx_number(&tx, "q_out"),
+                    None => q_out.ok_or_else(|| "sin campo q_out ni --q-out".to_string()),
+                };
+                let witness = q.and_then(|q| witness_from_tx(&tx, &model, q, activation).map_err(|e| e.to_string()));
+                items.push(match witness {
+                    Ok(witness) => {
+                        let tx_hash = (id.len() == 66 && id.starts_with("0x")).then(|| id.clone());
+                        BatchItem::Line(BatchWitness { id, tx_hash, witness })
+                    }
+                    Err(e) => BatchItem::Invalid(id, e),
+                });
+            }
+            let first = items.iter().find_map(|item| match item {
+                BatchItem::Line(line) => Some(&line.witness),
+                _ => None,
+            }).ok_or(format!("ninguna transacción de {archive} da un witness válido"))?;
+            let mut shape = base_circuit(first, activation, &domain).shape();
+            shape.registry_depth = registry.as_ref().map_or(0, |r| r.siblings.len());
+            let empty = TxCircuit::<Fr>::empty(shape.clone());
+            let pk = cached_pk::<TxCircuit<Fr>>(pk.as_deref(), || {
+                let vk = keygen_vk(&params_kzg, &empty)?;
+                Ok(keygen_pk(&params_kzg, vk, &empty)?)
+            })?;
+            fs::create_dir_all(&out_dir)?;
+            let prover = BatchProver {
+                params: &params_kzg,
+                params_sha256: &sha256_hex(&params_bytes),
+                pk: &pk,
+                shape: &shape,
+                activation,
+                domain: &domain,
+                out_dir: Path::new(&out_dir),
+                registry,
+                store: store.as_deref(),
+                batch_size: 1,
+            };
+            say!("Backfill de {} transacciones, modelo {} (vk {}).", items.len(), model_sha256(&model.w, model.b), vk_digest(pk.get_vk().transcript_repr()));
+            run_batch(&prover, items, archive, &out_dir, jobs, resume)?;
+        }
+        Cmd::Daemon { params, watch, out, pk, shape, preset, features_n, domain, poll_ms } => {
+            let params_bytes = read_input(&params)?;
+            let params_kzg = ParamsKZG::<Bn256>::read(&mut &params_bytes[..])?;
+            let shape = resolve_shape(shape, preset, features_n)?;
+            let empty = TxCircuit::<Fr>::empty(shape.clone());
+            let pk = timed("keygen", || cached_pk::<TxCircuit<Fr>>(pk.as_deref(), || {
+                let vk = keygen_vk(&params_kzg, &empty)?;
+                Ok(keygen_pk(&params_kzg, vk, &empty)?)
+            }))?;
+            fs::create_dir_all(&out)?;
+            let out_dir = Path::new(&out);
+            let prover = BatchProver {
+                params: &params_kzg,
+                params_sha256: &sha256_hex(&params_bytes),
+                pk: &pk,
+                shape: &shape,
+                activation: shape.activation,
+                domain: &domain,
+                out_dir,
+                registry: None,
+                store: None,
+                batch_size: 1,
+            };
+            say!("Vigilando {watch} (vk {}).", vk_digest(pk.get_vk().transcript_repr()));
+            // size seen at the previous poll, to skip files still being written
+            let mut pending: std::collections::HashMap<std::path::PathBuf, u64> = Default::default();
+            loop {
+                for entry in fs::read_dir(&watch)? {
+                    let path = entry?.path();
+                    let Some(stem) = Some(&path).filter(|p| is_doc(p)).and_then(|p| p.file_stem()).and_then(|s| s.to_str()) else { continue };
+                    if out_dir.join(format!("{stem}.proof")).exists() || out_dir.join(format!("{stem}.error")).exists() { continue; }
+                    let size = fs::metadata(&path)?.len();
+                    if pending.insert(path.clone(), size) != Some(size) { continue; }
+                    pending.remove(&path);
+                    let start = Instant::now();
+                    match prover.prove(&path) {
+                        Ok(()) => say!("{}: prueba en {} ms", path.display(), start.elapsed().as_millis()),
+                        Err(e) => {
+                            esay!("{}: {e}", path.display());
+                            write_out(out_dir.join(format!("{stem}.error")), e.to_string())?;
+                        }
+                    }
+                    // the run never ends: don't accumulate the --json output list
+                    OUTPUTS.lock().unwrap().clear();
+                }
+                std::thread::sleep(Duration::from_millis(poll_ms));
+            }
+        }
+        Cmd::VerifyBatch { params, dir, manifest, vk, domain, preset } => {
+            let params_bytes = read_input(&params)?;
+            let params_kzg = ParamsKZG::<Bn256>::read(&mut &params_bytes[..])?;
+            let pairs: Vec<ManifestEntry> = match (manifest, dir) {
+                (Some(path), _) => serde_json::from_str(&fs::read_to_string(path)?)?,
+                (None, Some(dir)) => {
+                    let mut proofs: Vec<_> = fs::read_dir(&dir)?
+                        .map(|e| e.map(|e| e.path()))
+                        .collect::<Result<Vec<_>, _>>()?
+                        .into_iter()
+                        .filter(|p| p.extension().is_some_and(|e| e == "proof"))
+                        .collect();
+                    proofs.sort();
+                    proofs.into_iter().map(|p| ManifestEntry {
+                        public: p.with_extension("public.json").display().to_string(),
+                        proof: p.display().to_string(),
+                    }).collect()
+                }
+                (None, None) => return Err("falta --dir o --manifest".into()),
+            };
+            let fixed_vk = vk.map(|path| -> Result<_, Box<dyn std::error::Error>> {
+                Ok(VerifyingKeyFile::read::<TxCircuit<Fr>>(&mut &read_input(&path)?[..])?)
+            }).transpose()?;
+            let mut vks = vec![];
+            let loaded: Vec<Result<LoadedProof, String>> = pairs.iter()
+                .map(|pair| load_pair(&params_kzg, &mut vks, fixed_vk.as_ref(), &domain, pair).map_err(|e| e.to_string()))
+                .collect();
+            let vk_of = |l: &LoadedProof| match l.vk {
+                Some(i) => &vks[i].1,
+                None => &fixed_vk.as_ref().expect("prueba cargada con --vk").vk,
+            };
+            let one_by_one = || -> Vec<Result<(), String>> {
+                loaded.iter().map(|l| {
+                    let l = l.as_ref().map_err(Clone::clone)?;
+                    let refs: Vec<&[Fr]> = l.instances.iter().map(|v| &v[..]).collect();
+                    check_kzg_proof(&params_kzg, vk_of(l), &l.envelope, &refs).map_err(|e| e.to_string())
+                }).collect()
+            };
+            let aggregate = preset.is_some_and(|p| p.spec().aggregate);
+            let results = match aggregate {
+                true => {
+                    let ok: Vec<&LoadedProof> = loaded.iter().filter_map(|l| l.as_ref().ok()).collect();
+                    let refs: Vec<Vec<&[Fr]>> = ok.iter().map(|l| l.instances.iter().map(|v| &v[..]).collect()).collect();
+                    let proofs: Vec<_> = ok.iter().zip(&refs).map(|(l, r)| (vk_of(l), &l.envelope, &r[..])).collect();
+                    match timed("verificación agregada", || prover::check_kzg_aggregated(&params_kzg, &proofs)) {
+                        Ok(()) => loaded.iter().map(|l| l.as_ref().map(|_| ()).map_err(Clone::clone)).collect(),
+                        Err(e) => {
+                            esay!("aviso: {e}; se verifica cada prueba por separado");
+                            one_by_one()
+                        }
+                    }
+                }
+                false => one_by_one(),
+            };
+            let entries: Vec<VerifyEntry> = pairs.iter().zip(results).map(|(pair, result)| {
+                VerifyEntry { proof: pair.proof.clone(), ok: result.is_ok(), error: result.err() }
+            }).collect();
+            let failed = entries.iter().filter(|e| !e.ok).count();
+            report(serde_json::json!({
+                "aggregated": aggregate,
+                "total": entries.len(),
+                "passed": entries.len() - failed,
+                "failed": failed,
+                "results": entries,
+            }));
+            if failed > 0 { return Err(format!("{failed} pruebas no verifican").into()); }
+        }
+        Cmd::ExportVerifier { params, shape, preset, features_n, vk, sol_out, bytecode_out } => {
+            let params = ParamsKZG::<Bn256>::read(&mut &read_input(&params)?[..])?;
+            let shape = resolve_shape(shape, preset, features_n)?;
+            let empty = TxCircuit::<Fr>::empty(shape.clone());
+            let vk = cached_vk::<TxCircuit<Fr>>(vk.as_deref(), || Ok(keygen_vk(&params, &empty)?))?;
+            let solidity = evm::verifier_solidity(&params, &vk, num_instances(shape))?;
+            write_out(&sol_out, &solidity)?;
+            if let Some(out) = bytecode_out {
+                let code = evm::bytecode(&solidity)?;
+                write_out(&out, hex::encode(&code))?;
+                say!("Bytecode del verificador: {} bytes en {out}.", code.len());
+            }
+            say!("Verificador EVM escrito en {sol_out} (vk {}).", vk_digest(vk.transcript_repr()));
+        }
+        Cmd::Stats { shape, preset, features_n, gates } => {
+            let stats = TxCircuit::<Fr>::empty(resolve_shape(shape, preset, features_n)?).stats();
+            if JSON_MODE.load(Ordering::Relaxed) {
+                report(serde_json::to_value(&stats)?);
+            } else {
+                let c = &stats.cost;
+                say!("Columnas advice/fixed/instance: {}/{}/{}", c.advice_columns, c.fixed_columns, c.instance_columns);
+                say!("Selectores:          {}", c.selectors);
+                say!("Puertas:             {} ({} restricciones)", stats.gates.len(), stats.gates.iter().map(|g| g.constraints).sum::<usize>());
+                say!("Grado máximo:        {}", stats.degree);
+                say!("Lookups:             {}", c.lookups);
+                say!("Permutación:         {} columnas en {} tramos", stats.permutation_columns, stats.permutation_chunks);
+                say!("Filas (estimadas):   {}", c.rows);
+                say!("k mínimo:            {}", c.min_k);
+                if gates {
+                    for g in &stats.gates { say!("  {:<40} {:>3} restricciones, grado {}", g.name, g.constraints, g.degree); }
+                }
+            }
+        }
+        Cmd::Store { cmd } => match cmd {
+            StoreCmd::Add { db, proof, public, tx_hash } => {
+                let pub_json: PublicInputs = read_doc(&public)?;
+                let id = store_proof(&db, &fs::read(&proof)?, &pub_json, tx_hash)?;
+                report(serde_json::json!({ "id": id }));
+                say!("Prueba archivada en {db} (id {id}).");
+            }
+            StoreCmd::Query { db, tx_hash, vk, date, since, until, limit } => {
+                let (since, until) = match date {
+                    Some(date) => store::utc_day(&date).map(|(s, u)| (Some(s), Some(u)))?,
+                    None => (since, until),
+                };
+                let rows = Store::open(&db)?.query(&store::Query { tx_hash, vk_fingerprint: vk, since, until, limit })?;
+                if JSON_MODE.load(Ordering::Relaxed) {
+                    report(serde_json::json!(rows.iter().map(|p| serde_json::json!({
+                        "id": p.id, "tx_hash": p.tx_hash, "vk_fingerprint": p.vk_fingerprint,
+                        "created_at": p.created_at, "stored_at": p.stored_at, "proof_bytes": p.proof.len(),
+                    })).collect::<Vec<_>>()));
+                } else {
+                    for p in &rows {
+                        say!("{:>6}  {}  {}  vk {}", p.id, p.created_at, p.tx_hash.as_deref().unwrap_or("-"), p.vk_fingerprint);
+                    }
+                    say!("{} pruebas.", rows.len());
+                }
+            }
+            StoreCmd::Get { db, id, proof, public } => {
+                let stored = Store::open(&db)?.get(id)?.ok_or_else(|| format!("no hay prueba con id {id} en {db}"))?;
+                write_out(&proof, &stored.proof)?;
+                write_out(&public, stored.public.as_bytes())?;
+                say!("Prueba {id} escrita en {proof}.");
+            }
+        },
+        Cmd::Completions { shell } => {
+            let mut cmd = Cli::command();
+            let name = cmd.get_name().to_string();
+            STDOUT_TAKEN.store(true, Ordering::Relaxed);
+            clap_complete::generate(shell, &mut cmd, name, &mut std::io::stdout());
+        }
+        Cmd::Selftest { params } => {
+            let wit = Witness { x: vec![ONE_Q16 as i64 / 2, ONE_Q16 as i64 / 4], w: vec![ONE_Q16 as i64 / 2, -(ONE_Q16 as i64) / 4], b: 0, alpha: ONE_Q16 as i64 / 2, q_out: ONE_Q16 as i64 / 2, score_pub: 0 };
+            let mut circ = base_circuit(&wit, Activation::default(), "selftest");
+            circ.score_pub = circ.score();
+            let k = min_k(circ.shape());
+            let step = |name: &str, ok: bool| -> Result<(), Box<dyn std::error::Error>> {
+                if !ok { return Err(format!("selftest: falló '{name}'").into()); }
+                say!("ok  {name}");
+                Ok(())
+            };
+
+            let params = match params {
+                Some(path) => {
+                    let mut p = ParamsKZG::<Bn256>::read(&mut &fs::read(&path)?[..])?;
+                    if p.k() < k { return Err(format!("selftest: {path} tiene k = {}, hace falta k >= {k}", p.k()).into()); }
+                    p.downsize(k);
+                    p
+                }
+                None => ParamsKZG::<Bn256>::setup(k, rand::thread_rng()),
+            };
+            step(&format!("params (k = {k})"), true)?;
+            let empty = TxCircuit::<Fr>::empty(circ.shape());
+            let pk = keygen_pk(&params, keygen_vk(&params, &empty)?, &empty)?;
+            step("keygen", true)?;
+            let instances = tx_instances(&circ);
+            let refs: Vec<&[Fr]> = instances.iter().map(|v| &v[..]).collect();
+            let envelope = create_kzg_proof(&params, &pk, circ, &refs, rand::thread_rng(), Transcript::Blake2b)?;
+            step("prueba", true)?;
+            step("verificación", check_kzg_proof(&params, pk.get_vk(), &envelope, &refs).is_ok())?;
+
+            let mut tampered = envelope.clone();
+            let mid = tampered.proof.len() / 2;
+            tampered.proof[mid] ^= 1;
+            step("rechaza una prueba alterada", check_kzg_proof(&params, pk.get_vk(), &tampered, &refs).is_err())?;
+            let mut wrong = instances.clone();
+            wrong[2][0] += Fr::ONE;
+            let wrong: Vec<&[Fr]> = wrong.iter().map(|v| &v[..]).collect();
+            step("rechaza un score_pub alterado", check_kzg_proof(&params, pk.get_vk(), &envelope, &wrong).is_err())?;
+            say!("Selftest correcto.");
+        }
+        Cmd::InspectProof { proof, vk } => {
+            let envelope = ProofEnvelope::from_bytes(&fs::read(&proof)?)?;
+            let created = envelope.created_at;
+            say!("formato:      v{}", envelope.version);
+            say!("esquema:      {:?}", envelope.scheme);
+            say!("transcript:   {:?}", envelope.transcript);
+            say!("k:            {}", envelope.k);
+            say!("vk:           {}", hex::encode(envelope.vk_digest));
+            say!("circuito:     {}{}", hex::encode(envelope.circuit_hash), if envelope.circuit_hash == halo2_tx_validator::envelope::circuit_hash() { " (esta versión)" } else { " (otra versión)" });
+            say!("creada:       {created} (Unix)");
+            say!("bytes:        {}", envelope.proof.len());
+            let mut sections = vec![];
+            if let Some(path) = vk {
+                if envelope.scheme != Scheme::KzgGwc { return Err("la estructura del transcript solo se conoce para KZG".into()); }
+                let vk = VerifyingKeyFile::read::<TxCircuit<Fr>>(&mut &read_input(&path)?[..])?.vk;
+                if vk.transcript_repr().to_repr() != envelope.vk_digest { say!("aviso: la vk no es la de la prueba"); }
+                // the EVM transcript writes points uncompressed
+                let point_bytes = match envelope.transcript { Transcript::Blake2b => 32, Transcript::Keccak => 64 };
+                let mut total = 0;
+                for (name, points, scalars) in transcript_layout(vk.cs()) {
+                    let bytes = points * point_bytes + scalars * 32;
+                    say!("  {name:<40} {points:>3} puntos {scalars:>4} escalares {bytes:>6} bytes");
+                    sections.push(serde_json::json!({ "section": name, "points": points, "scalars": scalars, "bytes": bytes }));
+                    total += bytes;
+                }
+                say!("tamaño esperado {total} bytes, real {}", envelope.proof.len());
+            }
+            if JSON_MODE.load(Ordering::Relaxed) { report(serde_json::json!({
+                "version": envelope.version,
+                "scheme": format!("{:?}", envelope.scheme),
+                "transcript": format!("{:?}", envelope.transcript),
+                "k": envelope.k,
+                "vk_digest": hex::encode(envelope.vk_digest),
+                "circuit_hash": hex::encode(envelope.circuit_hash),
+                "created_at": created,
+                "proof_bytes": envelope.proof.len(),
+                "sections": sections,
+            })); }
+        }
+        Cmd::Compress { input, out, level } => {
+            let raw = fs::read(input)?;
+            let packed = codec::compress(&ProofEnvelope::from_bytes(&raw)?, level)?;
+            write_out(&out, &packed)?;
+            say!("Prueba comprimida: {} -> {} bytes.", raw.len(), packed.len());
+        }
+        Cmd::Decompress { input, out } => {
+            let envelope = codec::decompress(&fs::read(input)?)?;
+            write_out(&out, envelope.to_bytes())?;
+            say!("Prueba descomprimida en {out}.");
+        }
+        Cmd::EncodeCalldata { proof, public, out } => {
+            let envelope = ProofEnvelope::from_bytes(&fs::read(proof)?)?;
+            if envelope.scheme != Scheme::KzgGwc || envelope.transcript != Transcript::Keccak {
+                return Err("el verificador EVM solo acepta pruebas KZG con --transcript keccak".into());
+            }
+            let pub_json: PublicInputs = read_doc(&public)?;
+            let calldata = evm_calldata(&pub_json.instances, &envelope.proof);
+            write_out(&out, format!("0x{}", hex::encode(&calldata)))?;
+            say!("Calldata: {} bytes en {out}.", calldata.len());
+        }
+        Cmd::ExportBundle { proof, public, out } => {
+            let envelope = ProofEnvelope::from_bytes(&read_input(&proof)?)?;
+            let pub_json: PublicInputs = read_doc(&public)?;
+            let evm = envelope.scheme == Scheme::KzgGwc && envelope.transcript == Transcript::Keccak;
+            let bundle = serde_json::json!({
+                "format": "quantum-guard-bundle/1",
+                "scheme": format!("{:?}", envelope.scheme),
+                "transcript": format!("{:?}", envelope.transcript),
+                "k": envelope.k,
+                "vk_fingerprint": hex::encode(envelope.vk_digest),
+                "proof": format!("0x{}", hex::encode(&envelope.proof)),
+                "instances": pub_json.instances.iter().flatten().map(|v| format!("0x{}", hex::encode(evm_word(v)))).collect::<Vec<_>>(),
+                "instance_counts": pub_json.instances.iter().map(Vec::len).collect::<Vec<_>>(),
+                "calldata": evm.then(|| format!("0x{}", hex::encode(evm_calldata(&pub_json.instances, &envelope.proof)))),
+                "valid_until": pub_json.valid_until,
+                "domain": pub_json.domain,
+            });
+            if !evm { esay!("aviso: sin calldata; el verificador EVM solo acepta pruebas KZG con --transcript keccak"); }
+            write_out(&out, serde_json::to_vec_pretty(&bundle)?)?;
+            say!("Bundle escrito en {out}.");
+        }
+        Cmd::VerifyEvm { bytecode, calldata } => {
+            let read_hex = |path: &str| -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+                Ok(hex::decode(fs::read_to_string(path)?.trim().trim_start_matches("0x"))?)
+            };
+            let gas = evm::deploy_and_call(read_hex(&bytecode)?, read_hex(&calldata)?)?;
+            say!("Verificación EVM correcta: {gas} de gas.");
+        }
+        Cmd::Cost { features_n, preset, activation, sanctions_depth, allowlist_depth, amount_index } => {
+            let features = features_n.or(preset.map(|p| p.spec().features)).ok_or("falta --features-n o --preset")?;
+            let shape = Shape { features, activation, sanctions_depth, allowlist_depth, amount_index, ..Default::default() };
+            let cost = match preset.map_or(1, |p| p.spec().batch_size) {
+                1 => TxCircuit::<Fr>::empty(shape).cost_estimate(),
+                n => TxBatch::<Fr>::empty(shape, n).cost_estimate(),
+            };
+            say!("Filas usadas:        {}", cost.rows);
+            say!("Columnas advice:     {}", cost.advice_columns);
+            say!("Columnas fixed:      {}", cost.fixed_columns);
+            say!("Columnas instance:   {}", cost.instance_columns);
+            say!("Selectores:          {}", cost.selectors);
+            say!("Lookups:             {}", cost.lookups);
+            say!("k mínimo:            {}", cost.min_k);
+        }
+        Cmd::Presets => {
+            for p in Preset::ALL {
+                let s = p.spec();
+                say!("{p:<10} features={:<5} lote={:<3} agregada={:<5} activación={:?} frac_bits={} k={}", s.features, s.batch_size, s.aggregate, s.activation, s.frac_bits, s.k);
+            }
+        }
+        Cmd::SanctionsBuild { list, depth, out } => {
+            let addresses: Vec<String> = fs::read_to_string(list)?
+                .lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')).map(String::from).collect();
+            let tree = SortedMerkleTree::new(&addresses, depth)?;
+            write_out(&out, serde_json::to_vec_pretty(&tree)?)?;
+            say!("Árbol de sanciones: {} direcciones, raíz {:?}", tree.addresses.len(), tree.root());
+        }
+        Cmd::ProveTree { backend, params, model, witness, proof, public, valid_until, now, domain } => {
+            let params_bytes = read_input(&params)?;
+            let model: TreeModel = serde_json::from_str(&fs::read_to_string(model)?)?;
+            let wit: TreeWitness = read_doc(&witness)?;
+            let now = now.unwrap_or_else(unix_now);
+            let valid_until = valid_until.unwrap_or(NEVER_EXPIRES);
+            if now > valid_until || valid_until > NEVER_EXPIRES {
+                return Err(format!("valid_until = {valid_until} inválido para now = {now}").into());
+            }
+            if let Some(s) = model.stumps.iter().find(|s| s.feature >= wit.x.len()) {
+                return Err(format!("el stump usa la feature {} de {}", s.feature, wit.x.len()).into());
+            }
+            let circ = TreeCircuit {
+                shape: TreeShape { features: wit.x.len(), splits: model.stumps.iter().map(|s| s.feature).collect() },
+                x: wit.x.iter().map(|v| to_fr_q16(*v)).collect(),
+                base: to_fr_q16(model.base),
+                stumps: model.stumps.iter().map(|s| stump_from_q16(s.threshold, s.left, s.right)).collect(),
+                now: Fr::from(now),
+                valid_until: Fr::from(valid_until),
+                domain: domain_from_tag(&domain),
+            };
+            let score = circ.score_q16();
+            if !(0..=ONE_Q16 as i64).contains(&score) {
+                return Err(format!("score = {score} fuera de rango [0, {ONE_Q16}]").into());
+            }
+            let instances = circ.instances();
+            let (commit_wb, commit_x) = (instances[0][0], instances[3][0]);
+            let shape = circ.shape.clone();
+            let (proof_bytes, _) = match backend {
+                Backend::Kzg => prove_kzg(&params_bytes, circ, &instances, None, rand::thread_rng(), Transcript::Blake2b)?,
+                Backend::Ipa => ipa::prove(&params_bytes, circ, &instances, None, rand::thread_rng(), Transcript::Blake2b)?,
+            };
+            write_out(&proof, &proof_bytes)?;
+            let pub_json = TreePublic {
+                shape,
+                score_pub: fe_to_hex(&to_fr_q16(score)),
+                commit_wb: fe_to_hex(&commit_wb),
+                commit_x: fe_to_hex(&commit_x),
+                valid_until,
+                domain,
+                instances,
+            };
+            write_doc(&public, &pub_json)?;
+            say!("Prueba de árbol creada (score = {:.6}).", score as f64 / ONE_Q16 as f64);
+        }
+        Cmd::VerifyTree { backend, params, proof, public, domain } => {
+            let params_bytes = read_input(&params)?;
+            let proof_bytes = fs::read(proof)?;
+            let pub_json: TreePublic = read_doc(&public)?;
+            if pub_json.instances.get(6).and_then(|v| v.first()) != Some(&domain_from_tag(&domain)) {
+                return Err(format!("la prueba no pertenece al dominio '{domain}' (archivo: '{}')", pub_json.domain).into());
+            }
+            let empty = TreeCircuit::empty(pub_json.shape);
+            match backend {
+                Backend::Kzg => verify_kzg(&params_bytes, &empty, &proof_bytes, &pub_json.instances, None)?,
+                Backend::Ipa => ipa::verify(&params_bytes, &empty, &proof_bytes, &pub_json.instances, None)?,
+            };
+            say!("¡Prueba de árbol verificada!");
+        }
+        Cmd::ProveEnsemble { backend, params, activation, witness, proof, public, domain } => {
+            let params_bytes = read_input(&params)?;
+            let wit: EnsembleWitness = read_doc(&witness)?;
+            if wit.models.is_empty() || wit.models.len() > ensemble::MAX_MODELS {
+                return Err(format!("{} modelos (entre 1 y {})", wit.models.len(), ensemble::MAX_MODELS).into());
+            }
+            if let Some(m) = wit.models.iter().find(|m| m.w.len() != wit.x.len()) {
+                return Err(format!("un modelo tiene {} pesos para {} features", m.w.len(), wit.x.len()).into());
+            }
+            // every member must be provable on its own: same bounds as a single-model witness
+            for m in &wit.models {
+                TxCircuit::<Fr>::builder().features(wit.x.clone()).weights(m.w.clone(), m.b).quantum(wit.q_out, wit.alpha)
+                    .activation(activation).build::<Fr>()?;
+            }
+            let circ = EnsembleCircuit {
+                x: wit.x.iter().map(|v| to_fr_q16(*v)).collect(),
+                models: wit.models.iter().map(|m| (m.w.iter().map(|v| to_fr_q16(*v)).collect(), to_fr_q16(m.b))).collect(),
+                alpha: to_fr_q16(wit.alpha),
+                q_out: to_fr_q16(wit.q_out),
+                domain: domain_from_tag(&domain),
+                activation,
+            };
+            if circ.score_q16() != wit.score_pub {
+                return Err(format!("score_pub = {} no es la media del ensemble ({})", wit.score_pub, circ.score_q16()).into());
+            }
+            let instances = circ.instances();
+            let (commit_wb, commit_x) = (instances[0].clone(), instances[1][0]);
+            let (proof_bytes, _) = match backend {
+                Backend::Kzg => prove_kzg(&params_bytes, circ, &instances, None, rand::thread_rng(), Transcript::Blake2b)?,
+                Backend::Ipa => ipa::prove(&params_bytes, circ, &instances, None, rand::thread_rng(), Transcript::Blake2b)?,
+            };
+            write_out(&proof, &proof_bytes)?;
+            let pub_json = EnsemblePublic {
+                features: wit.x.len(),
+                activation,
+                score_pub: fe_to_hex(&to_fr_q16(wit.score_pub)),
+                commit_wb: commit_wb.iter().map(fe_to_hex).collect(),
+                commit_x: fe_to_hex(&commit_x),
+                domain,
+                instances,
+            };
+            write_doc(&public, &pub_json)?;
+            say!("Prueba de ensemble creada ({} modelos).", wit.models.len());
+        }
+        Cmd::VerifyEnsemble { backend, params, proof, public, domain } => {
+            let params_bytes = read_input(&params)?;
+            let proof_bytes = fs::read(proof)?;
+            let pub_json: EnsemblePublic = read_doc(&public)?;
+            if pub_json.instances.get(3).and_then(|v| v.first()) != Some(&domain_from_tag(&domain)) {
+                return Err(format!("la prueba no pertenece al dominio '{domain}' (archivo: '{}')", pub_json.domain).into());
+            }
+            let models = pub_json.instances.first().map_or(0, Vec::len);
+            let empty = EnsembleCircuit::empty(pub_json.features, models, pub_json.activation);
+            match backend {
+                Backend::Kzg => verify_kzg(&params_bytes, &empty, &proof_bytes, &pub_json.instances, None)?,
+                Backend::Ipa => ipa::verify(&params_bytes, &empty, &proof_bytes, &pub_json.instances, None)?,
+            };
+            say!("¡Prueba de ensemble verificada! ({models} modelos)");
+        }
+        Cmd::ProveVelocity { backend, params, amounts, cap, proof, public, domain } => {
+            let params_bytes = read_input(&params)?;
+            let amounts: Vec<i64> = serde_json::from_str(&fs::read_to_string(amounts)?)?;
+            if amounts.len() > velocity::MAX_WINDOW {
+                return Err(format!("ventana de {} transacciones (máximo {})", amounts.len(), velocity::MAX_WINDOW).into());
+            }
+            if amounts.iter().any(|a| *a < 0) || cap < 0 {
+                return Err("importes y tope deben ser no negativos".into());
+            }
+            let total: i128 = amounts.iter().map(|a| *a as i128).sum();
+            if total > cap as i128 {
+                return Err(format!("total {total} supera el tope {cap}").into());
+            }
+            let circ = VelocityCircuit {
+                amounts: amounts.iter().map(|a| to_fr_q16(*a)).collect(),
+                cap: to_fr_q16(cap),
+                domain: domain_from_tag(&domain),
+            };
+            let commit = commit_in_domain(Tag::Amounts, circ.domain, &circ.amounts);
+            let instances = vec![vec![commit], vec![circ.cap], vec![circ.domain]];
+            let (proof_bytes, _) = match backend {
+                Backend::Kzg => prove_kzg(&params_bytes, circ, &instances, None, rand::thread_rng(), Transcript::Blake2b)?,
+                Backend::Ipa => ipa::prove(&params_bytes, circ, &instances, None, rand::thread_rng(), Transcript::Blake2b)?,
+            };
+            write_out(&proof, &proof_bytes)?;
+            let pub_json = VelocityPublic { window: amounts.len(), cap, commit_amounts: fe_to_hex(&commit), domain, instances };
+            write_doc(&public, &pub_json)?;
+            say!("Prueba de velocidad creada ({} transacciones).", amounts.len());
+        }
+        Cmd::VerifyVelocity { backend, params, proof, public, cap, domain } => {
+            let params_bytes = read_input(&params)?;
+            let proof_bytes = fs::read(proof)?;
+            let pub_json: VelocityPublic = read_doc(&public)?;
+            if pub_json.instances.get(1).and_then(|v| v.first()) != Some(&to_fr_q16(cap.unwrap_or(pub_json.cap))) {
+                return Err("el tope no coincide con la instancia pública".into());
+            }
+            if pub_json.instances.get(2).and_then(|v| v.first()) != Some(&domain_from_tag(&domain)) {
+                return Err(format!("la prueba no pertenece al dominio '{domain}' (archivo: '{}')", pub_json.domain).into());
+            }
+            let empty = VelocityCircuit::empty(pub_json.window);
+            match backend {
+                Backend::Kzg => verify_kzg(&params_bytes, &empty, &proof_bytes, &pub_json.instances, None)?,
+                Backend::Ipa => ipa::verify(&params_bytes, &empty, &proof_bytes, &pub_json.instances, None)?,
+            };
+            say!("¡Prueba de velocidad verificada!");
+        }
+        Cmd::RegistryBuild { models, depth, domain, out } => {
+            let domain = domain_from_tag(&domain);
+            let commits = models.iter()
+                .map(|path| -> Result<Fr, Box<dyn std::error::Error>> {
+                    let m: Model = serde_json::from_str(&fs::read_to_string(path)?)?;
+                    Ok(model_commit(domain, &m.w, m.b))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let registry = ModelRegistry::new(commits, depth)?;
+            write_out(&out, serde_json::to_vec_pretty(&registry)?)?;
+            say!("Registro: {} modelos, raíz {:?}", registry.commits.len(), registry.root());
+        }
+        Cmd::AllowlistBuild { csv, depth, out } => {
+            let tree = SortedMerkleTree::new(&read_address_csv(&csv)?, depth)?;
+            write_out(&out, serde_json::to_vec_pretty(&tree)?)?;
+            say!("Allowlist: {} direcciones, raíz {:?}", tree.addresses.len(), tree.root());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cli(args: &[&str]) -> Result<(), Box<dyn std::error::Error>> {
+        run(Cli::parse_from(["halo2_tx_validator", "--quiet"].iter().chain(args)).cmd)
+    }
+
+    // gen-params → prove → verify through the subcommands, as the binary runs them
+    #[test]
+    fn prove_then_verify() {
+        let dir = std::env::temp_dir().join(format!("qg-smoke-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+        let (params, witness, proof, public) = (path("params.bin"), path("w.json"), path("tx.proof"), path("tx.public.json"));
+        // two features, PWL activation: score_pub is the native score of this witness
+        fs::write(&witness, r#"{"x":[32768,16384],"w":[32768,-16384],"b":0,"alpha":32768,"q_out":32768,"score_pub":39393}"#).unwrap();
+
+        cli(&["gen-params", "--k", "12", "--out", &params]).unwrap();
+        cli(&["prove", "--params", &params, "--activation", "pwl", "--witness", &witness, "--proof", &proof, "--public", &public]).unwrap();
+        cli(&["verify", "--params", &params, "--proof", &proof, "--public", &public]).unwrap();
+        assert!(cli(&["verify", "--params", &params, "--proof", &proof, "--public", &public, "--domain", "1"]).is_err());
+
+        let mut bytes = fs::read(&proof).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        fs::write(&proof, bytes).unwrap();
+        assert!(cli(&["verify", "--params", &params, "--proof", &proof, "--public", &public]).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,302 @@
+// merkle.rs
+use crate::{poseidon_commit, CircuitField};
+use crate::{Hash, PoseidonChip, PoseidonConfig};
+use crate::circuit::{AssignedCell, Layouter, Value};
+use crate::FieldExt;
+use halo2_proofs::{
+    halo2curves::{bn256::Fr, ff::PrimeField},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Selector},
+    poly::Rotation,
+};
+use serde::{Deserialize, Serialize};
+
+// Sorted ("indexed") Merkle tree over addresses: leaf i = H(v_i, v_{i+1}) for the sorted set
+// plus the sentinels 0 and 2^160 - 1. Non-membership of `a` is one leaf with v_i < a < v_{i+1};
+// membership is a leaf with v_i = a.
+pub const ADDRESS_BITS: usize = 160;
+
+pub fn hash2<F: CircuitField>(l: F, r: F) -> F { poseidon_commit(&[l, r]) }
+
+/// Parses a 0x-prefixed 20-byte address into a field element (big-endian numeric value).
+pub fn address_to_fr(s: &str) -> Result<Fr, String> {
+    let bytes = hex::decode(s.trim().trim_start_matches("0x")).map_err(|e| format!("dirección inválida {s}: {e}"))?;
+    if bytes.len() != 20 { return Err(format!("dirección inválida {s}: se esperaban 20 bytes")); }
+    let hi = u128::from_be_bytes([&[0u8; 12][..], &bytes[..4]].concat().try_into().unwrap());
+    let lo = u128::from_be_bytes(bytes[4..].try_into().unwrap());
+    let two_64 = Fr::from_u128(1 << 64);
+    Ok(Fr::from_u128(hi) * two_64 * two_64 + Fr::from_u128(lo))
+}
+
+fn max_address() -> Fr {
+    let two_64 = Fr::from_u128(1 << 64);
+    Fr::from_u128((1 << 32) - 1) * two_64 * two_64 + Fr::from_u128(u128::MAX)
+}
+
+// Proof that `address` sits in the gap (low, next), or equals `low` for membership.
+#[derive(Clone, Debug)]
+pub struct MerkleWitness<F: CircuitField = Fr> {
+    pub address: F,
+    pub low: F,
+    pub next: F,
+    // bottom-up: sibling and whether the running node is the right child
+    pub siblings: Vec<F>,
+    pub is_right: Vec<bool>,
+}
+
+impl<F: CircuitField> MerkleWitness<F> {
+    /// Placeholder with the right shape, for keygen.
+    pub fn empty(depth: usize) -> Self {
+        Self { address: F::from(0), low: F::from(0), next: F::from(0), siblings: vec![F::from(0); depth], is_right: vec![false; depth] }
+    }
+
+    /// Native root, matching `MerkleConfig::root`.
+    pub fn root(&self) -> F {
+        self.siblings.iter().zip(&self.is_right).fold(hash2(self.low, self.next), |cur, (sib, right)| {
+            if *right { hash2(*sib, cur) } else { hash2(cur, *sib) }
+        })
+    }
+}
+
+// Zero-padded to 2^depth leaves; layers[depth] holds the root.
+fn build_layers(mut leaves: Vec<Fr>, depth: usize) -> Vec<Vec<Fr>> {
+    leaves.resize(1 << depth, Fr::from(0));
+    let mut layers = vec![leaves];
+    while layers.last().unwrap().len() > 1 {
+        let next = layers.last().unwrap().chunks(2).map(|c| hash2(c[0], c[1])).collect();
+        layers.push(next);
+    }
+    layers
+}
+
+fn path(layers: &[Vec<Fr>], mut index: usize) -> (Vec<Fr>, Vec<bool>) {
+    let depth = layers.len() - 1;
+    let mut siblings = Vec::with_capacity(depth);
+    let mut is_right = Vec::with_capacity(depth);
+    for layer in &layers[..depth] {
+        siblings.push(layer[index ^ 1]);
+        is_right.push(index & 1 == 1);
+        index >>= 1;
+    }
+    (siblings, is_right)
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SortedMerkleTree {
+    pub depth: usize,
+    // sorted, deduplicated, sentinels excluded; kept as the original hex strings
+    pub addresses: Vec<String>,
+    #[serde(skip)]
+    layers: Vec<Vec<Fr>>,
+    #[serde(skip)]
+    values: Vec<(Vec<u8>, Fr)>,
+}
+
+impl SortedMerkleTree {
+    pub fn new(addresses: &[String], depth: usize) -> Result<Self, String> {
+        let mut values = addresses.iter()
+            .map(|a| Ok((hex::decode(a.trim().trim_start_matches("0x")).map_err(|e| e.to_string())?, address_to_fr(a)?)))
+            .collect::<Result<Vec<_>, String>>()?;
+        values.sort_by(|a, b| a.0.cmp(&b.0));
+        values.dedup_by(|a, b| a.0 == b.0);
+        if values.len() + 1 > (1 << depth) { return Err(format!("{} direcciones no caben en un árbol de profundidad {depth}", values.len())); }
+
+        let mut chain = vec![Fr::from(0)];
+        chain.extend(values.iter().map(|v| v.1));
+        chain.push(max_address());
+        let leaves: Vec<Fr> = chain.windows(2).map(|w| hash2(w[0], w[1])).collect();
+        let layers = build_layers(leaves, depth);
+        let addresses = values.iter().map(|v| format!("0x{}", hex::encode(&v.0))).collect();
+        Ok(Self { depth, addresses, layers, values })
+    }
+
+    /// Rebuilds the hash layers after deserialization.
+    pub fn rebuild(self) -> Result<Self, String> { Self::new(&self.addresses, self.depth) }
+
+    pub fn root(&self) -> Fr { self.layers.last().unwrap()[0] }
+
+    fn path(&self, index: usize) -> (Vec<Fr>, Vec<bool>) { path(&self.layers, index) }
+
+    fn bounds(&self, i: usize) -> (Fr, Fr) {
+        let low = if i == 0 { Fr::from(0) } else { self.values[i - 1].1 };
+        let next = self.values.get(i).map(|v| v.1).unwrap_or_else(max_address);
+        (low, next)
+    }
+
+    /// Witness that `address` is absent, or an error if it is listed.
+    pub fn non_membership(&self, address: &str) -> Result<MerkleWitness, String> {
+        let key = hex::decode(address.trim().trim_start_matches("0x")).map_err(|e| e.to_string())?;
+        let i = match self.values.binary_search_by(|v| v.0.cmp(&key)) {
+            Ok(_) => return Err(format!("{address} está en la lista")),
+            Err(i) => i,
+        };
+        let (low, next) = self.bounds(i);
+        let (siblings, is_right) = self.path(i);
+        Ok(MerkleWitness { address: address_to_fr(address)?, low, next, siblings, is_right })
+    }
+
+    /// Witness that `address` is listed, or an error if it is not.
+    pub fn membership(&self, address: &str) -> Result<MerkleWitness, String> {
+        let key = hex::decode(address.trim().trim_start_matches("0x")).map_err(|e| e.to_string())?;
+        let i = self.values.binary_search_by(|v| v.0.cmp(&key)).map_err(|_| format!("{address} no está en la lista"))?;
+        // leaf i + 1 starts at values[i]
+        let (low, next) = self.bounds(i + 1);
+        let (siblings, is_right) = self.path(i + 1);
+        Ok(MerkleWitness { address: address_to_fr(address)?, low, next, siblings, is_right })
+    }
+}
+
+// Registry of model commitments indexed by model id: leaf id = H(id, commit_wb_id).
+// One vk serves every registered model; the proof shows the weights used hash to the
+// commitment registered under the public id.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ModelRegistry {
+    pub depth: usize,
+    pub commits: Vec<Fr>,
+    #[serde(skip)]
+    layers: Vec<Vec<Fr>>,
+}
+
+impl ModelRegistry {
+    pub fn new(commits: Vec<Fr>, depth: usize) -> Result<Self, String> {
+        if commits.len() > (1 << depth) { return Err(format!("{} modelos no caben en un registro de profundidad {depth}", commits.len())); }
+        let leaves = commits.iter().enumerate().map(|(id, c)| hash2(Fr::from(id as u64), *c)).collect();
+        Ok(Self { depth, layers: build_layers(leaves, depth), commits })
+    }
+
+    /// Rebuilds the hash layers after deserialization.
+    pub fn rebuild(self) -> Result<Self, String> { Self::new(self.commits, self.depth) }
+
+    pub fn root(&self) -> Fr { self.layers.last().unwrap()[0] }
+
+    /// Path for `id`; `low` is the id and `next` the registered commitment.
+    pub fn witness(&self, id: usize) -> Result<MerkleWitness, String> {
+        let commit = *self.commits.get(id).ok_or(format!("model_id {id} no registrado"))?;
+        let (siblings, is_right) = path(&self.layers, id);
+        Ok(MerkleWitness { address: Fr::from(id as u64), low: Fr::from(id as u64), next: commit, siblings, is_right })
+    }
+}
+
+// One path level per row: (cur, sibling, is_right) -> (left, right) ordered for hashing.
+#[derive(Clone, Debug)]
+pub struct MerkleConfig {
+    adv: [Column<Advice>; 5],
+    q_swap: Selector,
+}
+
+/// Rows used by `root` for a path of `depth` (swap rows plus one 2-input sponge per level and the leaf).
+pub fn path_rows(depth: usize, poseidon2_rows: usize) -> usize { depth * (1 + poseidon2_rows) + poseidon2_rows }
+
+impl MerkleConfig {
+    pub fn configure<F: FieldExt>(cs: &mut ConstraintSystem<F>, adv: [Column<Advice>; 5]) -> Self {
+        for c in adv { cs.enable_equality(c); }
+        let q_swap = cs.selector();
+        let [cur, sib, bit, left, right] = adv;
+        cs.create_gate("merkle swap", |meta| {
+            let q = meta.query_selector(q_swap);
+            let cur = meta.query_advice(cur, Rotation::cur());
+            let sib = meta.query_advice(sib, Rotation::cur());
+            let bit = meta.query_advice(bit, Rotation::cur());
+            let left = meta.query_advice(left, Rotation::cur());
+            let right = meta.query_advice(right, Rotation::cur());
+            let one = Expression::Constant(F::from(1));
+            vec![
+                q.clone() * bit.clone() * (one - bit.clone()),
+                q.clone() * (left - cur.clone() - bit.clone() * (sib.clone() - cur.clone())),
+                q * (right - sib.clone() - bit * (cur - sib)),
+            ]
+        });
+        Self { adv, q_swap }
+    }
+
+    fn hash<F: CircuitField>(&self, poseidon: &PoseidonConfig<F, 3, 2>, mut layouter: impl Layouter<F>, l: AssignedCell<F, F>, r: AssignedCell<F, F>) -> Result<AssignedCell<F, F>, Error> {
+        let mut hasher = Hash::<F, PoseidonChip<F>, 3, 2>::init(poseidon.clone(), layouter.namespace(|| "poseidon_node"))?;
+        hasher.update(layouter.namespace(|| "absorb_node"), vec![l, r])?;
+        hasher.squeeze(layouter.namespace(|| "squeeze_node"))
+    }
+
+    /// Hashes the leaf H(low, next) up the path and returns the root cell.
+    pub fn root<F: CircuitField>(&self, poseidon: &PoseidonConfig<F, 3, 2>, mut layouter: impl Layouter<F>, low: &AssignedCell<F, F>, next: &AssignedCell<F, F>, wit: &MerkleWitness<F>) -> Result<AssignedCell<F, F>, Error> {
+        let mut cur = self.hash(poseidon, layouter.namespace(|| "leaf"), low.clone(), next.clone())?;
+        for (level, (sib, is_right)) in wit.siblings.iter().zip(&wit.is_right).enumerate() {
+            let (l, r) = layouter.assign_region(
+                || format!("merkle level {level}"),
+                |mut region| {
+                    region.enable_selector(|| "q_swap", &self.q_swap, 0)?;
+                    let c = cur.copy_advice(|| "cur", &mut region, self.adv[0], 0)?;
+                    let s = region.assign_advice(|| "sibling", self.adv[1], 0, || Value::known(*sib))?;
+                    region.assign_advice(|| "is_right", self.adv[2], 0, || Value::known(F::from(*is_right as u64)))?;
+                    let (lv, rv) = if *is_right { (s.value().copied(), c.value().copied()) } else { (c.value().copied(), s.value().copied()) };
+                    let l = region.assign_advice(|| "left", self.adv[3], 0, || lv)?;
+                    let r = region.assign_advice(|| "right", self.adv[4], 0, || rv)?;
+                    Ok((l, r))
+                },
+            )?;
+            cur = self.hash(poseidon, layouter.namespace(|| format!("node {level}")), l, r)?;
+        }
+        Ok(cur)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{prover::tx_instances, TxCircuit, NEVER_EXPIRES, ONE_Q16};
+    use halo2_proofs::dev::{MockProver, VerifyFailure};
+
+    const ONE: i64 = ONE_Q16 as i64;
+
+    fn circuit(w: Vec<i64>) -> TxCircuit<Fr> {
+        TxCircuit::<Fr>::builder()
+            .features(vec![ONE / 2, ONE / 4]).weights(w, 0).quantum(ONE / 2, ONE / 2)
+            .domain("test").validity(0, NEVER_EXPIRES)
+            .build().unwrap()
+    }
+
+    fn check(mut circ: TxCircuit<Fr>, wit: MerkleWitness) -> Result<(), Vec<VerifyFailure>> {
+        circ.registry = Some(wit);
+        MockProver::run(circ.cost_estimate().min_k, &circ, tx_instances(&circ)).unwrap().verify()
+    }
+
+    #[test]
+    fn registry_binds_the_scoring_weights() {
+        let (a, b) = (vec![ONE / 2, -ONE / 4], vec![ONE, ONE / 8]);
+        let registry = ModelRegistry::new(vec![circuit(a.clone()).commit_wb(), circuit(b.clone()).commit_wb()], 2).unwrap();
+        assert_eq!(check(circuit(a.clone()), registry.witness(0).unwrap()), Ok(()));
+        assert_eq!(check(circuit(b.clone()), registry.witness(1).unwrap()), Ok(()));
+        // model 1's weights under id 0
+        assert!(check(circuit(b), registry.witness(0).unwrap()).is_err());
+        assert!(check(circuit(a), registry.witness(1).unwrap()).is_err());
+    }
+
+    #[test]
+    fn zero_address_is_not_on_the_allowlist() {
+        let addresses = ["0x00000000000000000000000000000000000000aa", "0x00000000000000000000000000000000000000cc"].map(String::from);
+        let tree = SortedMerkleTree::new(&addresses, 3).unwrap();
+        let allowed = |wit: MerkleWitness| {
+            let mut circ = circuit(vec![ONE / 2, -ONE / 4]);
+            circ.allowlist = Some(wit);
+            MockProver::run(circ.cost_estimate().min_k, &circ, tx_instances(&circ)).unwrap().verify()
+        };
+        assert_eq!(allowed(tree.membership(&addresses[1]).unwrap()), Ok(()));
+        // leaf 0 is H(0, first address): its low matches a zero destination
+        let zero = "0x0000000000000000000000000000000000000000";
+        assert!(tree.membership(zero).is_err());
+        let sentinel = tree.non_membership(zero).unwrap();
+        assert_eq!((sentinel.low, sentinel.root()), (Fr::from(0), tree.root()));
+        assert!(allowed(sentinel).is_err());
+    }
+
+    #[test]
+    fn sorted_tree_paths_reach_the_root() {
+        let addresses = ["0x00000000000000000000000000000000000000aa", "0x00000000000000000000000000000000000000cc"].map(String::from);
+        let tree = SortedMerkleTree::new(&addresses, 3).unwrap();
+        let inside = tree.membership(&addresses[0]).unwrap();
+        assert_eq!(inside.root(), tree.root());
+        assert_eq!(inside.low, inside.address);
+        let gap = tree.non_membership("0x00000000000000000000000000000000000000bb").unwrap();
+        assert_eq!(gap.root(), tree.root());
+        assert!(gap.low < gap.address && gap.address < gap.next);
+        assert!(tree.non_membership(&addresses[1]).is_err());
+        assert!(tree.membership("0x00000000000000000000000000000000000000bb").is_err());
+    }
+}
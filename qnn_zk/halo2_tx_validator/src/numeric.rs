@@ -0,0 +1,163 @@
+// numeric.rs
+use crate::FieldExt;
+use serde::{de, Deserialize, Deserializer};
+use std::fmt;
+
+// Numbers in witness and public files, from whatever upstream system wrote them:
+//   - JSON integers, or strings holding decimal ("-1234", "+5") or hex ("0x1f", "-0x1f") integers;
+//   - the sign is always explicit: hex is a magnitude, never two's complement ("0xffffffffffffffff"
+//     is 2^64 - 1, not -1);
+//   - Q16.16 values must fit i64;
+//   - field elements must be < p and a negative value -v means p - v; hex is a big-endian integer
+//     whether zero-padded or not, so fe_to_hex's 64 digits and an EVM uint256 read the same.
+
+fn split_sign(s: &str) -> (bool, &str) {
+    let s = s.trim();
+    match s.as_bytes().first() {
+        Some(b'-') => (true, &s[1..]),
+        Some(b'+') => (false, &s[1..]),
+        _ => (false, s),
+    }
+}
+
+/// Signed integer from a decimal or 0x-hex string (see above).
+pub fn parse_i64(s: &str) -> Result<i64, String> {
+    let (neg, digits) = split_sign(s);
+    let magnitude = match digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+        Some(h) => u64::from_str_radix(h, 16),
+        None => digits.parse::<u64>(),
+    }.map_err(|_| format!("número inválido: {s}"))?;
+    match neg {
+        false => i64::try_from(magnitude).map_err(|_| format!("{s} no cabe en i64")),
+        true if magnitude == 1 << 63 => Ok(i64::MIN),
+        true => i64::try_from(magnitude).map(|v| -v).map_err(|_| format!("{s} no cabe en i64")),
+    }
+}
+
+// Big-endian bytes of an unsigned integer in base 10 or 16; None on a bad digit.
+fn be_bytes(digits: &str, radix: u32) -> Option<Vec<u8>> {
+    if digits.is_empty() { return None; }
+    let mut out: Vec<u8> = vec![];
+    for c in digits.chars() {
+        let mut carry = c.to_digit(radix)?;
+        for byte in out.iter_mut().rev() {
+            let v = *byte as u32 * radix + carry;
+            *byte = v as u8;
+            carry = v >> 8;
+        }
+        while carry > 0 {
+            out.insert(0, carry as u8);
+            carry >>= 8;
+        }
+    }
+    Some(out)
+}
+
+/// Field element from any of the forms above; rejects values >= p.
+pub fn parse_fe<F: FieldExt>(s: &str) -> Result<F, String> {
+    let (neg, digits) = split_sign(s);
+    let len = F::Repr::default().as_ref().len();
+    let mut be = match digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+        Some(h) => be_bytes(h, 16),
+        None => be_bytes(digits, 10),
+    }.ok_or_else(|| format!("número inválido: {s}"))?;
+    while be.first() == Some(&0) && be.len() > 1 { be.remove(0); }
+    if be.len() > len { return Err(format!("{s} no es un elemento canónico del campo")); }
+    let mut repr = F::Repr::default();
+    for (dst, src) in repr.as_mut().iter_mut().zip(be.iter().rev()) { *dst = *src; }
+    let v: F = Option::from(F::from_repr(repr)).ok_or_else(|| format!("{s} no es un elemento canónico del campo"))?;
+    Ok(if neg { -v } else { v })
+}
+
+/// An i64 that deserializes from a JSON integer or a numeric string.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FlexI64(pub i64);
+
+impl<'de> Deserialize<'de> for FlexI64 {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        struct V;
+        impl de::Visitor<'_> for V {
+            type Value = FlexI64;
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result { f.write_str("un entero o una cadena numérica") }
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<FlexI64, E> { Ok(FlexI64(v)) }
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<FlexI64, E> {
+                i64::try_from(v).map(FlexI64).map_err(|_| E::custom(format!("{v} no cabe en i64")))
+            }
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<FlexI64, E> { parse_i64(v).map(FlexI64).map_err(E::custom) }
+        }
+        d.deserialize_any(V)
+    }
+}
+
+/// serde `deserialize_with` for i64 fields.
+pub fn flexible_i64<'de, D: Deserializer<'de>>(d: D) -> Result<i64, D::Error> { Ok(FlexI64::deserialize(d)?.0) }
+
+/// A field element that deserializes from a JSON integer or any string form above.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FlexFe<F>(pub F);
+
+impl<'de, F: FieldExt> Deserialize<'de> for FlexFe<F> {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        struct V<F>(std::marker::PhantomData<F>);
+        impl<F: FieldExt> de::Visitor<'_> for V<F> {
+            type Value = FlexFe<F>;
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result { f.write_str("un elemento de campo (entero o cadena)") }
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<FlexFe<F>, E> {
+                let f = F::from(v.unsigned_abs());
+                Ok(FlexFe(if v < 0 { -f } else { f }))
+            }
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<FlexFe<F>, E> { Ok(FlexFe(F::from(v))) }
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<FlexFe<F>, E> { parse_fe(v).map(FlexFe).map_err(E::custom) }
+        }
+        d.deserialize_any(V(std::marker::PhantomData))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fe_to_hex;
+    use halo2_proofs::halo2curves::{bn256::Fr, ff::Field};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    #[test]
+    fn fe_to_hex_round_trips() {
+        let mut rng = ChaCha20Rng::seed_from_u64(3);
+        for v in [Fr::ZERO, Fr::ONE, Fr::from(u64::MAX), -Fr::ONE, Fr::random(&mut rng), Fr::random(&mut rng)] {
+            let hex = fe_to_hex(&v);
+            assert_eq!(hex.len(), 66);
+            assert_eq!(parse_fe::<Fr>(&hex), Ok(v));
+        }
+    }
+
+    // an EVM uint256 is zero-padded big-endian; it must read as the same number unpadded hex does
+    #[test]
+    fn padded_and_unpadded_hex_agree() {
+        let expected = Fr::from(0x1234_5678_9abc_def0);
+        let padded = format!("0x{:064x}", 0x1234_5678_9abc_def0u64);
+        assert_eq!(fe_to_hex(&expected), padded);
+        for s in [padded.as_str(), "0x123456789abcdef0", "0x0000123456789abcdef0", "1311768467463790320"] {
+            assert_eq!(parse_fe::<Fr>(s), Ok(expected), "{s}");
+        }
+        assert_eq!(parse_fe::<Fr>(&format!("-{padded}")), Ok(-expected));
+        assert_eq!(parse_fe::<Fr>(&format!("0x{:064x}", 5u8)), Ok(Fr::from(5)));
+    }
+
+    #[test]
+    fn rejects_values_outside_the_field() {
+        // p itself, padded
+        let p = "0x30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000001";
+        assert!(parse_fe::<Fr>(p).is_err());
+        assert!(parse_fe::<Fr>(&format!("0x1{}", "0".repeat(64))).is_err());
+        assert!(parse_fe::<Fr>("0xzz").is_err());
+    }
+
+    #[test]
+    fn parse_i64_reads_signed_forms() {
+        assert_eq!(parse_i64("-0x1f"), Ok(-31));
+        assert_eq!(parse_i64("+5"), Ok(5));
+        assert_eq!(parse_i64(&i64::MIN.to_string()), Ok(i64::MIN));
+        assert!(parse_i64("0xffffffffffffffff").is_err());
+    }
+}
@@ -0,0 +1,358 @@
+// pasta.rs
+use crate::error::{QuantumGuardError as E, Result};
+use crate::TxCircuit;
+use core::iter::{Product, Sum};
+use core::ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+use halo2_proofs::{
+    halo2curves::{
+        ff::{Field, FromUniformBytes, PrimeField, WithSmallOrderMulGroup},
+        group::{prime::{PrimeCurve, PrimeCurveAffine, PrimeGroup}, Curve, Group, GroupEncoding},
+        pasta, Coordinates, CurveAffine, CurveExt,
+    },
+    plonk::{create_proof, keygen_pk, keygen_vk, verify_proof, ProvingKey, VerifyingKey},
+    poly::{commitment::ParamsProver, VerificationStrategy},
+    poly::ipa::{
+        commitment::{IPACommitmentScheme, ParamsIPA},
+        multiopen::{ProverIPA, VerifierIPA},
+        strategy::SingleStrategy,
+    },
+    transcript::{Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer},
+};
+use rand::RngCore;
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq, CtOption};
+
+// Pasta build: TxCircuit over the Vesta scalar field with transparent-setup IPA proofs, for
+// Halo2-native stacks that embed the scoring gadgets. Commitments use the same Poseidon spec over
+// Fp, so they are not interchangeable with BN256 ones; `prover::tx_instances` computes them.
+//
+// halo2-axiom's prover needs `Hash` on the scalar field and pasta_curves' Fp has none, so Fp and
+// the Vesta curve are wrapped here; every operation forwards to pasta_curves.
+
+pub type PastaTxCircuit = TxCircuit<Fp>;
+
+type Ipa = IPACommitmentScheme<VestaAffine>;
+
+/// Vesta scalar field (= Pallas base field), hashable.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Fp(pub pasta::Fp);
+
+/// Vesta in projective coordinates, with scalars in the wrapped `Fp`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Vesta(pub pasta::Eq);
+
+/// Vesta in affine coordinates; the IPA commitment group.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct VestaAffine(pub pasta::EqAffine);
+
+// `$out($tr::$f(lhs.0, rhs.0))` for every owned/borrowed combination of operands.
+macro_rules! forward_binop {
+    ($lhs:ident, $rhs:ident, $out:ident, $tr:ident, $f:ident) => {
+        impl $tr<$rhs> for $lhs {
+            type Output = $out;
+            fn $f(self, rhs: $rhs) -> $out { $out($tr::$f(self.0, rhs.0)) }
+        }
+        impl<'a> $tr<&'a $rhs> for $lhs {
+            type Output = $out;
+            fn $f(self, rhs: &'a $rhs) -> $out { $out($tr::$f(self.0, rhs.0)) }
+        }
+        impl<'a> $tr<$rhs> for &'a $lhs {
+            type Output = $out;
+            fn $f(self, rhs: $rhs) -> $out { $out($tr::$f(self.0, rhs.0)) }
+        }
+        impl<'a, 'b> $tr<&'b $rhs> for &'a $lhs {
+            type Output = $out;
+            fn $f(self, rhs: &'b $rhs) -> $out { $out($tr::$f(self.0, rhs.0)) }
+        }
+    };
+}
+
+macro_rules! forward_assign {
+    ($lhs:ident, $rhs:ident, $tr:ident, $f:ident) => {
+        impl $tr<$rhs> for $lhs {
+            fn $f(&mut self, rhs: $rhs) { $tr::$f(&mut self.0, rhs.0) }
+        }
+        impl<'a> $tr<&'a $rhs> for $lhs {
+            fn $f(&mut self, rhs: &'a $rhs) { $tr::$f(&mut self.0, rhs.0) }
+        }
+    };
+}
+
+macro_rules! forward_ct {
+    ($t:ident) => {
+        impl ConditionallySelectable for $t {
+            fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
+                $t(ConditionallySelectable::conditional_select(&a.0, &b.0, choice))
+            }
+        }
+        impl ConstantTimeEq for $t {
+            fn ct_eq(&self, other: &Self) -> Choice { self.0.ct_eq(&other.0) }
+        }
+        impl Neg for $t {
+            type Output = $t;
+            fn neg(self) -> $t { $t(-self.0) }
+        }
+        impl GroupEncoding for $t {
+            type Repr = <pasta::Eq as GroupEncoding>::Repr;
+            fn from_bytes(bytes: &Self::Repr) -> CtOption<Self> { GroupEncoding::from_bytes(bytes).map($t) }
+            fn from_bytes_unchecked(bytes: &Self::Repr) -> CtOption<Self> { GroupEncoding::from_bytes_unchecked(bytes).map($t) }
+            fn to_bytes(&self) -> Self::Repr { self.0.to_bytes() }
+        }
+    };
+}
+
+forward_binop!(Fp, Fp, Fp, Add, add);
+forward_binop!(Fp, Fp, Fp, Sub, sub);
+forward_binop!(Fp, Fp, Fp, Mul, mul);
+forward_assign!(Fp, Fp, AddAssign, add_assign);
+forward_assign!(Fp, Fp, SubAssign, sub_assign);
+forward_assign!(Fp, Fp, MulAssign, mul_assign);
+
+forward_binop!(Vesta, Vesta, Vesta, Add, add);
+forward_binop!(Vesta, Vesta, Vesta, Sub, sub);
+forward_binop!(Vesta, VestaAffine, Vesta, Add, add);
+forward_binop!(Vesta, VestaAffine, Vesta, Sub, sub);
+forward_binop!(Vesta, Fp, Vesta, Mul, mul);
+forward_assign!(Vesta, Vesta, AddAssign, add_assign);
+forward_assign!(Vesta, Vesta, SubAssign, sub_assign);
+forward_assign!(Vesta, VestaAffine, AddAssign, add_assign);
+forward_assign!(Vesta, VestaAffine, SubAssign, sub_assign);
+forward_assign!(Vesta, Fp, MulAssign, mul_assign);
+
+forward_binop!(VestaAffine, VestaAffine, Vesta, Add, add);
+forward_binop!(VestaAffine, VestaAffine, Vesta, Sub, sub);
+forward_binop!(VestaAffine, Fp, Vesta, Mul, mul);
+
+forward_ct!(Vesta);
+forward_ct!(VestaAffine);
+
+impl ConditionallySelectable for Fp {
+    fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
+        Fp(pasta::Fp::conditional_select(&a.0, &b.0, choice))
+    }
+}
+
+impl ConstantTimeEq for Fp {
+    fn ct_eq(&self, other: &Self) -> Choice { self.0.ct_eq(&other.0) }
+}
+
+impl Neg for Fp {
+    type Output = Fp;
+    fn neg(self) -> Fp { Fp(-self.0) }
+}
+
+// by canonical bytes, consistent with Eq
+impl std::hash::Hash for Fp {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) { self.0.to_repr().hash(state) }
+}
+
+impl Sum for Fp {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self { iter.fold(Self::ZERO, |acc, v| acc + v) }
+}
+
+impl<'a> Sum<&'a Fp> for Fp {
+    fn sum<I: Iterator<Item = &'a Fp>>(iter: I) -> Self { iter.fold(Self::ZERO, |acc, v| acc + v) }
+}
+
+impl Product for Fp {
+    fn product<I: Iterator<Item = Self>>(iter: I) -> Self { iter.fold(Self::ONE, |acc, v| acc * v) }
+}
+
+impl<'a> Product<&'a Fp> for Fp {
+    fn product<I: Iterator<Item = &'a Fp>>(iter: I) -> Self { iter.fold(Self::ONE, |acc, v| acc * v) }
+}
+
+impl From<u64> for Fp {
+    fn from(v: u64) -> Self { Fp(pasta::Fp::from(v)) }
+}
+
+impl Field for Fp {
+    const ZERO: Self = Fp(pasta::Fp::ZERO);
+    const ONE: Self = Fp(pasta::Fp::ONE);
+
+    fn random(rng: impl RngCore) -> Self { Fp(pasta::Fp::random(rng)) }
+    fn square(&self) -> Self { Fp(self.0.square()) }
+    fn double(&self) -> Self { Fp(self.0.double()) }
+    fn invert(&self) -> CtOption<Self> { self.0.invert().map(Fp) }
+    fn sqrt(&self) -> CtOption<Self> { self.0.sqrt().map(Fp) }
+
+    fn sqrt_ratio(num: &Self, div: &Self) -> (Choice, Self) {
+        let (is_square, root) = pasta::Fp::sqrt_ratio(&num.0, &div.0);
+        (is_square, Fp(root))
+    }
+}
+
+impl PrimeField for Fp {
+    type Repr = [u8; 32];
+
+    const MODULUS: &'static str = pasta::Fp::MODULUS;
+    const NUM_BITS: u32 = pasta::Fp::NUM_BITS;
+    const CAPACITY: u32 = pasta::Fp::CAPACITY;
+    const TWO_INV: Self = Fp(pasta::Fp::TWO_INV);
+    const MULTIPLICATIVE_GENERATOR: Self = Fp(pasta::Fp::MULTIPLICATIVE_GENERATOR);
+    const S: u32 = pasta::Fp::S;
+    const ROOT_OF_UNITY: Self = Fp(pasta::Fp::ROOT_OF_UNITY);
+    const ROOT_OF_UNITY_INV: Self = Fp(pasta::Fp::ROOT_OF_UNITY_INV);
+    const DELTA: Self = Fp(pasta::Fp::DELTA);
+
+    fn from_repr(repr: [u8; 32]) -> CtOption<Self> { pasta::Fp::from_repr(repr).map(Fp) }
+    fn to_repr(&self) -> [u8; 32] { self.0.to_repr() }
+    fn is_odd(&self) -> Choice { self.0.is_odd() }
+    fn from_u128(v: u128) -> Self { Fp(pasta::Fp::from_u128(v)) }
+}
+
+impl FromUniformBytes<64> for Fp {
+    fn from_uniform_bytes(bytes: &[u8; 64]) -> Self { Fp(pasta::Fp::from_uniform_bytes(bytes)) }
+}
+
+impl WithSmallOrderMulGroup<3> for Fp {
+    const ZETA: Self = Fp(pasta::Fp::ZETA);
+}
+
+impl Sum for Vesta {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self { iter.fold(Self::identity(), |acc, v| acc + v) }
+}
+
+impl<'a> Sum<&'a Vesta> for Vesta {
+    fn sum<I: Iterator<Item = &'a Vesta>>(iter: I) -> Self { iter.fold(Self::identity(), |acc, v| acc + v) }
+}
+
+impl From<VestaAffine> for Vesta {
+    fn from(p: VestaAffine) -> Self { Vesta(p.0.into()) }
+}
+
+impl From<Vesta> for VestaAffine {
+    fn from(p: Vesta) -> Self { VestaAffine(p.0.into()) }
+}
+
+impl Group for Vesta {
+    type Scalar = Fp;
+
+    fn random(rng: impl RngCore) -> Self { Vesta(pasta::Eq::random(rng)) }
+    fn identity() -> Self { Vesta(pasta::Eq::identity()) }
+    fn generator() -> Self { Vesta(pasta::Eq::generator()) }
+    fn is_identity(&self) -> Choice { self.0.is_identity() }
+    fn double(&self) -> Self { Vesta(self.0.double()) }
+}
+
+impl Curve for Vesta {
+    type AffineRepr = VestaAffine;
+
+    fn batch_normalize(p: &[Self], q: &mut [VestaAffine]) {
+        let p: Vec<_> = p.iter().map(|v| v.0).collect();
+        let mut out = vec![pasta::EqAffine::identity(); q.len()];
+        pasta::Eq::batch_normalize(&p, &mut out);
+        q.iter_mut().zip(out).for_each(|(q, o)| *q = VestaAffine(o));
+    }
+
+    fn to_affine(&self) -> VestaAffine { VestaAffine(self.0.to_affine()) }
+}
+
+impl PrimeGroup for Vesta {}
+
+impl PrimeCurve for Vesta {
+    type Affine = VestaAffine;
+}
+
+impl CurveExt for Vesta {
+    type ScalarExt = Fp;
+    type Base = pasta::Fq;
+    type AffineExt = VestaAffine;
+
+    const CURVE_ID: &'static str = pasta::Eq::CURVE_ID;
+
+    fn endo(&self) -> Self { Vesta(self.0.endo()) }
+    fn jacobian_coordinates(&self) -> (pasta::Fq, pasta::Fq, pasta::Fq) { self.0.jacobian_coordinates() }
+
+    fn hash_to_curve<'a>(domain_prefix: &'a str) -> Box<dyn Fn(&[u8]) -> Self + 'a> {
+        let hash = pasta::Eq::hash_to_curve(domain_prefix);
+        Box::new(move |msg| Vesta(hash(msg)))
+    }
+
+    fn is_on_curve(&self) -> Choice { CurveExt::is_on_curve(&self.0) }
+    fn a() -> pasta::Fq { <pasta::Eq as CurveExt>::a() }
+    fn b() -> pasta::Fq { <pasta::Eq as CurveExt>::b() }
+
+    fn new_jacobian(x: pasta::Fq, y: pasta::Fq, z: pasta::Fq) -> CtOption<Self> {
+        pasta::Eq::new_jacobian(x, y, z).map(Vesta)
+    }
+}
+
+impl PrimeCurveAffine for VestaAffine {
+    type Scalar = Fp;
+    type Curve = Vesta;
+
+    fn identity() -> Self { VestaAffine(pasta::EqAffine::identity()) }
+    fn generator() -> Self { VestaAffine(pasta::EqAffine::generator()) }
+    fn is_identity(&self) -> Choice { self.0.is_identity() }
+    fn to_curve(&self) -> Vesta { Vesta(self.0.to_curve()) }
+}
+
+impl CurveAffine for VestaAffine {
+    type ScalarExt = Fp;
+    type Base = pasta::Fq;
+    type CurveExt = Vesta;
+
+    fn coordinates(&self) -> CtOption<Coordinates<Self>> {
+        self.0.coordinates().and_then(|c| Coordinates::from_xy(*c.x(), *c.y()))
+    }
+
+    fn from_xy(x: pasta::Fq, y: pasta::Fq) -> CtOption<Self> { pasta::EqAffine::from_xy(x, y).map(VestaAffine) }
+    fn is_on_curve(&self) -> Choice { CurveAffine::is_on_curve(&self.0) }
+    fn a() -> pasta::Fq { <pasta::EqAffine as CurveAffine>::a() }
+    fn b() -> pasta::Fq { <pasta::EqAffine as CurveAffine>::b() }
+}
+
+/// Transparent IPA params for 2^k rows; deterministic, so prover and verifier derive the same.
+pub fn setup(k: u32) -> ParamsIPA<VestaAffine> { ParamsIPA::new(k) }
+
+/// Proving key for the layout of `empty` (e.g. `TxCircuit::empty(shape)`).
+pub fn keygen(params: &ParamsIPA<VestaAffine>, empty: &PastaTxCircuit) -> Result<ProvingKey<VestaAffine>> {
+    let vk = keygen_vk(params, empty).map_err(|e| E::Keygen(e.to_string()))?;
+    keygen_pk(params, vk, empty).map_err(|e| E::Keygen(e.to_string()))
+}
+
+/// IPA proof (Blake2b transcript) of `circ` against `instances` in column order.
+pub fn prove(params: &ParamsIPA<VestaAffine>, pk: &ProvingKey<VestaAffine>, circ: PastaTxCircuit, instances: &[&[Fp]], rng: impl RngCore) -> Result<Vec<u8>> {
+    let mut transcript = Blake2bWrite::<_, VestaAffine, Challenge255<_>>::init(vec![]);
+    create_proof::<Ipa, ProverIPA<_>, _, _, _, _>(params, pk, &[circ], &[instances], rng, &mut transcript)
+        .map_err(|e| E::Proving(e.to_string()))?;
+    Ok(transcript.finalize())
+}
+
+/// Checks an IPA proof from `prove` against `instances`.
+pub fn verify(params: &ParamsIPA<VestaAffine>, vk: &VerifyingKey<VestaAffine>, proof: &[u8], instances: &[&[Fp]]) -> Result<()> {
+    let mut transcript = Blake2bRead::<_, VestaAffine, Challenge255<_>>::init(proof);
+    verify_proof::<Ipa, VerifierIPA<_>, _, _, _>(params, vk, SingleStrategy::new(params), &[instances], &mut transcript)
+        .map(|_| ())
+        .map_err(|e| E::Verification(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{prover::tx_instances, NEVER_EXPIRES, ONE_Q16};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    const ONE: i64 = ONE_Q16 as i64;
+
+    #[test]
+    fn proves_and_verifies_over_vesta() {
+        let circ = PastaTxCircuit::builder()
+            .features(vec![ONE / 2, ONE / 4]).weights(vec![ONE, ONE / 2], ONE / 8).quantum(ONE / 2, ONE / 2)
+            .domain("test").validity(0, NEVER_EXPIRES)
+            .build().unwrap();
+        let instances = tx_instances(&circ);
+        let refs: Vec<&[Fp]> = instances.iter().map(Vec::as_slice).collect();
+        let params = setup(circ.cost_estimate().min_k);
+        let pk = keygen(&params, &TxCircuit::empty(circ.shape())).unwrap();
+        let proof = prove(&params, &pk, circ, &refs, ChaCha20Rng::seed_from_u64(7)).unwrap();
+        verify(&params, pk.get_vk(), &proof, &refs).unwrap();
+
+        // the same proof does not verify a different score
+        let mut forged = instances.clone();
+        forged[2][0] += Fp::ONE;
+        let forged: Vec<&[Fp]> = forged.iter().map(Vec::as_slice).collect();
+        assert!(verify(&params, pk.get_vk(), &proof, &forged).is_err());
+    }
+}
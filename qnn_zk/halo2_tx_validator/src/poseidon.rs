@@ -0,0 +1,258 @@
+// poseidon.rs
+use crate::circuit::{AssignedCell, Layouter, Value};
+use crate::CircuitField;
+use halo2_proofs::{
+    halo2curves::ff::PrimeField,
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, Selector},
+    poly::Rotation,
+};
+use poseidon_primitives::poseidon::primitives::Spec;
+use std::marker::PhantomData;
+
+// Poseidon over t = 3 (rate 2) with halo2_gadgets' P128Pow5T3 parameters: x^5, 8 full rounds,
+// 56 partial rounds, round constants and MDS from the reference Grain LFSR for the field.
+// Sponge: capacity element len * 2^64, zero padding, state[0] squeezed.
+// Layout per permutation: the absorb row also runs the first full round, then one row per full
+// round and `partial_per_row` partial rounds per row (the first element after each but the last
+// witnessed in adv[3..]); the last row is the output.
+const FULL_ROUNDS: usize = 8;
+
+/// Poseidon-128, x^5, width 3, generated for the field `F` (as in halo2_gadgets).
+#[derive(Debug)]
+pub struct P128Pow5T3<F>(PhantomData<F>);
+
+impl<F: PrimeField> Spec<F, 3, 2> for P128Pow5T3<F> {
+    fn full_rounds() -> usize { FULL_ROUNDS }
+
+    fn partial_rounds() -> usize { 56 }
+
+    fn sbox(val: F) -> F { val.pow_vartime([5]) }
+
+    fn secure_mds() -> usize { 0 }
+}
+
+fn partial_rounds<F: CircuitField>() -> usize { F::PoseidonSpec::partial_rounds() }
+
+// Largest grouping that divides the partial rounds and fits the three spare advice columns.
+fn partial_per_row<F: CircuitField>() -> usize {
+    [4, 3, 2, 1].into_iter().find(|k| partial_rounds::<F>().is_multiple_of(*k)).unwrap()
+}
+
+/// Rows taken by one permutation region.
+pub fn permutation_rows<F: CircuitField>() -> usize { FULL_ROUNDS + partial_rounds::<F>() / partial_per_row::<F>() + 1 }
+
+fn sbox<F: CircuitField>(x: F) -> F {
+    let x2 = x * x;
+    x2 * x2 * x
+}
+
+fn mds<F: CircuitField>(m: &[[F; 3]; 3], s: [F; 3]) -> [F; 3] {
+    m.map(|row| row[0] * s[0] + row[1] * s[1] + row[2] * s[2])
+}
+
+fn is_full<F: CircuitField>(round: usize) -> bool { round < FULL_ROUNDS / 2 || round >= FULL_ROUNDS / 2 + partial_rounds::<F>() }
+
+// State at the end of every row of the layout, plus the in-row intermediates of partial rows.
+fn trace<F: CircuitField>(mut s: [F; 3]) -> (Vec<[F; 3]>, Vec<[F; 3]>) {
+    let (rc, m, _) = F::PoseidonSpec::constants();
+    let per_row = partial_per_row::<F>();
+    let (mut rows, mut mids) = (Vec::with_capacity(permutation_rows::<F>()), Vec::new());
+    let mut mid = [F::from(0); 3];
+    for (r, c) in rc.iter().enumerate() {
+        if is_full::<F>(r) {
+            s = mds(&m, [sbox(s[0] + c[0]), sbox(s[1] + c[1]), sbox(s[2] + c[2])]);
+            rows.push(s);
+        } else {
+            s = mds(&m, [sbox(s[0] + c[0]), s[1] + c[1], s[2] + c[2]]);
+            let k = (r - FULL_ROUNDS / 2) % per_row;
+            if k + 1 < per_row { mid[k] = s[0]; } else { rows.push(s); mids.push(mid); }
+        }
+    }
+    (rows, mids)
+}
+
+/// Native permutation.
+pub fn permute<F: CircuitField>(state: &mut [F; 3]) {
+    *state = *trace(*state).0.last().unwrap();
+}
+
+/// Native sponge, matching `Hash::squeeze`.
+pub fn hash<F: CircuitField>(inputs: &[F]) -> F {
+    let mut state = [F::from(0), F::from(0), F::from_u128((inputs.len() as u128) << 64)];
+    for chunk in inputs.chunks(2) {
+        for (s, x) in state.iter_mut().zip(chunk) { *s += x; }
+        permute(&mut state);
+    }
+    if inputs.is_empty() { permute(&mut state); }
+    state[0]
+}
+
+#[derive(Clone, Debug)]
+pub struct Pow5Config<F, const T: usize, const RATE: usize> {
+    adv: [Column<Advice>; 6],
+    // three per partial round of a row; full rounds use the first three
+    rc: Vec<Column<Fixed>>,
+    q_first: Selector,
+    q_full: Selector,
+    q_partial: Selector,
+    _marker: PhantomData<F>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Pow5Chip<F>(PhantomData<F>);
+
+impl<F: CircuitField> Pow5Chip<F> {
+    pub fn configure(
+        cs: &mut ConstraintSystem<F>,
+        a0: Column<Advice>, a1: Column<Advice>, a2: Column<Advice>,
+        a3: Column<Advice>, a4: Column<Advice>, a5: Column<Advice>,
+    ) -> Pow5Config<F, 3, 2> {
+        let adv = [a0, a1, a2, a3, a4, a5];
+        for a in &adv { cs.enable_equality(*a); }
+        let per_row = partial_per_row::<F>();
+        let rc: Vec<_> = (0..3 * per_row).map(|_| cs.fixed_column()).collect();
+        let (q_first, q_full, q_partial) = (cs.selector(), cs.selector(), cs.selector());
+        let (_, m, _) = F::PoseidonSpec::constants();
+        let pow5 = |e: Expression<F>| e.clone() * e.clone() * e.clone() * e.clone() * e;
+        let mix = move |s: [Expression<F>; 3]| {
+            m.map(|row| {
+                Expression::Constant(row[0]) * s[0].clone() + Expression::Constant(row[1]) * s[1].clone() + Expression::Constant(row[2]) * s[2].clone()
+            })
+        };
+
+        // row 0: s0, s1, s2, x0, x1    row 1: M · sbox(s + x + rc)
+        cs.create_gate("poseidon absorb + full round", |meta| {
+            let q = meta.query_selector(q_first);
+            let s = [0, 1, 2].map(|i| meta.query_advice(adv[i], Rotation::cur()));
+            let x0 = meta.query_advice(adv[3], Rotation::cur());
+            let x1 = meta.query_advice(adv[4], Rotation::cur());
+            let next = [0, 1, 2].map(|i| meta.query_advice(adv[i], Rotation::next()));
+            let c = [0, 1, 2].map(|i| meta.query_fixed(rc[i], Rotation::cur()));
+            let u = [s[0].clone() + x0, s[1].clone() + x1, s[2].clone()];
+            let y = mix([0, 1, 2].map(|i| pow5(u[i].clone() + c[i].clone())));
+            (0..3).map(|i| q.clone() * (next[i].clone() - y[i].clone())).collect::<Vec<_>>()
+        });
+        cs.create_gate("poseidon full round", |meta| {
+            let q = meta.query_selector(q_full);
+            let s = [0, 1, 2].map(|i| meta.query_advice(adv[i], Rotation::cur()));
+            let next = [0, 1, 2].map(|i| meta.query_advice(adv[i], Rotation::next()));
+            let c = [0, 1, 2].map(|i| meta.query_fixed(rc[i], Rotation::cur()));
+            let y = mix([0, 1, 2].map(|i| pow5(s[i].clone() + c[i].clone())));
+            (0..3).map(|i| q.clone() * (next[i].clone() - y[i].clone())).collect::<Vec<_>>()
+        });
+        // row 0: a, b, c, then the first element after each of the row's rounds but the last
+        // row 1: state after the row's last round
+        cs.create_gate("poseidon partial rounds", |meta| {
+            let q = meta.query_selector(q_partial);
+            let mut s = [0, 1, 2].map(|i| meta.query_advice(adv[i], Rotation::cur()));
+            let next = [0, 1, 2].map(|i| meta.query_advice(adv[i], Rotation::next()));
+            let mut constraints = Vec::with_capacity(per_row + 2);
+            for k in 0..per_row {
+                let c = [0, 1, 2].map(|i| meta.query_fixed(rc[3 * k + i], Rotation::cur()));
+                let [a, b, d] = s.clone();
+                s = mix([pow5(a + c[0].clone()), b + c[1].clone(), d + c[2].clone()]);
+                let first = if k + 1 < per_row { meta.query_advice(adv[3 + k], Rotation::cur()) } else { next[0].clone() };
+                constraints.push(q.clone() * (first.clone() - s[0].clone()));
+                s[0] = first;
+            }
+            constraints.push(q.clone() * (next[1].clone() - s[1].clone()));
+            constraints.push(q * (next[2].clone() - s[2].clone()));
+            constraints
+        });
+
+        Pow5Config { adv, rc, q_first, q_full, q_partial, _marker: PhantomData }
+    }
+}
+
+/// Absorbed element: a fresh witness or an existing cell (copied in).
+#[derive(Clone, Debug)]
+pub enum Input<F: CircuitField> {
+    Value(Value<F>),
+    Cell(AssignedCell<F, F>),
+}
+
+impl<F: CircuitField> From<F> for Input<F> {
+    fn from(v: F) -> Self { Input::Value(Value::known(v)) }
+}
+
+impl<F: CircuitField> From<AssignedCell<F, F>> for Input<F> {
+    fn from(c: AssignedCell<F, F>) -> Self { Input::Cell(c) }
+}
+
+/// Constant-length sponge: `init`, `update` with values or cells, then `squeeze`.
+pub struct Hash<F: CircuitField, C, const T: usize, const RATE: usize> {
+    config: Pow5Config<F, T, RATE>,
+    inputs: Vec<Input<F>>,
+    _chip: PhantomData<C>,
+}
+
+impl<F: CircuitField> Hash<F, Pow5Chip<F>, 3, 2> {
+    pub fn init(config: Pow5Config<F, 3, 2>, _layouter: impl Layouter<F>) -> Result<Self, Error> {
+        Ok(Self { config, inputs: Vec::new(), _chip: PhantomData })
+    }
+
+    pub fn update<I: Into<Input<F>>>(&mut self, _layouter: impl Layouter<F>, inputs: Vec<I>) -> Result<(), Error> {
+        self.inputs.extend(inputs.into_iter().map(Into::into));
+        Ok(())
+    }
+
+    pub fn squeeze(&self, mut layouter: impl Layouter<F>) -> Result<AssignedCell<F, F>, Error> {
+        let cfg = &self.config;
+        let (rc, _, _) = F::PoseidonSpec::constants();
+        let per_row = partial_per_row::<F>();
+        let capacity = F::from_u128((self.inputs.len() as u128) << 64);
+        let chunks: Vec<&[Input<F>]> = if self.inputs.is_empty() { vec![&[]] } else { self.inputs.chunks(2).collect() };
+        let mut state: Option<[AssignedCell<F, F>; 3]> = None;
+        for (n, chunk) in chunks.iter().enumerate() {
+            state = Some(layouter.assign_region(
+                || format!("poseidon permutation {n}"),
+                |mut region| {
+                    let init = [F::from(0), F::from(0), capacity];
+                    let mut cells = cells3([0, 1, 2].map(|i| match &state {
+                        Some(prev) => prev[i].copy_advice(|| "state", &mut region, cfg.adv[i], 0),
+                        None => region.assign_advice_from_constant(|| "initial state", cfg.adv[i], 0, init[i]),
+                    }))?;
+                    let mut xs = Vec::with_capacity(2);
+                    for k in 0..2 {
+                        let x = match chunk.get(k) {
+                            Some(Input::Cell(c)) => c.copy_advice(|| "input", &mut region, cfg.adv[3 + k], 0)?,
+                            Some(Input::Value(v)) => region.assign_advice(|| "input", cfg.adv[3 + k], 0, || *v)?,
+                            None => region.assign_advice_from_constant(|| "padding", cfg.adv[3 + k], 0, F::from(0))?,
+                        };
+                        xs.push(x);
+                    }
+                    let absorbed = cells[0].value().zip(cells[1].value()).zip(cells[2].value()).zip(xs[0].value()).zip(xs[1].value())
+                        .map(|((((s0, s1), s2), x0), x1)| trace([*s0 + x0, *s1 + x1, *s2]));
+
+                    let mut row = 0;
+                    for (r, c) in rc.iter().enumerate() {
+                        if !is_full::<F>(r) && !(r - FULL_ROUNDS / 2).is_multiple_of(per_row) { continue; }
+                        if is_full::<F>(r) {
+                            let q = if row == 0 { &cfg.q_first } else { &cfg.q_full };
+                            region.enable_selector(|| "full round", q, row)?;
+                            for (col, c) in cfg.rc.iter().zip(c) { region.assign_fixed(|| "rc", *col, row, || Value::known(*c))?; }
+                        } else {
+                            region.enable_selector(|| "partial rounds", &cfg.q_partial, row)?;
+                            for (cols, c) in cfg.rc.chunks(3).zip(&rc[r..r + per_row]) {
+                                for (col, c) in cols.iter().zip(c) { region.assign_fixed(|| "rc", *col, row, || Value::known(*c))?; }
+                            }
+                            let p = (r - FULL_ROUNDS / 2) / per_row;
+                            for k in 0..per_row - 1 {
+                                region.assign_advice(|| "partial", cfg.adv[3 + k], row, || absorbed.as_ref().map(|(_, mids)| mids[p][k]))?;
+                            }
+                        }
+                        cells = cells3([0, 1, 2].map(|i| region.assign_advice(|| "state", cfg.adv[i], row + 1, || absorbed.as_ref().map(|(rows, _)| rows[row][i]))))?;
+                        row += 1;
+                    }
+                    Ok(cells)
+                },
+            )?);
+        }
+        Ok(state.unwrap()[0].clone())
+    }
+}
+
+fn cells3<F: CircuitField>(cells: [Result<AssignedCell<F, F>, Error>; 3]) -> Result<[AssignedCell<F, F>; 3], Error> {
+    let [a, b, c] = cells;
+    Ok([a?, b?, c?])
+}
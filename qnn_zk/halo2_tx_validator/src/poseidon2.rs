@@ -0,0 +1,383 @@
+// poseidon2.rs
+use crate::CircuitField;
+use crate::circuit::{AssignedCell, Layouter, Value};
+use halo2_proofs::{
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, Selector},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+// Poseidon2 over t = 3 (rate 2), x^5, 8 full + 56 partial rounds, with the same sponge as Pow5:
+// capacity element len * 2^64, zero padding, state[0] squeezed. External matrix circ(2, 1, 1),
+// internal matrix J + diag(1, 1, 2), round constants from the reference Grain LFSR: the permutation
+// matches the reference implementation (the sponge is this crate's own).
+// Layout per permutation: the absorb row also runs the first full round, partial rounds go
+// four per row (first element of rounds 1..3 witnessed in adv[3..6]), last row is the output.
+const FULL_ROUNDS: usize = 8;
+const PARTIAL_ROUNDS: usize = 56;
+const PARTIAL_PER_ROW: usize = 4;
+pub const PERMUTATION_ROWS: usize = FULL_ROUNDS + PARTIAL_ROUNDS / PARTIAL_PER_ROW + 1;
+
+fn sbox<F: CircuitField>(x: F) -> F {
+    let x2 = x * x;
+    x2 * x2 * x
+}
+
+fn external<F: CircuitField>(s: [F; 3]) -> [F; 3] {
+    let t = s[0] + s[1] + s[2];
+    [t + s[0], t + s[1], t + s[2]]
+}
+
+fn internal<F: CircuitField>(s: [F; 3]) -> [F; 3] {
+    let t = s[0] + s[1] + s[2];
+    [t + s[0], t + s[1], t + s[2] + s[2]]
+}
+
+fn is_full(round: usize) -> bool { !(FULL_ROUNDS / 2..FULL_ROUNDS / 2 + PARTIAL_ROUNDS).contains(&round) }
+
+// Grain LFSR of the reference parameter script, seeded with (prime field, x^5, field bits, t, R_F,
+// R_P, 30 ones); its first 160 outputs are discarded.
+struct Grain(u128);
+
+impl Grain {
+    fn new<F: CircuitField>() -> Self {
+        let seed = [(1, 2), (0, 4), (F::NUM_BITS as u128, 12), (3, 12), (FULL_ROUNDS as u128, 10), (PARTIAL_ROUNDS as u128, 10), ((1 << 30) - 1, 30)];
+        let mut grain = Grain(seed.iter().fold(0, |acc, (v, w)| acc << w | v));
+        for _ in 0..160 { grain.step(); }
+        grain
+    }
+
+    // bit i of the reference state is bit 79 - i here
+    fn step(&mut self) -> bool {
+        let b = [62, 51, 38, 23, 13, 0].iter().fold(0, |acc, i| acc ^ (self.0 >> (79 - i)));
+        self.0 = (self.0 << 1 | b & 1) & ((1 << 80) - 1);
+        b & 1 == 1
+    }
+
+    // pairs (b1, b2): b2 is kept when b1 is set
+    fn bit(&mut self) -> bool {
+        loop {
+            let (b1, b2) = (self.step(), self.step());
+            if b1 { return b2; }
+        }
+    }
+
+    // NUM_BITS bits, most significant first, resampled until below the modulus
+    fn element<F: CircuitField>(&mut self) -> F {
+        loop {
+            let mut repr = [0u8; 32];
+            for i in (0..F::NUM_BITS as usize).rev() {
+                if self.bit() { repr[i / 8] |= 1 << (i % 8); }
+            }
+            if let Some(x) = F::from_repr(repr).into() { return x; }
+        }
+    }
+}
+
+// R_F·t + R_P constants in order: full rounds take three, partial rounds one (the others are zero).
+fn round_constants<F: CircuitField>() -> Vec<[F; 3]> {
+    let mut grain = Grain::new::<F>();
+    (0..FULL_ROUNDS + PARTIAL_ROUNDS)
+        .map(|r| match is_full(r) {
+            true => [0; 3].map(|_| grain.element()),
+            false => [grain.element(), F::from(0), F::from(0)],
+        })
+        .collect()
+}
+
+// State at the end of every row of the layout, plus the in-row intermediates of partial rows.
+fn trace<F: CircuitField>(state: [F; 3]) -> (Vec<[F; 3]>, Vec<[F; 3]>) {
+    let rc = round_constants::<F>();
+    let mut s = external(state);
+    let (mut rows, mut mids) = (Vec::with_capacity(PERMUTATION_ROWS), Vec::new());
+    let mut mid = [F::from(0); 3];
+    for (r, c) in rc.iter().enumerate() {
+        if is_full(r) {
+            s = external([sbox(s[0] + c[0]), sbox(s[1] + c[1]), sbox(s[2] + c[2])]);
+            rows.push(s);
+        } else {
+            s[0] = sbox(s[0] + c[0]);
+            s = internal(s);
+            let k = (r - FULL_ROUNDS / 2) % PARTIAL_PER_ROW;
+            if k + 1 < PARTIAL_PER_ROW { mid[k] = s[0]; } else { rows.push(s); mids.push(mid); }
+        }
+    }
+    (rows, mids)
+}
+
+/// Native permutation.
+pub fn permute<F: CircuitField>(state: &mut [F; 3]) {
+    *state = *trace(*state).0.last().unwrap();
+}
+
+/// Native sponge, matching `Hash::squeeze`.
+pub fn hash<F: CircuitField>(inputs: &[F]) -> F {
+    let mut state = [F::from(0), F::from(0), F::from_u128((inputs.len() as u128) << 64)];
+    for chunk in inputs.chunks(2) {
+        for (s, x) in state.iter_mut().zip(chunk) { *s += x; }
+        permute(&mut state);
+    }
+    if inputs.is_empty() { permute(&mut state); }
+    state[0]
+}
+
+#[derive(Clone, Debug)]
+pub struct Poseidon2Config<F, const T: usize, const RATE: usize> {
+    adv: [Column<Advice>; 6],
+    rc: [Column<Fixed>; 4],
+    q_first: Selector,
+    q_full: Selector,
+    q_partial: Selector,
+    _marker: PhantomData<F>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Poseidon2Chip<F>(PhantomData<F>);
+
+impl<F: CircuitField> Poseidon2Chip<F> {
+    pub fn configure(
+        cs: &mut ConstraintSystem<F>,
+        a0: Column<Advice>, a1: Column<Advice>, a2: Column<Advice>,
+        a3: Column<Advice>, a4: Column<Advice>, a5: Column<Advice>,
+    ) -> Poseidon2Config<F, 3, 2> {
+        let adv = [a0, a1, a2, a3, a4, a5];
+        for a in &adv { cs.enable_equality(*a); }
+        let rc = [0, 1, 2, 3].map(|_| cs.fixed_column());
+        let (q_first, q_full, q_partial) = (cs.selector(), cs.selector(), cs.selector());
+        let pow5 = |e: Expression<F>| e.clone() * e.clone() * e.clone() * e.clone() * e;
+        let two = || Expression::Constant(F::from(2));
+        let three = || Expression::Constant(F::from(3));
+        let ext = |s: [Expression<F>; 3]| {
+            let t = s[0].clone() + s[1].clone() + s[2].clone();
+            [t.clone() + s[0].clone(), t.clone() + s[1].clone(), t + s[2].clone()]
+        };
+
+        // row 0: s0, s1, s2, x0, x1    row 1: M_E · sbox(M_E · (s + x) + rc)
+        cs.create_gate("poseidon2 absorb + full round", |meta| {
+            let q = meta.query_selector(q_first);
+            let s = [0, 1, 2].map(|i| meta.query_advice(adv[i], Rotation::cur()));
+            let x0 = meta.query_advice(adv[3], Rotation::cur());
+            let x1 = meta.query_advice(adv[4], Rotation::cur());
+            let next = [0, 1, 2].map(|i| meta.query_advice(adv[i], Rotation::next()));
+            let c = [0, 1, 2].map(|i| meta.query_fixed(rc[i], Rotation::cur()));
+            let u = ext([s[0].clone() + x0, s[1].clone() + x1, s[2].clone()]);
+            let y = ext([0, 1, 2].map(|i| pow5(u[i].clone() + c[i].clone())));
+            (0..3).map(|i| q.clone() * (next[i].clone() - y[i].clone())).collect::<Vec<_>>()
+        });
+        cs.create_gate("poseidon2 full round", |meta| {
+            let q = meta.query_selector(q_full);
+            let s = [0, 1, 2].map(|i| meta.query_advice(adv[i], Rotation::cur()));
+            let next = [0, 1, 2].map(|i| meta.query_advice(adv[i], Rotation::next()));
+            let c = [0, 1, 2].map(|i| meta.query_fixed(rc[i], Rotation::cur()));
+            let y = ext([0, 1, 2].map(|i| pow5(s[i].clone() + c[i].clone())));
+            (0..3).map(|i| q.clone() * (next[i].clone() - y[i].clone())).collect::<Vec<_>>()
+        });
+        // row 0: a, b, c, m1, m2, m3 (first element after rounds 1..3)    row 1: state after round 4
+        cs.create_gate("poseidon2 partial rounds", |meta| {
+            let q = meta.query_selector(q_partial);
+            let mut a = meta.query_advice(adv[0], Rotation::cur());
+            let mut b = meta.query_advice(adv[1], Rotation::cur());
+            let mut c = meta.query_advice(adv[2], Rotation::cur());
+            let mids = [3, 4, 5].map(|i| meta.query_advice(adv[i], Rotation::cur()));
+            let next = [0, 1, 2].map(|i| meta.query_advice(adv[i], Rotation::next()));
+            let mut constraints = Vec::with_capacity(PARTIAL_PER_ROW + 2);
+            for k in 0..PARTIAL_PER_ROW {
+                let y = pow5(a + meta.query_fixed(rc[k], Rotation::cur()));
+                let a_k = two() * y.clone() + b.clone() + c.clone();
+                let b_k = y.clone() + two() * b.clone() + c.clone();
+                let c_k = y + b + three() * c;
+                if k + 1 < PARTIAL_PER_ROW {
+                    constraints.push(q.clone() * (mids[k].clone() - a_k));
+                    a = mids[k].clone();
+                } else {
+                    constraints.push(q.clone() * (next[0].clone() - a_k));
+                    a = next[0].clone();
+                }
+                b = b_k;
+                c = c_k;
+            }
+            constraints.push(q.clone() * (next[1].clone() - b));
+            constraints.push(q * (next[2].clone() - c));
+            constraints
+        });
+
+        Poseidon2Config { adv, rc, q_first, q_full, q_partial, _marker: PhantomData }
+    }
+}
+
+/// Absorbed element: a fresh witness or an existing cell (copied in).
+#[derive(Clone, Debug)]
+pub enum Input<F: CircuitField> {
+    Value(Value<F>),
+    Cell(AssignedCell<F, F>),
+}
+
+impl<F: CircuitField> From<F> for Input<F> {
+    fn from(v: F) -> Self { Input::Value(Value::known(v)) }
+}
+
+impl<F: CircuitField> From<AssignedCell<F, F>> for Input<F> {
+    fn from(c: AssignedCell<F, F>) -> Self { Input::Cell(c) }
+}
+
+/// Constant-length sponge with the same calls as halo2_gadgets' `Hash`: `init`, `update`, `squeeze`.
+pub struct Hash<F: CircuitField, C, const T: usize, const RATE: usize> {
+    config: Poseidon2Config<F, T, RATE>,
+    inputs: Vec<Input<F>>,
+    _chip: PhantomData<C>,
+}
+
+impl<F: CircuitField> Hash<F, Poseidon2Chip<F>, 3, 2> {
+    pub fn init(config: Poseidon2Config<F, 3, 2>, _layouter: impl Layouter<F>) -> Result<Self, Error> {
+        Ok(Self { config, inputs: Vec::new(), _chip: PhantomData })
+    }
+
+    pub fn update<I: Into<Input<F>>>(&mut self, _layouter: impl Layouter<F>, inputs: Vec<I>) -> Result<(), Error> {
+        self.inputs.extend(inputs.into_iter().map(Into::into));
+        Ok(())
+    }
+
+    pub fn squeeze(&self, mut layouter: impl Layouter<F>) -> Result<AssignedCell<F, F>, Error> {
+        let cfg = &self.config;
+        let rc = round_constants::<F>();
+        let capacity = F::from_u128((self.inputs.len() as u128) << 64);
+        let chunks: Vec<&[Input<F>]> = if self.inputs.is_empty() { vec![&[]] } else { self.inputs.chunks(2).collect() };
+        let mut state: Option<[AssignedCell<F, F>; 3]> = None;
+        for (n, chunk) in chunks.iter().enumerate() {
+            state = Some(layouter.assign_region(
+                || format!("poseidon2 permutation {n}"),
+                |mut region| {
+                    let init = [F::from(0), F::from(0), capacity];
+                    let mut cells = cells3([0, 1, 2].map(|i| match &state {
+                        Some(prev) => prev[i].copy_advice(|| "state", &mut region, cfg.adv[i], 0),
+                        None => region.assign_advice_from_constant(|| "initial state", cfg.adv[i], 0, init[i]),
+                    }))?;
+                    let mut xs = Vec::with_capacity(2);
+                    for k in 0..2 {
+                        let x = match chunk.get(k) {
+                            Some(Input::Cell(c)) => c.copy_advice(|| "input", &mut region, cfg.adv[3 + k], 0)?,
+                            Some(Input::Value(v)) => region.assign_advice(|| "input", cfg.adv[3 + k], 0, || *v)?,
+                            None => region.assign_advice_from_constant(|| "padding", cfg.adv[3 + k], 0, F::from(0))?,
+                        };
+                        xs.push(x);
+                    }
+                    let absorbed = cells[0].value().zip(cells[1].value()).zip(cells[2].value()).zip(xs[0].value()).zip(xs[1].value())
+                        .map(|((((s0, s1), s2), x0), x1)| trace([*s0 + x0, *s1 + x1, *s2]));
+
+                    let mut row = 0;
+                    for (r, c) in rc.iter().enumerate() {
+                        if !is_full(r) && !(r - FULL_ROUNDS / 2).is_multiple_of(PARTIAL_PER_ROW) { continue; }
+                        if is_full(r) {
+                            if row == 0 { region.enable_selector(|| "q_first", &cfg.q_first, row)?; } else { region.enable_selector(|| "q_full", &cfg.q_full, row)?; }
+                            for (col, c) in cfg.rc.iter().zip(c) { region.assign_fixed(|| "rc", *col, row, || Value::known(*c))?; }
+                        } else {
+                            region.enable_selector(|| "q_partial", &cfg.q_partial, row)?;
+                            for k in 0..PARTIAL_PER_ROW {
+                                region.assign_fixed(|| "rc", cfg.rc[k], row, || Value::known(rc[r + k][0]))?;
+                            }
+                            let p = (r - FULL_ROUNDS / 2) / PARTIAL_PER_ROW;
+                            for k in 0..3 {
+                                region.assign_advice(|| "partial", cfg.adv[3 + k], row, || absorbed.as_ref().map(|(_, mids)| mids[p][k]))?;
+                            }
+                        }
+                        cells = cells3([0, 1, 2].map(|i| region.assign_advice(|| "state", cfg.adv[i], row + 1, || absorbed.as_ref().map(|(rows, _)| rows[row][i]))))?;
+                        row += 1;
+                    }
+                    Ok(cells)
+                },
+            )?);
+        }
+        Ok(state.unwrap()[0].clone())
+    }
+}
+
+fn cells3<F: CircuitField>(cells: [Result<AssignedCell<F, F>, Error>; 3]) -> Result<[AssignedCell<F, F>; 3], Error> {
+    let [a, b, c] = cells;
+    Ok([a?, b?, c?])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit::{pin_degree, SimpleFloorPlanner, StackedLayouter};
+    use halo2_proofs::{circuit as h2, dev::MockProver, halo2curves::{bn256::Fr, ff::PrimeField}, plonk::{Circuit, Instance}};
+
+    // sponge over `inputs` (first one witnessed as a cell), published at instance row 0
+    #[derive(Clone, Default)]
+    struct Sponge(Vec<Fr>);
+
+    impl Circuit<Fr> for Sponge {
+        type Config = (Poseidon2Config<Fr, 3, 2>, Column<Advice>, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+        type Params = ();
+
+        fn without_witnesses(&self) -> Self { self.clone() }
+
+        fn configure(cs: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let adv = [(); 6].map(|_| cs.advice_column());
+            let constants = cs.fixed_column();
+            cs.enable_constant(constants);
+            let instance = cs.instance_column();
+            cs.enable_equality(instance);
+            let poseidon = Poseidon2Chip::configure(cs, adv[0], adv[1], adv[2], adv[3], adv[4], adv[5]);
+            pin_degree(cs);
+            (poseidon, adv[0], instance)
+        }
+
+        fn synthesize(&self, (poseidon, adv, instance): Self::Config, layouter: impl h2::Layouter<Fr>) -> Result<(), Error> {
+            let mut layouter = StackedLayouter::new(layouter);
+            let mut inputs: Vec<Input<Fr>> = self.0.iter().copied().map(Input::from).collect();
+            if let Some(first) = self.0.first() {
+                let cell = layouter.assign_region(|| "first", |mut region| region.assign_advice(|| "x_0", adv, 0, || Value::known(*first)))?;
+                inputs[0] = cell.into();
+            }
+            let mut hasher = Hash::<Fr, Poseidon2Chip<Fr>, 3, 2>::init(poseidon, layouter.namespace(|| "poseidon2"))?;
+            hasher.update(layouter.namespace(|| "absorb"), inputs)?;
+            let digest = hasher.squeeze(layouter.namespace(|| "squeeze"))?;
+            layouter.constrain_instance(digest.cell(), instance, 0)
+        }
+    }
+
+    fn check(inputs: &[Fr], digest: Fr) -> bool {
+        MockProver::run(9, &Sponge(inputs.to_vec()), vec![vec![digest]]).unwrap().verify().is_ok()
+    }
+
+    fn hex(s: &str) -> Fr {
+        let mut repr = [0u8; 32];
+        for (i, b) in (0..64).step_by(2).rev().enumerate() { repr[i] = u8::from_str_radix(&s[b..b + 2], 16).unwrap(); }
+        Fr::from_repr(repr).unwrap()
+    }
+
+    // reference vectors for BN254, t = 3
+    #[test]
+    fn permutation_matches_reference_vector() {
+        assert_eq!(round_constants::<Fr>()[0][0], hex("1d066a255517b7fd8bddd3a93f7804ef7f8fcde48bb4c37a59a09a1a97052816"));
+        let mut state = [Fr::from(0), Fr::from(1), Fr::from(2)];
+        permute(&mut state);
+        assert_eq!(state, [
+            hex("0bb61d24daca55eebcb1929a82650f328134334da98ea4f847f760054f4a3033"),
+            hex("303b6f7c86d043bfcbcc80214f26a30277a15d3f74ca654992defe7ff8d03570"),
+            hex("1ed25194542b12eef8617361c3ba7c52e660b145994427cc86296242cf766ec8"),
+        ]);
+    }
+
+    #[test]
+    fn length_is_absorbed_into_the_capacity() {
+        assert_ne!(hash::<Fr>(&[]), hash(&[Fr::from(0)]));
+        assert_ne!(hash(&[Fr::from(0)]), hash(&[Fr::from(0), Fr::from(0)]));
+    }
+
+    #[test]
+    fn circuit_matches_native_hash() {
+        for n in [0, 1, 2, 3, 7] {
+            let inputs: Vec<Fr> = (0..n).map(|i| Fr::from(1000 + i)).collect();
+            assert!(check(&inputs, hash(&inputs)), "{n} entradas");
+        }
+    }
+
+    #[test]
+    fn other_digest_is_rejected() {
+        let inputs = [Fr::from(1), Fr::from(2), Fr::from(3)];
+        assert!(!check(&inputs, hash(&inputs[..2])));
+    }
+}
@@ -0,0 +1,78 @@
+// preset.rs
+use crate::{batch::TxBatch, Activation, Shape, TxCircuit, FRAC_BITS};
+use halo2_proofs::halo2curves::bn256::Fr;
+use std::{fmt, str::FromStr};
+
+// Named deployments, so operators pick one word instead of tuning every circuit knob.
+// batch64 proves 64 transactions per proof (see batch.rs); aggregated keeps one proof per
+// transaction and has verify-batch fold all of them into a single pairing check.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Preset { Small, Standard, Batch64, Aggregated }
+
+#[derive(Clone, Copy, Debug)]
+pub struct PresetSpec {
+    pub features: usize,
+    // transactions per proof
+    pub batch_size: usize,
+    // verify-batch checks every proof against one KZG accumulator
+    pub aggregate: bool,
+    pub activation: Activation,
+    pub frac_bits: u32,
+    pub k: u32,
+}
+
+impl Preset {
+    pub const ALL: [Preset; 4] = [Preset::Small, Preset::Standard, Preset::Batch64, Preset::Aggregated];
+
+    pub fn spec(self) -> PresetSpec {
+        let (features, batch_size, aggregate) = match self {
+            Preset::Small => (16, 1, false),
+            Preset::Standard => (64, 1, false),
+            Preset::Batch64 => (64, 64, false),
+            Preset::Aggregated => (64, 1, true),
+        };
+        let shape = Shape { features, activation: Activation::CubicSigmoid, ..Default::default() };
+        let cost = match batch_size {
+            1 => TxCircuit::<Fr>::empty(shape).cost_estimate(),
+            n => TxBatch::<Fr>::empty(shape, n).cost_estimate(),
+        };
+        PresetSpec {
+            features,
+            batch_size,
+            aggregate,
+            activation: Activation::CubicSigmoid,
+            frac_bits: FRAC_BITS,
+            // one doubling of headroom over the cost model
+            k: cost.min_k + 1,
+        }
+    }
+
+    pub fn shape(self) -> Shape {
+        let spec = self.spec();
+        Shape { features: spec.features, activation: spec.activation, ..Default::default() }
+    }
+}
+
+impl FromStr for Preset {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "small" => Ok(Preset::Small),
+            "standard" => Ok(Preset::Standard),
+            "batch64" => Ok(Preset::Batch64),
+            "aggregated" => Ok(Preset::Aggregated),
+            _ => Err(format!("preset desconocido: {s} (small, standard, batch64, aggregated)")),
+        }
+    }
+}
+
+impl fmt::Display for Preset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Preset::Small => "small",
+            Preset::Standard => "standard",
+            Preset::Batch64 => "batch64",
+            Preset::Aggregated => "aggregated",
+        })
+    }
+}
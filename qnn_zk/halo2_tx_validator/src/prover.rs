@@ -0,0 +1,310 @@
+// prover.rs
+use crate::envelope::{ProofEnvelope, Scheme, Transcript};
+use crate::error::{QuantumGuardError as E, Result};
+use crate::public::PublicInputs;
+use crate::witness::Witness;
+use crate::fixed::Fixed16;
+use crate::{commit_in_domain, disclosed_indices, CircuitField, domain_from_tag, Activation, Tag, TxCircuit, NEVER_EXPIRES};
+use halo2_proofs::{
+    halo2curves::bn256::{Bn256, Fr, G1Affine},
+    halo2curves::group::ff::PrimeField,
+    plonk::{create_proof, keygen_pk, keygen_vk, verify_proof, Circuit, ProvingKey, VerifyingKey},
+    poly::commitment::Params,
+    poly::kzg::{
+        commitment::{KZGCommitmentScheme, ParamsKZG},
+        multiopen::{ProverGWC, VerifierGWC},
+        strategy::{AccumulatorStrategy, SingleStrategy},
+    },
+    poly::VerificationStrategy,
+    transcript::{Blake2bRead, Blake2bWrite, Challenge255, EncodedChallenge, TranscriptReadBuffer, TranscriptWriterBuffer},
+};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// KZG (GWC) proving and verification for embedding: what the CLI's prove/verify do once files
+// are read. Keys and params are the caller's; nothing here touches the filesystem.
+
+type Kzg = KZGCommitmentScheme<Bn256>;
+// One proof of an aggregated check: its vk, envelope and instance columns.
+pub type AggregatedProof<'a> = (&'a VerifyingKey<G1Affine>, &'a ProofEnvelope, &'a [&'a [Fr]]);
+
+/// How `prove` instantiates the circuit for a witness.
+#[derive(Clone, Debug, Default)]
+pub struct ProveOptions {
+    pub activation: Activation,
+    // deployment tag, hashed as by `domain_from_tag`
+    pub domain: String,
+    pub transcript: Transcript,
+}
+
+/// A proof and the public inputs it was made for. Serialized with the envelope as hex of its
+/// file encoding; both halves carry their own format version.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Proof {
+    #[serde(with = "envelope_hex")] pub envelope: ProofEnvelope,
+    pub public: PublicInputs,
+}
+
+mod envelope_hex {
+    use crate::envelope::ProofEnvelope;
+    use serde::{de::Error as _, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(envelope: &ProofEnvelope, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&hex::encode(envelope.to_bytes()))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<ProofEnvelope, D::Error> {
+        let bytes = hex::decode(String::deserialize(d)?.trim_start_matches("0x")).map_err(D::Error::custom)?;
+        ProofEnvelope::from_bytes(&bytes).map_err(D::Error::custom)
+    }
+}
+
+/// TxCircuit for a witness with no policy gadgets (no expiry, screening, limits or bindings).
+pub fn base_circuit(wit: &Witness, activation: Activation, domain: &str) -> TxCircuit<Fr> {
+    let q16 = |v: i64| Fixed16::from_raw(v).to_field::<Fr>();
+    TxCircuit {
+        x: wit.x.iter().copied().map(q16).collect(),
+        w: wit.w.iter().copied().map(q16).collect(),
+        b: q16(wit.b),
+        alpha: q16(wit.alpha),
+        q_out: q16(wit.q_out),
+        score_pub: q16(wit.score_pub),
+        now: Fr::from(SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)),
+        valid_until: Fr::from(NEVER_EXPIRES),
+        domain: domain_from_tag(domain),
+        activation,
+        ..Default::default()
+    }
+}
+
+/// keccak256 digest as (hi, lo) 128-bit big-endian halves, matching the circuit's packing.
+pub fn tx_hash_instances<F: PrimeField>(digest: &[u8]) -> Vec<F> {
+    digest.chunks(16).map(|h| F::from_u128(u128::from_be_bytes(h.try_into().unwrap()))).collect()
+}
+
+/// Public inputs of a TxCircuit in column order.
+pub fn tx_instances<F: CircuitField>(circ: &TxCircuit<F>) -> Vec<Vec<F>> {
+    let commit_x = commit_in_domain(Tag::X, circ.domain, &circ.blinding.into_iter().chain(circ.x.iter().copied()).collect::<Vec<_>>());
+    let commit_now = commit_in_domain(Tag::Now, circ.domain, &[circ.now]);
+    // sanctions_root / commit_counterparty stay empty when screening is off
+    let (sanctions_root, commit_cp) = match &circ.sanctions {
+        Some(s) => (vec![s.root()], vec![commit_in_domain(Tag::Counterparty, circ.domain, &[s.address])]),
+        None => (vec![], vec![]),
+    };
+    let (allowlist_root, commit_dest) = match &circ.allowlist {
+        Some(s) => (vec![s.root()], vec![commit_in_domain(Tag::Destination, circ.domain, &[s.address])]),
+        None => (vec![], vec![]),
+    };
+    vec![
+        vec![circ.commit_wb()], vec![circ.commit_q()], vec![circ.score_pub],
+        vec![commit_x], vec![circ.valid_until], vec![commit_now], vec![circ.domain],
+        sanctions_root, commit_cp, allowlist_root, commit_dest,
+        circ.amount_index.map(|_| vec![circ.amount_limit]).unwrap_or_default(),
+        disclosed_indices(circ.disclosed).map(|i| circ.x[i]).collect(),
+        circ.registry.as_ref().map(|r| vec![r.root()]).unwrap_or_default(),
+        circ.registry.as_ref().map(|r| vec![r.low]).unwrap_or_default(),
+        circ.tx.as_ref().map(|tx| tx_hash_instances(&Keccak256::digest(tx))).unwrap_or_default(),
+        circ.oracle.map(|o| vec![o.key.0, o.key.1, o.epoch]).unwrap_or_default(),
+        circ.calibration.as_ref().map(|c| vec![c.commit(circ.domain)]).unwrap_or_default(),
+    ]
+}
+
+/// KZG params from their raw file encoding.
+pub fn read_params(bytes: &[u8]) -> Result<ParamsKZG<Bn256>> {
+    ParamsKZG::<Bn256>::read(&mut &bytes[..]).map_err(|e| E::Params(e.to_string()))
+}
+
+/// Proving key for the layout of `empty` (e.g. `TxCircuit::empty(shape)`).
+pub fn keygen<C: Circuit<Fr>>(params: &ParamsKZG<Bn256>, empty: &C) -> Result<ProvingKey<G1Affine>> {
+    let vk = keygen_vk(params, empty).map_err(|e| E::Keygen(e.to_string()))?;
+    keygen_pk(params, vk, empty).map_err(|e| E::Keygen(e.to_string()))
+}
+
+fn create_with<Ch: EncodedChallenge<G1Affine>, T: TranscriptWriterBuffer<Vec<u8>, G1Affine, Ch>, C: Circuit<Fr>>(
+    params: &ParamsKZG<Bn256>, pk: &ProvingKey<G1Affine>, circ: C, instances: &[&[Fr]], rng: impl RngCore,
+) -> Result<Vec<u8>> {
+    let mut transcript = T::init(vec![]);
+    create_proof::<Kzg, ProverGWC<_>, _, _, _, _>(params, pk, &[circ], &[instances], rng, &mut transcript)
+        .map_err(|e| E::Proving(e.to_string()))?;
+    Ok(transcript.finalize())
+}
+
+fn check_with<'a, Ch: EncodedChallenge<G1Affine>, T: TranscriptReadBuffer<&'a [u8], G1Affine, Ch>>(
+    params: &ParamsKZG<Bn256>, vk: &VerifyingKey<G1Affine>, proof: &'a [u8], instances: &[&[Fr]],
+) -> Result<()> {
+    let mut transcript = T::init(proof);
+    verify_proof::<Kzg, VerifierGWC<_>, _, _, _>(params, vk, SingleStrategy::<Bn256>::new(params), &[instances], &mut transcript)
+        .map_err(|e| E::Verification(e.to_string()))
+}
+
+// Keccak transcript with the EVM verifier's encoding of points and challenges.
+#[cfg(feature = "evm")]
+mod keccak_transcript {
+    use super::*;
+    use snark_verifier::{loader::native::NativeLoader, system::halo2::transcript::evm::EvmTranscript};
+
+    pub fn create<C: Circuit<Fr>>(params: &ParamsKZG<Bn256>, pk: &ProvingKey<G1Affine>, circ: C, instances: &[&[Fr]], rng: impl RngCore) -> Result<Vec<u8>> {
+        create_with::<_, EvmTranscript<G1Affine, NativeLoader, Vec<u8>, Vec<u8>>, _>(params, pk, circ, instances, rng)
+    }
+
+    pub fn check(params: &ParamsKZG<Bn256>, vk: &VerifyingKey<G1Affine>, proof: &[u8], instances: &[&[Fr]]) -> Result<()> {
+        check_with::<_, EvmTranscript<G1Affine, NativeLoader, &[u8], Vec<u8>>>(params, vk, proof, instances)
+    }
+}
+
+#[cfg(not(feature = "evm"))]
+mod keccak_transcript {
+    use super::*;
+    const MSG: &str = "transcript keccak no disponible: compila con --features evm";
+    pub fn create<C: Circuit<Fr>>(_: &ParamsKZG<Bn256>, _: &ProvingKey<G1Affine>, _: C, _: &[&[Fr]], _: impl RngCore) -> Result<Vec<u8>> { Err(E::Unavailable(MSG)) }
+    pub fn check(_: &ParamsKZG<Bn256>, _: &VerifyingKey<G1Affine>, _: &[u8], _: &[&[Fr]]) -> Result<()> { Err(E::Unavailable(MSG)) }
+}
+
+/// Proves any circuit of this crate and wraps the transcript in an envelope.
+pub fn create_kzg_proof<C: Circuit<Fr>>(params: &ParamsKZG<Bn256>, pk: &ProvingKey<G1Affine>, circ: C, instances: &[&[Fr]], rng: impl RngCore, transcript: Transcript) -> Result<ProofEnvelope> {
+    let proof = match transcript {
+        Transcript::Blake2b => create_with::<_, Blake2bWrite<_, _, Challenge255<_>>, _>(params, pk, circ, instances, rng)?,
+        Transcript::Keccak => keccak_transcript::create(params, pk, circ, instances, rng)?,
+    };
+    Ok(ProofEnvelope::new(Scheme::KzgGwc, transcript, params.k(), pk.get_vk().transcript_repr().to_repr(), proof))
+}
+
+/// Checks the envelope against params/vk, then the proof under the transcript it was made with.
+pub fn check_kzg_proof(params: &ParamsKZG<Bn256>, vk: &VerifyingKey<G1Affine>, envelope: &ProofEnvelope, instances: &[&[Fr]]) -> Result<()> {
+    envelope.check(Scheme::KzgGwc, params.k(), &vk.transcript_repr().to_repr()).map_err(E::Verification)?;
+    match envelope.transcript {
+        Transcript::Blake2b => check_with::<_, Blake2bRead<_, _, Challenge255<_>>>(params, vk, &envelope.proof, instances),
+        Transcript::Keccak => keccak_transcript::check(params, vk, &envelope.proof, instances),
+    }
+}
+
+/// Folds every proof into one KZG accumulator, each scaled by a fresh random factor, and checks it
+/// with a single pairing: accepts only if all of them verify. Entries are (vk, envelope, instances)
+/// and may mix shapes and batch sizes; only Blake2b transcripts are accepted.
+pub fn check_kzg_aggregated(params: &ParamsKZG<Bn256>, proofs: &[AggregatedProof]) -> Result<()> {
+    let mut strategy = AccumulatorStrategy::new(params);
+    for (i, (vk, envelope, instances)) in proofs.iter().enumerate() {
+        envelope.check(Scheme::KzgGwc, params.k(), &vk.transcript_repr().to_repr()).map_err(|e| E::Verification(format!("prueba {i}: {e}")))?;
+        if envelope.transcript != Transcript::Blake2b {
+            return Err(E::Verification(format!("prueba {i}: la agregación solo admite el transcript blake2b")));
+        }
+        let mut transcript = Blake2bRead::<_, G1Affine, Challenge255<_>>::init(&envelope.proof[..]);
+        strategy = verify_proof::<Kzg, VerifierGWC<_>, _, _, _>(params, vk, strategy, &[instances], &mut transcript)
+            .map_err(|e| E::Verification(format!("prueba {i}: {e}")))?;
+    }
+    match VerificationStrategy::<Kzg, VerifierGWC<_>>::finalize(strategy) {
+        true => Ok(()),
+        false => Err(E::Verification("la comprobación agregada falla: alguna prueba no es válida".into())),
+    }
+}
+
+/// Proves a witness against a proving key for its shape (see `TxCircuit::empty`).
+pub fn prove(params: &ParamsKZG<Bn256>, pk: &ProvingKey<G1Affine>, witness: &Witness, options: &ProveOptions, rng: impl RngCore) -> Result<Proof> {
+    // checked, unlike base_circuit: an unprovable witness fails here with the reason
+    let circ = TxCircuit::<Fr>::builder()
+        .features(witness.x.clone())
+        .weights(witness.w.clone(), witness.b)
+        .quantum(witness.q_out, witness.alpha)
+        .score_pub(witness.score_pub)
+        .activation(options.activation)
+        .domain(&options.domain)
+        .build()?;
+    let public = PublicInputs::new(&circ, &options.domain, hex::encode(pk.get_vk().transcript_repr().to_repr()));
+    let envelope = create_kzg_proof(params, pk, circ, &public.instance_refs(), rng, options.transcript)?;
+    Ok(Proof { envelope, public })
+}
+
+/// Verifies a proof against the public inputs it carries.
+pub fn verify(params: &ParamsKZG<Bn256>, vk: &VerifyingKey<G1Affine>, proof: &Proof) -> Result<()> {
+    check_kzg_proof(params, vk, &proof.envelope, &proof.public.instance_refs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ONE_Q16;
+    use halo2_proofs::halo2curves::ff::Field;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    const ONE: i64 = ONE_Q16 as i64;
+    // compressed G1 point
+    const POINT_BYTES: usize = 32;
+
+    fn hidden(x: Vec<i64>, w: Vec<i64>, b: i64, salt: u64) -> TxCircuit<Fr> {
+        let mut circ = TxCircuit::<Fr>::builder()
+            .features(x).weights(w, b).quantum(ONE / 2, ONE / 2)
+            .domain("test").validity(0, NEVER_EXPIRES)
+            .build().unwrap();
+        circ.blinding = Some(Fr::random(ChaCha20Rng::seed_from_u64(salt)));
+        circ
+    }
+
+    // two hidden witnesses with the same score: x and w differ, and so does b
+    fn pair() -> (TxCircuit<Fr>, TxCircuit<Fr>) {
+        let a = hidden(vec![ONE / 2, ONE / 4, -ONE], vec![ONE / 2, ONE, ONE / 8], 0, 1);
+        let b = hidden(vec![ONE, -ONE / 2, ONE / 4], vec![ONE / 8, -ONE / 2, ONE / 2], -ONE / 8, 2);
+        assert_eq!(a.score_pub, b.score_pub);
+        (a, b)
+    }
+
+    fn setup(circ: &TxCircuit<Fr>) -> (ParamsKZG<Bn256>, ProvingKey<G1Affine>) {
+        let params = ParamsKZG::<Bn256>::setup(circ.cost_estimate().min_k, ChaCha20Rng::seed_from_u64(0));
+        let pk = keygen(&params, &TxCircuit::empty(circ.shape())).unwrap();
+        (params, pk)
+    }
+
+    fn proof(params: &ParamsKZG<Bn256>, pk: &ProvingKey<G1Affine>, circ: &TxCircuit<Fr>, seed: u64) -> Vec<u8> {
+        let instances = tx_instances(circ);
+        let refs: Vec<&[Fr]> = instances.iter().map(Vec::as_slice).collect();
+        let envelope = create_kzg_proof(params, pk, circ.clone(), &refs, ChaCha20Rng::seed_from_u64(seed), Transcript::Blake2b).unwrap();
+        check_kzg_proof(params, pk.get_vk(), &envelope, &refs).unwrap();
+        envelope.proof
+    }
+
+    #[test]
+    fn same_score_witnesses_differ_only_in_their_commitments() {
+        let (a, b) = pair();
+        assert_eq!(a.shape(), b.shape());
+        let (ia, ib) = (tx_instances(&a), tx_instances(&b));
+        for (col, (va, vb)) in ia.iter().zip(&ib).enumerate() {
+            // commit_wb and the salted commit_x
+            if col == 0 || col == 3 { assert_ne!(va, vb) } else { assert_eq!(va, vb, "instance {col}") }
+        }
+
+        let (params, pk) = setup(&a);
+        assert_eq!(proof(&params, &pk, &a, 1).len(), proof(&params, &pk, &b, 1).len());
+    }
+
+    // halo2 fills the last rows of every advice column with randomness; if a column were left
+    // unblinded its commitment would be a function of the witness alone and repeat across proofs
+    #[test]
+    fn every_advice_commitment_is_rerandomized() {
+        let (a, _) = pair();
+        let (params, pk) = setup(&a);
+        let advice = pk.get_vk().cs().num_advice_columns();
+        let (p1, p2) = (proof(&params, &pk, &a, 1), proof(&params, &pk, &a, 2));
+        for (col, (c1, c2)) in p1.chunks(POINT_BYTES).zip(p2.chunks(POINT_BYTES)).take(advice).enumerate() {
+            assert_ne!(c1, c2, "advice column {col}");
+        }
+    }
+
+    #[test]
+    fn aggregated_check_needs_every_proof() {
+        let (a, b) = pair();
+        let (params, pk) = setup(&a);
+        let (ia, ib) = (tx_instances(&a), tx_instances(&b));
+        let (ra, rb): (Vec<&[Fr]>, Vec<&[Fr]>) = (ia.iter().map(Vec::as_slice).collect(), ib.iter().map(Vec::as_slice).collect());
+        let prove = |circ: &TxCircuit<Fr>, refs: &[&[Fr]]| create_kzg_proof(&params, &pk, circ.clone(), refs, ChaCha20Rng::seed_from_u64(1), Transcript::Blake2b).unwrap();
+        let (pa, pb) = (prove(&a, &ra), prove(&b, &rb));
+        let vk = pk.get_vk();
+        check_kzg_aggregated(&params, &[(vk, &pa, &ra), (vk, &pb, &rb)]).unwrap();
+        // each proof alone would fail its own pairing; folded in, the single check fails too
+        assert!(check_kzg_aggregated(&params, &[(vk, &pa, &ra), (vk, &pb, &ra)]).is_err());
+        let mut tampered = pb.clone();
+        tampered.proof[POINT_BYTES] ^= 1;
+        assert!(check_kzg_aggregated(&params, &[(vk, &pa, &ra), (vk, &tampered, &rb)]).is_err());
+    }
+}
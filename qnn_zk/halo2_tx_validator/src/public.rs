@@ -0,0 +1,239 @@
+// public.rs
+use crate::error::{QuantumGuardError, Result};
+use crate::{fe_to_hex, Shape, TxCircuit};
+use crate::FieldExt;
+use halo2_proofs::halo2curves::bn256::Fr;
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
+
+// Public file of a TxCircuit proof (`<stem>.public.json`). `instances` is what the verifier
+// checks, column by column in the circuit's order (see prover::tx_instances); the named fields
+// repeat its first entries in hex for readers, plus what the proof was made against.
+// Version 1 is also what files written before the field existed are read as. Version 2 writes
+// hex big-endian (fe_to_hex); version 1 files, written little-endian, are converted when read.
+pub const PUBLIC_VERSION: u32 = 2;
+
+/// Instances as hex field elements: written with `fe_to_hex`, read in any form numeric.rs accepts.
+pub mod hex_instances {
+    use crate::numeric::FlexFe;
+    use halo2_proofs::halo2curves::bn256::Fr;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(instances: &[Vec<Fr>], s: S) -> Result<S::Ok, S::Error> {
+        let hex: Vec<Vec<String>> = instances.iter().map(|col| col.iter().map(crate::fe_to_hex).collect()).collect();
+        hex.serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<Vec<Fr>>, D::Error> {
+        let cols = Vec::<Vec<FlexFe<Fr>>>::deserialize(d)?;
+        Ok(cols.into_iter().map(|col| col.into_iter().map(|v| v.0).collect()).collect())
+    }
+}
+
+fn v1() -> u32 { 1 }
+
+fn never_expires() -> u64 { crate::NEVER_EXPIRES }
+
+fn supported_version<'de, D: Deserializer<'de>>(d: D) -> std::result::Result<u32, D::Error> {
+    let v = u32::deserialize(d)?;
+    if v == 0 || v > PUBLIC_VERSION {
+        return Err(D::Error::custom(format!("versión de archivo público {v} no soportada (hasta {PUBLIC_VERSION})")));
+    }
+    Ok(v)
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(remote = "Self")]
+pub struct PublicInputs {
+    #[serde(default = "v1", deserialize_with = "supported_version")] pub version: u32,
+    pub commit_wb: String,
+    pub commit_q: String,
+    pub score_pub: String,
+    #[serde(default)] pub commit_x: String,
+    #[serde(default)] pub commit_now: String,
+    #[serde(default = "never_expires")] pub valid_until: u64,
+    // deployment tag, as given to domain_from_tag
+    #[serde(default)] pub domain: String,
+    pub shape: Shape,
+    // hex of the vk's transcript representation; absent in files from older releases
+    #[serde(default, skip_serializing_if = "String::is_empty")] pub vk_fingerprint: String,
+    // present when the witness was given in floats (values_f64)
+    #[serde(default, skip_serializing_if = "Option::is_none")] pub quantization: Option<Quantization>,
+    #[serde(with = "hex_instances")] pub instances: Vec<Vec<Fr>>,
+    #[serde(default)] pub audit: Option<Audit>,
+}
+
+impl Serialize for PublicInputs {
+    fn serialize<S: serde::Serializer>(&self, s: S) -> std::result::Result<S::Ok, S::Error> { PublicInputs::serialize(self, s) }
+}
+
+impl<'de> Deserialize<'de> for PublicInputs {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> std::result::Result<Self, D::Error> {
+        let mut raw = serde_json::Value::deserialize(d)?;
+        if raw.is_object() && raw.get("version").map_or(Some(1), serde_json::Value::as_u64) == Some(1) {
+            from_v1(&mut raw);
+        }
+        PublicInputs::deserialize(raw).map_err(D::Error::custom)
+    }
+}
+
+// Version 1 wrote every field element as "0x" + its 32-byte little-endian repr; rewrites them as
+// the big-endian hex numeric.rs reads. Anything else (integers, empty strings) is left as is.
+fn from_v1(raw: &mut serde_json::Value) {
+    use serde_json::Value;
+    let le_to_be = |v: &mut Value| {
+        let Some(h) = v.as_str().and_then(|s| s.strip_prefix("0x")).filter(|h| h.len() == 64) else { return };
+        if let Ok(mut bytes) = hex::decode(h) {
+            bytes.reverse();
+            *v = Value::String(format!("0x{}", hex::encode(bytes)));
+        }
+    };
+    for name in ["commit_wb", "commit_q", "score_pub", "commit_x", "commit_now"] {
+        raw.get_mut(name).map(le_to_be);
+    }
+    let columns = raw.get_mut("instances").and_then(Value::as_array_mut).into_iter().flatten();
+    columns.filter_map(Value::as_array_mut).flatten().for_each(le_to_be);
+    raw["version"] = Value::from(PUBLIC_VERSION);
+}
+
+// How a float witness was turned into the proven one.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Quantization {
+    pub frac_bits: u32,
+    pub rounding: String,
+    // worst |v - q/2^frac_bits| per group (x, w, escalares), in real units
+    pub max_error: BTreeMap<String, f64>,
+}
+
+// Digests of everything the proof was produced against, so a verifier can pin the full configuration.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Audit {
+    pub params_sha256: String,
+    pub vk_digest: String,
+    pub model_sha256: String,
+    pub policy_version: Option<String>,
+    pub feature_schema: Option<String>,
+}
+
+impl PublicInputs {
+    /// Public inputs of `circ` under the deployment tag it was built with.
+    pub fn new(circ: &TxCircuit<Fr>, domain: &str, vk_fingerprint: String) -> Self {
+        let instances = crate::prover::tx_instances(circ);
+        let first = |col: usize| instances[col].first().map(fe_to_hex).unwrap_or_default();
+        Self {
+            version: PUBLIC_VERSION,
+            commit_wb: first(0),
+            commit_q: first(1),
+            score_pub: first(2),
+            commit_x: first(3),
+            commit_now: first(5),
+            valid_until: circ.valid_until.get_lower_128() as u64,
+            domain: domain.to_string(),
+            shape: circ.shape(),
+            vk_fingerprint,
+            quantization: None,
+            audit: None,
+            instances,
+        }
+    }
+
+    /// Column slices as `verify_proof` takes them.
+    pub fn instance_refs(&self) -> Vec<&[Fr]> { self.instances.iter().map(|v| &v[..]).collect() }
+
+    /// First value of an instance column, if the column is in use.
+    pub fn instance(&self, column: usize) -> Option<Fr> { self.instances.get(column).and_then(|v| v.first()).copied() }
+
+    pub fn from_json(bytes: &[u8]) -> Result<Self> {
+        serde_json::from_slice(bytes).map_err(|e| QuantumGuardError::Verification(format!("archivo público inválido: {e}")))
+    }
+
+    pub fn to_json(&self) -> Vec<u8> { serde_json::to_vec_pretty(self).expect("serialización en memoria") }
+}
+
+// Public file of a TxBatch proof: the public inputs of each transaction it covers, in circuit
+// order. Padding (the last transaction repeated up to batch_size) is not listed.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BatchPublicInputs {
+    pub batch_size: usize,
+    pub items: Vec<PublicInputs>,
+}
+
+impl BatchPublicInputs {
+    /// The instance columns of the whole batch, padding included (see `TxBatch::instances`).
+    pub fn instances(&self) -> Result<Vec<Vec<Fr>>> {
+        let last = self.items.last().ok_or_else(|| QuantumGuardError::Verification("lote sin transacciones".into()))?;
+        if self.items.len() > self.batch_size {
+            return Err(QuantumGuardError::Verification(format!("{} transacciones en un lote de {}", self.items.len(), self.batch_size)));
+        }
+        let padding = std::iter::repeat_n(last, self.batch_size - self.items.len());
+        let mut columns = vec![vec![]; last.instances.len()];
+        for item in self.items.iter().chain(padding) {
+            if item.instances.len() != columns.len() || item.shape != last.shape {
+                return Err(QuantumGuardError::Verification("las transacciones del lote tienen otra forma".into()));
+            }
+            for (column, values) in columns.iter_mut().zip(&item.instances) { column.extend(values); }
+        }
+        Ok(columns)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{NEVER_EXPIRES, ONE_Q16};
+    use halo2_proofs::halo2curves::ff::PrimeField;
+
+    const ONE: i64 = ONE_Q16 as i64;
+
+    fn public() -> PublicInputs {
+        let circ = TxCircuit::<Fr>::builder()
+            .features(vec![ONE / 2, ONE / 4]).weights(vec![ONE, -ONE / 2], ONE / 8).quantum(ONE / 2, ONE / 2)
+            .domain("test").validity(0, NEVER_EXPIRES)
+            .build().unwrap();
+        PublicInputs::new(&circ, "test", "ab".repeat(32))
+    }
+
+    #[test]
+    fn json_round_trips() {
+        let public = public();
+        let read = PublicInputs::from_json(&public.to_json()).unwrap();
+        assert_eq!(read.version, PUBLIC_VERSION);
+        assert_eq!(read.instances, public.instances);
+        assert_eq!((read.commit_wb, read.commit_x), (public.commit_wb, public.commit_x));
+    }
+
+    // a version-1 file carries fe_to_hex's former little-endian hex
+    #[test]
+    fn version_1_hex_is_read_little_endian() {
+        let public = public();
+        let le = |f: &Fr| format!("0x{}", hex::encode(f.to_repr()));
+        let mut raw: serde_json::Value = serde_json::from_slice(&public.to_json()).unwrap();
+        raw["version"] = 1.into();
+        raw["commit_wb"] = le(&public.instances[0][0]).into();
+        raw["instances"] = public.instances.iter().map(|col| col.iter().map(le).collect::<Vec<_>>()).collect::<Vec<_>>().into();
+        let read = PublicInputs::from_json(&serde_json::to_vec(&raw).unwrap()).unwrap();
+        assert_eq!(read.instances, public.instances);
+        assert_eq!(read.commit_wb, public.commit_wb);
+        assert_eq!(read.version, PUBLIC_VERSION);
+    }
+
+    #[test]
+    fn batch_json_round_trips_and_pads() {
+        let batch = BatchPublicInputs { batch_size: 3, items: vec![public(), public()] };
+        let read: BatchPublicInputs = serde_json::from_slice(&serde_json::to_vec(&batch).unwrap()).unwrap();
+        assert_eq!(read.batch_size, 3);
+        let columns = read.instances().unwrap();
+        // every column holds three transactions, the last one repeated
+        assert_eq!(columns[2], vec![batch.items[1].instances[2][0]; 3]);
+
+        assert!(BatchPublicInputs { batch_size: 1, ..read }.instances().is_err());
+        assert!(BatchPublicInputs { batch_size: 3, items: vec![] }.instances().is_err());
+    }
+
+    #[test]
+    fn rejects_newer_versions() {
+        let mut raw: serde_json::Value = serde_json::from_slice(&public().to_json()).unwrap();
+        raw["version"] = (PUBLIC_VERSION + 1).into();
+        assert!(PublicInputs::from_json(&serde_json::to_vec(&raw).unwrap()).is_err());
+    }
+}
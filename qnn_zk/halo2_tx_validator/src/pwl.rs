@@ -0,0 +1,173 @@
+// pwl.rs
+use crate::decompose::{range_check_rows, DecomposeConfig};
+use crate::FRAC_BITS as FRAC;
+use crate::circuit::{AssignedCell, Layouter, Value};
+use crate::FieldExt;
+use halo2_proofs::{
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Selector, TableColumn},
+    poly::Rotation,
+};
+
+// Piecewise-linear sigmoid: 16 unit-width segments over [-8, 8), saturating outside.
+// z + 128 = seg + t (Q16.16, t < 1), so seg indexes a 256-row table of (slope, base) and
+// y = base[seg] + floor(slope[seg] * t / 2^16). Inputs outside [-128, 128) are unprovable.
+// The table carries a tag column, 1 on every segment row plus one all-zero row: rows with q_pwl
+// off look up (0, 0, 0, 0), which no enabled row can match.
+pub const PWL_SEGMENTS: i64 = 16;
+const SEG_BITS: usize = 8;
+const SEG_OFFSET: i64 = 1 << (SEG_BITS - 1);
+
+/// Rows of the loaded table: the segments and the all-zero row.
+pub const PWL_TABLE_ROWS: usize = (1 << SEG_BITS) + 1;
+
+fn sigmoid_q16(x: f64) -> u64 { (1.0 / (1.0 + (-x).exp()) * (1u64 << FRAC) as f64).round() as u64 }
+
+/// (seg, slope, base) rows for every 8-bit segment index.
+pub fn pwl_table() -> Vec<(u64, u64, u64)> {
+    let half = PWL_SEGMENTS / 2;
+    (0..(1i64 << SEG_BITS)).map(|seg| {
+        let left = seg - SEG_OFFSET;
+        let (slope, base) = if left < -half {
+            (0, sigmoid_q16(-half as f64))
+        } else if left >= half {
+            (0, sigmoid_q16(half as f64))
+        } else {
+            (sigmoid_q16((left + 1) as f64) - sigmoid_q16(left as f64), sigmoid_q16(left as f64))
+        };
+        (seg as u64, slope, base)
+    }).collect()
+}
+
+/// Native evaluation with exactly the circuit's rounding. `z` is signed Q16.16.
+pub fn sigmoid_pwl_q16(z: i64) -> i64 {
+    let shifted = z + (SEG_OFFSET << FRAC);
+    assert!((0..(1i64 << (SEG_BITS as u32 + FRAC))).contains(&shifted), "z fuera del dominio PWL");
+    let (seg, t) = (shifted >> FRAC, shifted & ((1 << FRAC) - 1));
+    let (_, slope, base) = pwl_table()[seg as usize];
+    base as i64 + ((slope as i64 * t) >> FRAC)
+}
+
+#[derive(Clone, Debug)]
+pub struct PwlConfig {
+    adv: [Column<Advice>; 5],
+    table: [TableColumn; 4],
+    q_pwl: Selector,
+    decompose: DecomposeConfig,
+}
+
+/// Rows used by one activation (segment/remainder range checks included).
+pub fn pwl_rows(word_bits: usize) -> usize {
+    2 + 2 * range_check_rows(FRAC as usize, word_bits) + range_check_rows(SEG_BITS, word_bits)
+}
+
+impl PwlConfig {
+    pub fn configure<F: FieldExt>(cs: &mut ConstraintSystem<F>, adv: [Column<Advice>; 5], decompose: DecomposeConfig) -> Self {
+        for c in adv { cs.enable_equality(c); }
+        let table = [0, 1, 2, 3].map(|_| cs.lookup_table_column());
+        let q_pwl = cs.complex_selector();
+        let [z, seg, t, slope, base] = adv;
+
+        // row 0: z, seg, t, slope, base    row 1: y, rem
+        cs.create_gate("pwl sigmoid", |meta| {
+            let q = meta.query_selector(q_pwl);
+            let z = meta.query_advice(z, Rotation::cur());
+            let seg_c = meta.query_advice(seg, Rotation::cur());
+            let t = meta.query_advice(t, Rotation::cur());
+            let slope_c = meta.query_advice(slope, Rotation::cur());
+            let base_c = meta.query_advice(base, Rotation::cur());
+            let y = meta.query_advice(adv[0], Rotation::next());
+            let rem = meta.query_advice(adv[1], Rotation::next());
+            let one = Expression::Constant(F::from(1u64 << FRAC));
+            let offset = Expression::Constant(F::from((SEG_OFFSET as u64) << FRAC));
+            vec![
+                q.clone() * (z + offset - seg_c * one.clone() - t.clone()),
+                q * (y * one.clone() - base_c * one - slope_c * t + rem),
+            ]
+        });
+        cs.lookup("pwl segment", |meta| {
+            let q = meta.query_selector(q_pwl);
+            let seg = meta.query_advice(seg, Rotation::cur());
+            let slope = meta.query_advice(slope, Rotation::cur());
+            let base = meta.query_advice(base, Rotation::cur());
+            vec![(q.clone(), table[0]), (q.clone() * seg, table[1]), (q.clone() * slope, table[2]), (q * base, table[3])]
+        });
+
+        Self { adv, table, q_pwl, decompose }
+    }
+
+    pub fn load_table<F: FieldExt>(&self, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "pwl table",
+            |mut t| {
+                let rows = std::iter::once((0, (0, 0, 0))).chain(pwl_table().into_iter().map(|r| (1, r)));
+                for (i, (tag, (seg, slope, base))) in rows.enumerate() {
+                    t.assign_cell(|| "tag", self.table[0], i, || Value::known(F::from(tag)))?;
+                    t.assign_cell(|| "seg", self.table[1], i, || Value::known(F::from(seg)))?;
+                    t.assign_cell(|| "slope", self.table[2], i, || Value::known(F::from(slope)))?;
+                    t.assign_cell(|| "base", self.table[3], i, || Value::known(F::from(base)))?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// Returns sigmoid(z) in Q16.16 for a signed Q16.16 pre-activation `z`.
+    pub fn assign<F: FieldExt>(&self, mut layouter: impl Layouter<F>, z: &AssignedCell<F, F>) -> Result<AssignedCell<F, F>, Error> {
+        let table = pwl_table();
+        let (seg, t, rem, y) = layouter.assign_region(
+            || "pwl sigmoid",
+            |mut region| {
+                region.enable_selector(|| "q_pwl", &self.q_pwl, 0)?;
+                z.copy_advice(|| "z", &mut region, self.adv[0], 0)?;
+                let shifted = z.value().map(|z| (*z + F::from((SEG_OFFSET as u64) << FRAC)).get_lower_128() as u64);
+                let seg_v = shifted.map(|s| (s >> FRAC).min((1 << SEG_BITS) - 1));
+                let t_v = shifted.map(|s| s & ((1 << FRAC) - 1));
+                let row = seg_v.map(|s| table[s as usize]);
+                let seg = region.assign_advice(|| "seg", self.adv[1], 0, || seg_v.map(F::from))?;
+                let t = region.assign_advice(|| "t", self.adv[2], 0, || t_v.map(F::from))?;
+                region.assign_advice(|| "slope", self.adv[3], 0, || row.map(|r| F::from(r.1)))?;
+                region.assign_advice(|| "base", self.adv[4], 0, || row.map(|r| F::from(r.2)))?;
+                let prod = row.zip(t_v).map(|(r, t)| r.1 * t);
+                let y = region.assign_advice(|| "y", self.adv[0], 1, || row.zip(prod).map(|(r, p)| F::from(r.2 + (p >> FRAC))))?;
+                let rem = region.assign_advice(|| "rem", self.adv[1], 1, || prod.map(|p| F::from(p & ((1 << FRAC) - 1))))?;
+                Ok((seg, t, rem, y))
+            },
+        )?;
+        self.decompose.range_check(layouter.namespace(|| "seg bits"), &seg, SEG_BITS)?;
+        self.decompose.range_check(layouter.namespace(|| "t bits"), &t, FRAC as usize)?;
+        self.decompose.range_check(layouter.namespace(|| "rem bits"), &rem, FRAC as usize)?;
+        Ok(y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{prover::tx_instances, Activation, TxCircuit, NEVER_EXPIRES, ONE_Q16};
+    use halo2_proofs::{dev::MockProver, halo2curves::bn256::Fr};
+
+    fn mock(activation: Activation) -> MockProver<Fr> {
+        let half = ONE_Q16 as i64 / 2;
+        let circ: TxCircuit<Fr> = TxCircuit::<Fr>::builder()
+            .features(vec![half, half / 2]).weights(vec![half, -half / 2], 0).quantum(half, half)
+            .activation(activation).domain("test").validity(0, NEVER_EXPIRES)
+            .build().unwrap();
+        MockProver::run(circ.cost_estimate().min_k, &circ, tx_instances(&circ)).unwrap()
+    }
+
+    #[test]
+    fn table_matches_native() {
+        for z in [-(128 << FRAC), -(8 << FRAC), -12345, 0, 1, 54321, 8 << FRAC, (128 << FRAC) - 1] {
+            let y = sigmoid_pwl_q16(z);
+            assert!((0..=1 << FRAC).contains(&y), "sigmoid({z}) = {y}");
+        }
+        assert_eq!(sigmoid_pwl_q16(0), sigmoid_q16(0.0) as i64);
+    }
+
+    #[test]
+    fn pwl_circuit_is_satisfied() { assert_eq!(mock(Activation::PwlSigmoid).verify(), Ok(())); }
+
+    // the pwl lookup is on every row of the circuit, with q_pwl off
+    #[test]
+    fn cubic_circuit_is_satisfied() { assert_eq!(mock(Activation::CubicSigmoid).verify(), Ok(())); }
+}
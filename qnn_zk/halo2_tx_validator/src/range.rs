@@ -0,0 +1,68 @@
+// range.rs
+use crate::decompose::{self, DecomposeConfig};
+use crate::circuit::{AssignedCell, Layouter, Value};
+use crate::FieldExt;
+use halo2_proofs::{
+    plonk::{Advice, Column, ConstraintSystem, Error, Fixed, Selector},
+    poly::Rotation,
+};
+
+// 256-row lookup table: a 17-bit check costs 3 word rows plus a short-word row.
+pub const RANGE_WORD_BITS: usize = 8;
+
+// Range checks go through the lookup-backed decomposition chip; bounds add a linear gate on top.
+#[derive(Clone, Debug)]
+pub struct RangeConfig {
+    pub decompose: DecomposeConfig,
+    bit: Column<Advice>,
+    acc: Column<Advice>,
+    bound: Column<Fixed>,
+    q_bound: Selector,
+}
+
+/// Rows used by `assign` for a `bits`-wide check.
+pub fn check_rows(bits: usize) -> usize { decompose::range_check_rows(bits, RANGE_WORD_BITS) }
+
+/// Rows used by `assign_bounded` for a `bits`-wide check.
+pub fn bounded_rows(bits: usize) -> usize { 1 + 2 * check_rows(bits) }
+
+impl RangeConfig {
+    pub fn configure<F: FieldExt>(cs: &mut ConstraintSystem<F>, bit: Column<Advice>, acc: Column<Advice>, constants: Column<Fixed>) -> Self {
+        let decompose = DecomposeConfig::configure(cs, acc, bit, constants, RANGE_WORD_BITS);
+        let bound = cs.fixed_column();
+        let q_bound = cs.selector();
+
+        // value + slack = bound, with value and slack both range-checked afterwards
+        cs.create_gate("range bound", |meta| {
+            let q = meta.query_selector(q_bound);
+            let v = meta.query_advice(bit, Rotation::cur());
+            let d = meta.query_advice(acc, Rotation::cur());
+            let max = meta.query_fixed(bound, Rotation::cur());
+            vec![q * (v + d - max)]
+        });
+
+        Self { decompose, bit, acc, bound, q_bound }
+    }
+
+    pub fn load<F: FieldExt>(&self, layouter: impl Layouter<F>) -> Result<(), Error> { self.decompose.load_table(layouter) }
+
+    /// Constrains `value < 2^bits`.
+    pub fn assign<F: FieldExt>(&self, layouter: impl Layouter<F>, value: &AssignedCell<F, F>, bits: usize) -> Result<(), Error> {
+        self.decompose.range_check(layouter, value, bits)
+    }
+
+    /// Constrains `0 <= value <= max`, where `max < 2^bits`.
+    pub fn assign_bounded<F: FieldExt>(&self, mut layouter: impl Layouter<F>, value: &AssignedCell<F, F>, max: u64, bits: usize) -> Result<(), Error> {
+        let slack = layouter.assign_region(
+            || "range bound",
+            |mut region| {
+                region.enable_selector(|| "q_bound", &self.q_bound, 0)?;
+                region.assign_fixed(|| "max", self.bound, 0, || Value::known(F::from(max)))?;
+                value.copy_advice(|| "value", &mut region, self.bit, 0)?;
+                region.assign_advice(|| "slack", self.acc, 0, || value.value().map(|v| F::from(max) - v))
+            },
+        )?;
+        self.assign(layouter.namespace(|| "value bits"), value, bits)?;
+        self.assign(layouter.namespace(|| "slack bits"), &slack, bits)
+    }
+}
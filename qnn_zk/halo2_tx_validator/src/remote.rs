@@ -0,0 +1,81 @@
+// remote.rs
+use sha2::{Digest, Sha256};
+use std::{fs, io::Read, path::PathBuf};
+
+// File arguments given as URIs: https://, s3://bucket/key or gs://bucket/object, optionally pinned
+// with a `#sha256=<hex>` fragment that the download must match.
+// s3:// goes to $AWS_ENDPOINT_URL (path style) or the bucket's virtual host, unsigned: public
+// buckets, or a bucket policy that admits the prover's network. gs:// goes to
+// storage.googleapis.com, with $GOOGLE_OAUTH_ACCESS_TOKEN as bearer token when set.
+// With $QUANTUM_GUARD_CACHE, pinned downloads are kept there by digest and not fetched again.
+const SCHEMES: [&str; 3] = ["https://", "s3://", "gs://"];
+
+pub fn is_remote(path: &str) -> bool { SCHEMES.iter().any(|s| path.starts_with(s)) }
+
+/// Splits `uri#sha256=<hex>` into the URI and the expected digest.
+fn split_pin(uri: &str) -> Result<(&str, Option<String>), String> {
+    match uri.split_once('#') {
+        None => Ok((uri, None)),
+        Some((uri, pin)) => {
+            let hex = pin.strip_prefix("sha256=").ok_or_else(|| format!("fragmento desconocido: #{pin} (se espera #sha256=<hex>)"))?;
+            if hex.len() != 64 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return Err(format!("sha256 inválido: {hex}"));
+            }
+            Ok((uri, Some(hex.to_ascii_lowercase())))
+        }
+    }
+}
+
+fn https_url(uri: &str) -> Result<String, String> {
+    if uri.starts_with("https://") {
+        return Ok(uri.to_string());
+    }
+    let (scheme, rest) = uri.split_once("://").ok_or_else(|| format!("URI inválida: {uri}"))?;
+    let (bucket, key) = rest.split_once('/').filter(|(b, k)| !b.is_empty() && !k.is_empty()).ok_or_else(|| format!("se espera {scheme}://bucket/objeto: {uri}"))?;
+    match scheme {
+        "s3" => Ok(match std::env::var("AWS_ENDPOINT_URL") {
+            Ok(endpoint) => format!("{}/{bucket}/{key}", endpoint.trim_end_matches('/')),
+            Err(_) => format!("https://{bucket}.s3.amazonaws.com/{key}"),
+        }),
+        "gs" => Ok(format!("https://storage.googleapis.com/{bucket}/{key}")),
+        _ => Err(format!("esquema no soportado: {scheme}://")),
+    }
+}
+
+fn cache_path(sha256: &str) -> Option<PathBuf> {
+    std::env::var_os("QUANTUM_GUARD_CACHE").map(|dir| PathBuf::from(dir).join(sha256))
+}
+
+/// Downloads `uri` (see above), checking the `#sha256=` pin when there is one.
+pub fn fetch(uri: &str) -> Result<Vec<u8>, String> {
+    let (uri, pin) = split_pin(uri)?;
+    if let Some(cached) = pin.as_deref().and_then(cache_path) {
+        if let Ok(bytes) = fs::read(&cached) {
+            if hex::encode(Sha256::digest(&bytes)) == pin.as_deref().unwrap() {
+                return Ok(bytes);
+            }
+        }
+    }
+    let url = https_url(uri)?;
+    let mut request = ureq::get(&url);
+    if uri.starts_with("gs://") {
+        if let Ok(token) = std::env::var("GOOGLE_OAUTH_ACCESS_TOKEN") {
+            request = request.set("Authorization", &format!("Bearer {token}"));
+        }
+    }
+    let response = request.call().map_err(|e| format!("descarga fallida ({uri}): {e}"))?;
+    let mut bytes = vec![];
+    response.into_reader().read_to_end(&mut bytes).map_err(|e| format!("descarga fallida ({uri}): {e}"))?;
+    if let Some(expected) = pin {
+        let digest = hex::encode(Sha256::digest(&bytes));
+        if digest != expected {
+            return Err(format!("sha256 de {uri} no coincide: {digest} != {expected}"));
+        }
+        // best effort: a read-only cache only costs the next download
+        if let Some(cached) = cache_path(&expected) {
+            let _ = cached.parent().map(fs::create_dir_all);
+            let _ = fs::write(cached, &bytes);
+        }
+    }
+    Ok(bytes)
+}
@@ -0,0 +1,312 @@
+// rlp.rs
+use crate::compare::{self, CompareConfig};
+use crate::decompose::{range_check_rows, DecomposeConfig};
+use crate::circuit::{AssignedCell, Layouter, Value};
+use crate::FieldExt;
+use halo2_proofs::{
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Selector},
+    poly::Rotation,
+};
+use serde::{Deserialize, Serialize};
+
+// Legacy (pre-EIP-2718) transaction: rlp([nonce, gasPrice, gasLimit, to, value, data, v, r, s]).
+// Only the first five items are read. Item j starts at o_j (o_0 = list header length,
+// o_{j+1} = o_j + 1 + len_j) and every byte position i in the window is classified with
+// the comparison chip: i == o_j picks the header byte, o_j < i < o_{j+1} the payload, which is
+// folded big-endian. Items are short strings of at most 31 bytes; `to` must be 20 bytes.
+pub const ITEMS: usize = 5;
+const MAX_ITEM_BYTES: u64 = 31;
+// 3-byte list header + 5 items of up to 32 bytes
+pub const WINDOW: usize = 3 + ITEMS * 32;
+const POS_BITS: usize = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Item { Nonce = 0, GasPrice = 1, GasLimit = 2, To = 3, Value = 4 }
+
+// Which feature receives each decoded field (None = not bound).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RlpFeatures {
+    pub nonce: Option<usize>,
+    pub gas_price: Option<usize>,
+    pub to: Option<usize>,
+    pub value: Option<usize>,
+}
+
+impl RlpFeatures {
+    pub fn bindings(&self) -> Vec<(Item, usize)> {
+        [(Item::Nonce, self.nonce), (Item::GasPrice, self.gas_price), (Item::To, self.to), (Item::Value, self.value)]
+            .into_iter()
+            .filter_map(|(item, f)| f.map(|f| (item, f)))
+            .collect()
+    }
+}
+
+/// Payloads of the first five items of a legacy transaction.
+pub fn parse_legacy(tx: &[u8]) -> Result<Vec<Vec<u8>>, String> {
+    let mut pos = match tx.first() {
+        Some(0xf8) => 2,
+        Some(0xf9) => 3,
+        _ => return Err("no es una transacción legacy (cabecera de lista 0xf8/0xf9)".into()),
+    };
+    let mut items = Vec::with_capacity(ITEMS);
+    for _ in 0..ITEMS {
+        let h = *tx.get(pos).ok_or("transacción truncada")?;
+        let item = match h {
+            0..=0x7f => { pos += 1; vec![h] }
+            0x80..=0x9f => {
+                let len = (h - 0x80) as usize;
+                let item = tx.get(pos + 1..pos + 1 + len).ok_or("transacción truncada")?.to_vec();
+                pos += 1 + len;
+                item
+            }
+            _ => return Err(format!("campo RLP no soportado (cabecera {h:#x})")),
+        };
+        items.push(item);
+    }
+    if items[Item::To as usize].len() != 20 { return Err("`to` debe tener 20 bytes (sin creación de contratos)".into()); }
+    if pos > WINDOW { return Err("campos fuera de la ventana RLP".into()); }
+    Ok(items)
+}
+
+/// Rows used by `decode` for a transaction of `tx_len` bytes.
+pub fn decode_rows(tx_len: usize, word_bits: usize) -> usize {
+    let w = tx_len.min(WINDOW);
+    let cmp = compare::compare_rows(POS_BITS, word_bits);
+    // positions, list header, per item: offset row, header rows + checks, three comparisons and a scan row per position
+    w + 1
+        + ITEMS * (1 + 2 + range_check_rows(7, word_bits) + range_check_rows(5, word_bits) + 1 + w * (3 * cmp + 1))
+        + cmp + 2
+}
+
+#[derive(Clone, Debug)]
+pub struct RlpConfig {
+    adv: [Column<Advice>; 6],
+    q_list: Selector,
+    q_offset: Selector,
+    q_header: Selector,
+    q_scan: Selector,
+    compare: CompareConfig,
+    decompose: DecomposeConfig,
+}
+
+impl RlpConfig {
+    pub fn configure<F: FieldExt>(cs: &mut ConstraintSystem<F>, adv: [Column<Advice>; 6], compare: CompareConfig, decompose: DecomposeConfig) -> Self {
+        for c in adv { cs.enable_equality(c); }
+        let [q_list, q_offset, q_header, q_scan] = [0, 1, 2, 3].map(|_| cs.selector());
+        let c = |v: u64| Expression::Constant(F::from(v));
+
+        // b0 in {0xf8, 0xf9}: header length L = b0 - 0xf6 in {2, 3}
+        cs.create_gate("rlp list", |meta| {
+            let q = meta.query_selector(q_list);
+            let b0 = meta.query_advice(adv[0], Rotation::cur());
+            let l = meta.query_advice(adv[1], Rotation::cur());
+            vec![
+                q.clone() * (l.clone() - b0 + c(0xf6)),
+                q * (l.clone() - c(2)) * (l - c(3)),
+            ]
+        });
+        // o, len, o' = o + 1 + len, p = o + 1
+        cs.create_gate("rlp offset", |meta| {
+            let q = meta.query_selector(q_offset);
+            let o = meta.query_advice(adv[0], Rotation::cur());
+            let len = meta.query_advice(adv[1], Rotation::cur());
+            let next = meta.query_advice(adv[2], Rotation::cur());
+            let p = meta.query_advice(adv[3], Rotation::cur());
+            vec![
+                q.clone() * (next - o.clone() - c(1) - len),
+                q * (p - o - c(1)),
+            ]
+        });
+        // row 0: h, top, low7, len, value, acc    row 1: slack = 31 - len
+        cs.create_gate("rlp item header", |meta| {
+            let q = meta.query_selector(q_header);
+            let h = meta.query_advice(adv[0], Rotation::cur());
+            let top = meta.query_advice(adv[1], Rotation::cur());
+            let low = meta.query_advice(adv[2], Rotation::cur());
+            let len = meta.query_advice(adv[3], Rotation::cur());
+            let value = meta.query_advice(adv[4], Rotation::cur());
+            let acc = meta.query_advice(adv[5], Rotation::cur());
+            let slack = meta.query_advice(adv[0], Rotation::next());
+            vec![
+                q.clone() * top.clone() * (c(1) - top.clone()),
+                q.clone() * (h.clone() - top.clone() * c(128) - low),
+                q.clone() * (len.clone() - top.clone() * (h.clone() - c(128))),
+                // a single byte < 0x80 is its own value; otherwise the folded payload
+                q.clone() * (value - (c(1) - top) * h - acc),
+                q * (slack + len - c(MAX_ITEM_BYTES)),
+            ]
+        });
+        // g1 = [i >= o], g2 = [i >= o + 1], g3 = [i >= o']: header where g1 - g2, payload where g2 - g3
+        cs.create_gate("rlp scan", |meta| {
+            let q = meta.query_selector(q_scan);
+            let b = meta.query_advice(adv[0], Rotation::cur());
+            let g1 = meta.query_advice(adv[1], Rotation::cur());
+            let g2 = meta.query_advice(adv[2], Rotation::cur());
+            let g3 = meta.query_advice(adv[3], Rotation::cur());
+            let hdr = meta.query_advice(adv[4], Rotation::cur());
+            let hdr_prev = meta.query_advice(adv[4], Rotation::prev());
+            let acc = meta.query_advice(adv[5], Rotation::cur());
+            let acc_prev = meta.query_advice(adv[5], Rotation::prev());
+            vec![
+                q.clone() * (hdr - hdr_prev - (g1 - g2.clone()) * b.clone()),
+                q * (acc - acc_prev.clone() - (g2 - g3) * (acc_prev * c(255) + b)),
+            ]
+        });
+
+        Self { adv, q_list, q_offset, q_header, q_scan, compare, decompose }
+    }
+
+    /// Decodes nonce, gasPrice, gasLimit, to and value from the transaction byte cells.
+    pub fn decode<F: FieldExt>(&self, mut layouter: impl Layouter<F>, bytes: &[AssignedCell<F, F>]) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        let w = bytes.len().min(WINDOW);
+        let positions = layouter.assign_region(
+            || "rlp positions",
+            |mut region| {
+                (0..=w).map(|i| {
+                    let cell = region.assign_advice(|| format!("pos {i}"), self.adv[0], i, || Value::known(F::from(i as u64)))?;
+                    region.constrain_constant(cell.cell(), F::from(i as u64))?;
+                    Ok(cell)
+                }).collect::<Result<Vec<_>, Error>>()
+            },
+        )?;
+
+        let mut offset = layouter.assign_region(
+            || "rlp list",
+            |mut region| {
+                region.enable_selector(|| "q_list", &self.q_list, 0)?;
+                let b0 = bytes[0].copy_advice(|| "b0", &mut region, self.adv[0], 0)?;
+                region.assign_advice(|| "L", self.adv[1], 0, || b0.value().map(|b| *b - F::from(0xf6)))
+            },
+        )?;
+
+        let window: Value<Vec<u64>> = bytes[..w].iter().fold(Value::known(vec![]), |acc, b| {
+            acc.zip(b.value()).map(|(mut v, b)| { v.push(b.get_lower_128() as u64); v })
+        });
+        let mut values = Vec::with_capacity(ITEMS);
+        for j in 0..ITEMS {
+            // witness: header byte at the current offset
+            let h = offset.value().zip(window.as_ref()).map(|(o, bs)| bs.get(o.get_lower_128() as usize).copied().unwrap_or(0));
+            let len = h.map(|h| h.saturating_sub(0x80));
+
+            let (o, len_cell, next, p) = layouter.assign_region(
+                || format!("rlp offset {j}"),
+                |mut region| {
+                    region.enable_selector(|| "q_offset", &self.q_offset, 0)?;
+                    let o = offset.copy_advice(|| "o", &mut region, self.adv[0], 0)?;
+                    let len = region.assign_advice(|| "len", self.adv[1], 0, || len.map(F::from))?;
+                    let next = region.assign_advice(|| "o'", self.adv[2], 0, || o.value().zip(len.value()).map(|(o, l)| *o + F::ONE + l))?;
+                    let p = region.assign_advice(|| "o + 1", self.adv[3], 0, || o.value().map(|o| *o + F::ONE))?;
+                    if j == Item::To as usize { region.constrain_constant(len.cell(), F::from(20))?; }
+                    Ok((o, len, next, p))
+                },
+            )?;
+
+            let mut gs = Vec::with_capacity(w);
+            for (i, pos) in positions[..w].iter().enumerate() {
+                let g1 = self.compare.ge(layouter.namespace(|| format!("item {j}: {i} >= o")), pos, &o, POS_BITS)?;
+                let g2 = self.compare.ge(layouter.namespace(|| format!("item {j}: {i} > o")), pos, &p, POS_BITS)?;
+                let g3 = self.compare.ge(layouter.namespace(|| format!("item {j}: {i} >= o'")), pos, &next, POS_BITS)?;
+                gs.push((g1, g2, g3));
+            }
+            let (hdr, acc) = layouter.assign_region(
+                || format!("rlp scan {j}"),
+                |mut region| {
+                    let zero_h = region.assign_advice(|| "hdr_0", self.adv[4], 0, || Value::known(F::ZERO))?;
+                    let zero_a = region.assign_advice(|| "acc_0", self.adv[5], 0, || Value::known(F::ZERO))?;
+                    region.constrain_constant(zero_h.cell(), F::ZERO)?;
+                    region.constrain_constant(zero_a.cell(), F::ZERO)?;
+                    let (mut h_cell, mut a_cell) = (zero_h, zero_a);
+                    for (i, (g1, g2, g3)) in gs.iter().enumerate() {
+                        let row = i + 1;
+                        region.enable_selector(|| "q_scan", &self.q_scan, row)?;
+                        let b = bytes[i].copy_advice(|| "b", &mut region, self.adv[0], row)?;
+                        g1.copy_advice(|| "g1", &mut region, self.adv[1], row)?;
+                        g2.copy_advice(|| "g2", &mut region, self.adv[2], row)?;
+                        g3.copy_advice(|| "g3", &mut region, self.adv[3], row)?;
+                        let eq = g1.value().zip(g2.value()).map(|(a, b)| *a - b);
+                        let inside = g2.value().zip(g3.value()).map(|(a, b)| *a - b);
+                        let h_next = h_cell.value().zip(eq).zip(b.value()).map(|((h, e), b)| *h + e * b);
+                        let a_next = a_cell.value().zip(inside).zip(b.value()).map(|((a, s), b)| *a + s * (*a * F::from(255) + b));
+                        h_cell = region.assign_advice(|| "hdr", self.adv[4], row, || h_next)?;
+                        a_cell = region.assign_advice(|| "acc", self.adv[5], row, || a_next)?;
+                    }
+                    Ok((h_cell, a_cell))
+                },
+            )?;
+
+            let (low, slack, value) = layouter.assign_region(
+                || format!("rlp item {j}"),
+                |mut region| {
+                    region.enable_selector(|| "q_header", &self.q_header, 0)?;
+                    let h = hdr.copy_advice(|| "h", &mut region, self.adv[0], 0)?;
+                    let top = h.value().map(|h| if h.get_lower_128() >= 0x80 { F::ONE } else { F::ZERO });
+                    region.assign_advice(|| "top", self.adv[1], 0, || top)?;
+                    let low = region.assign_advice(|| "low7", self.adv[2], 0, || h.value().map(|h| F::from((h.get_lower_128() & 0x7f) as u64)))?;
+                    let len = len_cell.copy_advice(|| "len", &mut region, self.adv[3], 0)?;
+                    let a = acc.copy_advice(|| "acc", &mut region, self.adv[5], 0)?;
+                    let value = region.assign_advice(|| "value", self.adv[4], 0, || top.zip(h.value()).zip(a.value()).map(|((t, h), a)| (F::ONE - t) * h + a))?;
+                    let slack = region.assign_advice(|| "31 - len", self.adv[0], 1, || len.value().map(|l| F::from(MAX_ITEM_BYTES) - l))?;
+                    Ok((low, slack, value))
+                },
+            )?;
+            self.decompose.range_check(layouter.namespace(|| format!("item {j} low7")), &low, 7)?;
+            self.decompose.range_check(layouter.namespace(|| format!("item {j} slack")), &slack, 5)?;
+            values.push(value);
+            offset = next;
+        }
+        let end = positions[w].clone();
+        self.compare.assert_le(layouter.namespace(|| "items inside window"), &offset, &end, POS_BITS)?;
+        Ok(values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{prover::tx_instances, TxCircuit, NEVER_EXPIRES, ONE_Q16};
+    use halo2_proofs::{dev::MockProver, halo2curves::bn256::Fr};
+
+    // EIP-155 example: nonce 9, 20 gwei, 21000 gas, to 0x3535..35, 1 ether
+    const TX: &str = "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83";
+    const ONE: i64 = ONE_Q16 as i64;
+
+    fn circuit(nonce: i64, gas_price: u64) -> TxCircuit<Fr> {
+        let mut circ = TxCircuit::<Fr>::builder()
+            .features(vec![nonce, 0, ONE / 2]).weights(vec![ONE / 64, 0, ONE / 2], 0).quantum(ONE / 2, ONE / 2)
+            .domain("test").validity(0, NEVER_EXPIRES)
+            .build().unwrap();
+        // wider than Q16.16, which the builder rejects; its weight is zero
+        circ.x[1] = Fr::from(gas_price);
+        circ.tx = Some(hex::decode(TX).unwrap());
+        circ.rlp = Some(RlpFeatures { nonce: Some(0), gas_price: Some(1), ..Default::default() });
+        circ
+    }
+
+    fn check(circ: &TxCircuit<Fr>) -> bool {
+        MockProver::run(circ.cost_estimate().min_k, circ, tx_instances(circ)).unwrap().verify().is_ok()
+    }
+
+    #[test]
+    fn parses_the_first_five_items() {
+        let items = parse_legacy(&hex::decode(TX).unwrap()).unwrap();
+        assert_eq!(items[Item::Nonce as usize], [9]);
+        assert_eq!(items[Item::GasPrice as usize], 20_000_000_000u64.to_be_bytes()[3..]);
+        assert_eq!(items[Item::GasLimit as usize], 21_000u32.to_be_bytes()[2..]);
+        assert_eq!(items[Item::To as usize], [0x35; 20]);
+        assert_eq!(items[Item::Value as usize], 1_000_000_000_000_000_000u64.to_be_bytes());
+    }
+
+    #[test]
+    fn rejects_typed_transactions_and_short_recipients() {
+        assert!(parse_legacy(&[0x02, 0xf8, 0x6c]).is_err());
+        // `to` header 0x94 (20 bytes) turned into 0x93
+        assert!(parse_legacy(&hex::decode(TX.replacen("94", "93", 1)).unwrap()).is_err());
+    }
+
+    #[test]
+    fn decoded_fields_bind_the_features() {
+        assert!(check(&circuit(9, 20_000_000_000)));
+        assert!(!check(&circuit(8, 20_000_000_000)));
+        assert!(!check(&circuit(9, 20_000_000_001)));
+    }
+}
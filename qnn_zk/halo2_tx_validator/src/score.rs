@@ -0,0 +1,370 @@
+// score.rs
+use crate::pwl::{self, PwlConfig};
+use crate::range::{self, RangeConfig};
+use crate::fixed::Fixed16;
+use crate::witness::Witness;
+use crate::{fr_from_qi128, fr_to_i128, Activation, FRAC_BITS, ONE_Q16};
+use crate::circuit::{AssignedCell, Layouter, Region, Value};
+use crate::FieldExt;
+use halo2_proofs::{
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, Selector},
+    poly::Rotation,
+};
+
+// Affine model + activation as an embeddable chip: z = w·x + b + alpha·q_out in Q16.16, then the
+// sigmoid. Every product is a floor row, a·b = e·2^16 + r with r < 2^16 and e + 2^(bits-1) <
+// 2^bits, so e is the floored product; a running sum over the e's gives z, then the activation.
+// q_out, alpha and the score are bounded to [0, 1]. Tables (range, pwl) are shared with the host
+// circuit, which loads them once.
+
+// 2^-bits as a power of 1/2: no inversion that could fail.
+pub(crate) fn inv_pow2<F: FieldExt>(bits: u32) -> F { F::TWO_INV.pow_vartime([bits as u64]) }
+
+// Inputs are bounded by the builder; anything outside i64 is a caller bug.
+fn fixed<F: FieldExt>(f: F) -> Fixed16 { Fixed16::from_field(f).expect("valor Q16.16 fuera de i64") }
+
+// sigmoid(z) ~ 1/2 + z/4 - z^3/48 near 0, coefficients in Q16.16
+const CUBIC_C0: Fixed16 = Fixed16::from_raw(1 << 15);
+const CUBIC_C1: Fixed16 = Fixed16::from_raw(1 << 14);
+const CUBIC_C3: Fixed16 = Fixed16::from_raw(-1365);
+
+// Signed widths of the floored products, from the builder's bounds: |w|, |x|, |b| < 2^31 and
+// q_out, alpha in [0, 1]. The cubic activation bounds z to 32 bits before using it.
+const PRODUCT_BITS: usize = 47;
+const MIX_BITS: usize = FRAC_BITS as usize + 2;
+const BIAS_BITS: usize = 32;
+const Z_BITS: usize = 32;
+const Z2_BITS: usize = 47;
+const Z3_BITS: usize = 62;
+const T1_BITS: usize = 31;
+const T3_BITS: usize = 58;
+
+/// Cubic sigmoid c0 + c1·z + c3·z^3 on a Q16.16 z, each product floored as the circuit does.
+pub fn sigmoid_cubic_q16(z: i64) -> i64 {
+    let z = Fixed16::from_raw(z);
+    (CUBIC_C0 + CUBIC_C1 * z + CUBIC_C3 * (z * z * z)).raw()
+}
+
+// One model applied to one feature vector; every value in Q16.16.
+#[derive(Clone, Debug, Default)]
+pub struct ScoreInputs<F> {
+    pub x: Vec<F>,
+    pub w: Vec<F>,
+    pub b: F,
+    pub alpha: F,
+    pub q_out: F,
+    pub activation: Activation,
+}
+
+impl<F: FieldExt> ScoreInputs<F> {
+    /// Integer pre-activation z in Q16.16, with floor rescaling after each product.
+    pub fn preactivation_q16(&self) -> i64 {
+        (self.products().into_iter().sum::<Fixed16>() + fixed(self.b) + self.mix()).raw()
+    }
+
+    /// Intermediate values of the computation, in the order the circuit assigns them.
+    pub fn trace(&self) -> ScoreTrace {
+        let products: Vec<i64> = self.products().into_iter().map(Fixed16::raw).collect();
+        let partial_sums = products.iter().scan(0i64, |acc, p| { *acc += p; Some(*acc) }).collect();
+        ScoreTrace {
+            dot: products.iter().sum(),
+            products,
+            partial_sums,
+            bias: fixed(self.b).raw(),
+            mix: self.mix().raw(),
+            z: self.preactivation_q16(),
+            score: fixed(self.score()).raw(),
+        }
+    }
+
+    // w_i·x_i and alpha·q_out, floored like the circuit's rescaling
+    fn products(&self) -> Vec<Fixed16> { self.w.iter().zip(&self.x).map(|(w, x)| fixed(*w) * fixed(*x)).collect() }
+
+    fn mix(&self) -> Fixed16 { fixed(self.alpha) * fixed(self.q_out) }
+
+    /// Native score for the configured activation, exactly as assigned by `ScoreConfig::assign`.
+    pub fn score(&self) -> F {
+        match self.activation {
+            Activation::CubicSigmoid => fr_from_qi128(sigmoid_cubic_q16(self.preactivation_q16()) as i128),
+            Activation::PwlSigmoid => fr_from_qi128(pwl::sigmoid_pwl_q16(self.preactivation_q16()) as i128),
+        }
+    }
+}
+
+/// The score the circuit enforces for a witness under `activation`, computed on integers only:
+/// products floored after rescaling, then the sigmoid with the circuit's rounding. Panics if z
+/// leaves the PWL domain or an intermediate overflows i64, which the builder's bounds rule out.
+pub fn score_reference(wit: &Witness, activation: Activation) -> Fixed16 {
+    let q = Fixed16::from_raw;
+    let dot: Fixed16 = wit.w.iter().zip(&wit.x).map(|(w, x)| q(*w) * q(*x)).sum();
+    let z = dot + q(wit.b) + q(wit.alpha) * q(wit.q_out);
+    q(match activation {
+        Activation::CubicSigmoid => sigmoid_cubic_q16(z.raw()),
+        Activation::PwlSigmoid => pwl::sigmoid_pwl_q16(z.raw()),
+    })
+}
+
+/// `ScoreInputs::trace`: every value Q16.16, products floored like the circuit's floor rows.
+#[derive(Clone, Debug, Default, serde::Serialize)]
+pub struct ScoreTrace {
+    // w_i·x_i / 2^16 per feature, and their running sum
+    pub products: Vec<i64>,
+    pub partial_sums: Vec<i64>,
+    pub dot: i64,
+    pub bias: i64,
+    // alpha·q_out / 2^16
+    pub mix: i64,
+    // dot + bias + mix
+    pub z: i64,
+    pub score: i64,
+}
+
+/// Cells produced by `ScoreConfig::assign`, for the host circuit to wire into its own constraints.
+#[derive(Clone, Debug)]
+pub struct ScoreCells<F: FieldExt> {
+    pub score: AssignedCell<F, F>,
+    pub x: Vec<AssignedCell<F, F>>,
+    pub w: Vec<AssignedCell<F, F>>,
+    pub b: AssignedCell<F, F>,
+    pub q_out: AssignedCell<F, F>,
+    pub alpha: AssignedCell<F, F>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ScoreConfig {
+    // a, b, e, r, running sum, e + offset
+    adv: [Column<Advice>; 6],
+    offset: Column<Fixed>,
+    q_floor: Selector,
+    q_sum: Selector,
+    range: RangeConfig,
+    pwl: PwlConfig,
+}
+
+// Operand of a floor row.
+enum Operand<'a, F: FieldExt> {
+    Witness(F),
+    Cell(&'a AssignedCell<F, F>),
+    Constant(F),
+}
+
+// A floor row's cells; r and e + offset are range-checked once the region is laid out.
+struct Floor<F: FieldExt> {
+    a: AssignedCell<F, F>,
+    b: AssignedCell<F, F>,
+    e: AssignedCell<F, F>,
+    r: AssignedCell<F, F>,
+    biased: AssignedCell<F, F>,
+    bits: usize,
+}
+
+fn floor_check_rows(bits: usize) -> usize { range::check_rows(FRAC_BITS as usize) + range::check_rows(bits) }
+
+/// Rows used by `assign` for `features` inputs (bounds checks and activation included).
+pub fn score_rows(features: usize, activation: Activation) -> usize {
+    features.max(1) + 2
+        + features * floor_check_rows(PRODUCT_BITS) + floor_check_rows(MIX_BITS) + floor_check_rows(BIAS_BITS)
+        + 3 * range::bounded_rows(FRAC_BITS as usize + 1)
+        + match activation {
+            Activation::CubicSigmoid => 5 + [Z_BITS, Z2_BITS, Z3_BITS, T1_BITS, T3_BITS].into_iter().map(floor_check_rows).sum::<usize>(),
+            Activation::PwlSigmoid => pwl::pwl_rows(range::RANGE_WORD_BITS),
+        }
+}
+
+impl ScoreConfig {
+    pub fn configure<F: FieldExt>(cs: &mut ConstraintSystem<F>, adv: [Column<Advice>; 6], range: RangeConfig, pwl: PwlConfig) -> Self {
+        for c in adv { cs.enable_equality(c); }
+        let offset = cs.fixed_column();
+        let q_floor = cs.selector();
+        let q_sum = cs.selector();
+
+        cs.create_gate("fixed-point product", |meta| {
+            let q = meta.query_selector(q_floor);
+            let a = meta.query_advice(adv[0], Rotation::cur());
+            let b = meta.query_advice(adv[1], Rotation::cur());
+            let e = meta.query_advice(adv[2], Rotation::cur());
+            let r = meta.query_advice(adv[3], Rotation::cur());
+            let biased = meta.query_advice(adv[5], Rotation::cur());
+            let offset = meta.query_fixed(offset, Rotation::cur());
+            let one = Expression::Constant(F::from(ONE_Q16));
+            vec![q.clone() * (a * b - e.clone() * one - r), q * (biased - e - offset)]
+        });
+        // s_i = s_{i-1} + e_i
+        cs.create_gate("affine running sum", |meta| {
+            let q = meta.query_selector(q_sum);
+            let e = meta.query_advice(adv[2], Rotation::cur());
+            let s = meta.query_advice(adv[4], Rotation::cur());
+            let prev = meta.query_advice(adv[4], Rotation::prev());
+            vec![q * (s - prev - e)]
+        });
+
+        Self { adv, offset, q_floor, q_sum, range, pwl }
+    }
+
+    fn operand<F: FieldExt>(&self, region: &mut Region<'_, F>, column: Column<Advice>, row: usize, op: Operand<F>) -> Result<AssignedCell<F, F>, Error> {
+        match op {
+            Operand::Witness(v) => region.assign_advice(|| "operand", column, row, || Value::known(v)),
+            Operand::Cell(c) => c.copy_advice(|| "operand", region, column, row),
+            Operand::Constant(v) => region.assign_advice_from_constant(|| "operand", column, row, v),
+        }
+    }
+
+    // a·b floored by 2^16 on `row`, with the quotient in `bits` signed bits.
+    fn floor<F: FieldExt>(&self, region: &mut Region<'_, F>, row: usize, a: Operand<F>, b: Operand<F>, bits: usize) -> Result<Floor<F>, Error> {
+        region.enable_selector(|| "q_floor", &self.q_floor, row)?;
+        let offset = 1i128 << (bits - 1);
+        region.assign_fixed(|| "offset", self.offset, row, || Value::known(fr_from_qi128::<F>(offset)))?;
+        let a = self.operand(region, self.adv[0], row, a)?;
+        let b = self.operand(region, self.adv[1], row, b)?;
+        let prod = a.value().zip(b.value()).map(|(a, b)| fr_to_i128(*a * *b));
+        let e_v = prod.map(|p| p.div_euclid(ONE_Q16 as i128));
+        let e = region.assign_advice(|| "e", self.adv[2], row, || e_v.map(fr_from_qi128))?;
+        let r = region.assign_advice(|| "r", self.adv[3], row, || prod.map(|p| fr_from_qi128(p.rem_euclid(ONE_Q16 as i128))))?;
+        let biased = region.assign_advice(|| "e + offset", self.adv[5], row, || e_v.map(|e| fr_from_qi128(e + offset)))?;
+        Ok(Floor { a, b, e, r, biased, bits })
+    }
+
+    // s on `row` as s on the row above plus that row's e
+    fn accumulate<F: FieldExt>(&self, region: &mut Region<'_, F>, row: usize, prev: &AssignedCell<F, F>, e: &AssignedCell<F, F>) -> Result<AssignedCell<F, F>, Error> {
+        region.enable_selector(|| "q_sum", &self.q_sum, row)?;
+        region.assign_advice(|| "s", self.adv[4], row, || prev.value().zip(e.value()).map(|(s, e)| *s + *e))
+    }
+
+    fn check_floors<F: FieldExt>(&self, mut layouter: impl Layouter<F>, floors: &[Floor<F>]) -> Result<(), Error> {
+        for (i, f) in floors.iter().enumerate() {
+            self.range.assign(layouter.namespace(|| format!("r_{i}")), &f.r, FRAC_BITS as usize)?;
+            self.range.assign(layouter.namespace(|| format!("e_{i}")), &f.biased, f.bits)?;
+        }
+        Ok(())
+    }
+
+    pub fn assign<F: FieldExt>(&self, mut layouter: impl Layouter<F>, inputs: &ScoreInputs<F>) -> Result<ScoreCells<F>, Error> {
+        // rows 0..n: w_i·x_i, then alpha·q_out and b·1; s ends at z on the last row
+        let n = inputs.x.len();
+        let (floors, z) = layouter.assign_region(
+            || "affine",
+            |mut region| {
+                let mut floors = Vec::with_capacity(n + 2);
+                let mut s: Option<AssignedCell<F, F>> = None;
+                let rows = inputs.x.iter().zip(&inputs.w).map(|(x, w)| (*x, *w, PRODUCT_BITS))
+                    .chain([(inputs.alpha, inputs.q_out, MIX_BITS), (inputs.b, F::from(ONE_Q16), BIAS_BITS)]);
+                for (row, (a, b, bits)) in rows.enumerate() {
+                    let b = if row == n + 1 { Operand::Constant(b) } else { Operand::Witness(b) };
+                    let f = self.floor(&mut region, row, Operand::Witness(a), b, bits)?;
+                    s = Some(match &s {
+                        None => f.e.copy_advice(|| "s", &mut region, self.adv[4], row)?,
+                        Some(prev) => self.accumulate(&mut region, row, prev, &f.e)?,
+                    });
+                    floors.push(f);
+                }
+                Ok((floors, s.expect("al menos una fila")))
+            }
+        )?;
+        self.check_floors(layouter.namespace(|| "affine bounds"), &floors)?;
+        let x = floors[..n].iter().map(|f| f.a.clone()).collect();
+        let w = floors[..n].iter().map(|f| f.b.clone()).collect();
+        let b = floors[n + 1].a.clone();
+        let (alpha, q_out) = (floors[n].a.clone(), floors[n].b.clone());
+
+        let score = match inputs.activation {
+            Activation::PwlSigmoid => self.pwl.assign(layouter.namespace(|| "pwl sigmoid"), &z)?,
+            Activation::CubicSigmoid => self.cubic(layouter.namespace(|| "cubic sigmoid"), &z)?,
+        };
+
+        // q_out is a measured probability and alpha a mixing weight: both must lie in [0, 1].
+        self.range.assign_bounded(layouter.namespace(|| "q_out in [0,1]"), &q_out, ONE_Q16, FRAC_BITS as usize + 1)?;
+        self.range.assign_bounded(layouter.namespace(|| "alpha in [0,1]"), &alpha, ONE_Q16, FRAC_BITS as usize + 1)?;
+        // a published probability must itself be in [0, 1]
+        self.range.assign_bounded(layouter.namespace(|| "score in [0,1]"), &score, ONE_Q16, FRAC_BITS as usize + 1)?;
+
+        Ok(ScoreCells { score, x, w, b, q_out, alpha })
+    }
+
+    // c0 + c1·z + c3·z^3 with floored products; z is bounded first so z^3 cannot wrap.
+    fn cubic<F: FieldExt>(&self, mut layouter: impl Layouter<F>, z: &AssignedCell<F, F>) -> Result<AssignedCell<F, F>, Error> {
+        let (floors, y) = layouter.assign_region(
+            || "cubic",
+            |mut region| {
+                let bound = self.floor(&mut region, 0, Operand::Cell(z), Operand::Constant(F::from(ONE_Q16)), Z_BITS)?;
+                let z2 = self.floor(&mut region, 1, Operand::Cell(z), Operand::Cell(z), Z2_BITS)?;
+                let z3 = self.floor(&mut region, 2, Operand::Cell(&z2.e), Operand::Cell(z), Z3_BITS)?;
+                let c0 = region.assign_advice_from_constant(|| "c0", self.adv[4], 2, CUBIC_C0.to_field::<F>())?;
+                let t1 = self.floor(&mut region, 3, Operand::Constant(CUBIC_C1.to_field()), Operand::Cell(z), T1_BITS)?;
+                let s = self.accumulate(&mut region, 3, &c0, &t1.e)?;
+                let t3 = self.floor(&mut region, 4, Operand::Constant(CUBIC_C3.to_field()), Operand::Cell(&z3.e), T3_BITS)?;
+                let y = self.accumulate(&mut region, 4, &s, &t3.e)?;
+                Ok((vec![bound, z2, z3, t1, t3], y))
+            }
+        )?;
+        self.check_floors(layouter.namespace(|| "cubic bounds"), &floors)?;
+        Ok(y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{prover::tx_instances, TxCircuit, NEVER_EXPIRES};
+    use halo2_proofs::{dev::MockProver, halo2curves::{bn256::Fr, ff::Field}};
+
+    const ONE: i64 = ONE_Q16 as i64;
+
+    fn witness(x: Vec<i64>, w: Vec<i64>, b: i64) -> Witness {
+        Witness { x, w, b, alpha: ONE / 4, q_out: 3 * ONE / 4, score_pub: 0 }
+    }
+
+    fn circuit(wit: &Witness, activation: Activation) -> TxCircuit<Fr> {
+        TxCircuit::<Fr>::builder()
+            .features(wit.x.clone()).weights(wit.w.clone(), wit.b).quantum(wit.q_out, wit.alpha)
+            .activation(activation).domain("test").validity(0, NEVER_EXPIRES)
+            .build().unwrap()
+    }
+
+    fn witnesses() -> Vec<Witness> {
+        vec![
+            witness(vec![ONE / 2, ONE / 4], vec![ONE / 2, -ONE / 4], 0),
+            witness(vec![-3 * ONE / 7, 12345, ONE], vec![ONE / 3, -ONE, 777], -ONE / 5),
+            witness(vec![-1], vec![1], -1),
+        ]
+    }
+
+    #[test]
+    fn cubic_is_monotone_near_zero() {
+        assert_eq!(sigmoid_cubic_q16(0), ONE / 2);
+        let ys: Vec<i64> = (-4..=4).map(|z| sigmoid_cubic_q16(z * ONE / 2)).collect();
+        assert!(ys.windows(2).all(|p| p[0] < p[1]), "{ys:?}");
+    }
+
+    #[test]
+    fn reference_matches_assigned_score() {
+        for wit in witnesses() {
+            for activation in [Activation::CubicSigmoid, Activation::PwlSigmoid] {
+                let circ = circuit(&wit, activation);
+                assert_eq!(score_reference(&wit, activation).to_field::<Fr>(), circ.score_pub, "{activation:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn affine_and_activation_are_satisfied() {
+        for wit in witnesses() {
+            for activation in [Activation::CubicSigmoid, Activation::PwlSigmoid] {
+                let circ = circuit(&wit, activation);
+                let prover = MockProver::run(circ.cost_estimate().min_k, &circ, tx_instances(&circ)).unwrap();
+                assert_eq!(prover.verify(), Ok(()), "{activation:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn other_score_is_rejected() {
+        let wit = &witnesses()[1];
+        for activation in [Activation::CubicSigmoid, Activation::PwlSigmoid] {
+            let circ = circuit(wit, activation);
+            let mut instances = tx_instances(&circ);
+            instances[2][0] += Fr::ONE;
+            let prover = MockProver::run(circ.cost_estimate().min_k, &circ, instances).unwrap();
+            assert!(prover.verify().is_err(), "{activation:?}");
+        }
+    }
+}
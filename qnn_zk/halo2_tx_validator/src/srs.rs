@@ -0,0 +1,149 @@
+// srs.rs
+use halo2_proofs::{
+    halo2curves::{bn256::{Bn256, Fq, Fq2, G1Affine, G2Affine}, CurveAffine},
+    poly::{commitment::Params, kzg::commitment::ParamsKZG},
+    SerdeFormat,
+};
+use halo2_proofs::halo2curves::serde::SerdeObject;
+use sha2::{Digest, Sha256};
+use std::io::Read;
+
+// Ceremony SRS instead of `setup(thread_rng)`, whose caller knows the toxic waste. The published
+// transcripts are already serialized as halo2 ParamsKZG (raw points), one file per k
+// (github.com/han0110/halo2-kzg-srs); a larger file is downsized to the requested k.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Source {
+    // Hermez (Polygon) ceremony, built on the perpetual powers of tau
+    Hermez,
+    // perpetual powers of tau (PSE), raw
+    Ppot,
+}
+
+impl Source {
+    pub fn url(self, k: u32) -> String {
+        let name = match self {
+            Source::Hermez => "hermez-raw",
+            Source::Ppot => "perpetual-powers-of-tau-raw",
+        };
+        format!("https://trusted-setup-halo2kzg.s3.eu-central-1.amazonaws.com/{name}-{k}")
+    }
+}
+
+impl std::str::FromStr for Source {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hermez" => Ok(Source::Hermez),
+            "ppot" => Ok(Source::Ppot),
+            _ => Err(format!("SRS desconocido: {s} (hermez, ppot)")),
+        }
+    }
+}
+
+/// Downloads `url` and checks it against the published SHA-256 before returning it.
+pub fn download(url: &str, expected_sha256: &str) -> Result<Vec<u8>, String> {
+    let response = ureq::get(url).call().map_err(|e| format!("descarga fallida ({url}): {e}"))?;
+    let mut bytes = vec![];
+    response.into_reader().read_to_end(&mut bytes).map_err(|e| format!("descarga fallida ({url}): {e}"))?;
+    let digest = hex::encode(Sha256::digest(&bytes));
+    if !digest.eq_ignore_ascii_case(expected_sha256.trim().trim_start_matches("0x")) {
+        return Err(format!("sha256 del SRS no coincide: {digest} != {expected_sha256}"));
+    }
+    Ok(bytes)
+}
+
+/// Parses a raw halo2 KZG transcript and trims it to 2^k points.
+pub fn params_from_raw(bytes: &[u8], k: u32) -> Result<ParamsKZG<Bn256>, String> {
+    let mut params = ParamsKZG::<Bn256>::read_custom(&mut &bytes[..], SerdeFormat::RawBytes)
+        .map_err(|e| format!("SRS ilegible: {e}"))?;
+    if params.k() < k {
+        return Err(format!("el SRS tiene k = {}, se pidió k = {k}", params.k()));
+    }
+    if params.k() > k { params.downsize(k); }
+    Ok(params)
+}
+
+// snarkjs .ptau (phase 1): "ptau", version, section count, then (id u32, size u64, data) sections.
+// 1 = header (n8, q, power, ceremony power), 2 = tau^i·G1 (2·2^power - 1 points),
+// 3 = tau^i·G2 (2^power points). Coordinates are little-endian Montgomery limbs, which is
+// also halo2curves' raw encoding.
+const BN254_Q: &str = "30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd47";
+
+struct Ptau<'a> {
+    bytes: &'a [u8],
+    // (id, offset, size)
+    sections: Vec<(u32, usize, usize)>,
+}
+
+impl<'a> Ptau<'a> {
+    fn parse(bytes: &'a [u8]) -> Result<Self, String> {
+        if bytes.get(..4) != Some(b"ptau") {
+            return Err("no es un archivo .ptau".into());
+        }
+        let count = read_u32(bytes, 8)?;
+        let mut sections = vec![];
+        let mut offset = 12;
+        for _ in 0..count {
+            let id = read_u32(bytes, offset)?;
+            let size = read_u64(bytes, offset + 4)? as usize;
+            let start = offset + 12;
+            if start + size > bytes.len() { return Err(format!("sección {id} del .ptau truncada")); }
+            sections.push((id, start, size));
+            offset = start + size;
+        }
+        Ok(Self { bytes, sections })
+    }
+
+    fn section(&self, id: u32) -> Result<&'a [u8], String> {
+        let (_, start, size) = self.sections.iter().find(|s| s.0 == id).ok_or(format!("falta la sección {id} del .ptau"))?;
+        Ok(&self.bytes[*start..start + size])
+    }
+}
+
+fn read_u32(b: &[u8], at: usize) -> Result<u32, String> {
+    b.get(at..at + 4).map(|v| u32::from_le_bytes(v.try_into().unwrap())).ok_or_else(|| "archivo .ptau truncado".into())
+}
+
+fn read_u64(b: &[u8], at: usize) -> Result<u64, String> {
+    b.get(at..at + 8).map(|v| u64::from_le_bytes(v.try_into().unwrap())).ok_or_else(|| "archivo .ptau truncado".into())
+}
+
+fn fq(b: &[u8]) -> Result<Fq, String> {
+    Fq::from_raw_bytes(b).ok_or_else(|| "coordenada fuera del campo".into())
+}
+
+fn g1(b: &[u8]) -> Result<G1Affine, String> {
+    Option::from(G1Affine::from_xy(fq(&b[..32])?, fq(&b[32..64])?)).ok_or_else(|| "punto G1 fuera de la curva".into())
+}
+
+fn g2(b: &[u8]) -> Result<G2Affine, String> {
+    let x = Fq2 { c0: fq(&b[..32])?, c1: fq(&b[32..64])? };
+    let y = Fq2 { c0: fq(&b[64..96])?, c1: fq(&b[96..128])? };
+    Option::from(G2Affine::from_xy(x, y)).ok_or_else(|| "punto G2 fuera de la curva".into())
+}
+
+/// Converts a snarkjs powers-of-tau file over bn128 into KZG params for 2^k points.
+pub fn params_from_ptau(bytes: &[u8], k: u32) -> Result<ParamsKZG<Bn256>, String> {
+    let ptau = Ptau::parse(bytes)?;
+    let header = ptau.section(1)?;
+    let n8 = read_u32(header, 0)? as usize;
+    let mut q = header.get(4..4 + n8).ok_or("cabecera .ptau truncada")?.to_vec();
+    q.reverse();
+    if n8 != 32 || hex::encode(q) != BN254_Q {
+        return Err("el .ptau no es de la curva bn128".into());
+    }
+    let power = read_u32(header, 4 + n8)?;
+    if power < k {
+        return Err(format!("el .ptau tiene power = {power}, se pidió k = {k}"));
+    }
+
+    let n = 1usize << k;
+    let tau_g1 = ptau.section(2)?;
+    let g = (0..n).map(|i| g1(tau_g1.get(i * 64..(i + 1) * 64).ok_or("sección tauG1 truncada")?)).collect::<Result<Vec<_>, String>>()?;
+    let tau_g2 = ptau.section(3)?;
+    let (g2_0, s_g2) = (g2(tau_g2.get(..128).ok_or("sección tauG2 truncada")?)?, g2(tau_g2.get(128..256).ok_or("sección tauG2 truncada")?)?);
+
+    // from_parts is a method in this halo2 version; a k = 1 setup serves as the receiver only
+    let receiver = ParamsKZG::<Bn256>::setup(1, rand::thread_rng());
+    Ok(receiver.from_parts(k, g, None, g2_0, s_g2))
+}
@@ -0,0 +1,142 @@
+// store.rs
+#[cfg(feature = "store")]
+use rusqlite::{params, Connection, OptionalExtension};
+
+// Local proof archive: one row per proof with what compliance searches by (the keccak256 of the
+// bound tx, the vk fingerprint, when it was proven and when it was stored), plus the proof envelope
+// and its public JSON verbatim so `store get` returns exactly what `verify` accepts.
+// SQLite needs the `store` feature; without it Store::open says how to enable it.
+#[cfg(feature = "store")]
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS proofs (
+    id             INTEGER PRIMARY KEY,
+    tx_hash        TEXT,
+    vk_fingerprint TEXT NOT NULL,
+    created_at     INTEGER NOT NULL,
+    stored_at      INTEGER NOT NULL,
+    proof          BLOB NOT NULL,
+    public         TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS proofs_tx ON proofs (tx_hash);
+CREATE INDEX IF NOT EXISTS proofs_created ON proofs (created_at);
+";
+
+#[cfg(feature = "store")]
+pub struct Store {
+    conn: Connection,
+}
+
+#[derive(Clone, Debug)]
+pub struct StoredProof {
+    pub id: i64,
+    // 0x-prefixed, lowercase
+    pub tx_hash: Option<String>,
+    pub vk_fingerprint: String,
+    // Unix seconds: envelope creation and insertion
+    pub created_at: u64,
+    pub stored_at: u64,
+    pub proof: Vec<u8>,
+    pub public: String,
+}
+
+/// Filters of `store query`; empty matches everything. Times are Unix seconds, `until` exclusive.
+#[derive(Clone, Debug, Default)]
+pub struct Query {
+    pub tx_hash: Option<String>,
+    pub vk_fingerprint: Option<String>,
+    pub since: Option<u64>,
+    pub until: Option<u64>,
+    pub limit: Option<usize>,
+}
+
+#[cfg(feature = "store")]
+fn db_err(e: rusqlite::Error) -> String { format!("almacén de pruebas: {e}") }
+
+#[cfg(feature = "store")]
+fn normalize_hash(h: &str) -> String { format!("0x{}", h.trim().trim_start_matches("0x").to_ascii_lowercase()) }
+
+#[cfg(feature = "store")]
+impl Store {
+    pub fn open(path: &str) -> Result<Self, String> {
+        let conn = Connection::open(path).map_err(db_err)?;
+        conn.execute_batch(SCHEMA).map_err(db_err)?;
+        Ok(Self { conn })
+    }
+
+    /// Inserts a proof and returns its id.
+    pub fn insert(&self, tx_hash: Option<&str>, vk_fingerprint: &str, created_at: u64, stored_at: u64, proof: &[u8], public: &str) -> Result<i64, String> {
+        self.conn.execute(
+            "INSERT INTO proofs (tx_hash, vk_fingerprint, created_at, stored_at, proof, public) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![tx_hash.map(normalize_hash), vk_fingerprint, created_at as i64, stored_at as i64, proof, public],
+        ).map_err(db_err)?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// Matching proofs, newest first.
+    pub fn query(&self, q: &Query) -> Result<Vec<StoredProof>, String> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, tx_hash, vk_fingerprint, created_at, stored_at, proof, public FROM proofs
+             WHERE (?1 IS NULL OR tx_hash = ?1) AND (?2 IS NULL OR vk_fingerprint = ?2)
+               AND (?3 IS NULL OR created_at >= ?3) AND (?4 IS NULL OR created_at < ?4)
+             ORDER BY created_at DESC, id DESC LIMIT ?5",
+        ).map_err(db_err)?;
+        let limit = q.limit.map_or(-1, |l| l as i64);
+        let rows = stmt.query_map(
+            params![q.tx_hash.as_deref().map(normalize_hash), q.vk_fingerprint, q.since.map(|t| t as i64), q.until.map(|t| t as i64), limit],
+            row_to_proof,
+        ).map_err(db_err)?;
+        rows.collect::<Result<_, _>>().map_err(db_err)
+    }
+
+    pub fn get(&self, id: i64) -> Result<Option<StoredProof>, String> {
+        self.conn.query_row(
+            "SELECT id, tx_hash, vk_fingerprint, created_at, stored_at, proof, public FROM proofs WHERE id = ?1",
+            params![id],
+            row_to_proof,
+        ).optional().map_err(db_err)
+    }
+}
+
+#[cfg(feature = "store")]
+fn row_to_proof(row: &rusqlite::Row) -> rusqlite::Result<StoredProof> {
+    Ok(StoredProof {
+        id: row.get(0)?,
+        tx_hash: row.get(1)?,
+        vk_fingerprint: row.get(2)?,
+        created_at: row.get::<_, i64>(3)? as u64,
+        stored_at: row.get::<_, i64>(4)? as u64,
+        proof: row.get(5)?,
+        public: row.get(6)?,
+    })
+}
+
+#[cfg(not(feature = "store"))]
+pub struct Store;
+
+#[cfg(not(feature = "store"))]
+impl Store {
+    const MSG: &'static str = "almacén de pruebas no disponible: compila con --features store";
+    pub fn open(_: &str) -> Result<Self, String> { Err(Self::MSG.into()) }
+    pub fn insert(&self, _: Option<&str>, _: &str, _: u64, _: u64, _: &[u8], _: &str) -> Result<i64, String> { Err(Self::MSG.into()) }
+    pub fn query(&self, _: &Query) -> Result<Vec<StoredProof>, String> { Err(Self::MSG.into()) }
+    pub fn get(&self, _: i64) -> Result<Option<StoredProof>, String> { Err(Self::MSG.into()) }
+}
+
+/// [start, end) in Unix seconds of a UTC calendar day given as YYYY-MM-DD.
+pub fn utc_day(date: &str) -> Result<(u64, u64), String> {
+    let bad = || format!("fecha inválida: {date} (se espera AAAA-MM-DD)");
+    let mut parts = date.splitn(3, '-').map(|p| p.parse::<i64>().map_err(|_| bad()));
+    let (y, m, d) = (parts.next().ok_or_else(bad)??, parts.next().ok_or_else(bad)??, parts.next().ok_or_else(bad)??);
+    if !(1..=12).contains(&m) || !(1..=31).contains(&d) || y < 1970 {
+        return Err(bad());
+    }
+    // days from civil (Howard Hinnant)
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((m + 9) % 12) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+    let start = days as u64 * 86_400;
+    Ok((start, start + 86_400))
+}
@@ -0,0 +1,329 @@
+// tree.rs
+use crate::compare::CompareConfig;
+use crate::range::RangeConfig;
+use crate::{commit_in_domain, fr_from_qi128, fr_to_i128, Tag, FRAC_BITS, ONE_Q16, TIME_BITS};
+use crate::{Hash, PoseidonChip, PoseidonConfig};
+use crate::circuit::{pin_degree, AssignedCell, Layouter, SimpleFloorPlanner, StackedLayouter, Value};
+use halo2_proofs::{
+    circuit as h2,
+    halo2curves::bn256::Fr,
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Expression, Instance, Selector},
+    poly::Rotation,
+};
+use serde::{Deserialize, Serialize};
+
+// Gradient-boosted stumps: score = base + sum_k (x[f_k] < t_k ? left_k : right_k).
+// Split features are part of the shape (fixed at keygen); thresholds, leaves and base are
+// committed in commit_wb, which absorbs the same cells the stump and sum regions copy. Public
+// inputs follow TxCircuit's first seven columns; with no quantum output, commit_q is pinned to 0.
+// Signed Q16.16 operands are shifted by 2^47 so the comparison chip sees non-negative values.
+pub const FEATURE_BITS: usize = 48;
+const SIGN_OFFSET: u64 = 1 << (FEATURE_BITS - 1);
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TreeShape {
+    pub features: usize,
+    // split feature of each stump
+    pub splits: Vec<usize>,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Stump {
+    pub threshold: Fr,
+    pub left: Fr,
+    pub right: Fr,
+}
+
+#[derive(Clone, Debug)]
+pub struct TreeConfig {
+    adv: [Column<Advice>; 6],
+    q_stump: Selector,
+    q_sum: Selector,
+    poseidon: PoseidonConfig<Fr, 3, 2>,
+    range: RangeConfig,
+    compare: CompareConfig,
+    instance: [Column<Instance>; 7], // commit_wb, commit_q, score_pub, commit_x, valid_until, commit_now, domain
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct TreeCircuit {
+    pub shape: TreeShape,
+    pub x: Vec<Fr>,
+    pub base: Fr,
+    pub stumps: Vec<Stump>,
+    pub now: Fr,
+    pub valid_until: Fr,
+    pub domain: Fr,
+}
+
+impl TreeCircuit {
+    /// Witness-free circuit for `shape`; use it for keygen.
+    pub fn empty(shape: TreeShape) -> Self {
+        Self {
+            x: vec![Fr::from(0); shape.features],
+            stumps: vec![Stump::default(); shape.splits.len()],
+            shape,
+            ..Default::default()
+        }
+    }
+
+    /// Native score in Q16.16.
+    pub fn score_q16(&self) -> i64 {
+        let leaves: i128 = self.shape.splits.iter().zip(&self.stumps).map(|(f, s)| {
+            if fr_to_i128(self.x[*f]) < fr_to_i128(s.threshold) { fr_to_i128(s.left) } else { fr_to_i128(s.right) }
+        }).sum();
+        (fr_to_i128(self.base) + leaves) as i64
+    }
+
+    /// Values absorbed (after the domain) by commit_wb.
+    pub fn model_inputs(&self) -> Vec<Fr> {
+        let mut inputs = vec![self.base];
+        for s in &self.stumps { inputs.extend([s.threshold, s.left, s.right]); }
+        inputs
+    }
+
+    /// Public inputs, TxCircuit's first seven columns with commit_q = 0.
+    pub fn instances(&self) -> Vec<Vec<Fr>> {
+        vec![
+            vec![commit_in_domain(Tag::Wb, self.domain, &self.model_inputs())],
+            vec![Fr::from(0)],
+            vec![fr_from_qi128(self.score_q16() as i128)],
+            vec![commit_in_domain(Tag::X, self.domain, &self.x)],
+            vec![self.valid_until],
+            vec![commit_in_domain(Tag::Now, self.domain, &[self.now])],
+            vec![self.domain],
+        ]
+    }
+}
+
+impl Circuit<Fr> for TreeCircuit {
+    type Config = TreeConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self { Self::empty(self.shape.clone()) }
+
+    fn configure(cs: &mut ConstraintSystem<Fr>) -> Self::Config {
+        let adv = [0,1,2,3,4,5].map(|_| cs.advice_column());
+        for a in &adv { cs.enable_equality(*a); }
+        let instance = [0,1,2,3,4,5,6].map(|_| cs.instance_column());
+        for i in &instance { cs.enable_equality(*i); }
+        let poseidon = PoseidonChip::configure(cs, adv[0], adv[1], adv[2], adv[3], adv[4], adv[5]);
+        let constants = cs.fixed_column();
+        let range = RangeConfig::configure(cs, adv[3], adv[4], constants);
+        let compare = CompareConfig::configure(cs, adv[0], adv[1], adv[2], adv[5], range.decompose.clone());
+
+        // row 0: x, t, x + 2^47, t + 2^47, left, right    row 1: lt, out
+        let q_stump = cs.selector();
+        cs.create_gate("stump", |meta| {
+            let q = meta.query_selector(q_stump);
+            let x = meta.query_advice(adv[0], Rotation::cur());
+            let t = meta.query_advice(adv[1], Rotation::cur());
+            let xs = meta.query_advice(adv[2], Rotation::cur());
+            let ts = meta.query_advice(adv[3], Rotation::cur());
+            let left = meta.query_advice(adv[4], Rotation::cur());
+            let right = meta.query_advice(adv[5], Rotation::cur());
+            let lt = meta.query_advice(adv[0], Rotation::next());
+            let out = meta.query_advice(adv[1], Rotation::next());
+            let offset = Expression::Constant(Fr::from(SIGN_OFFSET));
+            vec![
+                q.clone() * (xs - x - offset.clone()),
+                q.clone() * (ts - t - offset),
+                q * (out - right.clone() - lt * (left - right)),
+            ]
+        });
+        // total_k = total_{k-1} + out_k
+        let q_sum = cs.selector();
+        cs.create_gate("tree sum", |meta| {
+            let q = meta.query_selector(q_sum);
+            let out = meta.query_advice(adv[0], Rotation::cur());
+            let total = meta.query_advice(adv[1], Rotation::cur());
+            let prev = meta.query_advice(adv[1], Rotation::prev());
+            vec![q * (total - prev - out)]
+        });
+
+        pin_degree(cs);
+        TreeConfig { adv, q_stump, q_sum, poseidon, range, compare, instance }
+    }
+
+    fn synthesize(&self, cfg: Self::Config, layouter: impl h2::Layouter<Fr>) -> Result<(), Error> {
+        let mut layouter = StackedLayouter::new(layouter);
+        cfg.range.load(layouter.namespace(|| "range table"))?;
+
+        let domain_cell = layouter.assign_region(
+            || "domain",
+            |mut region| region.assign_advice(|| "domain", cfg.adv[0], 0, || Value::known(self.domain))
+        )?;
+        layouter.constrain_instance(domain_cell.cell(), cfg.instance[6], 0)?;
+
+        // base, then (threshold, left, right) per stump, in commit_wb's absorption order
+        let model = layouter.assign_region(
+            || "model",
+            |mut region| {
+                self.model_inputs().iter().enumerate()
+                    .map(|(i, v)| region.assign_advice(|| format!("model_{i}"), cfg.adv[i % 3], i / 3, || Value::known(*v)))
+                    .collect::<Result<Vec<_>, _>>()
+            }
+        )?;
+        let commit_wb = {
+            let mut hasher = Hash::<Fr, PoseidonChip<Fr>, 3, 2>::init(cfg.poseidon.clone(), layouter.namespace(|| "poseidon_wb"))?;
+            let tag = Tag::Wb.assign(layouter.namespace(|| "tag_wb"), cfg.adv[0])?;
+            let mut inputs = vec![tag, domain_cell.clone()];
+            inputs.extend(model.iter().cloned());
+            hasher.update(layouter.namespace(|| "absorb_wb"), inputs)?;
+            hasher.squeeze(layouter.namespace(|| "squeeze_wb"))?
+        };
+        layouter.constrain_instance(commit_wb.cell(), cfg.instance[0], 0)?;
+        let commit_q = layouter.assign_region(
+            || "commit_q",
+            |mut region| region.assign_advice_from_constant(|| "commit_q", cfg.adv[0], 0, Fr::from(0))
+        )?;
+        layouter.constrain_instance(commit_q.cell(), cfg.instance[1], 0)?;
+        let x_cells = layouter.assign_region(
+            || "features",
+            |mut region| {
+                self.x.iter().enumerate()
+                    .map(|(i, x)| region.assign_advice(|| format!("x_{i}"), cfg.adv[0], i, || Value::known(*x)))
+                    .collect::<Result<Vec<_>, _>>()
+            }
+        )?;
+        let commit_x = {
+            let mut hasher = Hash::<Fr, PoseidonChip<Fr>, 3, 2>::init(cfg.poseidon.clone(), layouter.namespace(|| "poseidon_x"))?;
+            let tag = Tag::X.assign(layouter.namespace(|| "tag_x"), cfg.adv[0])?;
+            let mut inputs = vec![tag, domain_cell.clone()];
+            inputs.extend(x_cells.iter().cloned());
+            hasher.update(layouter.namespace(|| "absorb_x"), inputs)?;
+            hasher.squeeze(layouter.namespace(|| "squeeze_x"))?
+        };
+        layouter.constrain_instance(commit_x.cell(), cfg.instance[3], 0)?;
+
+        let mut outs: Vec<AssignedCell<Fr, Fr>> = Vec::with_capacity(self.stumps.len());
+        for (k, f) in self.shape.splits.iter().enumerate() {
+            let [threshold, left, right] = [0, 1, 2].map(|i| &model[1 + 3 * k + i]);
+            let offset = Fr::from(SIGN_OFFSET);
+            let (xs, ts) = layouter.assign_region(
+                || format!("stump {k} operands"),
+                |mut region| {
+                    let xs = region.assign_advice(|| "x + 2^47", cfg.adv[2], 0, || x_cells[*f].value().map(|x| *x + offset))?;
+                    let ts = region.assign_advice(|| "t + 2^47", cfg.adv[3], 0, || threshold.value().map(|t| *t + offset))?;
+                    Ok((xs, ts))
+                }
+            )?;
+            let lt = cfg.compare.lt(layouter.namespace(|| format!("stump {k} x < t")), &xs, &ts, FEATURE_BITS)?;
+            let out = layouter.assign_region(
+                || format!("stump {k}"),
+                |mut region| {
+                    region.enable_selector(|| "q_stump", &cfg.q_stump, 0)?;
+                    x_cells[*f].copy_advice(|| "x", &mut region, cfg.adv[0], 0)?;
+                    threshold.copy_advice(|| "t", &mut region, cfg.adv[1], 0)?;
+                    xs.copy_advice(|| "x + 2^47", &mut region, cfg.adv[2], 0)?;
+                    ts.copy_advice(|| "t + 2^47", &mut region, cfg.adv[3], 0)?;
+                    let left = left.copy_advice(|| "left", &mut region, cfg.adv[4], 0)?;
+                    let right = right.copy_advice(|| "right", &mut region, cfg.adv[5], 0)?;
+                    let lt = lt.copy_advice(|| "lt", &mut region, cfg.adv[0], 1)?;
+                    let out = lt.value().zip(left.value()).zip(right.value())
+                        .map(|((b, l), r)| if *b == Fr::from(1) { *l } else { *r });
+                    region.assign_advice(|| "out", cfg.adv[1], 1, || out)
+                }
+            )?;
+            outs.push(out);
+        }
+
+        let score = layouter.assign_region(
+            || "tree sum",
+            |mut region| {
+                let mut total = model[0].copy_advice(|| "base", &mut region, cfg.adv[1], 0)?;
+                for (k, out) in outs.iter().enumerate() {
+                    region.enable_selector(|| "q_sum", &cfg.q_sum, k + 1)?;
+                    out.copy_advice(|| format!("out_{k}"), &mut region, cfg.adv[0], k + 1)?;
+                    let next = total.value().zip(out.value()).map(|(t, o)| *t + o);
+                    total = region.assign_advice(|| format!("total_{}", k + 1), cfg.adv[1], k + 1, || next)?;
+                }
+                Ok(total)
+            }
+        )?;
+        cfg.range.assign_bounded(layouter.namespace(|| "score in [0,1]"), &score, ONE_Q16, FRAC_BITS as usize + 1)?;
+        layouter.constrain_instance(score.cell(), cfg.instance[2], 0)?;
+
+        let (now_cell, until_cell) = layouter.assign_region(
+            || "expiry",
+            |mut region| {
+                let now = region.assign_advice(|| "now", cfg.adv[0], 0, || Value::known(self.now))?;
+                let until = region.assign_advice(|| "valid_until", cfg.adv[1], 0, || Value::known(self.valid_until))?;
+                Ok((now, until))
+            }
+        )?;
+        let commit_now = {
+            let tag = Tag::Now.assign(layouter.namespace(|| "tag_now"), cfg.adv[0])?;
+            let mut hasher = Hash::<Fr, PoseidonChip<Fr>, 3, 2>::init(cfg.poseidon.clone(), layouter.namespace(|| "poseidon_now"))?;
+            hasher.update(layouter.namespace(|| "absorb_now"), vec![tag, domain_cell, now_cell.clone()])?;
+            hasher.squeeze(layouter.namespace(|| "squeeze_now"))?
+        };
+        cfg.compare.assert_le(layouter.namespace(|| "now <= valid_until"), &now_cell, &until_cell, TIME_BITS)?;
+        layouter.constrain_instance(until_cell.cell(), cfg.instance[4], 0)?;
+        layouter.constrain_instance(commit_now.cell(), cfg.instance[5], 0)?;
+        Ok(())
+    }
+}
+
+/// Q16.16 helper for building stumps from integer model files.
+pub fn stump_from_q16(threshold: i64, left: i64, right: i64) -> Stump {
+    Stump { threshold: fr_from_qi128(threshold as i128), left: fr_from_qi128(left as i128), right: fr_from_qi128(right as i128) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NEVER_EXPIRES;
+    use halo2_proofs::dev::MockProver;
+
+    const ONE: i64 = ONE_Q16 as i64;
+
+    fn tree() -> TreeCircuit {
+        let x = [ONE / 2, -ONE, 3 * ONE];
+        TreeCircuit {
+            shape: TreeShape { features: x.len(), splits: vec![0, 2] },
+            x: x.map(|v| fr_from_qi128(v as i128)).to_vec(),
+            base: fr_from_qi128((ONE / 4) as i128),
+            stumps: vec![stump_from_q16(ONE, ONE / 4, -ONE / 8), stump_from_q16(2 * ONE, 0, ONE / 2)],
+            now: Fr::from(1_700_000_000),
+            valid_until: Fr::from(NEVER_EXPIRES),
+            domain: Fr::from(1),
+        }
+    }
+
+    fn check(circ: &TreeCircuit, instances: Vec<Vec<Fr>>) -> bool {
+        MockProver::run(13, circ, instances).unwrap().verify().is_ok()
+    }
+
+    #[test]
+    fn committed_stumps_are_satisfied() {
+        let circ = tree();
+        assert_eq!(circ.score_q16(), ONE / 4 + ONE / 4 + ONE / 2);
+        assert!(check(&circ, circ.instances()));
+    }
+
+    // a model other than the committed one cannot publish its score
+    #[test]
+    fn tampered_threshold_or_leaf_is_rejected() {
+        let circ = tree();
+        let mut threshold = circ.clone();
+        threshold.stumps[1].threshold = fr_from_qi128((4 * ONE) as i128);
+        let mut leaf = circ.clone();
+        leaf.stumps[0].left = fr_from_qi128(0);
+        for tampered in [threshold, leaf] {
+            assert_ne!(tampered.score_q16(), circ.score_q16());
+            let mut instances = circ.instances();
+            instances[2] = tampered.instances()[2].clone();
+            assert!(!check(&tampered, instances));
+        }
+    }
+
+    #[test]
+    fn commit_q_is_zero() {
+        let circ = tree();
+        let mut instances = circ.instances();
+        instances[1] = vec![Fr::from(1)];
+        assert!(!check(&circ, instances));
+    }
+}
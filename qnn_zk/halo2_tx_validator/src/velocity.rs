@@ -0,0 +1,134 @@
+// velocity.rs
+use crate::compare::CompareConfig;
+use crate::range::{self, RangeConfig};
+use crate::{compare, poseidon_rows, Tag, AMOUNT_BITS};
+use crate::{Hash, PoseidonChip, PoseidonConfig};
+use crate::circuit::{pin_degree, Layouter, SimpleFloorPlanner, StackedLayouter, Value};
+use halo2_proofs::{
+    circuit as h2,
+    halo2curves::bn256::Fr,
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Instance, Selector},
+    poly::Rotation,
+};
+
+// Rolling-window spending cap: the amounts of the last `window` transactions are committed
+// (domain first, like every other commitment) and their sum is proven <= a public cap.
+// Structuring shows up as many small amounts, so the window is proven as one batch.
+pub const MAX_WINDOW: usize = 256;
+// Sum of up to 256 amounts of AMOUNT_BITS each.
+pub const TOTAL_BITS: usize = AMOUNT_BITS + 8;
+
+#[derive(Clone, Debug)]
+pub struct VelocityConfig {
+    adv: [Column<Advice>; 6],
+    q_sum: Selector,
+    poseidon: PoseidonConfig<Fr, 3, 2>,
+    range: RangeConfig,
+    compare: CompareConfig,
+    instance: [Column<Instance>; 3], // commit_amounts, cap, domain
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct VelocityCircuit {
+    pub amounts: Vec<Fr>,
+    pub cap: Fr,
+    pub domain: Fr,
+}
+
+impl VelocityCircuit {
+    /// Witness-free circuit for a window of `window` transactions; use it for keygen.
+    pub fn empty(window: usize) -> Self {
+        Self { amounts: vec![Fr::from(0); window], ..Default::default() }
+    }
+
+    /// Rows used by a window of this size (running sum, per-amount checks, commitment, cap check).
+    pub fn rows(&self) -> usize {
+        let n = self.amounts.len();
+        (n + 1)
+            + n * range::check_rows(AMOUNT_BITS)
+            + 2 + poseidon_rows::<Fr>(2 + n)
+            + 1 + compare::compare_rows(TOTAL_BITS, range::RANGE_WORD_BITS)
+    }
+}
+
+impl Circuit<Fr> for VelocityCircuit {
+    type Config = VelocityConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+    type Params = ();
+
+    fn without_witnesses(&self) -> Self { Self::empty(self.amounts.len()) }
+
+    fn configure(cs: &mut ConstraintSystem<Fr>) -> Self::Config {
+        let adv = [0,1,2,3,4,5].map(|_| cs.advice_column());
+        for a in &adv { cs.enable_equality(*a); }
+        let instance = [0,1,2].map(|_| cs.instance_column());
+        for i in &instance { cs.enable_equality(*i); }
+        let poseidon = PoseidonChip::configure(cs, adv[0], adv[1], adv[2], adv[3], adv[4], adv[5]);
+        let constants = cs.fixed_column();
+        let range = RangeConfig::configure(cs, adv[3], adv[4], constants);
+        let compare = CompareConfig::configure(cs, adv[0], adv[1], adv[2], adv[5], range.decompose.clone());
+
+        // total_i = total_{i-1} + amount_i
+        let q_sum = cs.selector();
+        cs.create_gate("running total", |meta| {
+            let q = meta.query_selector(q_sum);
+            let amount = meta.query_advice(adv[0], Rotation::cur());
+            let total = meta.query_advice(adv[1], Rotation::cur());
+            let prev = meta.query_advice(adv[1], Rotation::prev());
+            vec![q * (total - prev - amount)]
+        });
+
+        pin_degree(cs);
+        VelocityConfig { adv, q_sum, poseidon, range, compare, instance }
+    }
+
+    fn synthesize(&self, cfg: Self::Config, layouter: impl h2::Layouter<Fr>) -> Result<(), Error> {
+        let mut layouter = StackedLayouter::new(layouter);
+        assert!(self.amounts.len() <= MAX_WINDOW, "ventana demasiado grande");
+        cfg.range.load(layouter.namespace(|| "range table"))?;
+
+        let domain_cell = layouter.assign_region(
+            || "domain",
+            |mut region| region.assign_advice(|| "domain", cfg.adv[0], 0, || Value::known(self.domain))
+        )?;
+        layouter.constrain_instance(domain_cell.cell(), cfg.instance[2], 0)?;
+
+        let (amount_cells, total) = layouter.assign_region(
+            || "running total",
+            |mut region| {
+                let mut total = region.assign_advice(|| "total_0", cfg.adv[1], 0, || Value::known(Fr::from(0)))?;
+                region.constrain_constant(total.cell(), Fr::from(0))?;
+                let mut cells = Vec::with_capacity(self.amounts.len());
+                for (i, a) in self.amounts.iter().enumerate() {
+                    region.enable_selector(|| "q_sum", &cfg.q_sum, i + 1)?;
+                    cells.push(region.assign_advice(|| format!("amount_{i}"), cfg.adv[0], i + 1, || Value::known(*a))?);
+                    let next = total.value().map(|t| *t + a);
+                    total = region.assign_advice(|| format!("total_{}", i + 1), cfg.adv[1], i + 1, || next)?;
+                }
+                Ok((cells, total))
+            }
+        )?;
+        // non-negative amounts, so the sum cannot wrap
+        for (i, a) in amount_cells.iter().enumerate() {
+            cfg.range.assign(layouter.namespace(|| format!("amount_{i} bits")), a, AMOUNT_BITS)?;
+        }
+
+        let commit = {
+            let tag = Tag::Amounts.assign(layouter.namespace(|| "tag_amounts"), cfg.adv[0])?;
+            let mut hasher = Hash::<Fr, PoseidonChip<Fr>, 3, 2>::init(cfg.poseidon.clone(), layouter.namespace(|| "poseidon_amounts"))?;
+            let mut inputs = vec![tag, domain_cell];
+            inputs.extend(amount_cells);
+            hasher.update(layouter.namespace(|| "absorb_amounts"), inputs)?;
+            hasher.squeeze(layouter.namespace(|| "squeeze_amounts"))?
+        };
+        layouter.constrain_instance(commit.cell(), cfg.instance[0], 0)?;
+
+        let cap = layouter.assign_region(
+            || "velocity cap",
+            |mut region| region.assign_advice(|| "cap", cfg.adv[1], 0, || Value::known(self.cap))
+        )?;
+        cfg.compare.assert_le(layouter.namespace(|| "total <= cap"), &total, &cap, TOTAL_BITS)?;
+        layouter.constrain_instance(cap.cell(), cfg.instance[1], 0)?;
+        Ok(())
+    }
+}
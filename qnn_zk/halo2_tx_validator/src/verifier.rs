@@ -0,0 +1,78 @@
+// verifier.rs
+#![deny(clippy::std_instead_of_core, clippy::std_instead_of_alloc)]
+use crate::envelope::{ProofEnvelope, Scheme, Transcript};
+use crate::keys::{PK_MAGIC, VK_MAGIC};
+use crate::TxCircuit;
+use alloc::{format, string::{String, ToString}};
+use core::fmt;
+use halo2_proofs::{
+    halo2curves::bn256::{Bn256, Fr, G1Affine},
+    halo2curves::group::ff::PrimeField,
+    plonk::{verify_proof, VerifyingKey},
+    poly::commitment::Params,
+    poly::kzg::{
+        commitment::{KZGCommitmentScheme, ParamsKZG},
+        multiopen::VerifierGWC,
+        strategy::SingleStrategy,
+    },
+    transcript::{Blake2bRead, Challenge255, TranscriptReadBuffer},
+    SerdeFormat,
+};
+
+// Verification core for constrained hosts (Substrate runtimes, TEEs, HSM firmware): bytes in,
+// verdict out, with no files, threads, clocks, JSON or feature-gated transcripts; the caller
+// decodes the public inputs. KZG/GWC with Blake2b only.
+//
+// Not a no_std build: halo2-axiom 0.5.3 has no no_std mode (it reads keys through std::io and
+// links rayon), so this crate always links std and there is no no_std target to check. What
+// holds is that this module names nothing from std beyond core and alloc, which the lints
+// below enforce under clippy; porting to a no_std halo2 backend leaves this file unchanged.
+
+#[derive(Debug)]
+pub enum VerifyError {
+    Envelope(String),
+    Key(String),
+    Params(String),
+    Unsupported(&'static str),
+    Rejected(String),
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VerifyError::Envelope(e) => write!(f, "sobre de prueba inválido: {e}"),
+            VerifyError::Key(e) => write!(f, "clave inválida: {e}"),
+            VerifyError::Params(e) => write!(f, "params inválidos: {e}"),
+            VerifyError::Unsupported(e) => write!(f, "{e}"),
+            VerifyError::Rejected(e) => write!(f, "prueba rechazada: {e}"),
+        }
+    }
+}
+
+// The raw key of a vk file, skipping its header unparsed (see keys.rs).
+fn raw_vk(bytes: &[u8]) -> Result<&[u8], VerifyError> {
+    match bytes.get(..4) {
+        Some(m) if m == PK_MAGIC => Err(VerifyError::Key("se esperaba una clave de verificación".into())),
+        Some(m) if m == VK_MAGIC => {
+            let len = bytes.get(6..10).map(|l| u32::from_le_bytes(l.try_into().unwrap()) as usize);
+            len.and_then(|len| bytes.get(10 + len..)).ok_or_else(|| VerifyError::Key("archivo de clave truncado".into()))
+        }
+        _ => Ok(bytes),
+    }
+}
+
+/// Verifies a TxCircuit proof envelope against a vk file, KZG params and the instance columns.
+pub fn verify(vk: &[u8], params: &[u8], proof: &[u8], instances: &[&[Fr]]) -> Result<(), VerifyError> {
+    let envelope = ProofEnvelope::from_bytes(proof).map_err(VerifyError::Envelope)?;
+    if envelope.scheme != Scheme::KzgGwc || envelope.transcript != Transcript::Blake2b {
+        return Err(VerifyError::Unsupported("el núcleo de verificación solo admite KZG con transcript blake2b"));
+    }
+    let params = ParamsKZG::<Bn256>::read(&mut &params[..]).map_err(|e| VerifyError::Params(e.to_string()))?;
+    let vk = VerifyingKey::<G1Affine>::read::<_, TxCircuit<Fr>>(&mut raw_vk(vk)?, SerdeFormat::RawBytes, ())
+        .map_err(|e| VerifyError::Key(e.to_string()))?;
+    envelope.check(Scheme::KzgGwc, params.k(), &vk.transcript_repr().to_repr()).map_err(VerifyError::Rejected)?;
+    let mut transcript = Blake2bRead::<_, G1Affine, Challenge255<_>>::init(&envelope.proof[..]);
+    verify_proof::<KZGCommitmentScheme<Bn256>, VerifierGWC<_>, _, _, _>(
+        &params, &vk, SingleStrategy::new(&params), &[instances], &mut transcript,
+    ).map_err(|e| VerifyError::Rejected(format!("{e:?}")))
+}
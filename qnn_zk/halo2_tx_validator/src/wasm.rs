@@ -0,0 +1,24 @@
+// wasm.rs
+use crate::envelope::ProofEnvelope;
+use crate::error::QuantumGuardError as E;
+use crate::keys::VerifyingKeyFile;
+use crate::public::PublicInputs;
+use crate::{prover, TxCircuit};
+use halo2_proofs::halo2curves::bn256::Fr;
+use wasm_bindgen::prelude::*;
+
+// Client-side verification for wallets (browser or Node, through wasm-pack): the same check as
+// `verify` without the CLI's policy options. Errors reject with the library's message.
+
+/// Verifies a TxCircuit proof given the vk file (see keys.rs), the KZG params, the proof
+/// envelope and the public JSON, all as written by the CLI.
+#[wasm_bindgen]
+pub fn verify(vk_bytes: &[u8], params_bytes: &[u8], proof: &[u8], public_json: &str) -> Result<(), JsError> {
+    let params = prover::read_params(params_bytes)?;
+    let vk = VerifyingKeyFile::read::<TxCircuit<Fr>>(&mut &vk_bytes[..])?;
+    let public = PublicInputs::from_json(public_json.as_bytes())?;
+    if let Some(header) = &vk.header { header.check_shape(&public.shape)?; }
+    let envelope = ProofEnvelope::from_bytes(proof).map_err(E::Verification)?;
+    prover::check_kzg_proof(&params, &vk.vk, &envelope, &public.instance_refs())?;
+    Ok(())
+}
@@ -0,0 +1,115 @@
+// witness.rs
+use crate::error::QuantumGuardError;
+use crate::numeric::{flexible_i64, FlexI64};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
+
+/// Witness of one score: feature and weight vectors and scalars, all Q16.16.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Witness {
+    // dense arrays, or sparse {"len", "values"} (see below)
+    #[serde(deserialize_with = "dense_or_sparse")] pub x: Vec<i64>,
+    #[serde(deserialize_with = "dense_or_sparse")] pub w: Vec<i64>,
+    // integers or numeric strings (see numeric.rs)
+    #[serde(deserialize_with = "flexible_i64")] pub b: i64,
+    #[serde(deserialize_with = "flexible_i64")] pub alpha: i64,
+    #[serde(deserialize_with = "flexible_i64")] pub q_out: i64,
+    #[serde(deserialize_with = "flexible_i64")] pub score_pub: i64,
+}
+
+// Binary witness (.qgw) for wide feature vectors: the bincode encoding of BinaryWitness, whose
+// x and w are raw little-endian i64 (Q16.16) byte strings. Decoding borrows them from the input,
+// so a 10k-feature witness is parsed without allocating or converting the arrays; values are
+// read on access.
+pub const MAGIC: [u8; 4] = *b"QGWB";
+pub const VERSION: u16 = 1;
+
+#[derive(Serialize, Deserialize)]
+pub struct BinaryWitness<'a> {
+    magic: [u8; 4],
+    version: u16,
+    pub b: i64,
+    pub alpha: i64,
+    pub q_out: i64,
+    pub score_pub: i64,
+    #[serde(borrow, with = "serde_bytes")]
+    x: &'a [u8],
+    #[serde(borrow, with = "serde_bytes")]
+    w: &'a [u8],
+}
+
+fn values(bytes: &[u8]) -> impl ExactSizeIterator<Item = i64> + '_ {
+    bytes.chunks_exact(8).map(|c| i64::from_le_bytes(c.try_into().unwrap()))
+}
+
+impl<'a> BinaryWitness<'a> {
+    pub fn decode(bytes: &'a [u8]) -> Result<Self, QuantumGuardError> {
+        let invalid = |msg: String| QuantumGuardError::Witness(msg);
+        if bytes.get(..4) != Some(&MAGIC[..]) {
+            return Err(invalid("el archivo no es un witness binario".into()));
+        }
+        let wit: Self = bincode::deserialize(bytes).map_err(|e| invalid(format!("witness binario ilegible: {e}")))?;
+        if wit.version != VERSION {
+            return Err(invalid(format!("versión de witness binario {} no soportada (se espera {VERSION})", wit.version)));
+        }
+        if !wit.x.len().is_multiple_of(8) || !wit.w.len().is_multiple_of(8) {
+            return Err(invalid("witness binario truncado: x y w deben ser múltiplos de 8 bytes".into()));
+        }
+        Ok(wit)
+    }
+
+    pub fn encode(x: &[i64], w: &[i64], b: i64, alpha: i64, q_out: i64, score_pub: i64) -> Vec<u8> {
+        let x: Vec<u8> = x.iter().flat_map(|v| v.to_le_bytes()).collect();
+        let w: Vec<u8> = w.iter().flat_map(|v| v.to_le_bytes()).collect();
+        let wit = BinaryWitness { magic: MAGIC, version: VERSION, b, alpha, q_out, score_pub, x: &x, w: &w };
+        bincode::serialize(&wit).expect("bincode de un struct plano")
+    }
+
+    pub fn x(&self) -> impl ExactSizeIterator<Item = i64> + 'a { values(self.x) }
+
+    pub fn w(&self) -> impl ExactSizeIterator<Item = i64> + 'a { values(self.w) }
+}
+
+// Sparse feature vector: {"len": n, "values": {"<index>": value}}, absent indices are zero.
+// Only the file is sparse: the circuit commits to and scores the dense vector, so a zero
+// feature still takes its row; what this saves is the witness size and parse time.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SparseVector {
+    pub len: usize,
+    #[serde(deserialize_with = "flexible_values")]
+    pub values: BTreeMap<usize, i64>,
+}
+
+fn flexible_values<'de, D: Deserializer<'de>>(d: D) -> Result<BTreeMap<usize, i64>, D::Error> {
+    Ok(BTreeMap::<usize, FlexI64>::deserialize(d)?.into_iter().map(|(i, v)| (i, v.0)).collect())
+}
+
+impl SparseVector {
+    pub fn from_dense(v: &[i64]) -> Self {
+        Self { len: v.len(), values: v.iter().enumerate().filter(|(_, v)| **v != 0).map(|(i, v)| (i, *v)).collect() }
+    }
+
+    pub fn to_dense(&self) -> Result<Vec<i64>, String> {
+        let mut dense = vec![0; self.len];
+        for (&i, &v) in &self.values {
+            *dense.get_mut(i).ok_or_else(|| format!("índice {i} fuera de len = {}", self.len))? = v;
+        }
+        Ok(dense)
+    }
+}
+
+/// serde `deserialize_with` for feature and weight vectors: a dense array or a SparseVector,
+/// with numbers in any form numeric.rs accepts.
+pub fn dense_or_sparse<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<i64>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Repr {
+        Dense(Vec<FlexI64>),
+        Sparse(SparseVector),
+    }
+    match Repr::deserialize(d)? {
+        Repr::Dense(v) => Ok(v.into_iter().map(|v| v.0).collect()),
+        Repr::Sparse(s) => s.to_dense().map_err(D::Error::custom),
+    }
+}